@@ -38,10 +38,13 @@ pub mod request_tracing;
 // Re-export main types
 pub use response::{ApiResponse, ApiResult};
 pub use pagination::{PaginationParams, PaginatedResponse, PaginationMeta};
+pub use pagination::{CursorParams, CursorPaginatedResponse, CursorMeta, PaginationLinks};
 pub use error::ApiError;
 pub use validation::RequestValidator;
 pub use service::{ApiService, ApiServiceConfig};
-pub use rate_limit::{InMemoryRateLimiter, RateLimitConfig, RateLimitResult, RateLimitMiddleware};
+pub use rate_limit::{InMemoryRateLimiter, RateLimitConfig, RateLimitResult, RateLimitMiddleware, RateLimitStore, RateLimitPolicy, RateLimitPolicies, RateLimitKey};
+#[cfg(feature = "database")]
+pub use rate_limit::PostgresRateLimitStore;
 pub use cors::{CorsConfig, CorsMiddleware, CorsResult};
 pub use docs::{
     ApiDocsBuilder,
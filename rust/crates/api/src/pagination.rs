@@ -4,6 +4,7 @@
 //! API responses with consistent metadata.
 
 use serde::{Deserialize, Serialize};
+use crate::error::ApiError;
 
 /// Pagination parameters from query string
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -223,6 +224,145 @@ impl PaginationLinks {
             last: Some(format!("{}?page={}&per_page={}", base_url, meta.total_pages, meta.per_page)),
         }
     }
+
+    /// Create links for a cursor-paginated response
+    ///
+    /// `first` restarts without a cursor; `last` is not known for keyset
+    /// pagination and is left empty.
+    pub fn for_cursor(path: &str, meta: &CursorMeta) -> Self {
+        let base_url = path.trim_end_matches('/');
+        let link = |cursor: &Option<String>| {
+            cursor
+                .as_ref()
+                .map(|cursor| format!("{}?cursor={}&limit={}", base_url, cursor, meta.limit))
+        };
+
+        Self {
+            first: Some(format!("{}?limit={}", base_url, meta.limit)),
+            prev: link(&meta.prev_cursor),
+            next: link(&meta.next_cursor),
+            last: None,
+        }
+    }
+}
+
+/// Cursor pagination parameters from query string
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CursorParams {
+    /// Opaque cursor from a previous response
+    pub cursor: Option<String>,
+    /// Number of items per page
+    pub limit: Option<u32>,
+}
+
+impl CursorParams {
+    /// Create cursor params for the first page
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the cursor
+    pub fn cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+
+    /// Set items per page
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Get the cursor, treating an empty value as absent
+    pub fn get_cursor(&self) -> Option<&str> {
+        self.cursor.as_deref().filter(|cursor| !cursor.is_empty())
+    }
+
+    /// Get validated items per page
+    pub fn get_limit(&self) -> u32 {
+        self.limit.unwrap_or(20).clamp(1, 1000) // Max 1000 items per page
+    }
+}
+
+/// Cursor pagination metadata for responses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CursorMeta {
+    /// Cursor for the page after this one
+    pub next_cursor: Option<String>,
+    /// Cursor for the page before this one
+    pub prev_cursor: Option<String>,
+    /// Items per page
+    pub limit: u32,
+    /// Whether there is a next page
+    pub has_next: bool,
+    /// Whether there is a previous page
+    pub has_prev: bool,
+}
+
+impl CursorMeta {
+    /// Create cursor metadata
+    pub fn new(limit: u32, next_cursor: Option<String>, prev_cursor: Option<String>) -> Self {
+        Self {
+            has_next: next_cursor.is_some(),
+            has_prev: prev_cursor.is_some(),
+            next_cursor,
+            prev_cursor,
+            limit,
+        }
+    }
+}
+
+/// Cursor-paginated response wrapper
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CursorPaginatedResponse<T> {
+    /// The data items
+    pub data: Vec<T>,
+    /// Pagination metadata
+    pub pagination: CursorMeta,
+    /// Navigation links
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<PaginationLinks>,
+}
+
+impl<T> CursorPaginatedResponse<T> {
+    /// Create a new cursor-paginated response
+    pub fn new(data: Vec<T>, pagination: CursorMeta) -> Self {
+        Self { data, pagination, links: None }
+    }
+
+    /// Add navigation links for a given path
+    pub fn with_links(mut self, path: &str) -> Self {
+        self.links = Some(PaginationLinks::for_cursor(path, &self.pagination));
+        self
+    }
+
+    /// Get the items
+    pub fn items(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Check if response is empty
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Get number of items in this page
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Check if this is the last page
+    pub fn is_last_page(&self) -> bool {
+        !self.pagination.has_next
+    }
+}
+
+#[cfg(feature = "database")]
+impl<T> From<cloudshuttle_database::CursorPaginatedResult<T>> for CursorPaginatedResponse<T> {
+    fn from(result: cloudshuttle_database::CursorPaginatedResult<T>) -> Self {
+        let meta = CursorMeta::new(result.page_size, result.next_cursor, result.prev_cursor);
+        Self::new(result.data, meta)
+    }
 }
 
 /// Pagination query builder for database queries
@@ -247,12 +387,42 @@ impl PaginationQueryBuilder {
     }
 
     /// Add ORDER BY clause if sorting is specified
-    pub fn with_sorting(mut self) -> Self {
+    ///
+    /// The sort field comes from the request, so it must be one of
+    /// `allowed_columns` and the order must be `asc` or `desc`; anything else
+    /// is rejected instead of being written into the query.
+    pub fn with_sorting(mut self, allowed_columns: &[&str]) -> Result<Self, Box<ApiError>> {
         if let Some(sort_by) = &self.params.sort_by {
-            let order = self.params.sort_order();
-            self.base_query.push_str(&format!(" ORDER BY {} {}", sort_by, order.to_uppercase()));
+            let column = allowed_columns
+                .iter()
+                .find(|column| *column == sort_by)
+                .ok_or_else(|| unsortable(sort_by))?;
+            let order = self.sql_sort_order()?;
+            self.base_query.push_str(&format!(" ORDER BY {} {}", quote_identifier(column), order));
+        }
+        Ok(self)
+    }
+
+    /// Add ORDER BY clause if sorting is specified, resolving the field through `columns`
+    ///
+    /// Unlike [`Self::with_sorting`], fields can be exposed under API names
+    /// that differ from the column.
+    #[cfg(feature = "database")]
+    pub fn with_column_sorting(mut self, columns: &cloudshuttle_database::ColumnAllowlist) -> Result<Self, Box<ApiError>> {
+        if let Some(sort_by) = &self.params.sort_by {
+            let column = columns.resolve(sort_by).map_err(|_| unsortable(sort_by))?;
+            let order = self.sql_sort_order()?;
+            self.base_query.push_str(&format!(" ORDER BY {} {}", column, order));
+        }
+        Ok(self)
+    }
+
+    fn sql_sort_order(&self) -> Result<&'static str, Box<ApiError>> {
+        match self.params.sort_order().to_ascii_lowercase().as_str() {
+            "asc" => Ok("ASC"),
+            "desc" => Ok("DESC"),
+            other => Err(Box::new(ApiError::bad_request(format!("Invalid sort order '{}'", other)))),
         }
-        self
     }
 
     /// Add LIMIT and OFFSET clauses
@@ -282,6 +452,19 @@ impl PaginationQueryBuilder {
     }
 }
 
+fn unsortable(field: &str) -> Box<ApiError> {
+    Box::new(ApiError::bad_request(format!("Cannot sort by '{}'", field)))
+}
+
+/// Double-quote each part of a (possibly qualified) column name
+fn quote_identifier(column: &str) -> String {
+    column
+        .split('.')
+        .map(|part| format!("\"{}\"", part.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -328,6 +511,32 @@ mod tests {
         assert!(!response.is_last_page());
     }
 
+    #[test]
+    fn test_cursor_params() {
+        let params = CursorParams::new().cursor("abc.def").limit(5000);
+        assert_eq!(params.get_cursor(), Some("abc.def"));
+        assert_eq!(params.get_limit(), 1000);
+        assert_eq!(CursorParams::new().cursor("").get_cursor(), None);
+        assert_eq!(CursorParams::new().get_limit(), 20);
+    }
+
+    #[test]
+    fn test_cursor_paginated_response_links() {
+        let meta = CursorMeta::new(2, Some("next.tag".to_string()), None);
+        let response = CursorPaginatedResponse::new(vec!["a", "b"], meta).with_links("/items/");
+
+        assert!(!response.is_last_page());
+        assert!(response.pagination.has_next && !response.pagination.has_prev);
+        let links = response.links.as_ref().unwrap();
+        assert_eq!(links.first.as_deref(), Some("/items?limit=2"));
+        assert_eq!(links.next.as_deref(), Some("/items?cursor=next.tag&limit=2"));
+        assert!(links.prev.is_none() && links.last.is_none());
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["pagination"]["next_cursor"], "next.tag");
+        assert!(json["pagination"]["prev_cursor"].is_null());
+    }
+
     #[test]
     fn test_pagination_query_builder() {
         let base = "SELECT * FROM users WHERE active = true";
@@ -338,12 +547,76 @@ mod tests {
 
         let query = PaginationQueryBuilder::new(base)
             .with_params(params)
-            .with_sorting()
+            .with_sorting(&["name", "created_at"])
+            .unwrap()
             .with_limits()
             .build();
 
-        assert!(query.contains("ORDER BY name ASC"));
+        assert!(query.contains("ORDER BY \"name\" ASC"));
         assert!(query.contains("LIMIT 5"));
         assert!(query.contains("OFFSET 5"));
     }
+
+    #[test]
+    fn test_pagination_sorting_rejects_unknown_fields() {
+        let base = "SELECT * FROM users";
+        let injected = PaginationParams::new().sort("name; DROP TABLE users", "asc");
+        let error = PaginationQueryBuilder::new(base)
+            .with_params(injected)
+            .with_sorting(&["name"])
+            .err()
+            .unwrap();
+        assert_eq!(error.status_code, 400);
+
+        let bad_order = PaginationParams::new().sort("name", "asc; DELETE FROM users");
+        assert!(PaginationQueryBuilder::new(base).with_params(bad_order).with_sorting(&["name"]).is_err());
+
+        let query = PaginationQueryBuilder::new(base)
+            .with_params(PaginationParams::new().sort("u.created_at", "DESC"))
+            .with_sorting(&["u.created_at"])
+            .unwrap()
+            .build();
+        assert!(query.ends_with("ORDER BY \"u\".\"created_at\" DESC"));
+    }
+
+    #[cfg(feature = "database")]
+    #[test]
+    fn test_pagination_column_sorting() {
+        use cloudshuttle_database::ColumnAllowlist;
+
+        let base = "SELECT * FROM users u";
+        let columns = ColumnAllowlist::new(["name"])
+            .unwrap()
+            .with_alias("createdAt", "u.created_at")
+            .unwrap();
+
+        let query = PaginationQueryBuilder::new(base)
+            .with_params(PaginationParams::new().sort("name", "asc"))
+            .with_column_sorting(&columns)
+            .unwrap()
+            .build();
+        assert!(query.ends_with("ORDER BY \"name\" ASC"));
+
+        let query = PaginationQueryBuilder::new(base)
+            .with_params(PaginationParams::new().sort("createdAt", "DESC"))
+            .with_column_sorting(&columns)
+            .unwrap()
+            .build();
+        assert!(query.ends_with("ORDER BY \"u\".\"created_at\" DESC"));
+
+        let injected = PaginationParams::new().sort("name; DROP TABLE users", "asc");
+        let error = PaginationQueryBuilder::new(base)
+            .with_params(injected)
+            .with_column_sorting(&columns)
+            .err()
+            .unwrap();
+        assert_eq!(error.status_code, 400);
+
+        // Only allowlisted field names are accepted, not the columns behind aliases
+        let column = PaginationParams::new().sort("u.created_at", "asc");
+        assert!(PaginationQueryBuilder::new(base).with_params(column).with_column_sorting(&columns).is_err());
+
+        let bad_order = PaginationParams::new().sort("name", "asc; DELETE FROM users");
+        assert!(PaginationQueryBuilder::new(base).with_params(bad_order).with_column_sorting(&columns).is_err());
+    }
 }
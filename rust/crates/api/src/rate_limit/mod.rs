@@ -0,0 +1,409 @@
+//! Rate limiting middleware for API endpoints
+//!
+//! This module provides configurable rate limiting functionality
+//! that can be applied to API endpoints to prevent abuse.
+//!
+//! Responses carry the IETF `RateLimit-Limit`, `RateLimit-Remaining` and
+//! `RateLimit-Reset` headers, plus `Retry-After` when the limit is exceeded.
+//!
+//! Request counts are kept in a `RateLimitStore`. The in-memory store is
+//! per-process; use the Postgres store (`database` feature) to share one
+//! quota across replicas.
+
+pub mod store;
+pub mod policy;
+#[cfg(feature = "database")]
+pub mod postgres;
+
+pub use store::{RateLimitStore, InMemoryRateLimiter};
+pub use policy::{RateLimitKey, RateLimitPolicy, RateLimitPolicies};
+#[cfg(feature = "database")]
+pub use postgres::PostgresRateLimitStore;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use axum::{
+    extract::Request,
+    middleware::Next,
+    response::{Response, IntoResponse},
+    http::{HeaderMap, HeaderValue, StatusCode},
+};
+use crate::error::ApiError;
+
+/// Rate limit configuration
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    /// Maximum number of requests allowed in the window
+    pub max_requests: u32,
+    /// Time window duration in seconds
+    pub window_seconds: u64,
+    /// Whether to use IP-based limiting (default) or user-based
+    pub by_ip: bool,
+    /// Custom identifier extractor function
+    pub identifier_extractor: Option<fn(&Request) -> String>,
+    /// Let requests through when the store fails instead of answering 503
+    pub fail_open: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_requests: 100,
+            window_seconds: 60, // 1 minute
+            by_ip: true,
+            identifier_extractor: None,
+            fail_open: true,
+        }
+    }
+}
+
+/// Rate limit check result
+#[derive(Debug, Clone)]
+pub enum RateLimitResult {
+    /// Request is allowed
+    Allowed {
+        /// Remaining requests in current window
+        remaining: u32,
+        /// When the rate limit window resets
+        reset_time: Option<Instant>,
+    },
+    /// Rate limit exceeded
+    Exceeded {
+        /// When the rate limit window resets
+        reset_time: Option<Instant>,
+    },
+}
+
+/// Rate limiting middleware
+pub struct RateLimitMiddleware<S: RateLimitStore = InMemoryRateLimiter> {
+    store: Arc<S>,
+    config: RateLimitConfig,
+}
+
+impl RateLimitMiddleware<InMemoryRateLimiter> {
+    /// Create new rate limiting middleware
+    pub fn new(limiter: Arc<InMemoryRateLimiter>) -> Self {
+        let config = limiter.config().clone();
+        Self::with_store(limiter, config)
+    }
+
+    /// Create the middleware function
+    pub fn layer(limiter: Arc<InMemoryRateLimiter>) -> impl Fn(Request, Next) -> std::pin::Pin<Box<dyn std::future::Future<Output = Response> + Send>> + Clone {
+        let config = limiter.config().clone();
+        Self::store_layer(limiter, config)
+    }
+}
+
+impl<S: RateLimitStore + 'static> RateLimitMiddleware<S> {
+    /// Create rate limiting middleware backed by the given store
+    pub fn with_store(store: Arc<S>, config: RateLimitConfig) -> Self {
+        Self { store, config }
+    }
+
+    /// Create the middleware function for a store and quota
+    pub fn store_layer(store: Arc<S>, config: RateLimitConfig) -> impl Fn(Request, Next) -> std::pin::Pin<Box<dyn std::future::Future<Output = Response> + Send>> + Clone {
+        let middleware = Arc::new(Self::with_store(store, config));
+        move |req: Request, next: Next| {
+            let middleware = middleware.clone();
+            Box::pin(async move {
+                let identifier = middleware.extract_identifier(&req);
+                let result = middleware.check_identifier(&identifier).await;
+                enforce(result, &middleware.config, &identifier, req, next).await
+            })
+        }
+    }
+
+    /// Extract identifier from request (IP address by default)
+    pub fn extract_identifier(&self, req: &Request) -> String {
+        if let Some(extractor) = self.config.identifier_extractor {
+            extractor(req)
+        } else if self.config.by_ip {
+            client_ip(req)
+        } else {
+            // For user-based limiting, this would need to be implemented
+            // based on authentication context
+            "anonymous".to_string()
+        }
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.config.window_seconds)
+    }
+
+    /// Check rate limit for an already extracted identifier
+    pub async fn check_identifier(&self, identifier: &str) -> Result<RateLimitResult, ApiError> {
+        self.store.acquire(identifier, self.config.max_requests, self.window()).await
+    }
+
+    /// Check rate limit for a request (utility method)
+    pub async fn check_request(&self, req: &Request) -> Result<RateLimitResult, ApiError> {
+        let identifier = self.extract_identifier(req);
+        self.check_identifier(&identifier).await
+    }
+
+    /// Get remaining requests for a request (utility method)
+    pub async fn remaining_requests(&self, req: &Request) -> Result<u32, ApiError> {
+        let identifier = self.extract_identifier(req);
+        self.store.remaining(&identifier, self.config.max_requests, self.window()).await
+    }
+
+    /// Get the quota enforced by this middleware
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+}
+
+/// Extract the client IP address from `x-forwarded-for`
+pub(crate) fn client_ip(req: &Request) -> String {
+    req.headers()
+        .get("x-forwarded-for")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.split(',').next())
+        .map(|s| s.trim())
+        .unwrap_or("127.0.0.1")
+        .to_string()
+}
+
+/// Seconds until an instant, rounded up so clients never retry early
+fn seconds_until(instant: Instant) -> u64 {
+    let duration = instant.saturating_duration_since(Instant::now());
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+/// Add the IETF `RateLimit-*` headers (and `Retry-After` when exceeded)
+pub fn apply_rate_limit_headers(headers: &mut HeaderMap, limit: u32, result: &RateLimitResult) {
+    let (remaining, reset_time) = match result {
+        RateLimitResult::Allowed { remaining, reset_time } => (*remaining, *reset_time),
+        RateLimitResult::Exceeded { reset_time } => (0, *reset_time),
+    };
+    let reset_seconds = reset_time.map(seconds_until).unwrap_or(0);
+
+    headers.insert("ratelimit-limit", HeaderValue::from(limit));
+    headers.insert("ratelimit-remaining", HeaderValue::from(remaining));
+    headers.insert("ratelimit-reset", HeaderValue::from(reset_seconds));
+
+    if matches!(result, RateLimitResult::Exceeded { .. }) {
+        headers.insert("retry-after", HeaderValue::from(reset_seconds));
+    }
+}
+
+/// Apply a rate limit decision to a request
+///
+/// Store failures are logged and, depending on `RateLimitConfig::fail_open`,
+/// either let the request through or answer 503.
+pub(crate) async fn enforce(
+    result: Result<RateLimitResult, ApiError>,
+    config: &RateLimitConfig,
+    identifier: &str,
+    req: Request,
+    next: Next,
+) -> Response {
+    let limit = config.max_requests;
+    match result {
+        Ok(result @ RateLimitResult::Allowed { .. }) => {
+            let mut response = next.run(req).await;
+            apply_rate_limit_headers(response.headers_mut(), limit, &result);
+            response
+        }
+        Ok(result @ RateLimitResult::Exceeded { .. }) => exceeded_response(limit, &result),
+        Err(error) => {
+            tracing::warn!(identifier, error = %error.message, fail_open = config.fail_open, "Rate limit store failed");
+            let (mut response, remaining) = if config.fail_open {
+                (next.run(req).await, limit)
+            } else {
+                ((StatusCode::SERVICE_UNAVAILABLE, "Rate limiting unavailable").into_response(), 0)
+            };
+            let result = RateLimitResult::Allowed { remaining, reset_time: None };
+            apply_rate_limit_headers(response.headers_mut(), limit, &result);
+            response
+        }
+    }
+}
+
+/// Build the 429 response for an exceeded limit
+pub(crate) fn exceeded_response(limit: u32, result: &RateLimitResult) -> Response {
+    let mut response = (StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded").into_response();
+    apply_rate_limit_headers(response.headers_mut(), limit, result);
+    response
+}
+
+/// Pre-configured rate limiters for common use cases
+pub mod presets {
+    use super::*;
+
+    /// Create a rate limiter for general API endpoints (100 requests per minute)
+    pub fn api_limiter() -> Arc<InMemoryRateLimiter> {
+        Arc::new(InMemoryRateLimiter::new(RateLimitConfig {
+            max_requests: 100,
+            window_seconds: 60,
+            ..Default::default()
+        }))
+    }
+
+    /// Create a rate limiter for authentication endpoints (10 requests per minute)
+    pub fn auth_limiter() -> Arc<InMemoryRateLimiter> {
+        Arc::new(InMemoryRateLimiter::new(RateLimitConfig {
+            max_requests: 10,
+            window_seconds: 60,
+            ..Default::default()
+        }))
+    }
+
+    /// Create a rate limiter for search endpoints (50 requests per minute)
+    pub fn search_limiter() -> Arc<InMemoryRateLimiter> {
+        Arc::new(InMemoryRateLimiter::new(RateLimitConfig {
+            max_requests: 50,
+            window_seconds: 60,
+            ..Default::default()
+        }))
+    }
+
+    /// Create a rate limiter for file upload endpoints (5 requests per minute)
+    pub fn upload_limiter() -> Arc<InMemoryRateLimiter> {
+        Arc::new(InMemoryRateLimiter::new(RateLimitConfig {
+            max_requests: 5,
+            window_seconds: 60,
+            ..Default::default()
+        }))
+    }
+
+    /// Mount all presets on one router: `/auth`, `/search`, `POST /upload`, and everything else
+    ///
+    /// Everything else goes through the fallback policy, using `api_limiter`.
+    pub fn route_policies() -> RateLimitPolicies {
+        RateLimitPolicies::new()
+            .fallback(RateLimitPolicy::new("/", api_limiter()).name("api"))
+            .policy(RateLimitPolicy::new("/auth", auth_limiter()).name("auth"))
+            .policy(RateLimitPolicy::new("/search", search_limiter()).name("search"))
+            .policy(RateLimitPolicy::new("/upload", upload_limiter()).name("upload").methods([axum::http::Method::POST]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = InMemoryRateLimiter::new(RateLimitConfig {
+            max_requests: 2,
+            window_seconds: 60,
+            ..Default::default()
+        });
+
+        // First request should be allowed
+        match limiter.check_limit("test") {
+            RateLimitResult::Allowed { remaining, .. } => assert_eq!(remaining, 1),
+            _ => panic!("Expected allowed"),
+        }
+
+        // Second request should be allowed
+        match limiter.check_limit("test") {
+            RateLimitResult::Allowed { remaining, .. } => assert_eq!(remaining, 0),
+            _ => panic!("Expected allowed"),
+        }
+
+        // Third request should be denied
+        match limiter.check_limit("test") {
+            RateLimitResult::Exceeded { .. } => {},
+            _ => panic!("Expected exceeded"),
+        }
+    }
+
+    #[test]
+    fn test_presets() {
+        let api_limiter = presets::api_limiter();
+        assert_eq!(api_limiter.config().max_requests, 100);
+
+        let auth_limiter = presets::auth_limiter();
+        assert_eq!(auth_limiter.config().max_requests, 10);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_with_store() {
+        let config = RateLimitConfig {
+            max_requests: 1,
+            window_seconds: 60,
+            ..Default::default()
+        };
+        let middleware = RateLimitMiddleware::with_store(Arc::new(InMemoryRateLimiter::new(config.clone())), config);
+        let request = || Request::builder()
+            .header("x-forwarded-for", "10.0.0.1, 10.0.0.2")
+            .body(axum::body::Body::empty())
+            .unwrap();
+
+        assert_eq!(middleware.remaining_requests(&request()).await.unwrap(), 1);
+        assert!(matches!(middleware.check_request(&request()).await.unwrap(), RateLimitResult::Allowed { .. }));
+        assert!(matches!(middleware.check_request(&request()).await.unwrap(), RateLimitResult::Exceeded { .. }));
+    }
+
+    /// Store that is always unavailable
+    struct FailingStore;
+
+    #[async_trait::async_trait]
+    impl RateLimitStore for FailingStore {
+        async fn acquire(&self, _key: &str, _max_requests: u32, _window: Duration) -> Result<RateLimitResult, ApiError> {
+            Err(ApiError::service_unavailable("store down"))
+        }
+
+        async fn remaining(&self, _key: &str, _max_requests: u32, _window: Duration) -> Result<u32, ApiError> {
+            Err(ApiError::service_unavailable("store down"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_failures_fail_open_or_closed() {
+        use tower::ServiceExt;
+
+        let app = |fail_open: bool| {
+            let config = RateLimitConfig { max_requests: 5, fail_open, ..Default::default() };
+            axum::Router::new()
+                .route("/", axum::routing::get(|| async { "ok" }))
+                .layer(axum::middleware::from_fn(RateLimitMiddleware::store_layer(Arc::new(FailingStore), config)))
+        };
+        let request = || Request::builder().uri("/").body(axum::body::Body::empty()).unwrap();
+
+        let response = app(true).oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["ratelimit-remaining"], "5");
+
+        let response = app(false).oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()["ratelimit-limit"], "5");
+        assert_eq!(response.headers()["ratelimit-remaining"], "0");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_headers_on_every_response() {
+        use tower::ServiceExt;
+
+        let app = axum::Router::new()
+            .route("/auth/login", axum::routing::post(|| async { "ok" }))
+            .route("/users", axum::routing::get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn(presets::route_policies().layer()));
+        let request = |method: &str, uri: &str| Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-forwarded-for", "10.0.0.9")
+            .body(axum::body::Body::empty())
+            .unwrap();
+
+        let response = app.clone().oneshot(request("GET", "/users")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["ratelimit-limit"], "100");
+        assert_eq!(response.headers()["ratelimit-remaining"], "99");
+        assert!(response.headers().get("retry-after").is_none());
+
+        for _ in 0..10 {
+            let response = app.clone().oneshot(request("POST", "/auth/login")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()["ratelimit-limit"], "10");
+        }
+
+        let response = app.clone().oneshot(request("POST", "/auth/login")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["ratelimit-remaining"], "0");
+        let retry_after: u64 = response.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+        assert!((1..=6).contains(&retry_after), "retry-after was {}", retry_after);
+    }
+}
@@ -0,0 +1,262 @@
+//! Per-route rate limit policies
+//!
+//! A `RateLimitPolicies` table maps path prefixes and methods to quotas so
+//! several limiters can be mounted on one router. The most specific
+//! matching prefix wins.
+
+use std::sync::Arc;
+use std::time::Duration;
+use axum::{
+    extract::Request,
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use cloudshuttle_auth::Claims;
+
+use super::store::{InMemoryRateLimiter, RateLimitStore};
+use super::{client_ip, enforce, presets, RateLimitConfig, RateLimitResult};
+
+/// What a policy counts requests against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitKey {
+    /// Client IP address from `x-forwarded-for`
+    Ip,
+    /// Authenticated user (`Claims::sub`), falling back to IP
+    User,
+    /// Authenticated tenant (`Claims::tenant_id`), falling back to IP
+    Tenant,
+}
+
+/// Rate limit policy for a group of routes
+#[derive(Clone)]
+pub struct RateLimitPolicy {
+    name: String,
+    path_prefix: String,
+    methods: Vec<Method>,
+    key: RateLimitKey,
+    config: RateLimitConfig,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl RateLimitPolicy {
+    /// Create a policy using an in-memory limiter and its configuration
+    pub fn new(path_prefix: impl Into<String>, limiter: Arc<InMemoryRateLimiter>) -> Self {
+        let config = limiter.config().clone();
+        Self::with_store(path_prefix, limiter, config)
+    }
+
+    /// Create a policy backed by any rate limit store
+    pub fn with_store(path_prefix: impl Into<String>, store: Arc<dyn RateLimitStore>, config: RateLimitConfig) -> Self {
+        let path_prefix = path_prefix.into();
+        let key = if config.by_ip { RateLimitKey::Ip } else { RateLimitKey::User };
+        Self {
+            name: path_prefix.clone(),
+            path_prefix,
+            methods: Vec::new(),
+            key,
+            config,
+            store,
+        }
+    }
+
+    /// Restrict the policy to the given methods (all methods by default)
+    pub fn methods(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+        self.methods = methods.into_iter().collect();
+        self
+    }
+
+    /// Set what requests are counted against
+    pub fn key(mut self, key: RateLimitKey) -> Self {
+        self.key = key;
+        self
+    }
+
+    /// Set the name used to namespace this policy's counters
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Get the quota enforced by this policy
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Check if the policy applies to a request
+    pub fn matches(&self, method: &Method, path: &str) -> bool {
+        let method_matches = self.methods.is_empty() || self.methods.contains(method);
+        method_matches && path_has_prefix(path, &self.path_prefix)
+    }
+
+    /// Build the store key for a request
+    pub fn identifier(&self, req: &Request) -> String {
+        let subject = if let Some(extractor) = self.config.identifier_extractor {
+            extractor(req)
+        } else {
+            let claims = req.extensions().get::<Claims>();
+            match (self.key, claims) {
+                (RateLimitKey::User, Some(claims)) => format!("user:{}", claims.sub),
+                (RateLimitKey::Tenant, Some(claims)) => format!("tenant:{}", claims.tenant_id),
+                _ => format!("ip:{}", client_ip(req)),
+            }
+        };
+
+        format!("{}:{}", self.name, subject)
+    }
+
+    /// Check and record a request against this policy
+    pub async fn check(&self, identifier: &str) -> Result<RateLimitResult, crate::ApiError> {
+        let window = Duration::from_secs(self.config.window_seconds);
+        self.store.acquire(identifier, self.config.max_requests, window).await
+    }
+}
+
+/// Match on whole path segments so `/auth` does not match `/authors`
+fn path_has_prefix(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    if prefix.is_empty() {
+        return true;
+    }
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+/// Ordered table of route policies
+///
+/// Requests that match no policy are counted against a fallback policy
+/// (the `api_limiter` preset unless overridden).
+#[derive(Clone)]
+pub struct RateLimitPolicies {
+    policies: Vec<RateLimitPolicy>,
+    fallback: RateLimitPolicy,
+}
+
+impl Default for RateLimitPolicies {
+    fn default() -> Self {
+        Self {
+            policies: Vec::new(),
+            fallback: RateLimitPolicy::new("/", presets::api_limiter()).name("default"),
+        }
+    }
+}
+
+impl RateLimitPolicies {
+    /// Create an empty policy table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a policy to the table
+    pub fn policy(mut self, policy: RateLimitPolicy) -> Self {
+        self.policies.push(policy);
+        self
+    }
+
+    /// Set the policy applied to requests that match no other policy
+    pub fn fallback(mut self, policy: RateLimitPolicy) -> Self {
+        self.fallback = policy;
+        self
+    }
+
+    /// Find the policy for a request, preferring the longest matching prefix
+    pub fn find(&self, method: &Method, path: &str) -> &RateLimitPolicy {
+        self.policies
+            .iter()
+            .filter(|policy| policy.matches(method, path))
+            .max_by_key(|policy| (policy.path_prefix.trim_end_matches('/').len(), !policy.methods.is_empty()))
+            .unwrap_or(&self.fallback)
+    }
+
+    /// Number of policies in the table
+    pub fn len(&self) -> usize {
+        self.policies.len()
+    }
+
+    /// Check if the table has no policies
+    pub fn is_empty(&self) -> bool {
+        self.policies.is_empty()
+    }
+
+    /// Create the middleware function applying the table
+    ///
+    /// Every response carries rate limit headers, using the fallback policy for
+    /// unmatched requests. For `User` and `Tenant` keys the authentication
+    /// middleware must run first.
+    pub fn layer(self) -> impl Fn(Request, Next) -> std::pin::Pin<Box<dyn std::future::Future<Output = Response> + Send>> + Clone {
+        let policies = Arc::new(self);
+        move |req: Request, next: Next| {
+            let policies = policies.clone();
+            Box::pin(async move {
+                let policy = policies.find(req.method(), req.uri().path()).clone();
+
+                let identifier = policy.identifier(&req);
+                let result = policy.check(&identifier).await;
+                enforce(result, &policy.config, &identifier, req, next).await
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_prefix_matching() {
+        assert!(path_has_prefix("/auth/login", "/auth"));
+        assert!(path_has_prefix("/auth", "/auth/"));
+        assert!(!path_has_prefix("/authors", "/auth"));
+        assert!(path_has_prefix("/anything", "/"));
+    }
+
+    #[test]
+    fn test_most_specific_policy_wins() {
+        let policies = presets::route_policies();
+        assert_eq!(policies.len(), 3);
+
+        let find = |method: Method, path: &str| policies.find(&method, path).config().max_requests;
+        assert_eq!(find(Method::POST, "/auth/login"), 10);
+        assert_eq!(find(Method::GET, "/search/users"), 50);
+        assert_eq!(find(Method::POST, "/upload/files"), 5);
+        assert_eq!(find(Method::GET, "/upload/files"), 100);
+        assert_eq!(find(Method::GET, "/users"), 100);
+    }
+
+    #[tokio::test]
+    async fn test_unmatched_requests_use_the_fallback_policy() {
+        use tower::ServiceExt;
+
+        let policies = RateLimitPolicies::new().policy(RateLimitPolicy::new("/auth", presets::auth_limiter()));
+        assert_eq!(policies.find(&Method::GET, "/users").config().max_requests, 100);
+
+        let fallback = RateLimitPolicies::new().fallback(RateLimitPolicy::new("/", presets::upload_limiter()));
+        assert_eq!(fallback.find(&Method::GET, "/users").config().max_requests, 5);
+
+        let app = axum::Router::new()
+            .route("/users", axum::routing::get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn(policies.layer()));
+        let response = app
+            .oneshot(Request::builder().uri("/users").body(axum::body::Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["ratelimit-limit"], "100");
+        assert_eq!(response.headers()["ratelimit-remaining"], "99");
+    }
+
+    #[test]
+    fn test_identifier_keys() {
+        let policy = RateLimitPolicy::new("/", presets::api_limiter()).name("api").key(RateLimitKey::Tenant);
+
+        let mut req = Request::builder()
+            .header("x-forwarded-for", "10.0.0.1")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        assert_eq!(policy.identifier(&req), "api:ip:10.0.0.1");
+
+        req.extensions_mut().insert(Claims::new("user-1".to_string(), "tenant-1".to_string()));
+        assert_eq!(policy.identifier(&req), "api:tenant:tenant-1");
+    }
+}
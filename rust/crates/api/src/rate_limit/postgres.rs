@@ -0,0 +1,178 @@
+//! PostgreSQL rate limit store
+//!
+//! Stores one GCRA timestamp per key in the `rate_limits` table and updates
+//! it with a single conditional upsert, so every replica sharing the
+//! database enforces the same quota.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use cloudshuttle_database::{DatabaseConnection, Migration, MigrationBuilder};
+use sqlx::PgPool;
+
+use crate::error::ApiError;
+use super::store::{Gcra, RateLimitStore};
+use super::RateLimitResult;
+
+/// Rate limit store shared through Postgres
+#[derive(Clone)]
+pub struct PostgresRateLimitStore {
+    pool: PgPool,
+}
+
+impl PostgresRateLimitStore {
+    /// Create a store using an existing connection pool
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a store sharing the pool of a database connection
+    pub fn from_connection(connection: &DatabaseConnection) -> Self {
+        Self::new(connection.pool().clone())
+    }
+
+    /// Migration creating the `rate_limits` table
+    pub fn migration() -> Migration {
+        MigrationBuilder::new("api_0001", "create_rate_limits")
+            .description("Create shared rate limit state for cloudshuttle-api")
+            .up_sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS rate_limits (
+                    key VARCHAR(255) PRIMARY KEY,
+                    tat_us BIGINT NOT NULL
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_rate_limits_tat_us ON rate_limits(tat_us);
+                "#,
+            )
+            .down_sql("DROP TABLE IF EXISTS rate_limits")
+            .build()
+    }
+
+    /// Delete keys whose quota has fully replenished, returning the number removed
+    pub async fn delete_expired(&self) -> Result<u64, ApiError> {
+        let result = sqlx::query("DELETE FROM rate_limits WHERE tat_us <= $1")
+            .bind(now_micros())
+            .execute(&self.pool)
+            .await
+            .map_err(database_error)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn current_tat(&self, key: &str) -> Result<Option<i64>, ApiError> {
+        sqlx::query_scalar::<_, i64>("SELECT tat_us FROM rate_limits WHERE key = $1")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(database_error)
+    }
+}
+
+fn now_micros() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_micros() as i64
+}
+
+fn offset(tat_us: i64, now_us: i64) -> Duration {
+    Duration::from_micros(tat_us.saturating_sub(now_us).max(0) as u64)
+}
+
+fn database_error(error: sqlx::Error) -> ApiError {
+    ApiError::service_unavailable(format!("Rate limit store unavailable: {}", error))
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for PostgresRateLimitStore {
+    async fn acquire(&self, key: &str, max_requests: u32, window: Duration) -> Result<RateLimitResult, ApiError> {
+        if max_requests == 0 {
+            return Ok(RateLimitResult::Exceeded { reset_time: None });
+        }
+
+        let gcra = Gcra::new(max_requests, window)?;
+        let now_us = now_micros();
+        let now = Instant::now();
+
+        // The upsert only advances the timestamp when the request fits in the window
+        let new_tat = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO rate_limits AS r (key, tat_us) VALUES ($1, $2 + $3) \
+             ON CONFLICT (key) DO UPDATE SET tat_us = GREATEST(r.tat_us, $2) + $3 \
+             WHERE GREATEST(r.tat_us, $2) + $3 - $4 <= $2 \
+             RETURNING tat_us"
+        )
+        .bind(key)
+        .bind(now_us)
+        .bind(gcra.emission_interval.as_micros() as i64)
+        .bind(window.as_micros() as i64)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(database_error)?;
+
+        match new_tat {
+            Some(tat_us) => {
+                let tat_offset = offset(tat_us, now_us);
+                Ok(RateLimitResult::Allowed {
+                    remaining: gcra.remaining(tat_offset),
+                    reset_time: Some(now + tat_offset),
+                })
+            }
+            None => {
+                let tat_offset = self.current_tat(key).await?
+                    .map(|tat_us| offset(tat_us, now_us))
+                    .unwrap_or_default();
+                Ok(RateLimitResult::Exceeded {
+                    reset_time: Some(now + gcra.retry_after(tat_offset)),
+                })
+            }
+        }
+    }
+
+    async fn remaining(&self, key: &str, max_requests: u32, window: Duration) -> Result<u32, ApiError> {
+        let now_us = now_micros();
+        Ok(match self.current_tat(key).await? {
+            Some(tat_us) => Gcra::new(max_requests, window)?.remaining(offset(tat_us, now_us)),
+            None => max_requests,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cloudshuttle_database::test_util;
+
+    async fn test_store() -> PostgresRateLimitStore {
+        PostgresRateLimitStore::new(test_util::migrated_pool(&PostgresRateLimitStore::migration()).await)
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_migration_up_and_down() {
+        test_util::assert_migration_reversible(&PostgresRateLimitStore::migration(), "rate_limits").await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_postgres_store_enforces_quota() {
+        let store = test_store().await;
+        let key = format!("test:{}", uuid::Uuid::new_v4());
+        let window = Duration::from_secs(60);
+
+        assert_eq!(store.remaining(&key, 2, window).await.unwrap(), 2);
+        assert!(matches!(store.acquire(&key, 2, window).await.unwrap(), RateLimitResult::Allowed { remaining: 1, .. }));
+        assert!(matches!(store.acquire(&key, 2, window).await.unwrap(), RateLimitResult::Allowed { remaining: 0, .. }));
+        assert!(matches!(store.acquire(&key, 2, window).await.unwrap(), RateLimitResult::Exceeded { reset_time: Some(_) }));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_postgres_store_is_shared() {
+        let first = test_store().await;
+        let second = test_store().await;
+        let key = format!("test:{}", uuid::Uuid::new_v4());
+        let window = Duration::from_secs(60);
+
+        assert!(matches!(first.acquire(&key, 1, window).await.unwrap(), RateLimitResult::Allowed { .. }));
+        assert!(matches!(second.acquire(&key, 1, window).await.unwrap(), RateLimitResult::Exceeded { .. }));
+    }
+}
@@ -0,0 +1,289 @@
+//! Rate limit storage backends
+//!
+//! This module defines the `RateLimitStore` trait used by the rate limiting
+//! middleware, along with the default in-memory GCRA implementation.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::ApiError;
+use super::{RateLimitConfig, RateLimitResult};
+
+/// Storage backend that records requests and enforces quotas
+///
+/// Implementations must check and record a request atomically so that
+/// concurrent requests sharing a quota cannot exceed it.
+#[async_trait::async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Check and record a request against a quota of `max_requests` per `window`
+    async fn acquire(&self, key: &str, max_requests: u32, window: Duration) -> Result<RateLimitResult, ApiError>;
+
+    /// Get remaining requests for a key without recording a request
+    async fn remaining(&self, key: &str, max_requests: u32, window: Duration) -> Result<u32, ApiError>;
+}
+
+/// Generic cell rate algorithm parameters for a quota
+///
+/// GCRA tracks a single "theoretical arrival time" per key instead of a log
+/// of requests, allowing bursts of up to `max_requests` within a window.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Gcra {
+    pub(crate) emission_interval: Duration,
+    pub(crate) window: Duration,
+    max_requests: u32,
+}
+
+impl Gcra {
+    /// Shortest emission interval a quota may have (the Postgres store's resolution)
+    const MIN_EMISSION_INTERVAL: Duration = Duration::from_micros(1);
+
+    /// Derive the parameters for a quota, rejecting quotas finer than the store resolution
+    pub(crate) fn new(max_requests: u32, window: Duration) -> Result<Self, InvalidQuota> {
+        let gcra = Self::clamped(max_requests, window);
+        if gcra.window != window {
+            return Err(InvalidQuota { max_requests, window });
+        }
+        Ok(gcra)
+    }
+
+    /// Derive the parameters for a quota, widening the window to the store resolution if needed
+    pub(crate) fn clamped(max_requests: u32, window: Duration) -> Self {
+        let window = window.max(Self::MIN_EMISSION_INTERVAL * max_requests.max(1));
+        Self {
+            emission_interval: window / max_requests.max(1),
+            window,
+            max_requests,
+        }
+    }
+
+    /// Requests still available when the theoretical arrival time is `tat_offset` ahead of now
+    pub(crate) fn remaining(&self, tat_offset: Duration) -> u32 {
+        let available = self.window.saturating_sub(tat_offset);
+        let remaining = available.as_nanos() / self.emission_interval.as_nanos();
+        remaining.min(u128::from(self.max_requests)) as u32
+    }
+
+    /// Time until a request would be allowed again
+    pub(crate) fn retry_after(&self, tat_offset: Duration) -> Duration {
+        (tat_offset + self.emission_interval).saturating_sub(self.window)
+    }
+}
+
+/// Quota finer than the store resolution
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct InvalidQuota {
+    max_requests: u32,
+    window: Duration,
+}
+
+impl From<InvalidQuota> for ApiError {
+    fn from(quota: InvalidQuota) -> Self {
+        ApiError::internal_server_error(format!(
+            "Invalid rate limit quota: {} requests per {:?}",
+            quota.max_requests, quota.window
+        ))
+    }
+}
+
+/// In-memory GCRA rate limiter
+///
+/// Keeps one timestamp per active identifier; idle identifiers are dropped
+/// once their quota has fully replenished. Quotas finer than a microsecond
+/// per request are widened to one request per microsecond.
+#[derive(Debug)]
+pub struct InMemoryRateLimiter {
+    states: Mutex<HashMap<String, Instant>>,
+    last_cleanup: Mutex<Instant>,
+    config: RateLimitConfig,
+    quota: Gcra,
+}
+
+impl InMemoryRateLimiter {
+    /// Create a new rate limiter
+    pub fn new(config: RateLimitConfig) -> Self {
+        let window = Duration::from_secs(config.window_seconds);
+        let quota = Gcra::clamped(config.max_requests, window);
+        if quota.window != window {
+            tracing::warn!(
+                max_requests = config.max_requests,
+                window_seconds = config.window_seconds,
+                "Rate limit window is too short for the quota, using {:?}",
+                quota.window
+            );
+        }
+
+        Self {
+            states: Mutex::new(HashMap::new()),
+            last_cleanup: Mutex::new(Instant::now()),
+            config,
+            quota,
+        }
+    }
+
+    /// Check if a request is allowed using the configured quota
+    pub fn check_limit(&self, identifier: &str) -> RateLimitResult {
+        self.check(identifier, self.quota)
+    }
+
+    /// Check if a request is allowed against an explicit quota
+    pub fn check_quota(&self, identifier: &str, max_requests: u32, window: Duration) -> RateLimitResult {
+        self.check(identifier, Gcra::clamped(max_requests, window))
+    }
+
+    fn check(&self, identifier: &str, gcra: Gcra) -> RateLimitResult {
+        let now = Instant::now();
+        self.maybe_cleanup(now);
+
+        if gcra.max_requests == 0 {
+            return RateLimitResult::Exceeded { reset_time: None };
+        }
+
+        let mut states = self.states.lock().unwrap();
+        let tat = states.get(identifier).copied().unwrap_or(now).max(now);
+        let new_tat = tat + gcra.emission_interval;
+
+        if new_tat.duration_since(now) > gcra.window {
+            let retry_after = gcra.retry_after(tat.duration_since(now));
+            return RateLimitResult::Exceeded { reset_time: Some(now + retry_after) };
+        }
+
+        states.insert(identifier.to_string(), new_tat);
+        RateLimitResult::Allowed {
+            remaining: gcra.remaining(new_tat.duration_since(now)),
+            reset_time: Some(new_tat),
+        }
+    }
+
+    /// Get remaining requests for an identifier
+    pub fn remaining_requests(&self, identifier: &str) -> u32 {
+        self.remaining_quota(identifier, self.quota)
+    }
+
+    fn remaining_quota(&self, identifier: &str, gcra: Gcra) -> u32 {
+        let now = Instant::now();
+        let states = self.states.lock().unwrap();
+        match states.get(identifier) {
+            Some(tat) => gcra.remaining(tat.saturating_duration_since(now)),
+            None => gcra.max_requests,
+        }
+    }
+
+    /// Get the current configuration
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Drop identifiers whose quota has fully replenished
+    pub fn cleanup(&self) {
+        let now = Instant::now();
+        self.states.lock().unwrap().retain(|_, tat| *tat > now);
+    }
+
+    /// Number of identifiers currently tracked
+    pub fn tracked_identifiers(&self) -> usize {
+        self.states.lock().unwrap().len()
+    }
+
+    /// Clear all rate limit states (useful for testing)
+    pub fn clear(&self) {
+        let mut states = self.states.lock().unwrap();
+        states.clear();
+    }
+
+    /// Expire idle identifiers at most once per configured window
+    fn maybe_cleanup(&self, now: Instant) {
+        let mut last_cleanup = self.last_cleanup.lock().unwrap();
+        if now.duration_since(*last_cleanup) >= Duration::from_secs(self.config.window_seconds) {
+            *last_cleanup = now;
+            drop(last_cleanup);
+            self.cleanup();
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for InMemoryRateLimiter {
+    async fn acquire(&self, key: &str, max_requests: u32, window: Duration) -> Result<RateLimitResult, ApiError> {
+        Ok(self.check(key, Gcra::new(max_requests, window)?))
+    }
+
+    async fn remaining(&self, key: &str, max_requests: u32, window: Duration) -> Result<u32, ApiError> {
+        Ok(self.remaining_quota(key, Gcra::new(max_requests, window)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcra_allows_burst_then_limits() {
+        let limiter = InMemoryRateLimiter::new(RateLimitConfig::default());
+        let window = Duration::from_secs(60);
+
+        // Should allow initial requests
+        assert!(matches!(limiter.check_quota("key", 3, window), RateLimitResult::Allowed { .. }));
+        assert!(matches!(limiter.check_quota("key", 3, window), RateLimitResult::Allowed { .. }));
+        assert!(matches!(limiter.check_quota("key", 3, window), RateLimitResult::Allowed { .. }));
+
+        // Should deny fourth request until one emission interval has passed
+        match limiter.check_quota("key", 3, window) {
+            RateLimitResult::Exceeded { reset_time: Some(reset_time) } => {
+                let retry_after = reset_time.duration_since(Instant::now());
+                assert!(retry_after <= Duration::from_secs(20));
+                assert!(retry_after > Duration::from_secs(19));
+            }
+            other => panic!("Expected exceeded, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_idle_identifiers_are_dropped() {
+        let limiter = InMemoryRateLimiter::new(RateLimitConfig::default());
+
+        limiter.check_quota("short", 10, Duration::from_millis(10));
+        limiter.check_quota("long", 10, Duration::from_secs(60));
+        assert_eq!(limiter.tracked_identifiers(), 2);
+
+        std::thread::sleep(Duration::from_millis(5));
+        limiter.cleanup();
+        assert_eq!(limiter.tracked_identifiers(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_quotas_finer_than_a_microsecond_are_rejected() {
+        let limiter = InMemoryRateLimiter::new(RateLimitConfig::default());
+
+        assert!(Gcra::new(10, Duration::ZERO).is_err());
+        assert!(Gcra::new(u32::MAX, Duration::from_secs(1)).is_err());
+        assert!(limiter.acquire("key", 10, Duration::ZERO).await.is_err());
+        assert_eq!(limiter.tracked_identifiers(), 0);
+
+        let gcra = Gcra::new(1_000_000, Duration::from_secs(1)).unwrap();
+        assert_eq!(gcra.remaining(Duration::ZERO), 1_000_000);
+    }
+
+    #[test]
+    fn test_configured_quota_is_widened_when_built() {
+        let limiter = InMemoryRateLimiter::new(RateLimitConfig {
+            max_requests: 2,
+            window_seconds: 0,
+            ..Default::default()
+        });
+
+        assert_eq!(limiter.remaining_requests("key"), 2);
+        assert!(matches!(limiter.check_limit("key"), RateLimitResult::Allowed { remaining: 1, .. }));
+        assert!(matches!(limiter.check_quota("other", 10, Duration::ZERO), RateLimitResult::Allowed { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_store_trait() {
+        let limiter = InMemoryRateLimiter::new(RateLimitConfig::default());
+        let window = Duration::from_secs(60);
+
+        assert_eq!(limiter.remaining("key", 2, window).await.unwrap(), 2);
+        assert!(matches!(limiter.acquire("key", 2, window).await.unwrap(), RateLimitResult::Allowed { remaining: 1, .. }));
+        assert_eq!(limiter.remaining("key", 2, window).await.unwrap(), 1);
+    }
+}
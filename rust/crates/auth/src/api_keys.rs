@@ -0,0 +1,19 @@
+//! API key authentication
+//!
+//! Long-lived keys for service-to-service calls and customer integrations:
+//! - `store`: API key records and storage
+//! - `manager`: Issuance, verification, last-used tracking and revocation
+//! - `postgres`: Postgres-backed `ApiKeyStore`
+//!
+//! Keys look like `csk_<lookup>_<secret>`. Only the public `csk_<lookup>`
+//! prefix and a SHA-256 hash of the full key are stored.
+
+pub mod store;
+pub mod manager;
+#[cfg(feature = "database")]
+pub mod postgres;
+
+pub use store::{ApiKey, ApiKeyStore, InMemoryApiKeyStore};
+pub use manager::{ApiKeyManager, ApiKeyConfig, ApiKeyRequest, IssuedApiKey};
+#[cfg(feature = "database")]
+pub use postgres::PostgresApiKeyStore;
@@ -0,0 +1,285 @@
+//! API key issuance and verification.
+
+use std::sync::Arc;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use cloudshuttle_crypto::random::{generate_random_bytes, generate_secure_token};
+use crate::security::CryptoUtils;
+use crate::types::{AuthResult, AuthError};
+use super::store::{ApiKey, ApiKeyStore, InMemoryApiKeyStore};
+#[cfg(feature = "observability")]
+use cloudshuttle_observability::audit::{audit_auth, AuditResult};
+
+/// API key format and bookkeeping settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyConfig {
+    /// Alphanumeric prefix identifying keys issued by this service
+    pub prefix: String,
+
+    /// Random bytes in the secret part of the key
+    pub secret_bytes: usize,
+
+    /// Minimum seconds between last-used writes for the same key
+    pub usage_update_interval_seconds: i64,
+}
+
+impl Default for ApiKeyConfig {
+    fn default() -> Self {
+        Self {
+            prefix: "csk".to_string(),
+            secret_bytes: 32,
+            usage_update_interval_seconds: 60,
+        }
+    }
+}
+
+/// Random bytes in the lookup part of the key
+const LOOKUP_BYTES: usize = 6;
+
+/// Parameters for issuing a key
+#[derive(Debug, Clone)]
+pub struct ApiKeyRequest {
+    pub name: String,
+    pub user_id: String,
+    pub tenant_id: String,
+    pub roles: Vec<String>,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKeyRequest {
+    /// Request a non-expiring key with no roles or scopes
+    pub fn new(name: impl Into<String>, user_id: impl Into<String>, tenant_id: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            user_id: user_id.into(),
+            tenant_id: tenant_id.into(),
+            roles: Vec::new(),
+            scopes: Vec::new(),
+            expires_at: None,
+        }
+    }
+
+    /// Grant roles
+    pub fn with_roles(mut self, roles: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.roles = roles.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Grant scopes
+    pub fn with_scopes(mut self, scopes: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Expire the key after a duration
+    pub fn expires_in(mut self, duration: Duration) -> Self {
+        self.expires_at = Some(Utc::now() + duration);
+        self
+    }
+}
+
+/// Newly issued key; `key` is shown to the caller once and never stored
+#[derive(Debug, Clone)]
+pub struct IssuedApiKey {
+    pub key: String,
+    pub record: ApiKey,
+}
+
+/// API key manager
+pub struct ApiKeyManager {
+    config: ApiKeyConfig,
+    store: Arc<dyn ApiKeyStore>,
+}
+
+impl ApiKeyManager {
+    /// Create a new manager backed by an in-memory store
+    pub fn new(config: ApiKeyConfig) -> AuthResult<Self> {
+        Self::with_store(config, Arc::new(InMemoryApiKeyStore::new()))
+    }
+
+    /// Create a new manager backed by the given store
+    pub fn with_store(config: ApiKeyConfig, store: Arc<dyn ApiKeyStore>) -> AuthResult<Self> {
+        if config.prefix.is_empty() || !config.prefix.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(AuthError::InternalError("API key prefix must be alphanumeric".to_string()));
+        }
+        if config.secret_bytes < 16 {
+            return Err(AuthError::InternalError("API key secrets need at least 16 random bytes".to_string()));
+        }
+        Ok(Self { config, store })
+    }
+
+    /// Get the key settings
+    pub fn config(&self) -> &ApiKeyConfig {
+        &self.config
+    }
+
+    /// Issue a key, returning the plaintext key alongside its stored record
+    pub async fn issue(&self, request: ApiKeyRequest) -> AuthResult<IssuedApiKey> {
+        let lookup = generate_random_bytes(LOOKUP_BYTES).map_err(random_error)?;
+        let secret = generate_secure_token(self.config.secret_bytes).map_err(random_error)?;
+        let prefix = format!("{}_{}", self.config.prefix, hex::encode(lookup));
+        let key = format!("{}_{}", prefix, secret);
+
+        let record = ApiKey {
+            id: uuid::Uuid::new_v4().to_string(),
+            prefix,
+            key_hash: CryptoUtils::sha256_hash(key.as_bytes()),
+            name: request.name,
+            user_id: request.user_id,
+            tenant_id: request.tenant_id,
+            roles: request.roles,
+            scopes: request.scopes,
+            created_at: Utc::now(),
+            expires_at: request.expires_at,
+            last_used_at: None,
+            revoked_at: None,
+        };
+        self.store.insert_key(record.clone()).await?;
+
+        #[cfg(feature = "observability")]
+        audit_auth("api_key_issued", Some(&record.user_id), AuditResult::Success);
+
+        Ok(IssuedApiKey { key, record })
+    }
+
+    /// Verify a presented key and record its use
+    pub async fn authenticate(&self, key: &str) -> AuthResult<ApiKey> {
+        let result = self.verify(key).await;
+
+        #[cfg(feature = "observability")]
+        if result.is_err() {
+            audit_auth("api_key_rejected", None, AuditResult::Failure);
+        }
+
+        let mut record = result?;
+        let now = Utc::now();
+        let stale = record.last_used_at.is_none_or(|last_used_at| {
+            now - last_used_at >= Duration::seconds(self.config.usage_update_interval_seconds)
+        });
+        if stale {
+            self.store.record_usage(&record.id, now).await?;
+            record.last_used_at = Some(now);
+        }
+        Ok(record)
+    }
+
+    async fn verify(&self, key: &str) -> AuthResult<ApiKey> {
+        let invalid = || AuthError::InvalidToken("Invalid API key".to_string());
+        let prefix = self.parse_prefix(key).ok_or_else(invalid)?;
+        let record = self.store.find_by_prefix(prefix).await?.ok_or_else(invalid)?;
+
+        if !CryptoUtils::constant_time_eq(CryptoUtils::sha256_hash(key.as_bytes()).as_bytes(), record.key_hash.as_bytes()) {
+            return Err(invalid());
+        }
+        if record.is_revoked() {
+            return Err(AuthError::TokenRevoked);
+        }
+        if record.is_expired() {
+            return Err(AuthError::TokenExpired);
+        }
+        Ok(record)
+    }
+
+    /// Split the public prefix off a presented key
+    fn parse_prefix<'a>(&self, key: &'a str) -> Option<&'a str> {
+        let rest = key.strip_prefix(self.config.prefix.as_str())?.strip_prefix('_')?;
+        let (lookup, secret) = rest.split_once('_')?;
+        if lookup.len() != LOOKUP_BYTES * 2 || secret.is_empty() {
+            return None;
+        }
+        Some(&key[..self.config.prefix.len() + 1 + lookup.len()])
+    }
+
+    /// Look up a key by id
+    pub async fn get_key(&self, id: &str) -> AuthResult<Option<ApiKey>> {
+        self.store.get_key(id).await
+    }
+
+    /// List a user's keys
+    pub async fn list_user_keys(&self, user_id: &str) -> AuthResult<Vec<ApiKey>> {
+        self.store.list_user_keys(user_id).await
+    }
+
+    /// Revoke a key, returning false if it was unknown or already revoked
+    pub async fn revoke(&self, id: &str) -> AuthResult<bool> {
+        let revoked = self.store.revoke_key(id, Utc::now()).await?;
+
+        #[cfg(feature = "observability")]
+        if revoked {
+            audit_auth("api_key_revoked", None, AuditResult::Success);
+        }
+
+        Ok(revoked)
+    }
+}
+
+fn random_error(error: cloudshuttle_crypto::random::RandomError) -> AuthError {
+    AuthError::InternalError(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> ApiKeyManager {
+        ApiKeyManager::new(ApiKeyConfig::default()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_issue_and_authenticate() {
+        let manager = manager();
+        let issued = manager
+            .issue(ApiKeyRequest::new("ci", "svc-deploy", "tenant1").with_scopes(["deployments:write"]))
+            .await
+            .unwrap();
+
+        assert!(issued.key.starts_with(&format!("{}_", issued.record.prefix)));
+        assert!(issued.record.prefix.starts_with("csk_"));
+        assert_ne!(issued.record.key_hash, issued.key);
+
+        let record = manager.authenticate(&issued.key).await.unwrap();
+        assert_eq!(record.user_id, "svc-deploy");
+        assert!(record.has_scope("deployments:write"));
+        assert!(manager.get_key(&record.id).await.unwrap().unwrap().last_used_at.is_some());
+
+        let context = record.to_auth_context();
+        assert_eq!(context.user_id, "svc-deploy");
+        assert_eq!(context.permissions, vec!["deployments:write".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_tampered_and_malformed_keys() {
+        let manager = manager();
+        let issued = manager.issue(ApiKeyRequest::new("ci", "svc", "tenant1")).await.unwrap();
+
+        let tampered = format!("{}x", issued.key);
+        assert!(matches!(manager.authenticate(&tampered).await, Err(AuthError::InvalidToken(_))));
+        assert!(manager.authenticate("csk_short_secret").await.is_err());
+        assert!(manager.authenticate("not-a-key").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_revoked_and_expired_keys() {
+        let manager = manager();
+        let issued = manager.issue(ApiKeyRequest::new("ci", "svc", "tenant1")).await.unwrap();
+        assert!(manager.revoke(&issued.record.id).await.unwrap());
+        assert!(!manager.revoke(&issued.record.id).await.unwrap());
+        assert!(matches!(manager.authenticate(&issued.key).await, Err(AuthError::TokenRevoked)));
+
+        let expired = manager
+            .issue(ApiKeyRequest::new("old", "svc", "tenant1").expires_in(Duration::seconds(-1)))
+            .await
+            .unwrap();
+        assert!(matches!(manager.authenticate(&expired.key).await, Err(AuthError::TokenExpired)));
+        assert_eq!(manager.list_user_keys("svc").await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let config = ApiKeyConfig { prefix: "bad_prefix".to_string(), ..Default::default() };
+        assert!(ApiKeyManager::new(config).is_err());
+        let config = ApiKeyConfig { secret_bytes: 8, ..Default::default() };
+        assert!(ApiKeyManager::new(config).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+//! PostgreSQL API key store.
+//!
+//! This module provides an `ApiKeyStore` backed by a shared Postgres table.
+//! Keys are looked up by their unique public prefix; only hashes are stored.
+
+use chrono::{DateTime, Utc};
+use cloudshuttle_database::{DatabaseConnection, Migration, MigrationBuilder};
+use sqlx::PgPool;
+use crate::types::{AuthResult, AuthError};
+use super::store::{ApiKey, ApiKeyStore};
+
+/// API key store persisted in the `api_keys` table
+#[derive(Clone)]
+pub struct PostgresApiKeyStore {
+    pool: PgPool,
+}
+
+impl PostgresApiKeyStore {
+    /// Create a store using an existing connection pool
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a store sharing the pool of a database connection
+    pub fn from_connection(connection: &DatabaseConnection) -> Self {
+        Self::new(connection.pool().clone())
+    }
+
+    /// Migration creating the `api_keys` table and its indexes
+    pub fn migration() -> Migration {
+        MigrationBuilder::new("auth_0004", "create_api_keys")
+            .description("Create API key storage for cloudshuttle-auth")
+            .up_sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS api_keys (
+                    id VARCHAR(255) PRIMARY KEY,
+                    prefix VARCHAR(64) NOT NULL UNIQUE,
+                    key_hash VARCHAR(64) NOT NULL,
+                    name VARCHAR(255) NOT NULL,
+                    user_id VARCHAR(255) NOT NULL,
+                    tenant_id VARCHAR(255) NOT NULL,
+                    roles TEXT[] NOT NULL DEFAULT '{}',
+                    scopes TEXT[] NOT NULL DEFAULT '{}',
+                    created_at TIMESTAMPTZ NOT NULL,
+                    expires_at TIMESTAMPTZ,
+                    last_used_at TIMESTAMPTZ,
+                    revoked_at TIMESTAMPTZ
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_api_keys_user_id ON api_keys(user_id);
+                "#,
+            )
+            .down_sql("DROP TABLE IF EXISTS api_keys")
+            .build()
+    }
+}
+
+/// Row representation of an API key
+#[derive(sqlx::FromRow)]
+struct ApiKeyRow {
+    id: String,
+    prefix: String,
+    key_hash: String,
+    name: String,
+    user_id: String,
+    tenant_id: String,
+    roles: Vec<String>,
+    scopes: Vec<String>,
+    created_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+    last_used_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApiKeyRow> for ApiKey {
+    fn from(row: ApiKeyRow) -> Self {
+        Self {
+            id: row.id,
+            prefix: row.prefix,
+            key_hash: row.key_hash,
+            name: row.name,
+            user_id: row.user_id,
+            tenant_id: row.tenant_id,
+            roles: row.roles,
+            scopes: row.scopes,
+            created_at: row.created_at,
+            expires_at: row.expires_at,
+            last_used_at: row.last_used_at,
+            revoked_at: row.revoked_at,
+        }
+    }
+}
+
+fn database_error(error: sqlx::Error) -> AuthError {
+    AuthError::DatabaseError(error.to_string())
+}
+
+#[async_trait::async_trait]
+impl ApiKeyStore for PostgresApiKeyStore {
+    async fn insert_key(&self, key: ApiKey) -> AuthResult<()> {
+        sqlx::query(
+            "INSERT INTO api_keys (id, prefix, key_hash, name, user_id, tenant_id, roles, scopes, created_at, expires_at, last_used_at, revoked_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"
+        )
+        .bind(&key.id)
+        .bind(&key.prefix)
+        .bind(&key.key_hash)
+        .bind(&key.name)
+        .bind(&key.user_id)
+        .bind(&key.tenant_id)
+        .bind(&key.roles)
+        .bind(&key.scopes)
+        .bind(key.created_at)
+        .bind(key.expires_at)
+        .bind(key.last_used_at)
+        .bind(key.revoked_at)
+        .execute(&self.pool)
+        .await
+        .map_err(database_error)?;
+
+        Ok(())
+    }
+
+    async fn get_key(&self, id: &str) -> AuthResult<Option<ApiKey>> {
+        let row = sqlx::query_as::<_, ApiKeyRow>("SELECT * FROM api_keys WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(database_error)?;
+
+        Ok(row.map(Into::into))
+    }
+
+    async fn find_by_prefix(&self, prefix: &str) -> AuthResult<Option<ApiKey>> {
+        let row = sqlx::query_as::<_, ApiKeyRow>("SELECT * FROM api_keys WHERE prefix = $1")
+            .bind(prefix)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(database_error)?;
+
+        Ok(row.map(Into::into))
+    }
+
+    async fn list_user_keys(&self, user_id: &str) -> AuthResult<Vec<ApiKey>> {
+        let rows = sqlx::query_as::<_, ApiKeyRow>("SELECT * FROM api_keys WHERE user_id = $1 ORDER BY created_at")
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(database_error)?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn record_usage(&self, id: &str, used_at: DateTime<Utc>) -> AuthResult<()> {
+        sqlx::query("UPDATE api_keys SET last_used_at = $2 WHERE id = $1")
+            .bind(id)
+            .bind(used_at)
+            .execute(&self.pool)
+            .await
+            .map_err(database_error)?;
+
+        Ok(())
+    }
+
+    async fn revoke_key(&self, id: &str, revoked_at: DateTime<Utc>) -> AuthResult<bool> {
+        let result = sqlx::query("UPDATE api_keys SET revoked_at = $2 WHERE id = $1 AND revoked_at IS NULL")
+            .bind(id)
+            .bind(revoked_at)
+            .execute(&self.pool)
+            .await
+            .map_err(database_error)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cloudshuttle_database::test_util;
+    use std::sync::Arc;
+    use crate::api_keys::{ApiKeyConfig, ApiKeyManager, ApiKeyRequest};
+
+    async fn test_store() -> PostgresApiKeyStore {
+        PostgresApiKeyStore::new(test_util::migrated_pool(&PostgresApiKeyStore::migration()).await)
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_migration_up_and_down() {
+        test_util::assert_migration_reversible(&PostgresApiKeyStore::migration(), "api_keys").await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_api_key_manager_with_postgres() {
+        let store = test_store().await;
+        let user_id = format!("svc_{}", uuid::Uuid::new_v4());
+        let manager = ApiKeyManager::with_store(ApiKeyConfig::default(), Arc::new(store)).unwrap();
+
+        let issued = manager
+            .issue(ApiKeyRequest::new("ci", user_id.clone(), "tenant1").with_roles(["deployer"]).with_scopes(["deployments:write"]))
+            .await
+            .unwrap();
+        let record = manager.authenticate(&issued.key).await.unwrap();
+        assert_eq!(record.roles, vec!["deployer".to_string()]);
+        assert!(manager.get_key(&record.id).await.unwrap().unwrap().last_used_at.is_some());
+
+        assert!(manager.revoke(&record.id).await.unwrap());
+        assert!(matches!(manager.authenticate(&issued.key).await, Err(AuthError::TokenRevoked)));
+        assert_eq!(manager.list_user_keys(&user_id).await.unwrap().len(), 1);
+    }
+}
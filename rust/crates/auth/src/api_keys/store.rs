@@ -0,0 +1,164 @@
+//! API key records and storage.
+
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use crate::types::{AuthContext, AuthResult};
+use crate::Claims;
+
+/// Stored API key; the plaintext key is only returned at issuance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// Key identifier
+    pub id: String,
+
+    /// Public lookup prefix, safe to display (e.g. `csk_3f9a1c2b7d4e`)
+    pub prefix: String,
+
+    /// SHA-256 hash of the full key
+    #[serde(skip_serializing, default)]
+    pub key_hash: String,
+
+    /// Human-readable key name
+    pub name: String,
+
+    /// Owning user or service account
+    pub user_id: String,
+
+    /// Owning tenant
+    pub tenant_id: String,
+
+    /// Roles granted to requests made with the key
+    pub roles: Vec<String>,
+
+    /// Scopes granted to requests made with the key, exposed as permissions
+    pub scopes: Vec<String>,
+
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    /// Check if the key has expired
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= Utc::now())
+    }
+
+    /// Check if the key has been revoked
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    /// Check if the key can authenticate requests
+    pub fn is_active(&self) -> bool {
+        !self.is_expired() && !self.is_revoked()
+    }
+
+    /// Check if the key grants a scope
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|granted| granted == scope)
+    }
+
+    /// Claims equivalent to an access token for the key's owner
+    ///
+    /// The key id is carried in the `api_key_id` custom claim.
+    pub fn to_claims(&self) -> Claims {
+        let mut claims = Claims::new(self.user_id.clone(), self.tenant_id.clone());
+        claims.roles = self.roles.clone();
+        claims.permissions = self.scopes.clone();
+        claims.token_type = Some("api_key".to_string());
+        claims.iat = self.created_at.timestamp() as u64;
+        claims.exp = self.expires_at.map_or(u64::MAX, |expires_at| expires_at.timestamp() as u64);
+        claims.jti = None;
+        claims.custom.insert("api_key_id".to_string(), serde_json::Value::String(self.id.clone()));
+        claims
+    }
+
+    /// Authentication context for requests made with the key
+    pub fn to_auth_context(&self) -> AuthContext {
+        AuthContext::from_claims(&self.to_claims())
+    }
+}
+
+/// API key storage
+#[async_trait::async_trait]
+pub trait ApiKeyStore: Send + Sync {
+    /// Store a newly issued key
+    async fn insert_key(&self, key: ApiKey) -> AuthResult<()>;
+
+    /// Look up a key by id
+    async fn get_key(&self, id: &str) -> AuthResult<Option<ApiKey>>;
+
+    /// Look up a key by its public prefix
+    async fn find_by_prefix(&self, prefix: &str) -> AuthResult<Option<ApiKey>>;
+
+    /// List a user's keys, including expired and revoked ones
+    async fn list_user_keys(&self, user_id: &str) -> AuthResult<Vec<ApiKey>>;
+
+    /// Record that a key was used
+    async fn record_usage(&self, id: &str, used_at: DateTime<Utc>) -> AuthResult<()>;
+
+    /// Mark a key revoked, returning false if it was unknown or already revoked
+    async fn revoke_key(&self, id: &str, revoked_at: DateTime<Utc>) -> AuthResult<bool>;
+}
+
+/// In-memory API key store
+#[derive(Default)]
+pub struct InMemoryApiKeyStore {
+    keys: RwLock<HashMap<String, ApiKey>>,
+}
+
+impl InMemoryApiKeyStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiKeyStore for InMemoryApiKeyStore {
+    async fn insert_key(&self, key: ApiKey) -> AuthResult<()> {
+        self.keys.write().await.insert(key.id.clone(), key);
+        Ok(())
+    }
+
+    async fn get_key(&self, id: &str) -> AuthResult<Option<ApiKey>> {
+        Ok(self.keys.read().await.get(id).cloned())
+    }
+
+    async fn find_by_prefix(&self, prefix: &str) -> AuthResult<Option<ApiKey>> {
+        Ok(self.keys.read().await.values().find(|key| key.prefix == prefix).cloned())
+    }
+
+    async fn list_user_keys(&self, user_id: &str) -> AuthResult<Vec<ApiKey>> {
+        let mut keys: Vec<ApiKey> = self.keys
+            .read()
+            .await
+            .values()
+            .filter(|key| key.user_id == user_id)
+            .cloned()
+            .collect();
+        keys.sort_by_key(|key| key.created_at);
+        Ok(keys)
+    }
+
+    async fn record_usage(&self, id: &str, used_at: DateTime<Utc>) -> AuthResult<()> {
+        if let Some(key) = self.keys.write().await.get_mut(id) {
+            key.last_used_at = Some(used_at);
+        }
+        Ok(())
+    }
+
+    async fn revoke_key(&self, id: &str, revoked_at: DateTime<Utc>) -> AuthResult<bool> {
+        match self.keys.write().await.get_mut(id) {
+            Some(key) if key.revoked_at.is_none() => {
+                key.revoked_at = Some(revoked_at);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! Attribute-based authorization
+//!
+//! Policies are declared in TOML or JSON and evaluated against the subject,
+//! the action and the resource of a request:
+//! - `permissions`: Wildcard and hierarchical permission matching
+//! - `attributes`: Subject, resource and access request attributes
+//! - `policy`: Policy documents and their conditions
+//! - `engine`: Deny-overrides evaluation with a reason for each decision
+//!
+//! Permission matching is shared with the role registry and guards and is
+//! always available; the policy engine requires the `authorization` feature.
+
+pub mod permissions;
+#[cfg(feature = "authorization")]
+pub mod attributes;
+#[cfg(feature = "authorization")]
+pub mod policy;
+#[cfg(feature = "authorization")]
+pub mod engine;
+
+pub use permissions::{permission_matches, grants_permission};
+#[cfg(feature = "authorization")]
+pub use attributes::{Subject, Resource, AccessRequest};
+#[cfg(feature = "authorization")]
+pub use policy::{Effect, Policy, PolicySet, SubjectMatcher, Condition, ConditionOperator};
+#[cfg(feature = "authorization")]
+pub use engine::{PolicyEngine, AuthorizationDecision};
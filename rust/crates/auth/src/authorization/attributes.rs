@@ -0,0 +1,186 @@
+//! Subject, resource and access request attributes
+//!
+//! Conditions address attributes by path: `subject.user_id`,
+//! `subject.roles`, `resource.type`, `resource.owner_id`, `action`, and so
+//! on. Paths that are not built-in fields fall back to the free-form
+//! attribute maps.
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::types::AuthContext;
+use crate::Claims;
+
+/// The user or service requesting access
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Subject {
+    pub user_id: String,
+    pub tenant_id: String,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+    pub attributes: HashMap<String, Value>,
+}
+
+impl Subject {
+    /// Create a subject with no roles or permissions
+    pub fn new(user_id: impl Into<String>, tenant_id: impl Into<String>) -> Self {
+        Self {
+            user_id: user_id.into(),
+            tenant_id: tenant_id.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Build a subject from token claims, exposing custom claims as attributes
+    pub fn from_claims(claims: &Claims) -> Self {
+        Self {
+            user_id: claims.sub.clone(),
+            tenant_id: claims.tenant_id.clone(),
+            roles: claims.roles.clone(),
+            permissions: claims.permissions.clone(),
+            attributes: claims.custom.clone(),
+        }
+    }
+
+    /// Build a subject from a request's authentication context
+    pub fn from_context(context: &AuthContext) -> Self {
+        Self {
+            user_id: context.user_id.clone(),
+            tenant_id: context.tenant_id.clone(),
+            roles: context.roles.clone(),
+            permissions: context.permissions.clone(),
+            attributes: HashMap::new(),
+        }
+    }
+
+    /// Add a free-form attribute
+    pub fn with_attribute(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.attributes.insert(name.into(), value.into());
+        self
+    }
+
+    fn attribute(&self, name: &str) -> Option<Value> {
+        match name {
+            "user_id" => Some(Value::from(self.user_id.as_str())),
+            "tenant_id" => Some(Value::from(self.tenant_id.as_str())),
+            "roles" => Some(Value::from(self.roles.clone())),
+            "permissions" => Some(Value::from(self.permissions.clone())),
+            _ => self.attributes.get(name).cloned(),
+        }
+    }
+}
+
+/// The resource being accessed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Resource {
+    pub resource_type: String,
+    pub id: Option<String>,
+    pub attributes: HashMap<String, Value>,
+}
+
+impl Resource {
+    /// Create a resource of the given type
+    pub fn new(resource_type: impl Into<String>) -> Self {
+        Self {
+            resource_type: resource_type.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the resource identifier
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set the owning user, available as `resource.owner_id`
+    pub fn with_owner(self, owner_id: impl Into<String>) -> Self {
+        self.with_attribute("owner_id", owner_id.into())
+    }
+
+    /// Set the owning tenant, available as `resource.tenant_id`
+    pub fn with_tenant(self, tenant_id: impl Into<String>) -> Self {
+        self.with_attribute("tenant_id", tenant_id.into())
+    }
+
+    /// Add a free-form attribute
+    pub fn with_attribute(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.attributes.insert(name.into(), value.into());
+        self
+    }
+
+    fn attribute(&self, name: &str) -> Option<Value> {
+        match name {
+            "type" => Some(Value::from(self.resource_type.as_str())),
+            "id" => self.id.as_deref().map(Value::from),
+            _ => self.attributes.get(name).cloned(),
+        }
+    }
+
+    /// Identifier used in audit events, e.g. `document/42`
+    pub fn audit_id(&self) -> String {
+        match &self.id {
+            Some(id) => format!("{}/{}", self.resource_type, id),
+            None => self.resource_type.clone(),
+        }
+    }
+}
+
+/// A request to perform an action on a resource
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessRequest {
+    pub subject: Subject,
+    pub action: String,
+    pub resource: Resource,
+}
+
+impl AccessRequest {
+    /// Create an access request
+    pub fn new(subject: Subject, action: impl Into<String>, resource: Resource) -> Self {
+        Self {
+            subject,
+            action: action.into(),
+            resource,
+        }
+    }
+
+    /// Resolve an attribute path such as `subject.user_id` or `resource.owner_id`
+    pub fn attribute(&self, path: &str) -> Option<Value> {
+        if path == "action" {
+            return Some(Value::from(self.action.as_str()));
+        }
+        match path.split_once('.') {
+            Some(("subject", name)) => self.subject.attribute(name),
+            Some(("resource", name)) => self.resource.attribute(name),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attribute_paths() {
+        let mut claims = Claims::new("user123", "tenant1");
+        claims.roles = vec!["editor".to_string()];
+        claims.custom.insert("department".to_string(), Value::from("legal"));
+
+        let request = AccessRequest::new(
+            Subject::from_claims(&claims),
+            "documents:update",
+            Resource::new("document").with_id("42").with_owner("user123").with_tenant("tenant1"),
+        );
+
+        assert_eq!(request.attribute("subject.user_id"), Some(Value::from("user123")));
+        assert_eq!(request.attribute("subject.roles"), Some(serde_json::json!(["editor"])));
+        assert_eq!(request.attribute("subject.department"), Some(Value::from("legal")));
+        assert_eq!(request.attribute("resource.type"), Some(Value::from("document")));
+        assert_eq!(request.attribute("resource.owner_id"), Some(Value::from("user123")));
+        assert_eq!(request.attribute("action"), Some(Value::from("documents:update")));
+        assert!(request.attribute("resource.missing").is_none());
+        assert!(request.attribute("environment.ip").is_none());
+        assert_eq!(request.resource.audit_id(), "document/42");
+    }
+}
@@ -0,0 +1,263 @@
+//! Policy evaluation
+//!
+//! Evaluation is deny-overrides: any applicable deny policy wins, then any
+//! applicable allow policy, and otherwise the set's default effect applies.
+//!
+//! Decisions are only written to the audit log when the engine is built
+//! `with_audit(true)`; otherwise callers that want an audit trail record the
+//! decisions they act on with `AuthorizationDecision::audit_result`.
+
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::types::{AuthResult, AuthError};
+#[cfg(feature = "observability")]
+use cloudshuttle_observability::audit::{audit_authz, AuditResult};
+
+use super::attributes::AccessRequest;
+use super::policy::{Effect, PolicySet};
+
+/// Result of evaluating an access request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorizationDecision {
+    pub effect: Effect,
+
+    /// Policy that decided the request (None when the default applied)
+    pub policy_id: Option<String>,
+
+    /// Human-readable explanation for logs and audit trails
+    pub reason: String,
+}
+
+impl AuthorizationDecision {
+    /// Check if access is allowed
+    pub fn is_allowed(&self) -> bool {
+        self.effect == Effect::Allow
+    }
+
+    /// Audit result for `audit_authz`
+    #[cfg(feature = "observability")]
+    pub fn audit_result(&self) -> AuditResult {
+        if self.is_allowed() {
+            AuditResult::Success
+        } else {
+            AuditResult::Failure
+        }
+    }
+}
+
+/// Attribute-based policy engine
+#[derive(Debug, Clone, Default)]
+pub struct PolicyEngine {
+    policies: PolicySet,
+    audit: bool,
+}
+
+impl PolicyEngine {
+    /// Create an engine for a validated policy set
+    pub fn new(policies: PolicySet) -> Self {
+        Self { policies, audit: false }
+    }
+
+    /// Write every decision to the audit log (off by default)
+    pub fn with_audit(mut self, audit: bool) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Create an engine from a TOML policy document
+    pub fn from_toml_str(document: &str) -> AuthResult<Self> {
+        PolicySet::from_toml_str(document).map(Self::new)
+    }
+
+    /// Create an engine from a JSON policy document
+    pub fn from_json_str(document: &str) -> AuthResult<Self> {
+        PolicySet::from_json_str(document).map(Self::new)
+    }
+
+    /// Create an engine from a `.toml` or `.json` policy file
+    pub fn from_file(path: impl AsRef<Path>) -> AuthResult<Self> {
+        PolicySet::from_file(path).map(Self::new)
+    }
+
+    /// Get the loaded policies
+    pub fn policies(&self) -> &PolicySet {
+        &self.policies
+    }
+
+    /// Evaluate an access request
+    pub fn evaluate(&self, request: &AccessRequest) -> AuthorizationDecision {
+        let mut allowed_by = None;
+        for policy in self.policies.policies.iter().filter(|policy| policy.applies_to(request)) {
+            match policy.effect {
+                Effect::Deny => {
+                    return self.decide(request, Effect::Deny, Some(&policy.id), format!(
+                        "{} on {} denied by policy '{}'",
+                        request.action, request.resource.resource_type, policy.id
+                    ));
+                }
+                Effect::Allow => {
+                    allowed_by.get_or_insert(policy);
+                }
+            }
+        }
+
+        match allowed_by {
+            Some(policy) => self.decide(request, Effect::Allow, Some(&policy.id), format!(
+                "{} on {} allowed by policy '{}'",
+                request.action, request.resource.resource_type, policy.id
+            )),
+            None => self.decide(request, self.policies.default_effect, None, format!(
+                "no policy applies to {} on {}",
+                request.action, request.resource.resource_type
+            )),
+        }
+    }
+
+    /// Evaluate an access request, failing with `InsufficientPermissions` on deny
+    pub fn enforce(&self, request: &AccessRequest) -> AuthResult<AuthorizationDecision> {
+        let decision = self.evaluate(request);
+        if decision.is_allowed() {
+            Ok(decision)
+        } else {
+            Err(AuthError::InsufficientPermissions {
+                required: vec![request.action.clone()],
+                actual: request.subject.permissions.clone(),
+            })
+        }
+    }
+
+    fn decide(&self, request: &AccessRequest, effect: Effect, policy_id: Option<&str>, reason: String) -> AuthorizationDecision {
+        let decision = AuthorizationDecision {
+            effect,
+            policy_id: policy_id.map(str::to_string),
+            reason,
+        };
+
+        tracing::debug!(
+            user_id = %request.subject.user_id,
+            action = %request.action,
+            resource = %request.resource.audit_id(),
+            reason = %decision.reason,
+            "Authorization decision"
+        );
+
+        #[cfg(feature = "observability")]
+        if self.audit {
+            audit_authz(
+                &request.action,
+                Some(&request.subject.user_id),
+                Some(&request.resource.audit_id()),
+                decision.audit_result(),
+            );
+        }
+
+        decision
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authorization::{Resource, Subject};
+    use crate::types::AuthContext;
+
+    const POLICIES: &str = r#"
+        [[policies]]
+        id = "editors-update-own-documents"
+        effect = "allow"
+        actions = ["documents:update", "documents:read"]
+        resources = ["document"]
+        subject = { roles = ["editor"] }
+        conditions = [
+            { attribute = "resource.owner_id", value_from = "subject.user_id" },
+            { attribute = "resource.tenant_id", value_from = "subject.tenant_id" },
+        ]
+
+        [[policies]]
+        id = "document-admins"
+        effect = "allow"
+        actions = ["documents:*"]
+        subject = { permissions = ["documents:admin"] }
+
+        [[policies]]
+        id = "locked-documents"
+        effect = "deny"
+        actions = ["documents:update", "documents:delete"]
+        conditions = [{ attribute = "resource.locked", value = true }]
+    "#;
+
+    fn editor() -> Subject {
+        let mut context = AuthContext::new("user123".to_string(), "tenant1".to_string());
+        context.roles = vec!["editor".to_string()];
+        Subject::from_context(&context)
+    }
+
+    fn document(owner: &str) -> Resource {
+        Resource::new("document")
+            .with_id("42")
+            .with_owner(owner)
+            .with_tenant("tenant1")
+            .with_attribute("locked", false)
+    }
+
+    #[test]
+    fn test_allow_by_attribute_policy() {
+        let engine = PolicyEngine::from_toml_str(POLICIES).unwrap();
+
+        let decision = engine.evaluate(&AccessRequest::new(editor(), "documents:update", document("user123")));
+        assert!(decision.is_allowed());
+        assert_eq!(decision.policy_id.as_deref(), Some("editors-update-own-documents"));
+
+        let decision = engine.evaluate(&AccessRequest::new(editor(), "documents:update", document("user456")));
+        assert!(!decision.is_allowed());
+        assert!(decision.policy_id.is_none());
+        assert_eq!(decision.reason, "no policy applies to documents:update on document");
+    }
+
+    #[test]
+    fn test_wildcard_permissions() {
+        let engine = PolicyEngine::from_toml_str(POLICIES).unwrap();
+        let mut admin = Subject::new("admin1", "tenant2");
+        admin.permissions = vec!["documents:*".to_string()];
+
+        let decision = engine.evaluate(&AccessRequest::new(admin, "documents:delete", document("user456")));
+        assert!(decision.is_allowed());
+        assert_eq!(decision.policy_id.as_deref(), Some("document-admins"));
+    }
+
+    #[test]
+    fn test_deny_overrides_allow() {
+        let engine = PolicyEngine::from_toml_str(POLICIES).unwrap();
+        let request = AccessRequest::new(editor(), "documents:update", document("user123").with_attribute("locked", true));
+
+        let decision = engine.evaluate(&request);
+        assert!(!decision.is_allowed());
+        assert_eq!(decision.policy_id.as_deref(), Some("locked-documents"));
+        assert_eq!(decision.reason, "documents:update on document denied by policy 'locked-documents'");
+
+        assert!(matches!(engine.enforce(&request), Err(AuthError::InsufficientPermissions { .. })));
+        let readable = AccessRequest::new(editor(), "documents:read", document("user123").with_attribute("locked", true));
+        assert!(engine.enforce(&readable).is_ok());
+    }
+
+    #[test]
+    fn test_deny_applies_when_attribute_is_missing() {
+        let engine = PolicyEngine::from_toml_str(POLICIES).unwrap();
+        let unknown = Resource::new("document").with_id("42").with_owner("user123").with_tenant("tenant1");
+
+        let decision = engine.evaluate(&AccessRequest::new(editor(), "documents:update", unknown));
+        assert!(!decision.is_allowed());
+        assert_eq!(decision.policy_id.as_deref(), Some("locked-documents"));
+    }
+
+    #[test]
+    fn test_load_from_file() {
+        let path = std::env::temp_dir().join(format!("policies-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, r#"{"default_effect": "allow"}"#).unwrap();
+        let engine = PolicyEngine::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(engine.evaluate(&AccessRequest::new(editor(), "anything", Resource::new("thing"))).is_allowed());
+        assert!(PolicyEngine::from_file("policies.yaml").is_err());
+    }
+}
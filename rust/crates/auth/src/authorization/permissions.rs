@@ -0,0 +1,60 @@
+//! Wildcard and hierarchical permission matching
+//!
+//! Permissions are colon-separated paths such as `documents:update`. A `*`
+//! segment matches any single segment, and a trailing `*` matches any
+//! remaining segments, so `documents:*` covers `documents:update` and
+//! `documents:drafts:update`.
+
+/// Check if a permission pattern covers a concrete permission
+pub fn permission_matches(pattern: &str, permission: &str) -> bool {
+    let mut pattern_segments = pattern.split(':').peekable();
+    let mut permission_segments = permission.split(':');
+
+    while let Some(pattern_segment) = pattern_segments.next() {
+        let Some(permission_segment) = permission_segments.next() else {
+            return false;
+        };
+        if pattern_segment == "*" {
+            if pattern_segments.peek().is_none() {
+                return true;
+            }
+        } else if pattern_segment != permission_segment {
+            return false;
+        }
+    }
+
+    permission_segments.next().is_none()
+}
+
+/// Check if any granted permission covers the required one
+pub fn grants_permission(granted: &[String], required: &str) -> bool {
+    granted.iter().any(|pattern| permission_matches(pattern, required))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_matching() {
+        assert!(permission_matches("documents:update", "documents:update"));
+        assert!(!permission_matches("documents:update", "documents:delete"));
+        assert!(permission_matches("documents:*", "documents:update"));
+        assert!(permission_matches("documents:*", "documents:drafts:update"));
+        assert!(!permission_matches("documents:*", "documents"));
+        assert!(!permission_matches("documents:*", "users:read"));
+        assert!(permission_matches("*", "users:read"));
+        assert!(permission_matches("*:read", "users:read"));
+        assert!(!permission_matches("*:read", "users:write"));
+        assert!(!permission_matches("*:read", "users:profile:read"));
+        assert!(!permission_matches("documents", "documents:update"));
+    }
+
+    #[test]
+    fn test_grants_permission() {
+        let granted = vec!["users:read".to_string(), "documents:*".to_string()];
+        assert!(grants_permission(&granted, "documents:publish"));
+        assert!(grants_permission(&granted, "users:read"));
+        assert!(!grants_permission(&granted, "users:delete"));
+    }
+}
@@ -0,0 +1,378 @@
+//! Policy documents
+//!
+//! A policy set is a list of policies plus a default effect, written in
+//! TOML or JSON:
+//!
+//! ```toml
+//! [[policies]]
+//! id = "editors-update-own-documents"
+//! description = "Editors may update documents they own within their tenant"
+//! effect = "allow"
+//! actions = ["documents:update"]
+//! resources = ["document"]
+//! subject = { roles = ["editor"] }
+//! conditions = [
+//!     { attribute = "resource.owner_id", value_from = "subject.user_id" },
+//!     { attribute = "resource.tenant_id", value_from = "subject.tenant_id" },
+//! ]
+//! ```
+//!
+//! A condition on an attribute that is missing from the request fails in an
+//! allow policy and holds in a deny policy, so missing data never grants
+//! access. `exists` conditions test presence and are never treated as missing.
+
+use std::collections::HashSet;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::types::{AuthResult, AuthError};
+
+use super::attributes::AccessRequest;
+use super::permissions::{grants_permission, permission_matches};
+
+/// Outcome of a policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Effect {
+    Allow,
+    Deny,
+}
+
+/// Requirements on the subject's roles and permissions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubjectMatcher {
+    /// Subject must hold at least one of these roles (empty matches any subject)
+    pub roles: Vec<String>,
+
+    /// Subject must be granted all of these permissions, wildcard grants included
+    pub permissions: Vec<String>,
+}
+
+impl SubjectMatcher {
+    fn matches(&self, request: &AccessRequest) -> bool {
+        let subject = &request.subject;
+        (self.roles.is_empty() || self.roles.iter().any(|role| subject.roles.contains(role)))
+            && self.permissions.iter().all(|permission| grants_permission(&subject.permissions, permission))
+    }
+}
+
+/// Comparison applied by a condition
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionOperator {
+    /// Attribute equals the value
+    #[default]
+    Equals,
+    /// Attribute differs from the value
+    NotEquals,
+    /// Attribute is one of the values in a list
+    In,
+    /// Attribute is a list containing the value
+    Contains,
+    /// Attribute is present
+    Exists,
+}
+
+/// Condition on a request attribute
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Condition {
+    /// Attribute path, e.g. `resource.owner_id`
+    pub attribute: String,
+
+    #[serde(default)]
+    pub operator: ConditionOperator,
+
+    /// Literal value to compare against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+
+    /// Attribute path whose value to compare against, e.g. `subject.user_id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_from: Option<String>,
+}
+
+impl Condition {
+    /// Evaluate the condition against a request
+    ///
+    /// Missing attributes make the condition false.
+    pub fn evaluate(&self, request: &AccessRequest) -> bool {
+        self.check(request).unwrap_or(false)
+    }
+
+    /// Evaluate the condition, or `None` if an attribute it compares is missing
+    pub fn check(&self, request: &AccessRequest) -> Option<bool> {
+        let actual = request.attribute(&self.attribute);
+        if self.operator == ConditionOperator::Exists {
+            return Some(actual.is_some());
+        }
+        let actual = actual?;
+
+        let expected = match (&self.value, &self.value_from) {
+            (Some(value), _) => value.clone(),
+            (None, Some(path)) => request.attribute(path)?,
+            (None, None) => return Some(false),
+        };
+
+        Some(match self.operator {
+            ConditionOperator::Equals => actual == expected,
+            ConditionOperator::NotEquals => actual != expected,
+            ConditionOperator::In => expected.as_array().is_some_and(|values| values.contains(&actual)),
+            ConditionOperator::Contains => actual.as_array().is_some_and(|values| values.contains(&expected)),
+            ConditionOperator::Exists => true,
+        })
+    }
+
+    fn validate(&self, policy_id: &str) -> AuthResult<()> {
+        let has_operand = match (&self.value, &self.value_from) {
+            (Some(_), Some(_)) => {
+                return Err(invalid(format!(
+                    "policy '{}': condition on '{}' sets both value and value_from",
+                    policy_id, self.attribute
+                )));
+            }
+            (None, None) => false,
+            _ => true,
+        };
+        if has_operand != (self.operator != ConditionOperator::Exists) {
+            return Err(invalid(format!(
+                "policy '{}': condition on '{}' needs exactly one of value or value_from unless the operator is exists",
+                policy_id, self.attribute
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Rule allowing or denying actions on resources
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Policy {
+    /// Unique policy identifier, reported in decisions
+    pub id: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    pub effect: Effect,
+
+    /// Action patterns, e.g. `documents:update` or `documents:*`
+    pub actions: Vec<String>,
+
+    /// Resource types the policy applies to (empty matches any type)
+    #[serde(default)]
+    pub resources: Vec<String>,
+
+    #[serde(default)]
+    pub subject: SubjectMatcher,
+
+    /// Conditions that must all hold
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+}
+
+impl Policy {
+    /// Check if the policy applies to a request
+    ///
+    /// Conditions on missing attributes hold for deny policies and fail for
+    /// allow policies.
+    pub fn applies_to(&self, request: &AccessRequest) -> bool {
+        let when_missing = self.effect == Effect::Deny;
+        self.actions.iter().any(|pattern| permission_matches(pattern, &request.action))
+            && (self.resources.is_empty()
+                || self.resources.iter().any(|resource| resource == "*" || *resource == request.resource.resource_type))
+            && self.subject.matches(request)
+            && self.conditions.iter().all(|condition| condition.check(request).unwrap_or(when_missing))
+    }
+}
+
+/// Set of policies with a default effect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicySet {
+    /// Effect when no policy applies
+    #[serde(default = "default_effect")]
+    pub default_effect: Effect,
+
+    #[serde(default)]
+    pub policies: Vec<Policy>,
+}
+
+fn default_effect() -> Effect {
+    Effect::Deny
+}
+
+impl Default for PolicySet {
+    fn default() -> Self {
+        Self {
+            default_effect: default_effect(),
+            policies: Vec::new(),
+        }
+    }
+}
+
+impl PolicySet {
+    /// Parse and validate a TOML policy document
+    pub fn from_toml_str(document: &str) -> AuthResult<Self> {
+        let set: Self = toml::from_str(document).map_err(|e| invalid(e.to_string()))?;
+        set.validate()?;
+        Ok(set)
+    }
+
+    /// Parse and validate a JSON policy document
+    pub fn from_json_str(document: &str) -> AuthResult<Self> {
+        let set: Self = serde_json::from_str(document).map_err(|e| invalid(e.to_string()))?;
+        set.validate()?;
+        Ok(set)
+    }
+
+    /// Load a policy document, choosing the format by file extension
+    pub fn from_file(path: impl AsRef<Path>) -> AuthResult<Self> {
+        let path = path.as_ref();
+        let document = std::fs::read_to_string(path)
+            .map_err(|e| invalid(format!("{}: {}", path.display(), e)))?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml_str(&document),
+            Some("json") => Self::from_json_str(&document),
+            _ => Err(invalid(format!("{}: expected a .toml or .json file", path.display()))),
+        }
+    }
+
+    /// Check policy ids are unique and every policy is well formed
+    pub fn validate(&self) -> AuthResult<()> {
+        let mut ids = HashSet::new();
+        for policy in &self.policies {
+            if policy.id.is_empty() {
+                return Err(invalid("policy id must not be empty".to_string()));
+            }
+            if !ids.insert(policy.id.as_str()) {
+                return Err(invalid(format!("duplicate policy id '{}'", policy.id)));
+            }
+            if policy.actions.is_empty() {
+                return Err(invalid(format!("policy '{}' has no actions", policy.id)));
+            }
+            for condition in &policy.conditions {
+                condition.validate(&policy.id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn invalid(message: String) -> AuthError {
+    AuthError::InvalidConfiguration(format!("Invalid policy document: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::authorization::{Resource, Subject};
+
+    const POLICIES: &str = r#"
+        [[policies]]
+        id = "editors-update-own-documents"
+        effect = "allow"
+        actions = ["documents:update"]
+        resources = ["document"]
+        subject = { roles = ["editor"] }
+        conditions = [
+            { attribute = "resource.owner_id", value_from = "subject.user_id" },
+            { attribute = "resource.tenant_id", value_from = "subject.tenant_id" },
+        ]
+    "#;
+
+    fn request(owner: &str, tenant: &str) -> AccessRequest {
+        let mut subject = Subject::new("user123", "tenant1");
+        subject.roles = vec!["editor".to_string()];
+        AccessRequest::new(subject, "documents:update", Resource::new("document").with_owner(owner).with_tenant(tenant))
+    }
+
+    #[test]
+    fn test_parse_toml_policies() {
+        let set = PolicySet::from_toml_str(POLICIES).unwrap();
+        assert_eq!(set.default_effect, Effect::Deny);
+        let policy = &set.policies[0];
+        assert_eq!(policy.conditions.len(), 2);
+
+        assert!(policy.applies_to(&request("user123", "tenant1")));
+        assert!(!policy.applies_to(&request("user456", "tenant1")));
+        assert!(!policy.applies_to(&request("user123", "tenant2")));
+    }
+
+    #[test]
+    fn test_parse_json_policies() {
+        let set = PolicySet::from_json_str(r#"{
+            "default_effect": "allow",
+            "policies": [{
+                "id": "no-deletes-on-archived",
+                "effect": "deny",
+                "actions": ["documents:delete"],
+                "conditions": [{ "attribute": "resource.status", "operator": "in", "value": ["archived", "locked"] }]
+            }]
+        }"#).unwrap();
+        assert_eq!(set.default_effect, Effect::Allow);
+
+        let subject = Subject::new("user123", "tenant1");
+        let archived = Resource::new("document").with_attribute("status", "archived");
+        assert!(set.policies[0].applies_to(&AccessRequest::new(subject.clone(), "documents:delete", archived)));
+        let draft = Resource::new("document").with_attribute("status", "draft");
+        assert!(!set.policies[0].applies_to(&AccessRequest::new(subject.clone(), "documents:delete", draft)));
+
+        // Without a status the deny policy can't rule the request out, so it applies
+        assert!(set.policies[0].applies_to(&AccessRequest::new(subject, "documents:delete", Resource::new("document"))));
+    }
+
+    #[test]
+    fn test_subject_permissions_and_operators() {
+        let policy = Policy {
+            id: "publishers".to_string(),
+            description: None,
+            effect: Effect::Allow,
+            actions: vec!["documents:*".to_string()],
+            resources: vec![],
+            subject: SubjectMatcher { roles: vec![], permissions: vec!["documents:publish".to_string()] },
+            conditions: vec![Condition {
+                attribute: "subject.roles".to_string(),
+                operator: ConditionOperator::Contains,
+                value: Some(Value::from("staff")),
+                value_from: None,
+            }],
+        };
+
+        let mut subject = Subject::new("user123", "tenant1");
+        subject.roles = vec!["staff".to_string()];
+        subject.permissions = vec!["documents:*".to_string()];
+        assert!(policy.applies_to(&AccessRequest::new(subject.clone(), "documents:publish", Resource::new("document"))));
+
+        subject.permissions = vec!["documents:read".to_string()];
+        assert!(!policy.applies_to(&AccessRequest::new(subject, "documents:publish", Resource::new("document"))));
+    }
+
+    #[test]
+    fn test_invalid_documents() {
+        assert!(PolicySet::from_toml_str("[[policies]]\nid = \"x\"\neffect = \"maybe\"\nactions = [\"a\"]").is_err());
+        assert!(PolicySet::from_toml_str("[[policies]]\nid = \"x\"\neffect = \"allow\"\nactions = []").is_err());
+
+        let duplicate = "[[policies]]\nid = \"x\"\neffect = \"allow\"\nactions = [\"a\"]\n".repeat(2);
+        assert!(PolicySet::from_toml_str(&duplicate).is_err());
+
+        let missing_operand = r#"{"policies": [{"id": "x", "effect": "allow", "actions": ["a"],
+            "conditions": [{"attribute": "resource.id"}]}]}"#;
+        assert!(matches!(PolicySet::from_json_str(missing_operand), Err(AuthError::InvalidConfiguration(_))));
+    }
+
+    #[test]
+    fn test_missing_attributes_fail_closed() {
+        let condition = |operator, value: Option<Value>| Condition {
+            attribute: "resource.status".to_string(),
+            operator,
+            value,
+            value_from: None,
+        };
+        let request = AccessRequest::new(Subject::new("user123", "tenant1"), "documents:read", Resource::new("document"));
+
+        let not_archived = condition(ConditionOperator::NotEquals, Some(Value::from("archived")));
+        assert_eq!(not_archived.check(&request), None);
+        assert!(!not_archived.evaluate(&request));
+        assert_eq!(condition(ConditionOperator::Exists, None).check(&request), Some(false));
+    }
+}
@@ -1,14 +1,36 @@
 //! Token introspection implementation (RFC 7662)
 //!
 //! This module provides RFC 7662 compliant token introspection capabilities,
-//! allowing services to validate and retrieve metadata about tokens.
+//! allowing services to validate and retrieve metadata about tokens:
+//! - `router`: Axum introspection endpoint for resource servers
+//! - `client`: Caching client for a remote introspection endpoint
 
+#[cfg(all(feature = "axum", feature = "oauth"))]
+pub mod router;
+#[cfg(feature = "introspection-client")]
+pub mod client;
+
+#[cfg(all(feature = "axum", feature = "oauth"))]
+pub use router::{introspection_router, INTROSPECTION_PATH};
+#[cfg(feature = "introspection-client")]
+pub use client::IntrospectionClient;
+
+use std::sync::Arc;
 use serde::{Deserialize, Serialize};
-use crate::types::{AuthResult, AuthError};
+use crate::types::AuthResult;
 #[cfg(feature = "observability")]
 use cloudshuttle_observability::audit::{audit_auth, AuditResult};
+#[cfg(feature = "refresh-tokens")]
+use crate::refresh_tokens::RefreshTokenManager;
+use crate::revocation::TokenDenylist;
 use crate::{Claims, JwtService};
 
+/// `token_type_hint` value for access tokens
+pub const ACCESS_TOKEN_HINT: &str = "access_token";
+
+/// `token_type_hint` value for refresh tokens
+pub const REFRESH_TOKEN_HINT: &str = "refresh_token";
+
 /// Token introspection request
 #[derive(Debug, Deserialize)]
 pub struct IntrospectionRequest {
@@ -74,29 +96,53 @@ pub struct IntrospectionResponse {
 /// Token introspection service
 pub struct TokenIntrospection {
     jwt_service: JwtService,
+    denylist: Option<Arc<dyn TokenDenylist>>,
+    #[cfg(feature = "refresh-tokens")]
+    refresh_tokens: Option<Arc<RefreshTokenManager>>,
 }
 
 impl TokenIntrospection {
     /// Create a new token introspection service
     pub fn new(jwt_service: JwtService) -> Self {
-        Self { jwt_service }
+        Self {
+            jwt_service,
+            denylist: None,
+            #[cfg(feature = "refresh-tokens")]
+            refresh_tokens: None,
+        }
+    }
+
+    /// Report tokens in the denylist as inactive
+    pub fn with_denylist(mut self, denylist: Arc<dyn TokenDenylist>) -> Self {
+        self.denylist = Some(denylist);
+        self
+    }
+
+    /// Look refresh tokens up in the manager's store, so revoked and
+    /// rotated refresh tokens are reported as inactive
+    #[cfg(feature = "refresh-tokens")]
+    pub fn with_refresh_tokens(mut self, refresh_tokens: Arc<RefreshTokenManager>) -> Self {
+        self.refresh_tokens = Some(refresh_tokens);
+        self
     }
 
     /// Introspect a token and return RFC 7662 compliant response
-    pub fn introspect(&self, request: IntrospectionRequest) -> AuthResult<IntrospectionResponse> {
+    ///
+    /// Fails only if the denylist or refresh token store cannot be consulted.
+    pub async fn introspect(&self, request: IntrospectionRequest) -> AuthResult<IntrospectionResponse> {
         // Try to validate the token (this will fail for expired/invalid tokens)
-        match self.jwt_service.extract_claims_unchecked(&request.token) {
-            Ok(claims) => {
+        match self.lookup(&request.token, request.token_type_hint.as_deref()).await? {
+            Some((claims, token_type)) => {
                 // Token is structurally valid, create active response
                 #[cfg(feature = "observability")]
                 audit_auth("token_introspected", Some(&claims.sub), AuditResult::Success);
 
                 Ok(IntrospectionResponse {
                     active: true,
-                    client_id: None, // Not implemented in basic JWT
+                    client_id: custom_string(&claims, "client_id"),
                     username: Some(claims.sub.clone()),
-                    scope: None, // Not implemented in basic JWT
-                    token_type: "Bearer".to_string(),
+                    scope: custom_string(&claims, "scope"),
+                    token_type: token_type.to_string(),
                     exp: Some(claims.exp),
                     iat: Some(claims.iat),
                     nbf: claims.nbf,
@@ -106,8 +152,8 @@ impl TokenIntrospection {
                     jti: claims.jti.clone(),
                 })
             }
-            Err(_) => {
-                // Token is invalid, expired, or malformed
+            None => {
+                // Token is invalid, expired, malformed or revoked
                 #[cfg(feature = "observability")]
                 audit_auth("token_introspection_failed", None, AuditResult::Failure);
 
@@ -130,58 +176,112 @@ impl TokenIntrospection {
     }
 
     /// Check if a token is active (convenience method)
-    pub fn is_token_active(&self, token: &str) -> bool {
-        match self.introspect(IntrospectionRequest {
-            token: token.to_string(),
-            token_type_hint: Some("access_token".to_string()),
-        }) {
-            Ok(response) => response.active,
-            Err(_) => false,
-        }
+    ///
+    /// Tokens are reported inactive if the denylist cannot be consulted.
+    pub async fn is_token_active(&self, token: &str) -> bool {
+        matches!(self.lookup(token, Some(ACCESS_TOKEN_HINT)).await, Ok(Some(_)))
     }
 
     /// Get token claims if active (convenience method)
-    pub fn get_active_claims(&self, token: &str) -> AuthResult<Option<Claims>> {
-        let response = self.introspect(IntrospectionRequest {
-            token: token.to_string(),
-            token_type_hint: Some("access_token".to_string()),
-        })?;
+    pub async fn get_active_claims(&self, token: &str) -> AuthResult<Option<Claims>> {
+        Ok(self.lookup(token, Some(ACCESS_TOKEN_HINT)).await?.map(|(claims, _)| claims))
+    }
 
-        if response.active {
-            // If active, we can safely decode the claims
-            self.jwt_service.extract_claims_unchecked(token).map(Some)
-        } else {
-            Ok(None)
+    /// Validate a token and find its active type, trying the hinted type first
+    ///
+    /// As RFC 7662 requires, a wrong hint never makes an active token
+    /// appear inactive; it only changes the order of the lookups.
+    async fn lookup(&self, token: &str, token_type_hint: Option<&str>) -> AuthResult<Option<(Claims, &'static str)>> {
+        let Ok(claims) = self.jwt_service.validate_token(token) else {
+            return Ok(None);
+        };
+
+        let refresh_first = token_type_hint == Some(REFRESH_TOKEN_HINT);
+        if refresh_first && self.is_refresh_token_active(token).await? {
+            return Ok(Some((claims, REFRESH_TOKEN_HINT)));
         }
+
+        if !is_refresh_token(&claims) {
+            if let Some(denylist) = &self.denylist {
+                if denylist.is_claims_revoked(&claims).await? {
+                    return Ok(None);
+                }
+            }
+            return Ok(Some((claims, "Bearer")));
+        }
+
+        if !refresh_first && self.is_refresh_token_active(token).await? {
+            return Ok(Some((claims, REFRESH_TOKEN_HINT)));
+        }
+        Ok(None)
+    }
+
+    /// Check a validated refresh token against the refresh token store
+    ///
+    /// Without a `RefreshTokenManager` revocation can't be checked, so
+    /// refresh tokens are reported inactive.
+    #[cfg_attr(not(feature = "refresh-tokens"), allow(unused_variables))]
+    async fn is_refresh_token_active(&self, token: &str) -> AuthResult<bool> {
+        #[cfg(feature = "refresh-tokens")]
+        if let Some(refresh_tokens) = &self.refresh_tokens {
+            return Ok(match refresh_tokens.get_token_record(token).await? {
+                Some(record) => !record.revoked && record.expires_at > current_timestamp(),
+                None => false,
+            });
+        }
+
+        Ok(false)
     }
 }
 
+/// Check if claims belong to a refresh token
+///
+/// `JwtService` marks refresh tokens with `token_type`, while
+/// `RefreshTokenManager` uses a custom `type` claim.
+fn is_refresh_token(claims: &Claims) -> bool {
+    claims.token_type.as_deref() == Some("refresh") || custom_string(claims, "type").as_deref() == Some("refresh")
+}
+
+fn custom_string(claims: &Claims, key: &str) -> Option<String> {
+    claims.custom.get(key).and_then(|v| v.as_str()).map(str::to_string)
+}
+
+#[cfg(feature = "refresh-tokens")]
+fn current_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
 /// RFC 7662 Token Introspection trait
+#[async_trait::async_trait]
 pub trait TokenIntrospectable {
     /// Introspect a token
-    fn introspect_token(&self, token: &str) -> AuthResult<IntrospectionResponse>;
+    async fn introspect_token(&self, token: &str) -> AuthResult<IntrospectionResponse>;
 
     /// Check if token is active
-    fn is_token_active(&self, token: &str) -> bool;
+    async fn is_token_active(&self, token: &str) -> bool;
 
     /// Get claims for active token
-    fn get_active_claims(&self, token: &str) -> AuthResult<Option<Claims>>;
+    async fn get_active_claims(&self, token: &str) -> AuthResult<Option<Claims>>;
 }
 
+#[async_trait::async_trait]
 impl TokenIntrospectable for TokenIntrospection {
-    fn introspect_token(&self, token: &str) -> AuthResult<IntrospectionResponse> {
+    async fn introspect_token(&self, token: &str) -> AuthResult<IntrospectionResponse> {
         self.introspect(IntrospectionRequest {
             token: token.to_string(),
-            token_type_hint: Some("access_token".to_string()),
-        })
+            token_type_hint: Some(ACCESS_TOKEN_HINT.to_string()),
+        }).await
     }
 
-    fn is_token_active(&self, token: &str) -> bool {
-        self.is_token_active(token)
+    async fn is_token_active(&self, token: &str) -> bool {
+        TokenIntrospection::is_token_active(self, token).await
     }
 
-    fn get_active_claims(&self, token: &str) -> AuthResult<Option<Claims>> {
-        self.get_active_claims(token)
+    async fn get_active_claims(&self, token: &str) -> AuthResult<Option<Claims>> {
+        TokenIntrospection::get_active_claims(self, token).await
     }
 }
 
@@ -190,8 +290,8 @@ mod tests {
     use super::*;
     use crate::Claims;
 
-    #[test]
-    fn test_token_introspection_valid_token() {
+    #[tokio::test]
+    async fn test_token_introspection_valid_token() {
         let jwt_service = JwtService::new(b"test-secret-key").unwrap();
         let claims = Claims::new("user123", "tenant456");
         let token = jwt_service.create_token(&claims).unwrap();
@@ -201,7 +301,7 @@ mod tests {
         let response = introspector.introspect(IntrospectionRequest {
             token: token.clone(),
             token_type_hint: Some("access_token".to_string()),
-        }).unwrap();
+        }).await.unwrap();
 
         assert!(response.active);
         assert_eq!(response.username, Some("user123".to_string()));
@@ -211,51 +311,63 @@ mod tests {
         assert!(response.iat.is_some());
     }
 
-    #[test]
-    fn test_token_introspection_invalid_token() {
+    #[tokio::test]
+    async fn test_token_introspection_invalid_token() {
         let jwt_service = JwtService::new(b"test-secret-key").unwrap();
         let introspector = TokenIntrospection::new(jwt_service);
 
         let response = introspector.introspect(IntrospectionRequest {
             token: "invalid-token".to_string(),
             token_type_hint: Some("access_token".to_string()),
-        }).unwrap();
+        }).await.unwrap();
 
         assert!(!response.active);
         assert!(response.username.is_none());
         assert!(response.exp.is_none());
     }
 
-    #[test]
-    fn test_is_token_active() {
+    #[tokio::test]
+    async fn test_is_token_active() {
         let jwt_service = JwtService::new(b"test-secret-key").unwrap();
         let claims = Claims::new("user123", "tenant456");
         let token = jwt_service.create_token(&claims).unwrap();
 
         let introspector = TokenIntrospection::new(jwt_service);
 
-        assert!(introspector.is_token_active(&token));
-        assert!(!introspector.is_token_active("invalid"));
+        assert!(introspector.is_token_active(&token).await);
+        assert!(!introspector.is_token_active("invalid").await);
     }
 
-    #[test]
-    fn test_get_active_claims() {
+    #[tokio::test]
+    async fn test_refresh_tokens_are_inactive_without_a_store() {
+        let jwt_service = JwtService::new(b"test-secret-key").unwrap();
+        let token = jwt_service.create_refresh_token("user123", "tenant456").unwrap();
+
+        // Revocation can't be checked, so a valid signature is not enough
+        let introspector = TokenIntrospection::new(jwt_service);
+        assert!(!introspector.is_token_active(&token).await);
+        let request = IntrospectionRequest { token, token_type_hint: Some(REFRESH_TOKEN_HINT.to_string()) };
+        assert!(!introspector.introspect(request).await.unwrap().active);
+    }
+
+    #[tokio::test]
+    async fn test_get_active_claims() {
         let jwt_service = JwtService::new(b"test-secret-key").unwrap();
         let claims = Claims::new("user123", "tenant456");
         let token = jwt_service.create_token(&claims).unwrap();
 
         let introspector = TokenIntrospection::new(jwt_service);
 
-        let active_claims = introspector.get_active_claims(&token).unwrap();
+        let active_claims = introspector.get_active_claims(&token).await.unwrap();
         assert!(active_claims.is_some());
         assert_eq!(active_claims.unwrap().sub, "user123");
 
-        let invalid_claims = introspector.get_active_claims("invalid").unwrap();
+        let invalid_claims = introspector.get_active_claims("invalid").await.unwrap();
         assert!(invalid_claims.is_none());
     }
 
-    #[test]
-    fn test_trait_implementation() {
+    #[tokio::test]
+    async fn test_trait_implementation() {
         let jwt_service = JwtService::new(b"test-secret-key").unwrap();
         let claims = Claims::new("user123", "tenant456");
         let token = jwt_service.create_token(&claims).unwrap();
@@ -263,8 +375,39 @@ mod tests {
         let introspector = TokenIntrospection::new(jwt_service);
 
         // Test trait methods
-        assert!(introspector.is_token_active(&token));
-        let response = introspector.introspect_token(&token).unwrap();
+        assert!(introspector.is_token_active(&token).await);
+        let response = introspector.introspect_token(&token).await.unwrap();
         assert!(response.active);
     }
+
+    #[tokio::test]
+    async fn test_expired_token_is_inactive() {
+        let jwt_service = JwtService::new(b"test-secret-key").unwrap();
+        let mut claims = Claims::new("user123", "tenant456");
+        claims.exp = claims.iat - 120;
+        let token = jwt_service.create_token(&claims).unwrap();
+
+        let introspector = TokenIntrospection::new(jwt_service);
+        assert!(!introspector.is_token_active(&token).await);
+    }
+
+    #[tokio::test]
+    async fn test_revoked_token_is_inactive() {
+        use crate::revocation::InMemoryTokenDenylist;
+
+        let jwt_service = JwtService::new(b"test-secret-key").unwrap();
+        let claims = Claims::new("user123", "tenant456");
+        let token = jwt_service.create_token(&claims).unwrap();
+
+        let denylist = Arc::new(InMemoryTokenDenylist::new());
+        let introspector = TokenIntrospection::new(jwt_service).with_denylist(denylist.clone());
+        assert!(introspector.is_token_active(&token).await);
+
+        denylist.revoke_claims(&claims).await.unwrap();
+        assert!(!introspector.is_token_active(&token).await);
+        let response = introspector.introspect_token(&token).await.unwrap();
+        assert!(!response.active);
+        assert!(response.jti.is_none());
+        assert!(introspector.get_active_claims(&token).await.unwrap().is_none());
+    }
 }
@@ -0,0 +1,241 @@
+//! Client for a remote introspection endpoint
+//!
+//! Resource servers that cannot validate tokens locally ask the
+//! authorization server about each one. Responses are cached for a short
+//! TTL, so a revocation may take up to that long to be noticed. The cache
+//! holds a bounded number of tokens and evicts the least recently used.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use hashlink::LruCache;
+use crate::security::CryptoUtils;
+use crate::types::{AuthResult, AuthError};
+
+use super::{IntrospectionResponse, ACCESS_TOKEN_HINT};
+
+/// Time allowed to connect to the introspection endpoint
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time allowed for a whole introspection request
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Number of tokens whose responses are cached
+const DEFAULT_CACHE_CAPACITY: usize = 10_000;
+
+/// Cached introspection response
+struct CachedResponse {
+    response: IntrospectionResponse,
+    expires_at: Instant,
+}
+
+/// Calls a remote RFC 7662 introspection endpoint with response caching
+pub struct IntrospectionClient {
+    endpoint: String,
+    client_id: String,
+    client_secret: String,
+    client: reqwest::Client,
+    cache: Mutex<LruCache<String, CachedResponse>>,
+    cache_ttl: Duration,
+}
+
+impl IntrospectionClient {
+    /// Create a client authenticating to `endpoint` with HTTP Basic credentials
+    ///
+    /// Requests time out after 10 seconds, or 5 seconds to connect.
+    pub fn new(endpoint: impl Into<String>, client_id: impl Into<String>, client_secret: impl Into<String>) -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(DEFAULT_CONNECT_TIMEOUT)
+            .timeout(DEFAULT_REQUEST_TIMEOUT)
+            .build()
+            .expect("HTTP client with timeouts can be built");
+
+        Self {
+            endpoint: endpoint.into(),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            client,
+            cache: Mutex::new(LruCache::new(DEFAULT_CACHE_CAPACITY)),
+            cache_ttl: Duration::from_secs(60),
+        }
+    }
+
+    /// Configure how long responses are cached (zero disables caching)
+    ///
+    /// Active responses are never cached past the token's expiry.
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Configure how many tokens' responses are cached
+    pub fn with_cache_capacity(self, capacity: usize) -> Self {
+        self.cache.lock().unwrap().set_capacity(capacity);
+        self
+    }
+
+    /// Use a custom HTTP client
+    ///
+    /// The client should set its own timeouts; without them a stalled
+    /// endpoint holds requests open indefinitely.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Introspect a token, using a cached response if one is fresh
+    pub async fn introspect(&self, token: &str, token_type_hint: Option<&str>) -> AuthResult<IntrospectionResponse> {
+        let cache_key = CryptoUtils::sha256_hash(token.as_bytes());
+        {
+            let mut cache = self.cache.lock().unwrap();
+            match cache.get(&cache_key) {
+                Some(cached) if cached.expires_at > Instant::now() => return Ok(cached.response.clone()),
+                Some(_) => {
+                    cache.remove(&cache_key);
+                }
+                None => {}
+            }
+        }
+
+        let mut form = vec![("token", token)];
+        if let Some(hint) = token_type_hint {
+            form.push(("token_type_hint", hint));
+        }
+
+        let response: IntrospectionResponse = self.client
+            .post(&self.endpoint)
+            .basic_auth(form_urlencode(&self.client_id), Some(form_urlencode(&self.client_secret)))
+            .form(&form)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| AuthError::ExternalServiceError(format!("Introspection request failed: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| AuthError::ExternalServiceError(format!("Invalid introspection response: {}", e)))?;
+
+        self.cache_response(cache_key, &response);
+        Ok(response)
+    }
+
+    /// Check if an access token is active
+    ///
+    /// Tokens are reported inactive if the endpoint cannot be reached.
+    pub async fn is_token_active(&self, token: &str) -> bool {
+        matches!(self.introspect(token, Some(ACCESS_TOKEN_HINT)).await, Ok(response) if response.active)
+    }
+
+    /// Drop all cached responses
+    pub fn clear_cache(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    fn cache_response(&self, cache_key: String, response: &IntrospectionResponse) {
+        let mut ttl = self.cache_ttl;
+        if let Some(exp) = response.exp.filter(|_| response.active) {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+            ttl = ttl.min(Duration::from_secs(exp.saturating_sub(now)));
+        }
+        if ttl.is_zero() {
+            return;
+        }
+
+        self.cache.lock().unwrap().insert(cache_key, CachedResponse {
+            response: response.clone(),
+            expires_at: Instant::now() + ttl,
+        });
+    }
+}
+
+/// Form-urlencode a client credential for HTTP Basic (RFC 6749 section 2.3.1)
+fn form_urlencode(value: &str) -> String {
+    value.bytes().fold(String::with_capacity(value.len()), |mut encoded, byte| {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'*' => encoded.push(byte as char),
+            b' ' => encoded.push('+'),
+            byte => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+        encoded
+    })
+}
+
+#[cfg(all(test, feature = "axum", feature = "oauth"))]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::introspection::{introspection_router, TokenIntrospection, INTROSPECTION_PATH};
+    use crate::oauth::{ClientRegistry, OAuthClient};
+    use crate::revocation::{InMemoryTokenDenylist, TokenDenylist};
+    use crate::{Claims, JwtService};
+
+    /// Serve an introspection endpoint from a local stand-in server
+    ///
+    /// The client secret holds characters that Basic credentials must form-urlencode.
+    async fn spawn_server(jwt_service: JwtService, denylist: Arc<InMemoryTokenDenylist>) -> String {
+        let introspection = TokenIntrospection::new(jwt_service).with_denylist(denylist);
+        let clients = Arc::new(ClientRegistry::new());
+        clients.register(OAuthClient::confidential("orders-api", "s3cret+%:", vec!["https://orders.example.com/cb".to_string()]).unwrap());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, introspection_router(Arc::new(introspection), clients)).await.unwrap();
+        });
+        format!("http://{}{}", address, INTROSPECTION_PATH)
+    }
+
+    #[tokio::test]
+    async fn test_remote_introspection_is_cached() {
+        let jwt_service = JwtService::new(b"test-secret-key").unwrap();
+        let denylist = Arc::new(InMemoryTokenDenylist::new());
+        let url = spawn_server(jwt_service.clone(), denylist.clone()).await;
+
+        let claims = Claims::new("user123", "tenant456");
+        let token = jwt_service.create_token(&claims).unwrap();
+        let client = IntrospectionClient::new(url.clone(), "orders-api", "s3cret+%:");
+
+        let response = client.introspect(&token, None).await.unwrap();
+        assert!(response.active);
+        assert_eq!(response.sub.as_deref(), Some("user123"));
+
+        denylist.revoke_claims(&claims).await.unwrap();
+        assert!(client.is_token_active(&token).await);
+
+        client.clear_cache();
+        assert!(!client.is_token_active(&token).await);
+
+        let uncached = IntrospectionClient::new(url, "orders-api", "s3cret+%:").with_cache_ttl(Duration::ZERO);
+        assert!(!uncached.is_token_active(&token).await);
+    }
+
+    #[tokio::test]
+    async fn test_cache_evicts_least_recently_used() {
+        let jwt_service = JwtService::new(b"test-secret-key").unwrap();
+        let denylist = Arc::new(InMemoryTokenDenylist::new());
+        let url = spawn_server(jwt_service.clone(), denylist.clone()).await;
+
+        let first = Claims::new("user1", "tenant456");
+        let second = Claims::new("user2", "tenant456");
+        let (first_token, second_token) = (jwt_service.create_token(&first).unwrap(), jwt_service.create_token(&second).unwrap());
+        let client = IntrospectionClient::new(url, "orders-api", "s3cret+%:").with_cache_capacity(1);
+
+        assert!(client.is_token_active(&first_token).await);
+        assert!(client.is_token_active(&second_token).await);
+        assert_eq!(client.cache.lock().unwrap().len(), 1);
+
+        // The first response was evicted, so its revocation is seen at once
+        denylist.revoke_claims(&first).await.unwrap();
+        denylist.revoke_claims(&second).await.unwrap();
+        assert!(!client.is_token_active(&first_token).await);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_credentials_are_errors() {
+        let jwt_service = JwtService::new(b"test-secret-key").unwrap();
+        let url = spawn_server(jwt_service.clone(), Arc::new(InMemoryTokenDenylist::new())).await;
+        let token = jwt_service.create_token(&Claims::new("user123", "tenant456")).unwrap();
+
+        let client = IntrospectionClient::new(url, "orders-api", "wrong");
+        assert!(matches!(client.introspect(&token, None).await, Err(AuthError::ExternalServiceError(_))));
+        assert!(!client.is_token_active(&token).await);
+    }
+}
@@ -0,0 +1,156 @@
+//! Introspection endpoint router
+
+use std::sync::Arc;
+use axum::{
+    extract::{rejection::FormRejection, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    routing::post,
+    Form, Json, Router,
+};
+use crate::oauth::router::basic_credentials;
+use crate::oauth::{ClientRegistry, OAuthError};
+
+use super::{IntrospectionRequest, IntrospectionResponse, TokenIntrospection};
+
+/// Path of the introspection endpoint
+pub const INTROSPECTION_PATH: &str = "/introspect";
+
+#[derive(Clone)]
+struct IntrospectionState {
+    introspection: Arc<TokenIntrospection>,
+    clients: Arc<ClientRegistry>,
+}
+
+/// Router serving the RFC 7662 introspection endpoint at `/introspect`
+///
+/// Resource servers must be registered as confidential clients and
+/// authenticate with HTTP Basic credentials.
+pub fn introspection_router(introspection: Arc<TokenIntrospection>, clients: Arc<ClientRegistry>) -> Router {
+    Router::new()
+        .route(INTROSPECTION_PATH, post(introspection_endpoint))
+        .with_state(IntrospectionState { introspection, clients })
+}
+
+async fn introspection_endpoint(
+    State(state): State<IntrospectionState>,
+    headers: HeaderMap,
+    form: Result<Form<IntrospectionRequest>, FormRejection>,
+) -> Response {
+    match handle_introspection_request(&state, &headers, form).await {
+        Ok(response) => {
+            let mut response = Json(response).into_response();
+            response.headers_mut().insert(http::header::CACHE_CONTROL, http::HeaderValue::from_static("no-store"));
+            response
+        }
+        Err(error) => error.into_response(),
+    }
+}
+
+async fn handle_introspection_request(
+    state: &IntrospectionState,
+    headers: &HeaderMap,
+    form: Result<Form<IntrospectionRequest>, FormRejection>,
+) -> Result<IntrospectionResponse, OAuthError> {
+    let (client_id, client_secret) = basic_credentials(headers)?.ok_or(OAuthError::InvalidClient)?;
+    state.clients
+        .authenticate(&client_id, Some(&client_secret))
+        .await
+        .ok_or(OAuthError::InvalidClient)?;
+
+    let Form(request) = form.map_err(|e| OAuthError::InvalidRequest(e.body_text()))?;
+    Ok(state.introspection.introspect(request).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use base64::Engine;
+    use tower::ServiceExt;
+    use crate::oauth::OAuthClient;
+    use crate::refresh_tokens::{RefreshTokenConfig, RefreshTokenManager};
+    use crate::{Claims, JwtService};
+
+    fn app() -> (Router, JwtService, Arc<RefreshTokenManager>) {
+        let jwt_service = JwtService::new(b"test-secret-key").unwrap();
+        let refresh_tokens = Arc::new(RefreshTokenManager::new(jwt_service.clone(), RefreshTokenConfig::default()));
+        let introspection = TokenIntrospection::new(jwt_service.clone()).with_refresh_tokens(refresh_tokens.clone());
+        let clients = Arc::new(ClientRegistry::new());
+        clients.register(OAuthClient::confidential("orders-api", "s3cret", vec!["https://orders.example.com/cb".to_string()]).unwrap());
+        clients.register(OAuthClient::public("spa", vec!["https://app.example.com/cb".to_string()]).unwrap());
+        (introspection_router(Arc::new(introspection), clients), jwt_service, refresh_tokens)
+    }
+
+    fn introspect_request(body: String, credentials: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri(INTROSPECTION_PATH)
+            .header("content-type", "application/x-www-form-urlencoded");
+        if let Some(credentials) = credentials {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+            builder = builder.header("authorization", format!("Basic {}", encoded));
+        }
+        builder.body(Body::from(body)).unwrap()
+    }
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_introspect_access_token() {
+        let (app, jwt_service, _) = app();
+        let token = jwt_service.create_token(&Claims::new("user123", "tenant456")).unwrap();
+
+        let body = format!("token={}&token_type_hint=access_token", token);
+        let response = app.clone().oneshot(introspect_request(body, Some("orders-api:s3cret"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["cache-control"], "no-store");
+        let json = json_body(response).await;
+        assert_eq!(json["active"], true);
+        assert_eq!(json["sub"], "user123");
+        assert_eq!(json["token_type"], "Bearer");
+
+        let response = app.oneshot(introspect_request("token=garbage".to_string(), Some("orders-api:s3cret"))).await.unwrap();
+        assert_eq!(json_body(response).await["active"], false);
+    }
+
+    #[tokio::test]
+    async fn test_introspect_refresh_token() {
+        let (app, _, refresh_tokens) = app();
+        let token = refresh_tokens.create_refresh_token("user123", None, None, None).await.unwrap();
+
+        // A wrong hint only changes the lookup order
+        for hint in ["refresh_token", "access_token"] {
+            let body = format!("token={}&token_type_hint={}", token, hint);
+            let response = app.clone().oneshot(introspect_request(body, Some("orders-api:s3cret"))).await.unwrap();
+            let json = json_body(response).await;
+            assert_eq!(json["active"], true);
+            assert_eq!(json["token_type"], "refresh_token");
+        }
+
+        refresh_tokens.revoke_token(&token).await.unwrap();
+        let body = format!("token={}&token_type_hint=refresh_token", token);
+        let response = app.oneshot(introspect_request(body, Some("orders-api:s3cret"))).await.unwrap();
+        assert_eq!(json_body(response).await["active"], false);
+    }
+
+    #[tokio::test]
+    async fn test_requires_client_authentication() {
+        let (app, jwt_service, _) = app();
+        let token = jwt_service.create_token(&Claims::new("user123", "tenant456")).unwrap();
+        let body = format!("token={}", token);
+
+        for credentials in [None, Some("orders-api:wrong"), Some("spa:")] {
+            let response = app.clone().oneshot(introspect_request(body.clone(), credentials)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(response.headers()["www-authenticate"], "Basic");
+        }
+
+        let response = app.oneshot(introspect_request(String::new(), Some("orders-api:s3cret"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
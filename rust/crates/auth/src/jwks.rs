@@ -0,0 +1,266 @@
+//! JSON Web Key Set publishing and validation.
+//!
+//! This module serves the public keys of a `KeyManager` as a JWKS document
+//! and validates tokens issued by other services against their published
+//! JWKS, selecting the verification key by the token's `kid` header.
+
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use tokio::sync::RwLock;
+use crate::Claims;
+use crate::types::{AuthResult, AuthError};
+#[cfg(feature = "observability")]
+use cloudshuttle_observability::audit::{audit_auth, AuditResult};
+
+/// Well-known path for the JWKS document
+pub const JWKS_PATH: &str = "/.well-known/jwks.json";
+
+/// Router serving the key manager's public keys at `/.well-known/jwks.json`
+///
+/// The key manager is read on every request, so rotations are published
+/// immediately.
+#[cfg(feature = "axum")]
+pub fn jwks_router(keys: std::sync::Arc<std::sync::RwLock<crate::keys::KeyManager>>) -> axum::Router {
+    use axum::{extract::State, response::IntoResponse, routing::get, Json};
+
+    async fn serve_jwks(
+        State(keys): State<std::sync::Arc<std::sync::RwLock<crate::keys::KeyManager>>>,
+    ) -> axum::response::Response {
+        let jwks = match keys.read().unwrap().jwks() {
+            Ok(jwks) => jwks,
+            Err(e) => return e.into_response(),
+        };
+        (
+            [(http::header::CACHE_CONTROL, "public, max-age=300")],
+            Json(jwks),
+        ).into_response()
+    }
+
+    axum::Router::new()
+        .route(JWKS_PATH, get(serve_jwks))
+        .with_state(keys)
+}
+
+/// Cached copy of a remote JWKS document
+struct CachedJwks {
+    keys: JwkSet,
+    fetched_at: Instant,
+}
+
+/// Validates tokens against a remote JWKS endpoint
+pub struct JwksValidator {
+    jwks_url: String,
+    client: reqwest::Client,
+    cache: RwLock<Option<CachedJwks>>,
+    cache_ttl: Duration,
+    min_refresh_interval: Duration,
+    issuer: Option<String>,
+    audience: String,
+    leeway: u64,
+}
+
+impl JwksValidator {
+    /// Create a validator for the JWKS document at `jwks_url`
+    pub fn new(jwks_url: impl Into<String>) -> Self {
+        Self {
+            jwks_url: jwks_url.into(),
+            client: reqwest::Client::new(),
+            cache: RwLock::new(None),
+            cache_ttl: Duration::from_secs(300),
+            min_refresh_interval: Duration::from_secs(30),
+            issuer: None,
+            audience: "cloudshuttle-api".to_string(),
+            leeway: 30,
+        }
+    }
+
+    /// Configure how long a fetched JWKS document is trusted
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Configure the minimum time between refetches triggered by unknown key IDs
+    pub fn with_min_refresh_interval(mut self, interval: Duration) -> Self {
+        self.min_refresh_interval = interval;
+        self
+    }
+
+    /// Require tokens to have this issuer
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Require tokens to have this audience
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = audience.into();
+        self
+    }
+
+    /// Use a preconfigured HTTP client (timeouts, proxies, TLS roots)
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Validate and decode a token signed by a key in the remote JWKS
+    pub async fn validate_token(&self, token: &str) -> AuthResult<Claims> {
+        let header = decode_header(token)
+            .map_err(|e| AuthError::TokenValidation(e.to_string()))?;
+
+        if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+            return Err(AuthError::InvalidToken("Symmetric algorithms cannot be verified with a JWKS".to_string()));
+        }
+
+        let key_id = header.kid
+            .ok_or_else(|| AuthError::InvalidToken("Missing key ID".to_string()))?;
+        let jwk = self.find_key(&key_id).await?;
+
+        if let Some(key_algorithm) = jwk.common.key_algorithm {
+            let expected = Algorithm::from_str(&key_algorithm.to_string()).ok();
+            if expected != Some(header.alg) {
+                return Err(AuthError::InvalidToken("Token algorithm does not match key".to_string()));
+            }
+        }
+
+        let decoding_key = DecodingKey::from_jwk(&jwk)
+            .map_err(|e| AuthError::InvalidKey(e.to_string()))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.validate_exp = true;
+        validation.validate_nbf = false;
+        validation.leeway = self.leeway;
+        validation.set_audience(&[&self.audience]);
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+
+        let token_data = decode::<Claims>(token, &decoding_key, &validation)
+            .map_err(|e| {
+                #[cfg(feature = "observability")]
+                audit_auth("jwks_token_validation_failed", None, AuditResult::Failure);
+                AuthError::TokenValidation(e.to_string())
+            })?;
+
+        Ok(token_data.claims)
+    }
+
+    /// Fetch the JWKS document and replace the cache
+    pub async fn refresh(&self) -> AuthResult<JwkSet> {
+        let keys = self.client
+            .get(&self.jwks_url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| AuthError::ExternalServiceError(format!("JWKS fetch failed: {}", e)))?
+            .json::<JwkSet>()
+            .await
+            .map_err(|e| AuthError::ExternalServiceError(format!("Invalid JWKS document: {}", e)))?;
+
+        let mut cache = self.cache.write().await;
+        *cache = Some(CachedJwks {
+            keys: keys.clone(),
+            fetched_at: Instant::now(),
+        });
+
+        Ok(keys)
+    }
+
+    /// Find a key by ID, refetching when the cache is stale or the key is unknown
+    async fn find_key(&self, key_id: &str) -> AuthResult<Jwk> {
+        let should_refresh = {
+            let cache = self.cache.read().await;
+            match cache.as_ref() {
+                Some(cached) if cached.fetched_at.elapsed() < self.cache_ttl => {
+                    if let Some(jwk) = cached.keys.find(key_id) {
+                        return Ok(jwk.clone());
+                    }
+                    // Unknown key: the issuer may have rotated, but avoid refetch storms
+                    cached.fetched_at.elapsed() >= self.min_refresh_interval
+                }
+                _ => true,
+            }
+        };
+
+        if !should_refresh {
+            return Err(AuthError::InvalidToken("Unknown key ID".to_string()));
+        }
+
+        self.refresh().await?
+            .find(key_id)
+            .cloned()
+            .ok_or_else(|| AuthError::InvalidToken("Unknown key ID".to_string()))
+    }
+}
+
+#[cfg(all(test, feature = "axum"))]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, RwLock as StdRwLock};
+    use crate::keys::{KeyManager, SigningKeyPair};
+    use crate::JwtService;
+
+    /// Serve a key manager's JWKS from a local stand-in issuer
+    async fn spawn_issuer(keys: Arc<StdRwLock<KeyManager>>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, jwks_router(keys)).await.unwrap();
+        });
+        format!("http://{}{}", address, JWKS_PATH)
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_from_remote_jwks() {
+        let key = SigningKeyPair::generate_ed25519().unwrap();
+        let issuer = JwtService::from_signing_key(&key).unwrap();
+        let keys = Arc::new(StdRwLock::new(KeyManager::new(key)));
+        let url = spawn_issuer(keys).await;
+
+        let token = issuer.create_access_token("user-123", "tenant-456", vec!["admin".to_string()]).unwrap();
+        let validator = JwksValidator::new(url);
+
+        let claims = validator.validate_token(&token).await.unwrap();
+        assert_eq!(claims.sub, "user-123");
+        assert_eq!(claims.roles, vec!["admin".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_rotated_key_is_picked_up() {
+        let old_key = SigningKeyPair::generate_ed25519().unwrap();
+        let keys = Arc::new(StdRwLock::new(KeyManager::new(old_key.clone())));
+        let url = spawn_issuer(keys.clone()).await;
+        let validator = JwksValidator::new(url).with_min_refresh_interval(Duration::ZERO);
+
+        let old_token = JwtService::from_signing_key(&old_key).unwrap()
+            .create_access_token("user-123", "tenant-456", vec![]).unwrap();
+        assert!(validator.validate_token(&old_token).await.is_ok());
+
+        // Rotate on the issuer; the validator refetches on the unknown kid
+        let new_key = SigningKeyPair::generate_ed25519().unwrap();
+        keys.write().unwrap().rotate_key(new_key.clone()).unwrap();
+        let new_token = JwtService::from_signing_key(&new_key).unwrap()
+            .create_access_token("user-123", "tenant-456", vec![]).unwrap();
+
+        assert!(validator.validate_token(&new_token).await.is_ok());
+        assert!(validator.validate_token(&old_token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unknown_and_symmetric_tokens() {
+        let keys = Arc::new(StdRwLock::new(KeyManager::new(SigningKeyPair::generate_ed25519().unwrap())));
+        let url = spawn_issuer(keys).await;
+        let validator = JwksValidator::new(url);
+
+        let stranger = JwtService::from_signing_key(&SigningKeyPair::generate_ed25519().unwrap()).unwrap()
+            .create_access_token("user-123", "tenant-456", vec![]).unwrap();
+        assert!(matches!(validator.validate_token(&stranger).await, Err(AuthError::InvalidToken(_))));
+
+        let hmac = JwtService::new(b"test-secret-key").unwrap().with_key_id("shared")
+            .create_access_token("user-123", "tenant-456", vec![]).unwrap();
+        assert!(matches!(validator.validate_token(&hmac).await, Err(AuthError::InvalidToken(_))));
+    }
+}
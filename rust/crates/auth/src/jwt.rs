@@ -18,6 +18,7 @@ use crate::Claims;
 use crate::types::{AuthResult, AuthError};
 
 /// Legacy JWT service for backward compatibility
+#[derive(Clone)]
 pub struct JwtService {
     token_service: TokenService,
 }
@@ -47,6 +48,26 @@ impl JwtService {
         Ok(Self { token_service })
     }
 
+    /// Create a JWT service signing with an asymmetric key pair
+    pub fn from_signing_key(key: &crate::keys::SigningKeyPair) -> AuthResult<Self> {
+        let token_service = TokenService::from_signing_key(key)?;
+        Ok(Self { token_service })
+    }
+
+    /// Create a JWT service that signs and validates with a shared key manager,
+    /// picking up its rotations as they happen
+    pub fn from_key_manager(keys: std::sync::Arc<std::sync::RwLock<crate::keys::KeyManager>>) -> AuthResult<Self> {
+        let token_service = TokenService::from_key_manager(keys)?;
+        Ok(Self { token_service })
+    }
+
+    /// Configure the key ID stamped into the `kid` header of issued tokens
+    pub fn with_key_id(self, key_id: impl Into<String>) -> Self {
+        Self {
+            token_service: self.token_service.with_key_id(key_id),
+        }
+    }
+
     /// Configure issuer (backward compatibility)
     pub fn with_issuer(self, issuer: impl Into<String>) -> Self {
         Self {
@@ -68,6 +89,30 @@ impl JwtService {
         }
     }
 
+    /// Expand roles and permissions through the registry in every issued token
+    pub fn with_role_registry(self, role_registry: std::sync::Arc<crate::roles::RoleRegistry>) -> Self {
+        Self {
+            token_service: self.token_service.with_role_registry(role_registry),
+        }
+    }
+
+    /// Configure how long rotated-out keys remain valid for validation
+    pub fn with_grace_period(self, grace_period: chrono::Duration) -> Self {
+        Self {
+            token_service: self.token_service.with_grace_period(grace_period),
+        }
+    }
+
+    /// Rotate to a new secret, keeping the previous one valid for the grace period
+    pub fn rotate_secret(&mut self, new_secret: &[u8]) -> AuthResult<()> {
+        self.token_service.rotate_secret(new_secret)
+    }
+
+    /// Rotate to a new signing key, keeping the previous one valid for the grace period
+    pub fn rotate_signing_key(&mut self, key: &crate::keys::SigningKeyPair) -> AuthResult<()> {
+        self.token_service.rotate_signing_key(key)
+    }
+
     /// Create a JWT token from claims (backward compatibility)
     pub fn create_token(&self, claims: &Claims) -> AuthResult<String> {
         self.token_service.create_token(claims)
@@ -1,13 +1,35 @@
 //! JWT token creation, validation, and refresh operations
 
-use jsonwebtoken::{encode, decode, Header, EncodingKey, DecodingKey, Validation, Algorithm};
+use chrono::{DateTime, Duration, Utc};
+use std::borrow::Cow;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{encode, decode, decode_header, Header, EncodingKey, DecodingKey, TokenData, Validation, Algorithm};
+use std::sync::{Arc, PoisonError, RwLock};
 use std::time::{SystemTime, UNIX_EPOCH};
+use crate::keys::{KeyManager, DEFAULT_KEY_GRACE_PERIOD_DAYS};
 use crate::Claims;
+use crate::roles::RoleRegistry;
 use crate::types::{AuthResult, AuthError};
 #[cfg(feature = "observability")]
 use cloudshuttle_observability::audit::{audit_auth, AuditResult};
 
+/// Verification key kept after a rotation so outstanding tokens stay valid
+#[derive(Clone)]
+struct RetiredKey {
+    key_id: Option<String>,
+    algorithm: Algorithm,
+    decoding_key: DecodingKey,
+    expires_at: DateTime<Utc>,
+}
+
+impl RetiredKey {
+    fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at > now
+    }
+}
+
 /// JWT token service
+#[derive(Clone)]
 pub struct TokenService {
     encoding_key: EncodingKey,
     decoding_key: DecodingKey,
@@ -15,6 +37,11 @@ pub struct TokenService {
     issuer: String,
     audience: String,
     default_expiry: u64,
+    key_id: Option<String>,
+    previous_keys: Vec<RetiredKey>,
+    grace_period: Duration,
+    key_manager: Option<Arc<RwLock<KeyManager>>>,
+    role_registry: Option<Arc<RoleRegistry>>,
 }
 
 impl TokenService {
@@ -40,9 +67,54 @@ impl TokenService {
             issuer: "cloudshuttle".to_string(),
             audience: "cloudshuttle-api".to_string(),
             default_expiry: 3600, // 1 hour
+            key_id: None,
+            previous_keys: Vec::new(),
+            grace_period: Duration::days(DEFAULT_KEY_GRACE_PERIOD_DAYS),
+            key_manager: None,
+            role_registry: None,
         })
     }
 
+    /// Create a token service signing with an asymmetric key pair
+    ///
+    /// Issued tokens carry the key's ID in the `kid` header so verifiers
+    /// can select the matching key from a JWKS document.
+    pub fn from_signing_key(key: &crate::keys::SigningKeyPair) -> AuthResult<Self> {
+        let algorithm = key.jwt_algorithm()?;
+
+        let mut validation = Validation::new(algorithm);
+        validation.validate_exp = true;
+        validation.validate_nbf = false;
+        validation.leeway = 30;
+        validation.set_audience(&["cloudshuttle-api"]);
+
+        Ok(Self {
+            encoding_key: key.encoding_key()?,
+            decoding_key: key.decoding_key()?,
+            validation,
+            issuer: "cloudshuttle".to_string(),
+            audience: "cloudshuttle-api".to_string(),
+            default_expiry: 3600,
+            key_id: Some(key.key_id()?),
+            previous_keys: Vec::new(),
+            grace_period: Duration::days(DEFAULT_KEY_GRACE_PERIOD_DAYS),
+            key_manager: None,
+            role_registry: None,
+        })
+    }
+
+    /// Create a token service that signs and validates with a shared key manager
+    ///
+    /// Every token is signed with the manager's current key and validated
+    /// against its current and retired keys, so rotations made through the
+    /// manager (including [`KeyManager::spawn_rotation`]) take effect
+    /// immediately.
+    pub fn from_key_manager(keys: Arc<RwLock<KeyManager>>) -> AuthResult<Self> {
+        let mut service = Self::from_signing_key(keys.read().unwrap_or_else(PoisonError::into_inner).current_key())?;
+        service.key_manager = Some(keys);
+        Ok(service)
+    }
+
     /// Configure issuer
     pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
         self.issuer = issuer.into();
@@ -61,17 +133,108 @@ impl TokenService {
         self
     }
 
+    /// Configure the key ID stamped into the `kid` header of issued tokens
+    pub fn with_key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    /// Get the key ID stamped into issued tokens
+    pub fn key_id(&self) -> Option<String> {
+        match &self.key_manager {
+            Some(keys) => keys.read().unwrap_or_else(PoisonError::into_inner).current_key().key_id().ok(),
+            None => self.key_id.clone(),
+        }
+    }
+
+    /// Configure how long rotated-out keys remain valid for validation
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Expand roles and permissions through the registry in every issued token
+    pub fn with_role_registry(mut self, role_registry: Arc<RoleRegistry>) -> Self {
+        self.role_registry = Some(role_registry);
+        self
+    }
+
+    /// Rotate to a new secret using the current algorithm
+    ///
+    /// Tokens signed with the previous secret keep validating for the grace period.
+    pub fn rotate_secret(&mut self, new_secret: &[u8]) -> AuthResult<()> {
+        if self.key_manager.is_some() {
+            return Err(AuthError::UnsupportedAlgorithm(
+                "Services backed by a key manager cannot rotate to an HMAC secret".to_string()
+            ));
+        }
+        let (encoding_key, decoding_key) = Self::create_keys(new_secret, self.validation.algorithms[0])?;
+        self.retire_current_key(encoding_key, decoding_key);
+        Ok(())
+    }
+
+    /// Rotate to a new asymmetric signing key
+    ///
+    /// Tokens signed with the previous key keep validating for the grace period.
+    /// A service backed by a key manager rotates the manager itself.
+    pub fn rotate_signing_key(&mut self, key: &crate::keys::SigningKeyPair) -> AuthResult<()> {
+        if let Some(keys) = &self.key_manager {
+            return keys.write().unwrap_or_else(PoisonError::into_inner).rotate_key(key.clone());
+        }
+        let algorithm = key.jwt_algorithm()?;
+        let key_id = key.key_id()?;
+        self.retire_current_key(key.encoding_key()?, key.decoding_key()?);
+        self.validation.algorithms = vec![algorithm];
+        self.key_id = Some(key_id);
+        Ok(())
+    }
+
+    fn retire_current_key(&mut self, encoding_key: EncodingKey, decoding_key: DecodingKey) {
+        let now = Utc::now();
+        let previous = std::mem::replace(&mut self.decoding_key, decoding_key);
+        self.encoding_key = encoding_key;
+        self.previous_keys.push(RetiredKey {
+            key_id: self.key_id.clone(),
+            algorithm: self.validation.algorithms[0],
+            decoding_key: previous,
+            expires_at: now + self.grace_period,
+        });
+        self.previous_keys.retain(|key| key.is_active(now));
+    }
+
     /// Create a JWT token from claims
+    ///
+    /// With a role registry configured, the roles and permissions are
+    /// replaced by their effective sets before signing.
     pub fn create_token(&self, claims: &Claims) -> AuthResult<String> {
-        let header = Header::new(self.validation.algorithms[0]);
+        let claims = match &self.role_registry {
+            Some(role_registry) => {
+                let mut claims = claims.clone();
+                role_registry.resolve_claims(&mut claims);
+                Cow::Owned(claims)
+            }
+            None => Cow::Borrowed(claims),
+        };
+
+        if let Some(keys) = &self.key_manager {
+            let keys = keys.read().unwrap_or_else(PoisonError::into_inner);
+            let key = keys.current_key().jwt_keys()?;
+            let mut header = Header::new(key.algorithm);
+            header.kid = Some(key.key_id.clone());
+            return encode(&header, &*claims, &key.encoding_key)
+                .map_err(|e| AuthError::TokenCreation(e.to_string()));
+        }
 
-        encode(&header, claims, &self.encoding_key)
+        let mut header = Header::new(self.validation.algorithms[0]);
+        header.kid = self.key_id.clone();
+
+        encode(&header, &*claims, &self.encoding_key)
             .map_err(|e| AuthError::TokenCreation(e.to_string()))
     }
 
     /// Validate and decode a JWT token
     pub fn validate_token(&self, token: &str) -> AuthResult<Claims> {
-        let token_data = decode::<Claims>(token, &self.decoding_key, &self.validation)
+        let token_data = self.decode_claims(token, &self.validation)
             .map_err(|e| {
                 #[cfg(feature = "observability")]
                 audit_auth("token_validation_failed", None, AuditResult::Failure);
@@ -123,7 +286,7 @@ impl TokenService {
         let mut validation = self.validation.clone();
         validation.validate_exp = false; // Skip expiry validation
 
-        let token_data = decode::<Claims>(token, &self.decoding_key, &validation)
+        let token_data = self.decode_claims(token, &validation)
             .map_err(|e| AuthError::TokenValidation(e.to_string()))?;
 
         Ok(token_data.claims)
@@ -154,6 +317,36 @@ impl TokenService {
         Ok(())
     }
 
+    /// Decode with the current key, falling back to retired keys
+    ///
+    /// A `kid` naming a retired key selects it directly; otherwise retired
+    /// keys are only tried when the current key's signature check fails.
+    fn decode_claims(&self, token: &str, validation: &Validation) -> jsonwebtoken::errors::Result<TokenData<Claims>> {
+        if let Some(keys) = &self.key_manager {
+            return decode_with_manager(token, &keys.read().unwrap_or_else(PoisonError::into_inner), validation);
+        }
+
+        let now = Utc::now();
+        let kid = decode_header(token).ok().and_then(|header| header.kid);
+        let mut retired = self.previous_keys.iter().rev().filter(|key| key.is_active(now));
+
+        if let Some(kid) = kid.as_deref().filter(|kid| Some(*kid) != self.key_id.as_deref()) {
+            if let Some(key) = retired.clone().find(|key| key.key_id.as_deref() == Some(kid)) {
+                return decode_with_retired(token, key, validation);
+            }
+        }
+
+        let result = decode::<Claims>(token, &self.decoding_key, validation);
+        match &result {
+            Err(e) if matches!(e.kind(), ErrorKind::InvalidSignature | ErrorKind::InvalidAlgorithm) => {
+                retired
+                    .find_map(|key| decode_with_retired(token, key, validation).ok())
+                    .map_or(result, Ok)
+            }
+            _ => result,
+        }
+    }
+
     fn create_keys(secret: &[u8], algorithm: Algorithm) -> AuthResult<(EncodingKey, DecodingKey)> {
         let encoding_key = match algorithm {
             Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
@@ -196,6 +389,36 @@ impl TokenService {
     }
 }
 
+/// Decode with the key the `kid` header names, or else each of the manager's keys in turn
+fn decode_with_manager(token: &str, keys: &KeyManager, validation: &Validation) -> jsonwebtoken::errors::Result<TokenData<Claims>> {
+    let kid = decode_header(token)?.kid;
+    let candidates = match kid.as_deref().and_then(|kid| keys.find_key(kid)) {
+        Some(key) => vec![key],
+        None => keys.all_keys(),
+    };
+
+    let mut result = Err(ErrorKind::InvalidSignature.into());
+    for key in candidates {
+        let Ok(key) = key.jwt_keys() else {
+            continue;
+        };
+        let mut validation = validation.clone();
+        validation.algorithms = vec![key.algorithm];
+        result = decode::<Claims>(token, &key.decoding_key, &validation);
+        match &result {
+            Err(e) if matches!(e.kind(), ErrorKind::InvalidSignature) => continue,
+            _ => break,
+        }
+    }
+    result
+}
+
+fn decode_with_retired(token: &str, key: &RetiredKey, validation: &Validation) -> jsonwebtoken::errors::Result<TokenData<Claims>> {
+    let mut validation = validation.clone();
+    validation.algorithms = vec![key.algorithm];
+    decode::<Claims>(token, &key.decoding_key, &validation)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -243,6 +466,29 @@ mod tests {
         assert!(validated.exp > validated.iat);
     }
 
+    #[test]
+    fn test_access_token_resolves_role_hierarchy() {
+        use crate::roles::Role;
+
+        let registry = RoleRegistry::from_roles([
+            Role::new("admin").inherits(["editor"]).with_permissions(["users:write"]),
+            Role::new("editor").with_permissions(["documents:write"]),
+        ]).unwrap();
+        let service = TokenService::new(b"test-secret-key").unwrap().with_role_registry(Arc::new(registry));
+
+        let token = service.create_access_token("user-123", "tenant-456", vec!["admin".to_string()]).unwrap();
+        let validated = service.validate_token(&token).unwrap();
+
+        assert!(validated.has_role("editor"));
+        assert_eq!(validated.permissions, vec!["documents:write".to_string(), "users:write".to_string()]);
+
+        // Tokens built from caller-supplied claims are resolved too
+        let mut claims = Claims::new("user-123", "tenant-456");
+        claims.roles = vec!["editor".to_string()];
+        let validated = service.validate_token(&service.create_token(&claims).unwrap()).unwrap();
+        assert_eq!(validated.permissions, vec!["documents:write".to_string()]);
+    }
+
     #[test]
     fn test_refresh_token_creation() {
         let service = TokenService::new(b"test-secret-key").unwrap();
@@ -265,4 +511,67 @@ mod tests {
         assert!(service.validate_token_type(&claims, "refresh").is_ok());
         assert!(service.validate_token_type(&claims, "access").is_err());
     }
+
+    #[test]
+    fn test_signing_key_stamps_kid() {
+        let key = crate::keys::SigningKeyPair::generate_ed25519().unwrap();
+        let service = TokenService::from_signing_key(&key).unwrap();
+
+        let token = service.create_access_token("user-123", "tenant-456", vec![]).unwrap();
+        let header = jsonwebtoken::decode_header(&token).unwrap();
+        assert_eq!(header.alg, Algorithm::EdDSA);
+        assert_eq!(header.kid, Some(key.key_id().unwrap()));
+
+        let validated = service.validate_token(&token).unwrap();
+        assert_eq!(validated.sub, "user-123");
+    }
+
+    #[test]
+    fn test_rotated_secret_validates_during_grace_period() {
+        let mut service = TokenService::new(b"original-secret").unwrap();
+        let old_token = service.create_access_token("user-123", "tenant-456", vec![]).unwrap();
+
+        service.rotate_secret(b"rotated-secret").unwrap();
+        let new_token = service.create_access_token("user-123", "tenant-456", vec![]).unwrap();
+
+        assert!(service.validate_token(&old_token).is_ok());
+        assert!(service.validate_token(&new_token).is_ok());
+        assert!(TokenService::new(b"original-secret").unwrap().validate_token(&new_token).is_err());
+    }
+
+    #[test]
+    fn test_retired_key_rejected_after_grace_period() {
+        let mut service = TokenService::new(b"original-secret").unwrap()
+            .with_grace_period(Duration::zero());
+        let old_token = service.create_access_token("user-123", "tenant-456", vec![]).unwrap();
+
+        service.rotate_secret(b"rotated-secret").unwrap();
+        assert!(service.validate_token(&old_token).is_err());
+    }
+
+    #[test]
+    fn test_rotated_signing_key_selected_by_kid() {
+        let old_key = crate::keys::SigningKeyPair::generate_ed25519().unwrap();
+        let new_key = crate::keys::SigningKeyPair::generate_ed25519().unwrap();
+
+        let keys = Arc::new(RwLock::new(KeyManager::new(old_key.clone())));
+        let service = TokenService::from_key_manager(keys.clone()).unwrap();
+        let old_token = service.create_access_token("user-123", "tenant-456", vec![]).unwrap();
+        assert_eq!(service.key_id(), Some(old_key.key_id().unwrap()));
+
+        // Rotating the shared manager switches the service without rebuilding it
+        keys.write().unwrap().rotate_key(new_key.clone()).unwrap();
+        assert_eq!(service.key_id(), Some(new_key.key_id().unwrap()));
+        let new_token = service.create_access_token("user-123", "tenant-456", vec![]).unwrap();
+        assert_eq!(jsonwebtoken::decode_header(&new_token).unwrap().kid, Some(new_key.key_id().unwrap()));
+        assert!(service.validate_token(&old_token).is_ok());
+        assert!(service.validate_token(&new_token).is_ok());
+        assert!(TokenService::from_signing_key(&old_key).unwrap().validate_token(&new_token).is_err());
+
+        let mut rotated = TokenService::from_signing_key(&old_key).unwrap();
+        rotated.rotate_signing_key(&new_key).unwrap();
+        let new_token = rotated.create_access_token("user-123", "tenant-456", vec![]).unwrap();
+        assert!(rotated.validate_token(&old_token).is_ok());
+        assert!(rotated.validate_token(&new_token).is_ok());
+    }
 }
@@ -1,17 +1,38 @@
 //! Key management for JWT tokens
 
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm as JwkAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, ThumbprintHash,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
 use ring::signature::{Ed25519KeyPair, KeyPair as RingKeyPair};
 use ring::rand::SystemRandom;
 use std::fs;
 use std::path::Path;
+use std::sync::{Arc, PoisonError, RwLock};
 use crate::types::{AuthResult, AuthError};
 
+/// DER prefix of an Ed25519 SubjectPublicKeyInfo, followed by the 32-byte key
+const ED25519_SPKI_PREFIX: [u8; 12] = [0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+
 /// Key pair for asymmetric signing
 #[derive(Clone)]
 pub struct SigningKeyPair {
     private_key: Vec<u8>,
     public_key: Vec<u8>,
     algorithm: KeyAlgorithm,
+    jwt: Option<JwtSigningKeys>,
+}
+
+/// JWT material derived from a key pair once, when the key is created or loaded
+#[derive(Clone)]
+pub(crate) struct JwtSigningKeys {
+    pub(crate) key_id: String,
+    pub(crate) algorithm: Algorithm,
+    pub(crate) encoding_key: EncodingKey,
+    pub(crate) decoding_key: DecodingKey,
 }
 
 /// Supported key algorithms
@@ -32,11 +53,11 @@ impl SigningKeyPair {
         let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8_bytes.as_ref())
             .map_err(|_| AuthError::InvalidKey("Failed to create Ed25519 key pair".to_string()))?;
 
-        Ok(Self {
-            private_key: pkcs8_bytes.as_ref().to_vec(),
-            public_key: key_pair.public_key().as_ref().to_vec(),
-            algorithm: KeyAlgorithm::Ed25519,
-        })
+        Self::from_parts(
+            pkcs8_bytes.as_ref().to_vec(),
+            key_pair.public_key().as_ref().to_vec(),
+            KeyAlgorithm::Ed25519,
+        )
     }
 
     /// Generate a new RSA key pair
@@ -53,6 +74,15 @@ impl SigningKeyPair {
         Err(AuthError::UnsupportedAlgorithm("ECDSA key generation not implemented".to_string()))
     }
 
+    /// Generate a new key pair of the given algorithm
+    pub fn generate(algorithm: KeyAlgorithm) -> AuthResult<Self> {
+        match algorithm {
+            KeyAlgorithm::Ed25519 => Self::generate_ed25519(),
+            KeyAlgorithm::RSA => Self::generate_rsa(),
+            KeyAlgorithm::ECDSA => Self::generate_ecdsa(),
+        }
+    }
+
     /// Load key pair from PEM files
     pub fn from_pem(private_key_path: &Path, public_key_path: &Path) -> AuthResult<Self> {
         let private_key = fs::read(private_key_path)
@@ -72,11 +102,28 @@ impl SigningKeyPair {
             return Err(AuthError::InvalidKey("Unknown key format".to_string()));
         };
 
-        Ok(Self {
+        Self::from_parts(private_key, public_key, algorithm)
+    }
+
+    /// Build a key pair, deriving its key ID and JWT keys up front
+    ///
+    /// Only Ed25519 keys can sign JWTs; other algorithms carry no JWT keys.
+    fn from_parts(private_key: Vec<u8>, public_key: Vec<u8>, algorithm: KeyAlgorithm) -> AuthResult<Self> {
+        let mut key = Self {
             private_key,
             public_key,
             algorithm,
-        })
+            jwt: None,
+        };
+        if matches!(algorithm, KeyAlgorithm::Ed25519) {
+            key.jwt = Some(JwtSigningKeys {
+                key_id: key.public_jwk_parameters()?.thumbprint(ThumbprintHash::SHA256),
+                algorithm: Algorithm::EdDSA,
+                encoding_key: key.build_encoding_key()?,
+                decoding_key: key.build_decoding_key()?,
+            });
+        }
+        Ok(key)
     }
 
     /// Get private key bytes
@@ -94,6 +141,106 @@ impl SigningKeyPair {
         self.algorithm
     }
 
+    /// Get the JWT signing algorithm for this key
+    pub fn jwt_algorithm(&self) -> AuthResult<Algorithm> {
+        Ok(self.jwt_keys()?.algorithm)
+    }
+
+    /// Get the key ID (RFC 7638 JWK thumbprint of the public key)
+    pub fn key_id(&self) -> AuthResult<String> {
+        Ok(self.jwt_keys()?.key_id.clone())
+    }
+
+    /// Export the public key as a JWK
+    pub fn to_jwk(&self) -> AuthResult<Jwk> {
+        let mut jwk = self.public_jwk_parameters()?;
+        jwk.common.key_id = Some(self.key_id()?);
+        Ok(jwk)
+    }
+
+    /// Get the jsonwebtoken encoding key
+    pub fn encoding_key(&self) -> AuthResult<EncodingKey> {
+        Ok(self.jwt_keys()?.encoding_key.clone())
+    }
+
+    /// Get the jsonwebtoken decoding key
+    pub fn decoding_key(&self) -> AuthResult<DecodingKey> {
+        Ok(self.jwt_keys()?.decoding_key.clone())
+    }
+
+    /// JWT keys derived when the key was created
+    pub(crate) fn jwt_keys(&self) -> AuthResult<&JwtSigningKeys> {
+        self.jwt.as_ref().ok_or_else(|| {
+            AuthError::UnsupportedAlgorithm(format!("{:?} JWT signing not implemented", self.algorithm))
+        })
+    }
+
+    fn build_encoding_key(&self) -> AuthResult<EncodingKey> {
+        match self.algorithm {
+            KeyAlgorithm::Ed25519 if self.is_raw_ed25519() => Ok(EncodingKey::from_ed_der(&self.private_key)),
+            KeyAlgorithm::Ed25519 => EncodingKey::from_ed_pem(&self.private_key)
+                .map_err(|e| AuthError::InvalidKey(e.to_string())),
+            other => Err(AuthError::UnsupportedAlgorithm(format!("{:?} JWT signing not implemented", other))),
+        }
+    }
+
+    fn build_decoding_key(&self) -> AuthResult<DecodingKey> {
+        match self.algorithm {
+            KeyAlgorithm::Ed25519 if self.is_raw_ed25519() => Ok(DecodingKey::from_ed_der(&self.public_key)),
+            KeyAlgorithm::Ed25519 => DecodingKey::from_ed_pem(&self.public_key)
+                .map_err(|e| AuthError::InvalidKey(e.to_string())),
+            other => Err(AuthError::UnsupportedAlgorithm(format!("{:?} JWT signing not implemented", other))),
+        }
+    }
+
+    /// Generated Ed25519 keys hold a raw 32-byte public key rather than PEM
+    fn is_raw_ed25519(&self) -> bool {
+        self.public_key.len() == 32
+    }
+
+    /// The raw 32-byte Ed25519 public key, read from the SPKI for PEM keys
+    fn raw_ed25519_public_key(&self) -> AuthResult<Vec<u8>> {
+        if self.is_raw_ed25519() {
+            return Ok(self.public_key.clone());
+        }
+
+        let pem = std::str::from_utf8(&self.public_key)
+            .map_err(|_| AuthError::InvalidKey("Public key is not PEM encoded".to_string()))?;
+        let body: String = pem
+            .lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect();
+        let der = base64::engine::general_purpose::STANDARD
+            .decode(body.trim())
+            .map_err(|e| AuthError::InvalidKey(format!("Invalid public key PEM: {}", e)))?;
+
+        match der.strip_prefix(ED25519_SPKI_PREFIX.as_slice()) {
+            Some(key) if key.len() == 32 => Ok(key.to_vec()),
+            _ => Err(AuthError::InvalidKey("Public key is not an Ed25519 SubjectPublicKeyInfo".to_string())),
+        }
+    }
+
+    fn public_jwk_parameters(&self) -> AuthResult<Jwk> {
+        if !matches!(self.algorithm, KeyAlgorithm::Ed25519) {
+            return Err(AuthError::UnsupportedAlgorithm(
+                "JWK export is only supported for Ed25519 keys".to_string()
+            ));
+        }
+
+        Ok(Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(JwkAlgorithm::EdDSA),
+                ..Default::default()
+            },
+            algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(self.raw_ed25519_public_key()?),
+            }),
+        })
+    }
+
     /// Save keys to PEM files
     pub fn save_to_pem(&self, private_key_path: &Path, public_key_path: &Path) -> AuthResult<()> {
         fs::write(private_key_path, &self.private_key)
@@ -106,12 +253,28 @@ impl SigningKeyPair {
     }
 }
 
+/// Default time retired keys remain valid for verification after rotation
+///
+/// Matches the longest default token lifetime, the 30-day refresh token
+/// `max_lifetime`, so no outstanding token is invalidated by a rotation.
+/// Signed refresh tokens from `TokenService` live 7 days by default.
+pub const DEFAULT_KEY_GRACE_PERIOD_DAYS: i64 = 30;
+
+/// Key retired by a rotation, kept for verification until its grace period ends
+#[derive(Clone)]
+struct RetiredSigningKey {
+    key: SigningKeyPair,
+    expires_at: DateTime<Utc>,
+}
+
 /// Key manager for handling key rotation and multiple keys
 pub struct KeyManager {
     current_key: SigningKeyPair,
-    previous_keys: Vec<SigningKeyPair>,
+    current_key_created_at: DateTime<Utc>,
+    previous_keys: Vec<RetiredSigningKey>,
     key_rotation_enabled: bool,
     rotation_interval_days: u32,
+    grace_period: Duration,
 }
 
 impl KeyManager {
@@ -119,16 +282,28 @@ impl KeyManager {
     pub fn new(key: SigningKeyPair) -> Self {
         Self {
             current_key: key,
+            current_key_created_at: Utc::now(),
             previous_keys: Vec::new(),
             key_rotation_enabled: false,
             rotation_interval_days: 30,
+            grace_period: Duration::days(DEFAULT_KEY_GRACE_PERIOD_DAYS),
         }
     }
 
     /// Enable automatic key rotation
+    ///
+    /// Intervals shorter than a day are raised to one day. Rotation generates
+    /// a key of the current key's algorithm; only Ed25519 keys can be
+    /// generated, so `rotate_if_due` fails for RSA and ECDSA keys.
     pub fn with_rotation(mut self, interval_days: u32) -> Self {
         self.key_rotation_enabled = true;
-        self.rotation_interval_days = interval_days;
+        self.rotation_interval_days = interval_days.max(1);
+        self
+    }
+
+    /// Configure how long retired keys remain valid for verification
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
         self
     }
 
@@ -137,39 +312,105 @@ impl KeyManager {
         &self.current_key
     }
 
+    /// Get when the current signing key was put into service
+    pub fn current_key_created_at(&self) -> DateTime<Utc> {
+        self.current_key_created_at
+    }
+
     /// Get all valid keys (current + previous for validation)
+    ///
+    /// Previous keys past their grace period are excluded even before
+    /// `cleanup_old_keys` removes them.
     pub fn all_keys(&self) -> Vec<&SigningKeyPair> {
         let mut keys = vec![&self.current_key];
-        keys.extend(self.previous_keys.iter());
+        keys.extend(self.retired_keys().map(|(key, _)| key));
         keys
     }
 
+    /// Get previous keys still within their grace period, with their expiry
+    pub fn retired_keys(&self) -> impl Iterator<Item = (&SigningKeyPair, DateTime<Utc>)> {
+        let now = Utc::now();
+        self.previous_keys
+            .iter()
+            .rev()
+            .filter(move |retired| retired.expires_at > now)
+            .map(|retired| (&retired.key, retired.expires_at))
+    }
+
     /// Rotate to a new key
+    ///
+    /// The replaced key remains valid for verification for the grace period.
     pub fn rotate_key(&mut self, new_key: SigningKeyPair) -> AuthResult<()> {
-        self.previous_keys.push(self.current_key.clone());
-        self.current_key = new_key;
-
-        // Keep only the last few previous keys to prevent unlimited growth
-        if self.previous_keys.len() > 5 {
-            self.previous_keys.remove(0);
-        }
-
+        let now = Utc::now();
+        let retired = std::mem::replace(&mut self.current_key, new_key);
+        self.previous_keys.push(RetiredSigningKey {
+            key: retired,
+            expires_at: now + self.grace_period,
+        });
+        self.current_key_created_at = now;
+        self.cleanup_old_keys();
         Ok(())
     }
 
     /// Check if key rotation is needed
     pub fn should_rotate(&self) -> bool {
-        // This would typically check against a timestamp stored with the key
-        // For now, always return false
-        false
+        self.key_rotation_enabled
+            && Utc::now() - self.current_key_created_at >= Duration::days(self.rotation_interval_days as i64)
     }
 
-    /// Clean up old keys (remove keys older than retention period)
-    pub fn cleanup_old_keys(&mut self) {
-        // Keep only recent keys
-        while self.previous_keys.len() > 3 {
-            self.previous_keys.remove(0);
+    /// Rotate to a freshly generated key if the rotation interval has elapsed
+    ///
+    /// Returns whether a rotation took place.
+    pub fn rotate_if_due(&mut self) -> AuthResult<bool> {
+        if !self.should_rotate() {
+            return Ok(false);
         }
+
+        self.rotate_key(SigningKeyPair::generate(self.current_key.algorithm())?)?;
+        Ok(true)
+    }
+
+    /// Clean up old keys (remove keys whose grace period has ended)
+    pub fn cleanup_old_keys(&mut self) {
+        let now = Utc::now();
+        self.previous_keys.retain(|retired| retired.expires_at > now);
+    }
+
+    /// Spawn a task that rotates keys on schedule and drops expired keys
+    ///
+    /// The manager is checked every `check_interval`; rotation happens once
+    /// the configured `rotation_interval_days` has elapsed. Token services
+    /// built with `TokenService::from_key_manager` on the same manager sign
+    /// with the new key as soon as it is rotated in.
+    ///
+    /// The new key is generated before the write lock is taken, so token
+    /// signing and validation only wait for the swap.
+    pub fn spawn_rotation(
+        manager: Arc<RwLock<KeyManager>>,
+        check_interval: std::time::Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(check_interval);
+            loop {
+                interval.tick().await;
+                let due = {
+                    let manager = manager.read().unwrap_or_else(PoisonError::into_inner);
+                    manager.should_rotate().then(|| manager.current_key().algorithm())
+                };
+                let new_key = due.map(SigningKeyPair::generate).transpose();
+
+                let mut manager = manager.write().unwrap_or_else(PoisonError::into_inner);
+                match new_key {
+                    Ok(Some(key)) if manager.should_rotate() => match manager.rotate_key(key) {
+                        Ok(()) => tracing::info!("Rotated signing key"),
+                        Err(e) => tracing::warn!("Scheduled key rotation failed: {}", e),
+                    },
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Scheduled key rotation failed: {}", e),
+                }
+                manager.cleanup_old_keys();
+            }
+        })
     }
 
     /// Get key by ID or index
@@ -177,7 +418,7 @@ impl KeyManager {
         if index == 0 {
             Some(&self.current_key)
         } else {
-            self.previous_keys.get(index - 1)
+            self.previous_keys.get(index - 1).map(|retired| &retired.key)
         }
     }
 
@@ -189,6 +430,26 @@ impl KeyManager {
             index - 1 < self.previous_keys.len()
         }
     }
+
+    /// Find a current or previous key by its key ID
+    pub fn find_key(&self, key_id: &str) -> Option<&SigningKeyPair> {
+        self.all_keys()
+            .into_iter()
+            .find(|key| key.jwt_keys().is_ok_and(|keys| keys.key_id == key_id))
+    }
+
+    /// Publish the current and previous public keys as a JWKS document
+    ///
+    /// Fails if any key cannot be exported as a JWK, rather than publishing
+    /// a document that is missing keys tokens are still signed with.
+    pub fn jwks(&self) -> AuthResult<JwkSet> {
+        Ok(JwkSet {
+            keys: self.all_keys()
+                .into_iter()
+                .map(SigningKeyPair::to_jwk)
+                .collect::<AuthResult<_>>()?,
+        })
+    }
 }
 
 /// Key store for persistent key management
@@ -318,3 +579,100 @@ impl Default for KeyRotationPolicy {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_is_due_after_interval() {
+        let mut manager = KeyManager::new(SigningKeyPair::generate_ed25519().unwrap());
+        assert!(!manager.should_rotate());
+
+        manager = manager.with_rotation(30);
+        assert!(!manager.rotate_if_due().unwrap());
+
+        manager.current_key_created_at = Utc::now() - Duration::days(31);
+        let previous_id = manager.current_key().key_id().unwrap();
+        assert!(manager.rotate_if_due().unwrap());
+        assert_ne!(manager.current_key().key_id().unwrap(), previous_id);
+        assert!(manager.find_key(&previous_id).is_some());
+        assert_eq!(manager.jwks().unwrap().keys.len(), 2);
+    }
+
+    fn to_pem(key: &SigningKeyPair) -> SigningKeyPair {
+        let pem = |label: &str, der: &[u8]| {
+            format!(
+                "-----BEGIN {label}-----\n{}\n-----END {label}-----\n",
+                base64::engine::general_purpose::STANDARD.encode(der)
+            )
+            .into_bytes()
+        };
+        let spki = [ED25519_SPKI_PREFIX.as_slice(), key.public_key()].concat();
+        SigningKeyPair::from_parts(pem("PRIVATE KEY", key.private_key()), pem("PUBLIC KEY", &spki), KeyAlgorithm::Ed25519)
+            .unwrap()
+    }
+
+    fn rsa_key() -> SigningKeyPair {
+        let key = SigningKeyPair::generate_ed25519().unwrap();
+        SigningKeyPair::from_parts(key.private_key, key.public_key, KeyAlgorithm::RSA).unwrap()
+    }
+
+    #[test]
+    fn test_pem_keys_export_the_same_jwk() {
+        let key = SigningKeyPair::generate_ed25519().unwrap();
+        let pem_key = to_pem(&key);
+
+        assert_eq!(pem_key.key_id().unwrap(), key.key_id().unwrap());
+        assert_eq!(pem_key.to_jwk().unwrap(), key.to_jwk().unwrap());
+
+        let service = crate::jwt::TokenService::from_signing_key(&pem_key).unwrap();
+        let token = service.create_token(&crate::Claims::new("user-123", "tenant-456")).unwrap();
+        assert_eq!(jsonwebtoken::decode_header(&token).unwrap().kid, Some(key.key_id().unwrap()));
+        assert_eq!(service.validate_token(&token).unwrap().sub, "user-123");
+    }
+
+    #[test]
+    fn test_jwks_fails_for_keys_without_jwk() {
+        let mut manager = KeyManager::new(rsa_key());
+        assert!(manager.jwks().is_err());
+
+        manager.rotate_key(SigningKeyPair::generate_ed25519().unwrap()).unwrap();
+        assert!(manager.jwks().is_err());
+    }
+
+    #[test]
+    fn test_rotation_fails_for_keys_that_cannot_be_generated() {
+        let mut manager = KeyManager::new(rsa_key()).with_rotation(0);
+        assert_eq!(manager.rotation_interval_days, 1);
+
+        // An RSA manager is never silently switched to a generated Ed25519 key
+        manager.current_key_created_at = Utc::now() - Duration::days(2);
+        assert!(matches!(manager.rotate_if_due(), Err(AuthError::UnsupportedAlgorithm(_))));
+        assert!(matches!(manager.current_key().algorithm(), KeyAlgorithm::RSA));
+    }
+
+    #[test]
+    fn test_retired_keys_dropped_after_grace_period() {
+        let mut manager = KeyManager::new(SigningKeyPair::generate_ed25519().unwrap())
+            .with_grace_period(Duration::zero());
+        let previous_id = manager.current_key().key_id().unwrap();
+
+        manager.rotate_key(SigningKeyPair::generate_ed25519().unwrap()).unwrap();
+        assert!(manager.find_key(&previous_id).is_none());
+        assert_eq!(manager.all_keys().len(), 1);
+        assert!(!manager.has_key(1));
+    }
+
+    #[test]
+    fn test_retired_keys_are_kept_until_they_expire() {
+        let mut manager = KeyManager::new(SigningKeyPair::generate_ed25519().unwrap());
+        let first_id = manager.current_key().key_id().unwrap();
+
+        for _ in 0..7 {
+            manager.rotate_key(SigningKeyPair::generate_ed25519().unwrap()).unwrap();
+        }
+        assert_eq!(manager.all_keys().len(), 8);
+        assert!(manager.find_key(&first_id).is_some());
+    }
+}
@@ -8,6 +8,11 @@
 //! - Claims structure and management
 //! - Authentication middleware
 //! - Token refresh utilities
+//! - Access token revocation
+//! - Server-side sessions with sliding expiry
+//! - Attribute-based authorization policies
+//! - Role hierarchies with permission inheritance
+//! - API key authentication
 //! - Secure key management
 //!
 //! ## Example
@@ -33,6 +38,9 @@ pub mod middleware;
 pub mod keys;
 pub mod types;
 pub mod refresh;
+pub mod revocation;
+pub mod roles;
+pub mod sessions;
 pub mod security;
 #[cfg(feature = "token-introspection")]
 pub mod introspection;
@@ -40,22 +48,62 @@ pub mod introspection;
 pub mod pkce;
 #[cfg(feature = "refresh-tokens")]
 pub mod refresh_tokens;
+#[cfg(feature = "jwks")]
+pub mod jwks;
+#[cfg(feature = "mfa")]
+pub mod mfa;
+#[cfg(feature = "oauth")]
+pub mod oauth;
+pub mod authorization;
+#[cfg(feature = "api-keys")]
+pub mod api_keys;
 
 // Re-export main types
 pub use jwt::JwtService;
 pub use claims::Claims;
 #[cfg(feature = "middleware")]
-pub use middleware::AuthMiddleware;
+pub use middleware::{AuthMiddleware, SessionMiddleware};
 pub use keys::{KeyManager, SigningKeyPair as KeyPair};
 pub use types::*;
 pub use refresh::TokenRefresh;
+pub use revocation::{TokenDenylist, InMemoryTokenDenylist};
+#[cfg(feature = "database")]
+pub use revocation::PostgresTokenDenylist;
+pub use roles::{Role, RoleRegistry, EffectiveAccess};
+pub use sessions::{SessionManager, SessionConfig};
+#[cfg(feature = "database")]
+pub use sessions::PostgresSessionStore;
 pub use security::SecurityValidator;
 #[cfg(feature = "token-introspection")]
 pub use introspection::{TokenIntrospection, TokenIntrospectable, IntrospectionRequest, IntrospectionResponse};
+#[cfg(all(feature = "token-introspection", feature = "axum", feature = "oauth"))]
+pub use introspection::introspection_router;
+#[cfg(feature = "introspection-client")]
+pub use introspection::IntrospectionClient;
 #[cfg(feature = "pkce")]
 pub use pkce::{PkceHandler, PkcePair, PkceMethod, PkceAuthorizationRequest, PkceTokenRequest};
 #[cfg(feature = "refresh-tokens")]
-pub use refresh_tokens::{RefreshTokenManager, RefreshTokenConfig, RefreshTokenRequest, RefreshTokenResponse, RefreshTokenRecord};
+pub use refresh_tokens::{RefreshTokenManager, RefreshTokenConfig, RefreshTokenRequest, RefreshTokenResponse, RefreshTokenRecord, RefreshTokenStore, InMemoryRefreshTokenStore};
+#[cfg(all(feature = "refresh-tokens", feature = "database"))]
+pub use refresh_tokens::PostgresRefreshTokenStore;
+#[cfg(feature = "jwks")]
+pub use jwks::JwksValidator;
+#[cfg(all(feature = "jwks", feature = "axum"))]
+pub use jwks::jwks_router;
+#[cfg(feature = "mfa")]
+pub use mfa::{MfaService, UserMfa, TotpEnrollment, Totp, TotpConfig, TotpSecret, BackupCodes, BackupCodeKey, TrustedDevice, TrustedDeviceManager, TrustedDeviceStore, InMemoryTrustedDeviceStore};
+#[cfg(feature = "oauth")]
+pub use oauth::{AuthorizationServer, ClientRegistry, OAuthClient, OAuthError};
+#[cfg(all(feature = "oauth", feature = "axum"))]
+pub use oauth::oauth_router;
+#[cfg(feature = "authorization")]
+pub use authorization::{PolicyEngine, PolicySet, Policy, AccessRequest, Subject, Resource, AuthorizationDecision};
+#[cfg(feature = "api-keys")]
+pub use api_keys::{ApiKeyManager, ApiKeyConfig, ApiKeyRequest, IssuedApiKey, ApiKey, ApiKeyStore, InMemoryApiKeyStore};
+#[cfg(all(feature = "api-keys", feature = "database"))]
+pub use api_keys::PostgresApiKeyStore;
+#[cfg(all(feature = "api-keys", feature = "middleware"))]
+pub use middleware::ApiKeyMiddleware;
 
 // Middleware types
 #[cfg(feature = "middleware")]
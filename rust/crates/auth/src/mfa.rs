@@ -0,0 +1,282 @@
+//! Multi-factor authentication
+//!
+//! This module orchestrates second factors through specialized sub-modules:
+//! - `totp`: RFC 6238 time-based one-time passwords
+//! - `backup_codes`: Hashed single-use recovery codes
+//! - `trusted_devices`: Remember-device tokens that let trusted devices skip MFA
+//!
+//! `MfaService` applies an `MfaConfig` to a user's enrolled factors so login
+//! flows can demand and verify a second factor. Flows check
+//! `TrustedDeviceManager::is_trusted` first and only call `verify_login`
+//! for devices that are not remembered.
+//!
+//! `verify_login` does not limit attempts. Six-digit codes can be guessed
+//! within a drift window if attempts are unlimited, so login flows must
+//! throttle it per account, e.g. with `security::AccountLockout`.
+
+pub mod totp;
+pub mod backup_codes;
+pub mod trusted_devices;
+
+pub use totp::{Totp, TotpAlgorithm, TotpConfig, TotpSecret};
+pub use backup_codes::{BackupCodes, BackupCodeKey};
+pub use trusted_devices::{TrustedDevice, TrustedDeviceManager, TrustedDeviceStore, InMemoryTrustedDeviceStore, device_fingerprint};
+
+use serde::{Deserialize, Serialize};
+use crate::types::{AuthResult, AuthError, MfaConfig, MfaMethod};
+
+/// Second factors enrolled by a user, persisted by the application
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserMfa {
+    /// Base32 TOTP secret, if an authenticator app is enrolled
+    pub totp_secret: Option<String>,
+    /// Last accepted TOTP time step, used for replay protection
+    pub last_totp_step: Option<u64>,
+    /// Unused backup codes
+    pub backup_codes: BackupCodes,
+}
+
+impl UserMfa {
+    /// Check if the user has any usable second factor
+    pub fn is_enrolled(&self) -> bool {
+        self.totp_secret.is_some() || !self.backup_codes.is_empty()
+    }
+}
+
+/// New TOTP enrollment to present to the user
+#[derive(Debug, Clone)]
+pub struct TotpEnrollment {
+    /// Base32 secret for manual entry
+    pub secret: String,
+    /// `otpauth://` URI for QR code enrollment
+    pub provisioning_uri: String,
+}
+
+/// Multi-factor authentication service
+#[derive(Debug, Clone)]
+pub struct MfaService {
+    config: MfaConfig,
+    totp_config: TotpConfig,
+    backup_code_key: Option<BackupCodeKey>,
+}
+
+impl MfaService {
+    /// Create a service applying the given MFA policy
+    pub fn new(config: MfaConfig) -> Self {
+        Self {
+            config,
+            totp_config: TotpConfig::default(),
+            backup_code_key: None,
+        }
+    }
+
+    /// Configure the secret backup code digests are keyed with
+    ///
+    /// Required to issue or accept backup codes.
+    pub fn with_backup_code_key(mut self, key: BackupCodeKey) -> Self {
+        self.backup_code_key = Some(key);
+        self
+    }
+
+    /// Configure TOTP parameters (issuer, digits, period, drift window)
+    pub fn with_totp_config(mut self, totp_config: TotpConfig) -> Self {
+        self.totp_config = totp_config;
+        self
+    }
+
+    /// Get the MFA policy
+    pub fn config(&self) -> &MfaConfig {
+        &self.config
+    }
+
+    /// Check if a user with the given roles must present a second factor
+    pub fn is_required(&self, user_roles: &[String]) -> bool {
+        self.config.is_required_for_user(user_roles)
+    }
+
+    /// Start TOTP enrollment for an account
+    ///
+    /// The secret should be stored in `UserMfa::totp_secret` once the user
+    /// has confirmed a code with `confirm_totp`.
+    pub fn enroll_totp(&self, account_name: &str) -> AuthResult<TotpEnrollment> {
+        self.ensure_allowed(&MfaMethod::TOTP)?;
+
+        let totp = Totp::with_config(TotpSecret::generate()?, self.totp_config.clone());
+        Ok(TotpEnrollment {
+            secret: totp.secret().to_base32(),
+            provisioning_uri: totp.provisioning_uri(account_name),
+        })
+    }
+
+    /// Confirm a pending TOTP enrollment and record it for the user
+    pub fn confirm_totp(&self, user: &mut UserMfa, secret: &str, code: &str) -> AuthResult<()> {
+        let totp = Totp::with_config(TotpSecret::from_base32(secret)?, self.totp_config.clone());
+        let step = totp.verify(code, None)?;
+
+        user.totp_secret = Some(secret.to_string());
+        user.last_totp_step = Some(step);
+        Ok(())
+    }
+
+    /// Replace the user's backup codes with `backup_codes_count` new ones
+    ///
+    /// Returns the plaintext codes to show to the user once.
+    pub fn regenerate_backup_codes(&self, user: &mut UserMfa) -> AuthResult<Vec<String>> {
+        self.ensure_allowed(&MfaMethod::BackupCodes)?;
+
+        let (backup_codes, codes) = BackupCodes::generate(self.config.backup_codes_count, self.backup_code_key()?)?;
+        user.backup_codes = backup_codes;
+        Ok(codes)
+    }
+
+    /// Verify the second factor for a login
+    ///
+    /// Returns `Ok(None)` when the policy does not require MFA for these
+    /// roles, or the method that was used. Fails with `MfaRequired` when no
+    /// code was supplied and `MfaSetupRequired` when the user has no factor
+    /// enrolled. The caller must persist `user` after a successful check, as
+    /// the TOTP replay step or remaining backup codes will have changed.
+    ///
+    /// Attempts are not limited here; the caller must throttle failures per
+    /// account (see the module documentation).
+    pub fn verify_login(
+        &self,
+        user_roles: &[String],
+        user: &mut UserMfa,
+        code: Option<&str>,
+    ) -> AuthResult<Option<MfaMethod>> {
+        if !self.is_required(user_roles) {
+            return Ok(None);
+        }
+        if !user.is_enrolled() {
+            return Err(AuthError::MfaSetupRequired);
+        }
+        let code = code.ok_or(AuthError::MfaRequired)?;
+
+        if let Some(secret) = user.totp_secret.as_deref().filter(|_| self.config.is_method_allowed(&MfaMethod::TOTP)) {
+            let totp = Totp::with_config(TotpSecret::from_base32(secret)?, self.totp_config.clone());
+            if let Ok(step) = totp.verify(code, user.last_totp_step) {
+                user.last_totp_step = Some(step);
+                return Ok(Some(MfaMethod::TOTP));
+            }
+        }
+
+        if self.config.is_method_allowed(&MfaMethod::BackupCodes)
+            && !user.backup_codes.is_empty()
+            && user.backup_codes.consume(code, self.backup_code_key()?).is_ok()
+        {
+            return Ok(Some(MfaMethod::BackupCodes));
+        }
+
+        Err(AuthError::InvalidMfaCode)
+    }
+
+    fn backup_code_key(&self) -> AuthResult<&BackupCodeKey> {
+        self.backup_code_key
+            .as_ref()
+            .ok_or_else(|| AuthError::InvalidKey("No backup code key configured".to_string()))
+    }
+
+    fn ensure_allowed(&self, method: &MfaMethod) -> AuthResult<()> {
+        if self.config.is_method_allowed(method) {
+            Ok(())
+        } else {
+            Err(AuthError::InvalidRequest(format!("MFA method {:?} is not allowed", method)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service() -> MfaService {
+        MfaService::new(MfaConfig {
+            enabled: true,
+            allowed_methods: vec![MfaMethod::TOTP, MfaMethod::BackupCodes],
+            backup_codes_count: 2,
+            ..MfaConfig::default()
+        })
+        .with_backup_code_key(BackupCodeKey::new(b"backup-code-test-key"))
+    }
+
+    fn enrolled_user(service: &MfaService) -> (UserMfa, Totp) {
+        let enrollment = service.enroll_totp("alice@example.com").unwrap();
+        let totp = Totp::new(TotpSecret::from_base32(&enrollment.secret).unwrap());
+        let user = UserMfa {
+            totp_secret: Some(enrollment.secret),
+            ..UserMfa::default()
+        };
+        (user, totp)
+    }
+
+    #[test]
+    fn test_not_required_for_regular_users() {
+        let service = service();
+        let mut user = UserMfa::default();
+
+        assert_eq!(service.verify_login(&["user".to_string()], &mut user, None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_login_demands_second_factor() {
+        let service = service();
+        let admin = vec!["admin".to_string()];
+
+        assert!(matches!(service.verify_login(&admin, &mut UserMfa::default(), None), Err(AuthError::MfaSetupRequired)));
+
+        let (mut user, totp) = enrolled_user(&service);
+        assert!(matches!(service.verify_login(&admin, &mut user, None), Err(AuthError::MfaRequired)));
+
+        let code = totp.generate_current();
+        let wrong = if code == "000000" { "111111" } else { "000000" };
+        assert!(matches!(service.verify_login(&admin, &mut user, Some(wrong)), Err(AuthError::InvalidMfaCode)));
+        assert_eq!(service.verify_login(&admin, &mut user, Some(&code)).unwrap(), Some(MfaMethod::TOTP));
+        assert!(service.verify_login(&admin, &mut user, Some(&code)).is_err());
+    }
+
+    #[test]
+    fn test_backup_codes_honor_count() {
+        let service = service();
+        let admin = vec!["admin".to_string()];
+        let (mut user, _) = enrolled_user(&service);
+
+        let codes = service.regenerate_backup_codes(&mut user).unwrap();
+        assert_eq!(codes.len(), 2);
+
+        assert_eq!(service.verify_login(&admin, &mut user, Some(&codes[0])).unwrap(), Some(MfaMethod::BackupCodes));
+        assert_eq!(user.backup_codes.remaining(), 1);
+    }
+
+    #[test]
+    fn test_disallowed_methods_are_rejected() {
+        let service = MfaService::new(MfaConfig { enabled: true, ..MfaConfig::default() });
+
+        assert!(service.enroll_totp("alice@example.com").is_ok());
+        assert!(matches!(service.regenerate_backup_codes(&mut UserMfa::default()), Err(AuthError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn test_backup_codes_need_a_key() {
+        let service = MfaService::new(MfaConfig {
+            enabled: true,
+            allowed_methods: vec![MfaMethod::BackupCodes],
+            ..MfaConfig::default()
+        });
+
+        assert!(matches!(service.regenerate_backup_codes(&mut UserMfa::default()), Err(AuthError::InvalidKey(_))));
+    }
+
+    #[test]
+    fn test_confirm_totp_enrollment() {
+        let service = service();
+        let enrollment = service.enroll_totp("alice@example.com").unwrap();
+        assert!(enrollment.provisioning_uri.starts_with("otpauth://totp/CloudShuttle:alice%40example.com?secret="));
+
+        let code = Totp::new(TotpSecret::from_base32(&enrollment.secret).unwrap()).generate_current();
+        let mut user = UserMfa::default();
+        service.confirm_totp(&mut user, &enrollment.secret, &code).unwrap();
+        assert!(user.is_enrolled());
+        assert!(user.last_totp_step.is_some());
+    }
+}
@@ -0,0 +1,171 @@
+//! Single-use backup codes
+//!
+//! Codes are shown to the user once and only their HMAC-SHA256 digests are
+//! stored, keyed with a server-side [`BackupCodeKey`], so a leaked database
+//! does not reveal usable codes. Codes are random rather than chosen by the
+//! user, so a keyed hash protects them without a slow password hash.
+
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use crate::security::CryptoUtils;
+use crate::types::{AuthResult, AuthError};
+
+/// Unambiguous characters used in backup codes (no 0/O or 1/I/L)
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 10;
+
+/// Server-side secret that backup code digests are keyed with
+///
+/// Keep it outside the database that stores the digests; changing it
+/// invalidates every issued code.
+#[derive(Clone)]
+pub struct BackupCodeKey {
+    key: hmac::Key,
+}
+
+impl BackupCodeKey {
+    /// Create a key from secret bytes
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            key: hmac::Key::new(hmac::HMAC_SHA256, secret),
+        }
+    }
+
+    /// Hex HMAC-SHA256 digest of a normalized code
+    fn digest(&self, code: &str) -> String {
+        hex::encode(hmac::sign(&self.key, code.as_bytes()).as_ref())
+    }
+}
+
+impl std::fmt::Debug for BackupCodeKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("BackupCodeKey(..)")
+    }
+}
+
+/// Stored set of unused backup code digests
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackupCodes {
+    hashes: Vec<String>,
+}
+
+impl BackupCodes {
+    /// Generate `count` codes, returning the stored set and the plaintext codes
+    ///
+    /// The plaintext codes must be shown to the user immediately; they
+    /// cannot be recovered from the stored set.
+    pub fn generate(count: u32, key: &BackupCodeKey) -> AuthResult<(Self, Vec<String>)> {
+        let mut codes = Vec::with_capacity(count as usize);
+        let mut hashes = Vec::with_capacity(count as usize);
+
+        for _ in 0..count {
+            let code = generate_code()?;
+            hashes.push(key.digest(&normalize(&code)));
+            codes.push(code);
+        }
+
+        Ok((Self { hashes }, codes))
+    }
+
+    /// Restore a stored set of digests
+    pub fn from_hashes(hashes: Vec<String>) -> Self {
+        Self { hashes }
+    }
+
+    /// Digests of the unused codes, for persistence
+    pub fn hashes(&self) -> &[String] {
+        &self.hashes
+    }
+
+    /// Number of unused codes
+    pub fn remaining(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Check if every code has been used
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// Consume a code, removing it so it cannot be used again
+    ///
+    /// Input is case-insensitive and ignores separators. Every stored digest
+    /// is compared in constant time, so timing does not reveal which
+    /// position matched.
+    pub fn consume(&mut self, code: &str, key: &BackupCodeKey) -> AuthResult<()> {
+        let code = normalize(code);
+        if code.len() != CODE_LENGTH {
+            return Err(AuthError::InvalidMfaCode);
+        }
+
+        let digest = key.digest(&code);
+        let matched = self.hashes.iter().enumerate().fold(None, |matched, (index, stored)| {
+            if CryptoUtils::constant_time_eq(stored.as_bytes(), digest.as_bytes()) {
+                Some(index)
+            } else {
+                matched
+            }
+        });
+
+        let index = matched.ok_or(AuthError::InvalidMfaCode)?;
+        self.hashes.remove(index);
+        Ok(())
+    }
+}
+
+/// Generate a code formatted as `XXXXX-XXXXX`
+fn generate_code() -> AuthResult<String> {
+    // Reject bytes above the largest multiple of the alphabet size to avoid modulo bias
+    let limit = (256 / CODE_ALPHABET.len() * CODE_ALPHABET.len()) as u8;
+    let mut chars = String::with_capacity(CODE_LENGTH);
+    while chars.len() < CODE_LENGTH {
+        for byte in CryptoUtils::generate_random_bytes(CODE_LENGTH)? {
+            if byte < limit && chars.len() < CODE_LENGTH {
+                chars.push(CODE_ALPHABET[byte as usize % CODE_ALPHABET.len()] as char);
+            }
+        }
+    }
+    Ok(format!("{}-{}", &chars[..CODE_LENGTH / 2], &chars[CODE_LENGTH / 2..]))
+}
+
+fn normalize(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> BackupCodeKey {
+        BackupCodeKey::new(b"backup-code-test-key")
+    }
+
+    #[test]
+    fn test_generate_stores_only_digests() {
+        let (stored, codes) = BackupCodes::generate(3, &key()).unwrap();
+
+        assert_eq!(codes.len(), 3);
+        assert_eq!(stored.remaining(), 3);
+        assert!(codes.iter().all(|code| code.len() == CODE_LENGTH + 1 && code.as_bytes()[5] == b'-'));
+        assert!(stored.hashes().iter().all(|hash| hash.len() == 64));
+        assert!(codes.iter().all(|code| !stored.hashes().contains(&normalize(code))));
+    }
+
+    #[test]
+    fn test_codes_are_single_use() {
+        let (mut stored, codes) = BackupCodes::generate(2, &key()).unwrap();
+
+        assert!(stored.consume(&codes[0].to_lowercase().replace('-', " "), &key()).is_ok());
+        assert_eq!(stored.remaining(), 1);
+        assert!(matches!(stored.consume(&codes[0], &key()), Err(AuthError::InvalidMfaCode)));
+        assert!(stored.consume("AAAAA-AAAAA", &key()).is_err());
+
+        let mut restored = BackupCodes::from_hashes(stored.hashes().to_vec());
+        assert!(restored.consume(&codes[1], &BackupCodeKey::new(b"another-key")).is_err());
+        assert!(restored.consume(&codes[1], &key()).is_ok());
+        assert!(restored.is_empty());
+    }
+}
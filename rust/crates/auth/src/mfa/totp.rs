@@ -0,0 +1,363 @@
+//! RFC 6238 time-based one-time passwords
+//!
+//! Secrets are exchanged as unpadded base32 strings, the format expected by
+//! authenticator apps scanning an `otpauth://` provisioning URI.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use crate::security::CryptoUtils;
+use crate::types::{AuthResult, AuthError};
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// HMAC algorithm used to derive codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TotpAlgorithm {
+    /// HMAC-SHA1 (default, supported by every authenticator app)
+    #[serde(rename = "SHA1")]
+    Sha1,
+    /// HMAC-SHA256
+    #[serde(rename = "SHA256")]
+    Sha256,
+    /// HMAC-SHA512
+    #[serde(rename = "SHA512")]
+    Sha512,
+}
+
+impl TotpAlgorithm {
+    /// Get the algorithm name used in provisioning URIs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sha1 => "SHA1",
+            Self::Sha256 => "SHA256",
+            Self::Sha512 => "SHA512",
+        }
+    }
+
+    fn hmac_algorithm(&self) -> hmac::Algorithm {
+        match self {
+            Self::Sha1 => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+            Self::Sha256 => hmac::HMAC_SHA256,
+            Self::Sha512 => hmac::HMAC_SHA512,
+        }
+    }
+}
+
+/// TOTP parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotpConfig {
+    /// Issuer shown in authenticator apps
+    pub issuer: String,
+    /// Number of digits per code (6-8)
+    pub digits: u32,
+    /// Time step in seconds
+    pub period: u64,
+    /// HMAC algorithm
+    pub algorithm: TotpAlgorithm,
+    /// Number of time steps accepted either side of the current one to absorb clock drift
+    pub skew: u64,
+}
+
+impl Default for TotpConfig {
+    fn default() -> Self {
+        Self {
+            issuer: "CloudShuttle".to_string(),
+            digits: 6,
+            period: 30,
+            algorithm: TotpAlgorithm::Sha1,
+            skew: 1,
+        }
+    }
+}
+
+/// Shared TOTP secret
+#[derive(Clone, PartialEq, Eq)]
+pub struct TotpSecret {
+    bytes: Vec<u8>,
+}
+
+impl TotpSecret {
+    /// Generate a random 160-bit secret
+    pub fn generate() -> AuthResult<Self> {
+        Ok(Self {
+            bytes: CryptoUtils::generate_random_bytes(20)?,
+        })
+    }
+
+    /// Create a secret from raw bytes
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Self {
+        Self { bytes: bytes.into() }
+    }
+
+    /// Parse a base32 secret, ignoring case, spaces and padding
+    pub fn from_base32(encoded: &str) -> AuthResult<Self> {
+        let mut bytes = Vec::with_capacity(encoded.len() * 5 / 8);
+        let mut buffer: u64 = 0;
+        let mut bits = 0;
+
+        for c in encoded.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+            let value = BASE32_ALPHABET
+                .iter()
+                .position(|&a| a == c.to_ascii_uppercase() as u8)
+                .ok_or_else(|| AuthError::InvalidKey("Invalid base32 TOTP secret".to_string()))?;
+            buffer = (buffer << 5) | value as u64;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push((buffer >> bits) as u8);
+            }
+        }
+
+        if bytes.is_empty() {
+            return Err(AuthError::InvalidKey("Empty TOTP secret".to_string()));
+        }
+
+        Ok(Self { bytes })
+    }
+
+    /// Encode the secret as unpadded base32
+    pub fn to_base32(&self) -> String {
+        let mut encoded = String::with_capacity(self.bytes.len().div_ceil(5) * 8);
+        let mut buffer: u64 = 0;
+        let mut bits = 0;
+
+        for &byte in &self.bytes {
+            buffer = (buffer << 8) | byte as u64;
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+            }
+        }
+        if bits > 0 {
+            encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+        }
+
+        encoded
+    }
+
+    /// Raw secret bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl std::fmt::Debug for TotpSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TotpSecret(..)")
+    }
+}
+
+/// TOTP generator and verifier for one secret
+#[derive(Debug, Clone)]
+pub struct Totp {
+    secret: TotpSecret,
+    config: TotpConfig,
+}
+
+impl Totp {
+    /// Create a TOTP instance with the default configuration
+    pub fn new(secret: TotpSecret) -> Self {
+        Self::with_config(secret, TotpConfig::default())
+    }
+
+    /// Create a TOTP instance with a specific configuration
+    ///
+    /// `digits` is clamped to 6-8 and a zero `period` is raised to one
+    /// second, so codes, verification and the provisioning URI agree.
+    pub fn with_config(secret: TotpSecret, mut config: TotpConfig) -> Self {
+        config.digits = config.digits.clamp(6, 8);
+        config.period = config.period.max(1);
+        Self { secret, config }
+    }
+
+    /// Get the secret
+    pub fn secret(&self) -> &TotpSecret {
+        &self.secret
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &TotpConfig {
+        &self.config
+    }
+
+    /// Time step containing a Unix timestamp
+    pub fn time_step(&self, unix_time: u64) -> u64 {
+        unix_time / self.config.period
+    }
+
+    /// Generate the code for the current time
+    pub fn generate_current(&self) -> String {
+        self.generate_at(current_timestamp())
+    }
+
+    /// Generate the code for a Unix timestamp
+    pub fn generate_at(&self, unix_time: u64) -> String {
+        self.generate_for_step(self.time_step(unix_time))
+    }
+
+    /// Verify a code against the current time
+    ///
+    /// See [`Totp::verify_at`].
+    pub fn verify(&self, code: &str, last_used_step: Option<u64>) -> AuthResult<u64> {
+        self.verify_at(code, current_timestamp(), last_used_step)
+    }
+
+    /// Verify a code against a Unix timestamp, allowing `skew` steps of drift
+    ///
+    /// Returns the matched time step, which the caller must persist and pass
+    /// back as `last_used_step`: codes from that step or earlier are
+    /// rejected so an observed code cannot be replayed.
+    pub fn verify_at(&self, code: &str, unix_time: u64, last_used_step: Option<u64>) -> AuthResult<u64> {
+        let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+        if code.len() != self.config.digits as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+            return Err(AuthError::InvalidMfaCode);
+        }
+
+        let current = self.time_step(unix_time);
+        let first = current.saturating_sub(self.config.skew);
+        let last = current + self.config.skew;
+
+        (first..=last)
+            .filter(|step| last_used_step.is_none_or(|used| *step > used))
+            .find(|step| CryptoUtils::constant_time_eq(self.generate_for_step(*step).as_bytes(), code.as_bytes()))
+            .ok_or(AuthError::InvalidMfaCode)
+    }
+
+    /// Build the `otpauth://` URI for enrolling an authenticator app
+    pub fn provisioning_uri(&self, account_name: &str) -> String {
+        let issuer = percent_encode(&self.config.issuer);
+        format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm={}&digits={}&period={}",
+            issuer,
+            percent_encode(account_name),
+            self.secret.to_base32(),
+            issuer,
+            self.config.algorithm.as_str(),
+            self.config.digits,
+            self.config.period,
+        )
+    }
+
+    /// RFC 4226 HOTP with dynamic truncation
+    fn generate_for_step(&self, step: u64) -> String {
+        let key = hmac::Key::new(self.config.algorithm.hmac_algorithm(), self.secret.as_bytes());
+        let tag = hmac::sign(&key, &step.to_be_bytes());
+        let digest = tag.as_ref();
+
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+
+        let digits = self.config.digits;
+        format!("{:0width$}", binary % 10u32.pow(digits), width = digits as usize)
+    }
+}
+
+fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Percent-encode a URI label component, keeping RFC 3986 unreserved characters
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rfc_totp(secret: &[u8], algorithm: TotpAlgorithm) -> Totp {
+        Totp::with_config(
+            TotpSecret::from_bytes(secret),
+            TotpConfig { digits: 8, algorithm, ..TotpConfig::default() },
+        )
+    }
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        let sha1 = rfc_totp(b"12345678901234567890", TotpAlgorithm::Sha1);
+        assert_eq!(sha1.generate_at(59), "94287082");
+        assert_eq!(sha1.generate_at(1111111109), "07081804");
+        assert_eq!(sha1.generate_at(20000000000), "65353130");
+
+        let sha256 = rfc_totp(b"12345678901234567890123456789012", TotpAlgorithm::Sha256);
+        assert_eq!(sha256.generate_at(59), "46119246");
+
+        let sha512 = rfc_totp(
+            b"1234567890123456789012345678901234567890123456789012345678901234",
+            TotpAlgorithm::Sha512,
+        );
+        assert_eq!(sha512.generate_at(59), "90693936");
+    }
+
+    #[test]
+    fn test_base32_round_trip() {
+        let secret = TotpSecret::from_bytes(b"12345678901234567890".to_vec());
+        assert_eq!(secret.to_base32(), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(TotpSecret::from_base32("gezd gnbv gy3t qojq gezd gnbv gy3t qojq").unwrap(), secret);
+
+        let generated = TotpSecret::generate().unwrap();
+        assert_eq!(TotpSecret::from_base32(&generated.to_base32()).unwrap(), generated);
+        assert!(TotpSecret::from_base32("not-base32!").is_err());
+    }
+
+    #[test]
+    fn test_verify_with_drift_window() {
+        let totp = Totp::new(TotpSecret::generate().unwrap());
+        let now = 1_700_000_000;
+
+        let previous = totp.generate_at(now - 30);
+        assert_eq!(totp.verify_at(&previous, now, None).unwrap(), totp.time_step(now) - 1);
+        assert!(totp.verify_at(&totp.generate_at(now - 90), now, None).is_err());
+        assert!(totp.verify_at("12345", now, None).is_err());
+    }
+
+    #[test]
+    fn test_replay_is_rejected() {
+        let totp = Totp::new(TotpSecret::generate().unwrap());
+        let now = 1_700_000_000;
+        let code = totp.generate_at(now);
+
+        let step = totp.verify_at(&code, now, None).unwrap();
+        assert!(matches!(totp.verify_at(&code, now, Some(step)), Err(AuthError::InvalidMfaCode)));
+        assert!(totp.verify_at(&totp.generate_at(now + 30), now + 30, Some(step)).is_ok());
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let totp = Totp::new(TotpSecret::from_bytes(b"12345678901234567890".to_vec()));
+        assert_eq!(
+            totp.provisioning_uri("alice@example.com"),
+            "otpauth://totp/CloudShuttle:alice%40example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=CloudShuttle&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+
+    #[test]
+    fn test_out_of_range_digits_are_normalised() {
+        let totp = Totp::with_config(
+            TotpSecret::from_bytes(b"12345678901234567890".to_vec()),
+            TotpConfig { digits: 10, ..TotpConfig::default() },
+        );
+        assert_eq!(totp.config().digits, 8);
+
+        let code = totp.generate_at(59);
+        assert_eq!(code.len(), 8);
+        assert!(totp.verify_at(&code, 59, None).is_ok());
+        assert!(totp.provisioning_uri("alice").contains("&digits=8&"));
+    }
+}
@@ -0,0 +1,369 @@
+//! Remember-device tokens
+//!
+//! After a successful second factor, a user may mark the browser or device
+//! as trusted. The device receives a signed token bound to the user and a
+//! fingerprint of its user agent and optional device ID; while the token is
+//! valid and unrevoked, login flows can skip the MFA prompt.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use crate::security::CryptoUtils;
+use crate::types::{AuthResult, AuthError, MfaConfig};
+
+const TOKEN_TYPE: &str = "trusted_device";
+
+/// Trusted device record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedDevice {
+    pub device_token_id: String,
+    pub user_id: String,
+    pub fingerprint: String,
+    pub device_id: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub last_used_at: Option<u64>,
+    pub revoked: bool,
+}
+
+impl TrustedDevice {
+    /// Check if the device is still trusted at `now`
+    pub fn is_active(&self, now: u64) -> bool {
+        !self.revoked && self.expires_at > now
+    }
+}
+
+/// Signed payload of a remember-device token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TrustedDeviceClaims {
+    sub: String,
+    jti: String,
+    fph: String,
+    iat: u64,
+    exp: u64,
+    token_type: String,
+}
+
+/// Persistent storage for trusted devices
+#[async_trait::async_trait]
+pub trait TrustedDeviceStore: Send + Sync {
+    /// Store a newly trusted device
+    async fn insert_device(&self, device: TrustedDevice) -> AuthResult<()>;
+
+    /// Look up a device by its token ID
+    async fn get_device(&self, device_token_id: &str) -> AuthResult<Option<TrustedDevice>>;
+
+    /// Record that a device was used to skip MFA
+    async fn touch_device(&self, device_token_id: &str, now: u64) -> AuthResult<()>;
+
+    /// Revoke a user's device, returning `true` if it was active before the call
+    async fn revoke_device(&self, user_id: &str, device_token_id: &str) -> AuthResult<bool>;
+
+    /// Revoke all of a user's devices, returning the number revoked
+    async fn revoke_user_devices(&self, user_id: &str) -> AuthResult<usize>;
+
+    /// List unrevoked, unexpired devices for a user
+    async fn active_user_devices(&self, user_id: &str, now: u64) -> AuthResult<Vec<TrustedDevice>>;
+
+    /// Delete devices that expired before `now`, returning the number removed
+    async fn delete_expired(&self, now: u64) -> AuthResult<usize>;
+}
+
+/// In-memory trusted device store for single-instance deployments and testing
+#[derive(Default)]
+pub struct InMemoryTrustedDeviceStore {
+    devices: Mutex<HashMap<String, TrustedDevice>>,
+}
+
+impl InMemoryTrustedDeviceStore {
+    /// Create an empty in-memory store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl TrustedDeviceStore for InMemoryTrustedDeviceStore {
+    async fn insert_device(&self, device: TrustedDevice) -> AuthResult<()> {
+        let mut devices = self.devices.lock().unwrap();
+        devices.insert(device.device_token_id.clone(), device);
+        Ok(())
+    }
+
+    async fn get_device(&self, device_token_id: &str) -> AuthResult<Option<TrustedDevice>> {
+        let devices = self.devices.lock().unwrap();
+        Ok(devices.get(device_token_id).cloned())
+    }
+
+    async fn touch_device(&self, device_token_id: &str, now: u64) -> AuthResult<()> {
+        let mut devices = self.devices.lock().unwrap();
+        if let Some(device) = devices.get_mut(device_token_id) {
+            device.last_used_at = Some(now);
+        }
+        Ok(())
+    }
+
+    async fn revoke_device(&self, user_id: &str, device_token_id: &str) -> AuthResult<bool> {
+        let mut devices = self.devices.lock().unwrap();
+        match devices.get_mut(device_token_id) {
+            Some(device) if device.user_id == user_id && !device.revoked => {
+                device.revoked = true;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn revoke_user_devices(&self, user_id: &str) -> AuthResult<usize> {
+        let mut devices = self.devices.lock().unwrap();
+        let mut revoked_count = 0;
+        for device in devices.values_mut() {
+            if device.user_id == user_id && !device.revoked {
+                device.revoked = true;
+                revoked_count += 1;
+            }
+        }
+        Ok(revoked_count)
+    }
+
+    async fn active_user_devices(&self, user_id: &str, now: u64) -> AuthResult<Vec<TrustedDevice>> {
+        let devices = self.devices.lock().unwrap();
+        Ok(devices
+            .values()
+            .filter(|device| device.user_id == user_id && device.is_active(now))
+            .cloned()
+            .collect())
+    }
+
+    async fn delete_expired(&self, now: u64) -> AuthResult<usize> {
+        let mut devices = self.devices.lock().unwrap();
+        let initial_count = devices.len();
+        devices.retain(|_, device| device.expires_at > now);
+        Ok(initial_count - devices.len())
+    }
+}
+
+/// Issues and checks remember-device tokens
+#[derive(Clone)]
+pub struct TrustedDeviceManager {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    config: MfaConfig,
+    store: Arc<dyn TrustedDeviceStore>,
+}
+
+impl TrustedDeviceManager {
+    /// Create a manager with an in-memory store, using `MfaConfig::remember_device_days`
+    pub fn new(secret: &[u8], config: &MfaConfig) -> Self {
+        Self::with_store(secret, config, Arc::new(InMemoryTrustedDeviceStore::new()))
+    }
+
+    /// Create a manager backed by a custom store
+    pub fn with_store(secret: &[u8], config: &MfaConfig, store: Arc<dyn TrustedDeviceStore>) -> Self {
+        Self {
+            encoding_key: EncodingKey::from_secret(secret),
+            decoding_key: DecodingKey::from_secret(secret),
+            config: config.clone(),
+            store,
+        }
+    }
+
+    /// Check if device remembering is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.config.device_remembering_enabled()
+    }
+
+    /// Trust the current device for the user, returning the token to store on the device
+    ///
+    /// Call only after the user has passed a second factor.
+    pub async fn remember_device(
+        &self,
+        user_id: &str,
+        user_agent: Option<String>,
+        device_id: Option<String>,
+        ip_address: Option<String>,
+    ) -> AuthResult<String> {
+        let days = self.config.remember_device_days
+            .ok_or_else(|| AuthError::InvalidRequest("Device remembering is disabled".to_string()))?;
+
+        let now = current_timestamp();
+        let device = TrustedDevice {
+            device_token_id: format!("td_{}", uuid::Uuid::new_v4()),
+            user_id: user_id.to_string(),
+            fingerprint: device_fingerprint(user_agent.as_deref(), device_id.as_deref()),
+            device_id,
+            user_agent,
+            ip_address,
+            created_at: now,
+            expires_at: now + days as u64 * 24 * 60 * 60,
+            last_used_at: None,
+            revoked: false,
+        };
+
+        let claims = TrustedDeviceClaims {
+            sub: device.user_id.clone(),
+            jti: device.device_token_id.clone(),
+            fph: device.fingerprint.clone(),
+            iat: device.created_at,
+            exp: device.expires_at,
+            token_type: TOKEN_TYPE.to_string(),
+        };
+        let token = encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)
+            .map_err(|e| AuthError::TokenCreation(e.to_string()))?;
+
+        self.store.insert_device(device).await?;
+        Ok(token)
+    }
+
+    /// Check if a remember-device token lets this user skip MFA on this device
+    ///
+    /// Returns `false` for tokens that are invalid, expired, revoked, issued
+    /// to another user, or presented with a different fingerprint.
+    pub async fn is_trusted(
+        &self,
+        token: &str,
+        user_id: &str,
+        user_agent: Option<&str>,
+        device_id: Option<&str>,
+    ) -> AuthResult<bool> {
+        if !self.is_enabled() {
+            return Ok(false);
+        }
+
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.validate_aud = false;
+        let Ok(token_data) = decode::<TrustedDeviceClaims>(token, &self.decoding_key, &validation) else {
+            return Ok(false);
+        };
+        let claims = token_data.claims;
+
+        let fingerprint = device_fingerprint(user_agent, device_id);
+        if claims.token_type != TOKEN_TYPE || claims.sub != user_id || claims.fph != fingerprint {
+            return Ok(false);
+        }
+
+        let now = current_timestamp();
+        let trusted = match self.store.get_device(&claims.jti).await? {
+            Some(device) => device.user_id == user_id && device.fingerprint == fingerprint && device.is_active(now),
+            None => false,
+        };
+
+        if trusted {
+            self.store.touch_device(&claims.jti, now).await?;
+        }
+        Ok(trusted)
+    }
+
+    /// List a user's trusted devices
+    pub async fn list_devices(&self, user_id: &str) -> AuthResult<Vec<TrustedDevice>> {
+        let mut devices = self.store.active_user_devices(user_id, current_timestamp()).await?;
+        devices.sort_by_key(|device| std::cmp::Reverse(device.created_at));
+        Ok(devices)
+    }
+
+    /// Stop trusting one of a user's devices
+    pub async fn revoke_device(&self, user_id: &str, device_token_id: &str) -> AuthResult<()> {
+        if self.store.revoke_device(user_id, device_token_id).await? {
+            Ok(())
+        } else {
+            Err(AuthError::TokenNotFound)
+        }
+    }
+
+    /// Stop trusting all of a user's devices, e.g. after a password or MFA change
+    pub async fn revoke_all_devices(&self, user_id: &str) -> AuthResult<usize> {
+        self.store.revoke_user_devices(user_id).await
+    }
+
+    /// Remove expired devices from the store
+    pub async fn cleanup_expired_devices(&self) -> AuthResult<usize> {
+        self.store.delete_expired(current_timestamp()).await
+    }
+}
+
+/// Fingerprint a device from its user agent and optional device ID
+pub fn device_fingerprint(user_agent: Option<&str>, device_id: Option<&str>) -> String {
+    let material = format!("{}\n{}", user_agent.unwrap_or_default(), device_id.unwrap_or_default());
+    CryptoUtils::sha256_hash(material.as_bytes())
+}
+
+fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64)";
+
+    fn manager() -> TrustedDeviceManager {
+        TrustedDeviceManager::new(b"test-secret-key", &MfaConfig::default())
+    }
+
+    #[tokio::test]
+    async fn test_remembered_device_is_trusted() {
+        let manager = manager();
+        let token = manager
+            .remember_device("user123", Some(USER_AGENT.to_string()), Some("device456".to_string()), None)
+            .await
+            .unwrap();
+
+        assert!(manager.is_trusted(&token, "user123", Some(USER_AGENT), Some("device456")).await.unwrap());
+        assert!(manager.list_devices("user123").await.unwrap()[0].last_used_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_token_bound_to_user_and_fingerprint() {
+        let manager = manager();
+        let token = manager
+            .remember_device("user123", Some(USER_AGENT.to_string()), None, None)
+            .await
+            .unwrap();
+
+        assert!(!manager.is_trusted(&token, "other-user", Some(USER_AGENT), None).await.unwrap());
+        assert!(!manager.is_trusted(&token, "user123", Some("curl/8.0"), None).await.unwrap());
+        assert!(!manager.is_trusted(&token, "user123", Some(USER_AGENT), Some("device456")).await.unwrap());
+
+        let forged = TrustedDeviceManager::new(b"other-secret", &MfaConfig::default())
+            .remember_device("user123", Some(USER_AGENT.to_string()), None, None)
+            .await
+            .unwrap();
+        assert!(!manager.is_trusted(&forged, "user123", Some(USER_AGENT), None).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_revocation() {
+        let manager = manager();
+        let first = manager.remember_device("user123", Some(USER_AGENT.to_string()), None, None).await.unwrap();
+        let second = manager.remember_device("user123", Some("curl/8.0".to_string()), None, None).await.unwrap();
+
+        let devices = manager.list_devices("user123").await.unwrap();
+        assert_eq!(devices.len(), 2);
+
+        let first_device = devices.iter().find(|device| device.user_agent.as_deref() == Some(USER_AGENT)).unwrap();
+        assert!(manager.revoke_device("other-user", &first_device.device_token_id).await.is_err());
+        manager.revoke_device("user123", &first_device.device_token_id).await.unwrap();
+        assert!(!manager.is_trusted(&first, "user123", Some(USER_AGENT), None).await.unwrap());
+        assert!(manager.is_trusted(&second, "user123", Some("curl/8.0"), None).await.unwrap());
+
+        assert_eq!(manager.revoke_all_devices("user123").await.unwrap(), 1);
+        assert!(manager.list_devices("user123").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_disabled_by_config() {
+        let config = MfaConfig { remember_device_days: None, ..MfaConfig::default() };
+        let manager = TrustedDeviceManager::new(b"test-secret-key", &config);
+
+        assert!(!manager.is_enabled());
+        assert!(manager.remember_device("user123", None, None, None).await.is_err());
+    }
+}
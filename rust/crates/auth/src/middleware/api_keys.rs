@@ -0,0 +1,131 @@
+//! API key authentication middleware for Axum.
+//!
+//! This module authenticates `Authorization: ApiKey ...` or `X-API-Key`
+//! headers through an `ApiKeyManager`, exposing the same `Claims` and
+//! `AuthContext` extensions as JWT authentication.
+
+use std::sync::Arc;
+use axum::http::{header, HeaderMap};
+use http::request::Parts;
+use crate::api_keys::ApiKeyManager;
+use crate::{AuthError, MiddlewareFn};
+use super::services::{guard_middleware, RequestGuard};
+
+/// Header carrying an API key on its own
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Extract an API key from the Authorization or X-API-Key header
+pub fn extract_api_key(headers: &HeaderMap) -> Option<String> {
+    let from_authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("ApiKey "));
+    let from_header = || headers.get(API_KEY_HEADER).and_then(|value| value.to_str().ok());
+
+    from_authorization
+        .or_else(from_header)
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+}
+
+/// API key authentication middleware
+#[derive(Clone)]
+pub struct ApiKeyMiddleware {
+    api_keys: Arc<ApiKeyManager>,
+    optional_auth: bool,
+}
+
+impl ApiKeyMiddleware {
+    /// Create new middleware requiring a valid API key
+    pub fn new(api_keys: Arc<ApiKeyManager>) -> Self {
+        Self { api_keys, optional_auth: false }
+    }
+
+    /// Create new middleware that lets requests without a key through
+    ///
+    /// Requests presenting an invalid key are still rejected.
+    pub fn optional(api_keys: Arc<ApiKeyManager>) -> Self {
+        Self { api_keys, optional_auth: true }
+    }
+
+    /// Convert to Axum middleware function
+    ///
+    /// The key's `Claims`, `AuthContext` and `ApiKey` record are added to
+    /// the request extensions.
+    pub fn into_layer(self) -> MiddlewareFn {
+        guard_middleware(self)
+    }
+}
+
+#[async_trait::async_trait]
+impl RequestGuard for ApiKeyMiddleware {
+    async fn check(&self, parts: &mut Parts) -> Result<(), AuthError> {
+        let Some(key) = extract_api_key(&parts.headers) else {
+            return if self.optional_auth { Ok(()) } else { Err(AuthError::MissingToken) };
+        };
+
+        let record = self.api_keys.authenticate(&key).await?;
+        let claims = record.to_claims();
+        parts.extensions.insert(crate::types::AuthContext::from_claims(&claims));
+        parts.extensions.insert(claims);
+        parts.extensions.insert(record);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, extract::Request, http::StatusCode, middleware::Next, routing::get, Extension, Router};
+    use tower::ServiceExt;
+    use crate::api_keys::{ApiKeyConfig, ApiKeyRequest};
+    use crate::types::AuthContext;
+
+    fn app(middleware: ApiKeyMiddleware) -> Router {
+        let layer = Arc::new(middleware.into_layer());
+        Router::new()
+            .route("/me", get(|context: Option<Extension<AuthContext>>| async move {
+                context.map(|Extension(context)| context.user_id).unwrap_or_default()
+            }))
+            .layer(axum::middleware::from_fn(move |req: Request, next: Next| {
+                let layer = layer.clone();
+                async move { layer(req, next).await }
+            }))
+    }
+
+    fn request(name: &str, value: &str) -> Request<Body> {
+        Request::builder().uri("/me").header(name, value).body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn test_extract_api_key() {
+        let mut headers = HeaderMap::new();
+        assert!(extract_api_key(&headers).is_none());
+        headers.insert(API_KEY_HEADER, "csk_abc_def".parse().unwrap());
+        assert_eq!(extract_api_key(&headers).as_deref(), Some("csk_abc_def"));
+        headers.insert(header::AUTHORIZATION, "ApiKey csk_123_456".parse().unwrap());
+        assert_eq!(extract_api_key(&headers).as_deref(), Some("csk_123_456"));
+    }
+
+    #[tokio::test]
+    async fn test_api_key_resolves_auth_context() {
+        let api_keys = Arc::new(ApiKeyManager::new(ApiKeyConfig::default()).unwrap());
+        let issued = api_keys.issue(ApiKeyRequest::new("ci", "svc-deploy", "tenant1")).await.unwrap();
+        let app = app(ApiKeyMiddleware::new(api_keys.clone()));
+
+        let response = app.clone().oneshot(request("authorization", &format!("ApiKey {}", issued.key))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&bytes[..], b"svc-deploy");
+
+        let response = app.clone().oneshot(request(API_KEY_HEADER, &issued.key)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        api_keys.revoke(&issued.record.id).await.unwrap();
+        let response = app.clone().oneshot(request(API_KEY_HEADER, &issued.key)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.oneshot(Request::builder().uri("/me").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,216 @@
+//! Typed authorization extractors for Axum handlers.
+//!
+//! Requirements are marker types declared with [`role_marker!`] and
+//! [`permission_marker!`], so they appear in handler signatures:
+//!
+//! ```rust,ignore
+//! use cloudshuttle_auth::{permission_marker, middleware::{Admin, Authorized, HasPermission}};
+//!
+//! permission_marker!(pub ReadUsers => "users:read");
+//!
+//! async fn list_users(HasPermission(context, ..): HasPermission<ReadUsers>) { /* ... */ }
+//! async fn delete_tenant(_: Authorized<Admin>) { /* ... */ }
+//! ```
+//!
+//! The extractors read the `AuthContext` added by the JWT, API key or
+//! session middleware. A missing context is rejected with 401 and an unmet
+//! requirement with 403, both as `AuthError` JSON bodies. Several
+//! extractors on one handler must all be satisfied. Permissions match
+//! wildcards such as `users:*`, as in the permission guard.
+
+use std::marker::PhantomData;
+use axum::{extract::FromRequestParts, http::request::Parts};
+use crate::authorization::grants_permission;
+use crate::types::AuthContext;
+use crate::AuthError;
+
+/// Roles accepted by [`Authorized`]; holding any one of them is sufficient
+pub trait RoleRequirement: Send + Sync + 'static {
+    const ROLES: &'static [&'static str];
+}
+
+/// Permissions accepted by [`HasPermission`]; holding any one of them is sufficient
+pub trait PermissionRequirement: Send + Sync + 'static {
+    const PERMISSIONS: &'static [&'static str];
+}
+
+/// Declare a role requirement marker type
+///
+/// `role_marker!(pub Billing => "billing_admin", "admin");`
+#[macro_export]
+macro_rules! role_marker {
+    ($(#[$meta:meta])* $vis:vis $name:ident => $($role:literal),+ $(,)?) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy)]
+        $vis struct $name;
+
+        impl $crate::middleware::RoleRequirement for $name {
+            const ROLES: &'static [&'static str] = &[$($role),+];
+        }
+    };
+}
+
+/// Declare a permission requirement marker type
+///
+/// `permission_marker!(pub ReadUsers => "users:read");`
+#[macro_export]
+macro_rules! permission_marker {
+    ($(#[$meta:meta])* $vis:vis $name:ident => $($permission:literal),+ $(,)?) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy)]
+        $vis struct $name;
+
+        impl $crate::middleware::PermissionRequirement for $name {
+            const PERMISSIONS: &'static [&'static str] = &[$($permission),+];
+        }
+    };
+}
+
+role_marker!(
+    /// Requires the `admin` role
+    pub Admin => "admin"
+);
+
+role_marker!(
+    /// Requires the `tenant_admin` or `admin` role
+    pub TenantAdmin => "tenant_admin", "admin"
+);
+
+/// Extractor for an authenticated user holding one of `R::ROLES`
+pub struct Authorized<R: RoleRequirement>(pub AuthContext, pub PhantomData<R>);
+
+/// Extractor for an authenticated user holding one of `P::PERMISSIONS`
+pub struct HasPermission<P: PermissionRequirement>(pub AuthContext, pub PhantomData<P>);
+
+impl<R: RoleRequirement> Authorized<R> {
+    /// Get the authorized user's context
+    pub fn context(&self) -> &AuthContext {
+        &self.0
+    }
+}
+
+impl<P: PermissionRequirement> HasPermission<P> {
+    /// Get the authorized user's context
+    pub fn context(&self) -> &AuthContext {
+        &self.0
+    }
+}
+
+fn auth_context(parts: &Parts) -> Result<AuthContext, AuthError> {
+    parts.extensions.get::<AuthContext>().cloned().ok_or(AuthError::MissingToken)
+}
+
+fn to_strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[async_trait::async_trait]
+impl<R, S> FromRequestParts<S> for Authorized<R>
+where
+    R: RoleRequirement,
+    S: Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let context = auth_context(parts)?;
+        if !context.has_any_role(R::ROLES) {
+            return Err(AuthError::InsufficientPermissions {
+                required: to_strings(R::ROLES),
+                actual: context.roles,
+            });
+        }
+        Ok(Self(context, PhantomData))
+    }
+}
+
+#[async_trait::async_trait]
+impl<P, S> FromRequestParts<S> for HasPermission<P>
+where
+    P: PermissionRequirement,
+    S: Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let context = auth_context(parts)?;
+        if !P::PERMISSIONS.iter().any(|permission| grants_permission(&context.permissions, permission)) {
+            return Err(AuthError::InsufficientPermissions {
+                required: to_strings(P::PERMISSIONS),
+                actual: context.permissions,
+            });
+        }
+        Ok(Self(context, PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, extract::Request, http::StatusCode, middleware::Next, routing::get, Router};
+    use tower::ServiceExt;
+
+    permission_marker!(ReadUsers => "users:read");
+    permission_marker!(WriteUsers => "users:write", "users:admin");
+
+    fn app(context: Option<AuthContext>) -> Router {
+        Router::new()
+            .route("/admin", get(|Authorized(context, _): Authorized<Admin>| async move { context.user_id }))
+            .route("/users", get(|_: HasPermission<ReadUsers>| async { "ok" }))
+            .route("/users/edit", get(|_: HasPermission<ReadUsers>, _: HasPermission<WriteUsers>| async { "ok" }))
+            .layer(axum::middleware::from_fn(move |mut req: Request, next: Next| {
+                if let Some(context) = context.clone() {
+                    req.extensions_mut().insert(context);
+                }
+                async move { next.run(req).await }
+            }))
+    }
+
+    async fn get_status(app: &Router, uri: &str) -> StatusCode {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        app.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_role_extractor() {
+        let mut context = AuthContext::new("user123".to_string(), "tenant1".to_string());
+        assert_eq!(get_status(&app(None), "/admin").await, StatusCode::UNAUTHORIZED);
+        assert_eq!(get_status(&app(Some(context.clone())), "/admin").await, StatusCode::FORBIDDEN);
+
+        context.roles = vec!["admin".to_string()];
+        let response = app(Some(context))
+            .oneshot(Request::builder().uri("/admin").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&bytes[..], b"user123");
+    }
+
+    #[tokio::test]
+    async fn test_permission_extractors_compose() {
+        let mut context = AuthContext::new("user123".to_string(), "tenant1".to_string());
+        context.permissions = vec!["users:read".to_string()];
+        let app = app(Some(context));
+
+        assert_eq!(get_status(&app, "/users").await, StatusCode::OK);
+        let response = app
+            .oneshot(Request::builder().uri("/users/edit").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["code"], "INSUFFICIENT_PERMISSIONS");
+    }
+
+    #[tokio::test]
+    async fn test_permission_extractor_honours_wildcards() {
+        let mut context = AuthContext::new("user123".to_string(), "tenant1".to_string());
+        context.permissions = vec!["users:*".to_string()];
+        let app = app(Some(context));
+
+        assert_eq!(get_status(&app, "/users").await, StatusCode::OK);
+        assert_eq!(get_status(&app, "/users/edit").await, StatusCode::OK);
+    }
+}
@@ -69,7 +69,7 @@ where
 {
     type Rejection = Response;
 
-    async fn from_request(req: Request, _state: &mut S) -> Result<Self, Self::Rejection> {
+    async fn from_request(req: Request, _state: &S) -> Result<Self, Self::Rejection> {
         match req.extensions().get::<Claims>() {
             Some(claims) => Ok(AuthenticatedUser(claims.clone())),
             None => Err(AuthError::MissingToken.into_response()),
@@ -89,8 +89,28 @@ where
 {
     type Rejection = Response;
 
-    async fn from_request(req: Request, _state: &mut S) -> Result<Self, Self::Rejection> {
+    async fn from_request(req: Request, _state: &S) -> Result<Self, Self::Rejection> {
         let claims = req.extensions().get::<Claims>().cloned();
         Ok(OptionalUser(claims))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, extract::FromRequest};
+
+    #[tokio::test]
+    async fn test_extractors_read_claims_from_extensions() {
+        let mut req = Request::new(Body::empty());
+        req.extensions_mut().insert(Claims::new("user123", "tenant456"));
+        let user = AuthenticatedUser::from_request(req, &()).await.unwrap();
+        assert_eq!(user.user_id(), "user123");
+
+        let req = Request::new(Body::empty());
+        assert!(AuthenticatedUser::from_request(req, &()).await.is_err());
+
+        let req = Request::new(Body::empty());
+        assert!(OptionalUser::from_request(req, &()).await.unwrap().0.is_none());
+    }
+}
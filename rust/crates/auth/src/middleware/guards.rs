@@ -2,18 +2,33 @@
 //!
 //! This module contains middleware guards for enforcing authorization
 //! policies based on user roles, permissions, and tenant isolation.
+//! Role and permission guards resolve inherited roles and permissions when
+//! given a [`RoleRegistry`].
 
-use axum::{extract::Request, middleware::Next, response::{Response, IntoResponse}};
+use std::sync::Arc;
+use axum::extract::{FromRequestParts, RawPathParams};
+use http::request::Parts;
 use crate::{Claims, AuthError, MiddlewareFn};
+use crate::authorization::grants_permission;
+use crate::roles::RoleRegistry;
+use super::services::{guard_middleware, RequestGuard};
 
 /// Role-based authorization middleware
+#[derive(Clone)]
 pub struct RoleGuard {
     required_roles: Vec<String>,
+    role_registry: Option<Arc<RoleRegistry>>,
 }
 
 impl RoleGuard {
     pub fn new(roles: Vec<String>) -> Self {
-        Self { required_roles: roles }
+        Self { required_roles: roles, role_registry: None }
+    }
+
+    /// Accept roles inherited through the registry
+    pub fn with_role_registry(mut self, role_registry: Arc<RoleRegistry>) -> Self {
+        self.role_registry = Some(role_registry);
+        self
     }
 
     pub fn require_admin() -> Self {
@@ -25,82 +40,145 @@ impl RoleGuard {
     }
 
     pub fn into_layer(self) -> MiddlewareFn {
-        let required_roles = self.required_roles;
-
-        Box::new(move |req: Request, next: Next| {
-            let required_roles = required_roles.clone();
-
-            Box::pin(async move {
-                match req.extensions().get::<Claims>() {
-                    Some(claims) => {
-                        if claims.has_any_role(&required_roles) {
-                            next.run(req).await
-                        } else {
-                            AuthError::InsufficientPermissions {
-                                required: required_roles,
-                                actual: claims.roles.clone(),
-                            }.into_response()
-                        }
-                    }
-                    None => AuthError::MissingToken.into_response(),
-                }
+        guard_middleware(self)
+    }
+}
+
+#[async_trait::async_trait]
+impl RequestGuard for RoleGuard {
+    async fn check(&self, parts: &mut Parts) -> Result<(), AuthError> {
+        let claims = parts.extensions.get::<Claims>().ok_or(AuthError::MissingToken)?;
+        let actual: Vec<String> = match &self.role_registry {
+            Some(registry) => registry.effective_access(&claims.roles, &[]).roles.into_iter().collect(),
+            None => claims.roles.clone(),
+        };
+
+        if self.required_roles.iter().any(|role| actual.contains(role)) {
+            Ok(())
+        } else {
+            Err(AuthError::InsufficientPermissions {
+                required: self.required_roles.clone(),
+                actual,
             })
-        })
+        }
     }
 }
 
 /// Permission-based authorization middleware
+#[derive(Clone)]
 pub struct PermissionGuard {
     required_permissions: Vec<String>,
+    role_registry: Option<Arc<RoleRegistry>>,
 }
 
 impl PermissionGuard {
     pub fn new(permissions: Vec<String>) -> Self {
-        Self { required_permissions: permissions }
+        Self { required_permissions: permissions, role_registry: None }
+    }
+
+    /// Accept permissions granted by the user's roles through the registry
+    pub fn with_role_registry(mut self, role_registry: Arc<RoleRegistry>) -> Self {
+        self.role_registry = Some(role_registry);
+        self
     }
 
     pub fn into_layer(self) -> MiddlewareFn {
-        let required_permissions = self.required_permissions;
-
-        Box::new(move |req: Request, next: Next| {
-            let required_permissions = required_permissions.clone();
-
-            Box::pin(async move {
-                match req.extensions().get::<Claims>() {
-                    Some(claims) => {
-                        if claims.has_any_permission(&required_permissions) {
-                            next.run(req).await
-                        } else {
-                            AuthError::InsufficientPermissions {
-                                required: required_permissions,
-                                actual: claims.permissions.clone(),
-                            }.into_response()
-                        }
-                    }
-                    None => AuthError::MissingToken.into_response(),
-                }
+        guard_middleware(self)
+    }
+}
+
+#[async_trait::async_trait]
+impl RequestGuard for PermissionGuard {
+    async fn check(&self, parts: &mut Parts) -> Result<(), AuthError> {
+        let claims = parts.extensions.get::<Claims>().ok_or(AuthError::MissingToken)?;
+        let actual: Vec<String> = match &self.role_registry {
+            Some(registry) => registry
+                .effective_access(&claims.roles, &claims.permissions)
+                .permissions
+                .into_iter()
+                .collect(),
+            None => claims.permissions.clone(),
+        };
+
+        if self.required_permissions.iter().any(|permission| grants_permission(&actual, permission)) {
+            Ok(())
+        } else {
+            Err(AuthError::InsufficientPermissions {
+                required: self.required_permissions.clone(),
+                actual,
             })
-        })
+        }
     }
 }
 
+/// Header naming the tenant a request targets
+pub const TENANT_HEADER: &str = "x-tenant-id";
+
 /// Tenant isolation middleware
-pub struct TenantGuard;
+///
+/// Rejects requests whose tenant, taken from the `X-Tenant-ID` header or a
+/// path parameter, differs from the tenant in the user's `Claims`. Requests
+/// that don't name a tenant act on the user's own tenant and pass. Path
+/// parameters are only visible when the guard runs after routing, e.g. via
+/// `Router::route_layer`.
+#[derive(Clone)]
+pub struct TenantGuard {
+    header: String,
+    path_param: Option<String>,
+}
 
 impl TenantGuard {
     pub fn new() -> Self {
-        Self
+        Self { header: TENANT_HEADER.to_string(), path_param: None }
+    }
+
+    /// Read the tenant from a different header
+    pub fn with_header(mut self, header: impl Into<String>) -> Self {
+        self.header = header.into();
+        self
+    }
+
+    /// Also read the tenant from a path parameter, such as `tenant_id` in
+    /// `/tenants/:tenant_id/users`
+    pub fn with_path_param(mut self, name: impl Into<String>) -> Self {
+        self.path_param = Some(name.into());
+        self
     }
 
     pub fn into_layer(self) -> MiddlewareFn {
-        Box::new(move |req: Request, next: Next| {
-            Box::pin(async move {
-                // This would typically extract tenant ID from path or header
-                // and verify it matches the user's tenant ID
-                // For now, just pass through
-                next.run(req).await
-            })
-        })
+        guard_middleware(self)
+    }
+
+    async fn requested_tenants(&self, parts: &mut Parts) -> Result<Vec<String>, AuthError> {
+        let mut tenants = Vec::new();
+        for value in parts.headers.get_all(self.header.as_str()) {
+            let tenant = value
+                .to_str()
+                .map_err(|_| AuthError::InvalidRequest("Invalid tenant header".to_string()))?;
+            tenants.push(tenant.trim().to_string());
+        }
+
+        if let Some(name) = &self.path_param {
+            if let Ok(params) = RawPathParams::from_request_parts(parts, &()).await {
+                tenants.extend(params.iter().filter(|(key, _)| key == name).map(|(_, value)| value.to_string()));
+            }
+        }
+        Ok(tenants)
+    }
+}
+
+#[async_trait::async_trait]
+impl RequestGuard for TenantGuard {
+    async fn check(&self, parts: &mut Parts) -> Result<(), AuthError> {
+        let tenant_id = parts.extensions.get::<Claims>().ok_or(AuthError::MissingToken)?.tenant_id.clone();
+
+        match self.requested_tenants(parts).await?.into_iter().find(|tenant| *tenant != tenant_id) {
+            Some(requested) => Err(AuthError::InsufficientPermissions {
+                required: vec![format!("tenant:{}", requested)],
+                actual: vec![format!("tenant:{}", tenant_id)],
+            }),
+            None => Ok(()),
+        }
     }
 }
 
@@ -109,3 +187,86 @@ impl Default for TenantGuard {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, extract::Request, http::{Request as HttpRequest, StatusCode}, middleware::Next, routing::get, Router};
+    use tower::ServiceExt;
+    use crate::roles::Role;
+
+    fn app(guard: MiddlewareFn, claims: Claims) -> Router {
+        let guard = Arc::new(guard);
+        Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn(move |req, next| {
+                let guard = guard.clone();
+                async move { guard(req, next).await }
+            }))
+            .layer(axum::middleware::from_fn(move |mut req: Request, next: Next| {
+                req.extensions_mut().insert(claims.clone());
+                async move { next.run(req).await }
+            }))
+    }
+
+    async fn status(app: Router) -> StatusCode {
+        app.oneshot(HttpRequest::builder().uri("/").body(Body::empty()).unwrap()).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_permission_guard_resolves_inherited_permissions() {
+        let registry = Arc::new(RoleRegistry::from_roles([
+            Role::new("admin").inherits(["viewer"]),
+            Role::new("viewer").with_permissions(["users:read"]),
+        ]).unwrap());
+        let mut claims = Claims::new("user123", "tenant1");
+        claims.roles = vec!["admin".to_string()];
+
+        let exact = PermissionGuard::new(vec!["users:read".to_string()]).into_layer();
+        assert_eq!(status(app(exact, claims.clone())).await, StatusCode::FORBIDDEN);
+
+        let resolved = PermissionGuard::new(vec!["users:read".to_string()])
+            .with_role_registry(registry.clone())
+            .into_layer();
+        assert_eq!(status(app(resolved, claims.clone())).await, StatusCode::OK);
+
+        let role = RoleGuard::new(vec!["viewer".to_string()]).with_role_registry(registry).into_layer();
+        assert_eq!(status(app(role, claims)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_permission_guard_honours_wildcards() {
+        let mut claims = Claims::new("user123", "tenant1");
+        claims.permissions = vec!["documents:*".to_string()];
+
+        let granted = PermissionGuard::new(vec!["documents:publish".to_string()]).into_layer();
+        assert_eq!(status(app(granted, claims.clone())).await, StatusCode::OK);
+
+        let denied = PermissionGuard::new(vec!["users:read".to_string()]).into_layer();
+        assert_eq!(status(app(denied, claims)).await, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_guard_rejects_other_tenants() {
+        let claims = Claims::new("user123", "tenant1");
+        let app = Router::new()
+            .route("/tenants/:tenant_id", get(|| async { "ok" }))
+            .route_layer(TenantGuard::new().with_path_param("tenant_id"))
+            .layer(axum::middleware::from_fn(move |mut req: Request, next: Next| {
+                req.extensions_mut().insert(claims.clone());
+                async move { next.run(req).await }
+            }));
+        let request = |uri: &str, tenant: Option<&str>| {
+            let mut builder = HttpRequest::builder().uri(uri);
+            if let Some(tenant) = tenant {
+                builder = builder.header(TENANT_HEADER, tenant);
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        assert_eq!(app.clone().oneshot(request("/tenants/tenant1", None)).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(app.clone().oneshot(request("/tenants/tenant1", Some("tenant1"))).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(app.clone().oneshot(request("/tenants/tenant2", None)).await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(app.oneshot(request("/tenants/tenant1", Some("tenant2"))).await.unwrap().status(), StatusCode::FORBIDDEN);
+    }
+}
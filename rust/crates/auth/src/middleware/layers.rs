@@ -4,13 +4,18 @@
 //! that handle JWT validation and request authorization.
 
 use std::sync::Arc;
-use axum::{extract::Request, middleware::Next, response::{Response, IntoResponse}};
+use axum::{extract::Request, middleware::Next};
+use http::request::Parts;
+use crate::revocation::TokenDenylist;
 use crate::{JwtService, Claims, AuthError, MiddlewareFn};
 use super::extractors::extract_token_from_header;
+use super::services::{guard_middleware, RequestGuard};
 
 /// Main authentication middleware
+#[derive(Clone)]
 pub struct AuthMiddleware {
     jwt_service: Arc<JwtService>,
+    denylist: Option<Arc<dyn TokenDenylist>>,
     required_roles: Vec<String>,
     optional_auth: bool,
 }
@@ -20,6 +25,7 @@ impl AuthMiddleware {
     pub fn new(jwt_service: Arc<JwtService>) -> Self {
         Self {
             jwt_service,
+            denylist: None,
             required_roles: Vec::new(),
             optional_auth: false,
         }
@@ -29,11 +35,18 @@ impl AuthMiddleware {
     pub fn optional(jwt_service: Arc<JwtService>) -> Self {
         Self {
             jwt_service,
+            denylist: None,
             required_roles: Vec::new(),
             optional_auth: true,
         }
     }
 
+    /// Reject tokens that have been revoked
+    pub fn with_denylist(mut self, denylist: Arc<dyn TokenDenylist>) -> Self {
+        self.denylist = Some(denylist);
+        self
+    }
+
     /// Require specific roles for access
     pub fn require_roles(mut self, roles: Vec<String>) -> Self {
         self.required_roles = roles;
@@ -52,60 +65,57 @@ impl AuthMiddleware {
 
     /// Convert to Axum middleware function
     pub fn into_layer(self) -> MiddlewareFn {
-        let jwt_service = self.jwt_service.clone();
-        let required_roles = self.required_roles.clone();
-        let optional_auth = self.optional_auth;
+        guard_middleware(self)
+    }
+}
 
-        Box::new(move |mut req: Request, next: Next| {
-            let jwt_service = jwt_service.clone();
-            let required_roles = required_roles.clone();
+#[async_trait::async_trait]
+impl RequestGuard for AuthMiddleware {
+    async fn check(&self, parts: &mut Parts) -> Result<(), AuthError> {
+        // Extract token from Authorization header
+        let Some(token) = extract_token_from_header(&parts.headers) else {
+            // For optional auth, continue without claims
+            return if self.optional_auth { Ok(()) } else { Err(AuthError::MissingToken) };
+        };
+
+        let claims = match validate_token(&self.jwt_service, self.denylist.as_deref(), &token).await {
+            Ok(claims) => claims,
+            Err(_) if self.optional_auth => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        // Check role requirements
+        if !self.required_roles.is_empty() && !claims.has_any_role(&self.required_roles) {
+            return Err(AuthError::InsufficientPermissions {
+                required: self.required_roles.clone(),
+                actual: claims.roles,
+            });
+        }
 
-            Box::pin(async move {
-                // Extract token from Authorization header
-                let token = extract_token_from_header(req.headers());
-
-                match token {
-                    Some(token) => {
-                        match jwt_service.validate_token(&token) {
-                            Ok(claims) => {
-                                // Check role requirements
-                                if !required_roles.is_empty() {
-                                    if !claims.has_any_role(&required_roles) {
-                                        return AuthError::InsufficientPermissions {
-                                            required: required_roles,
-                                            actual: claims.roles,
-                                        }.into_response();
-                                    }
-                                }
-
-                                // Add claims to request extensions
-                                req.extensions_mut().insert(claims);
-                                next.run(req).await
-                            }
-                            Err(e) => {
-                                if optional_auth {
-                                    // For optional auth, continue without claims
-                                    next.run(req).await
-                                } else {
-                                    e.into_response()
-                                }
-                            }
-                        }
-                    }
-                    None => {
-                        if optional_auth {
-                            // For optional auth, continue without claims
-                            next.run(req).await
-                        } else {
-                            AuthError::MissingToken.into_response()
-                        }
-                    }
-                }
-            })
-        })
+        // Add claims and their auth context to request extensions
+        parts.extensions.insert(crate::types::AuthContext::from_claims(&claims));
+        parts.extensions.insert(claims);
+        Ok(())
     }
 }
 
+/// Validate a token and check it against the denylist, if any
+async fn validate_token(
+    jwt_service: &JwtService,
+    denylist: Option<&dyn TokenDenylist>,
+    token: &str,
+) -> Result<Claims, AuthError> {
+    let claims = jwt_service.validate_token(token)?;
+
+    if let Some(denylist) = denylist {
+        if denylist.is_claims_revoked(&claims).await? {
+            return Err(AuthError::TokenRevoked);
+        }
+    }
+
+    Ok(claims)
+}
+
 /// CORS middleware for authenticated endpoints
 pub struct CorsAuthLayer;
 
@@ -149,3 +159,49 @@ impl Default for CorsAuthLayer {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::revocation::InMemoryTokenDenylist;
+
+    #[tokio::test]
+    async fn test_validate_token_consults_denylist() {
+        let jwt_service = JwtService::new(b"test-secret-key").unwrap();
+        let claims = Claims::new("user123", "tenant456");
+        let token = jwt_service.create_token(&claims).unwrap();
+        let denylist = InMemoryTokenDenylist::new();
+
+        assert!(validate_token(&jwt_service, None, &token).await.is_ok());
+        assert!(validate_token(&jwt_service, Some(&denylist), &token).await.is_ok());
+
+        denylist.revoke_claims(&claims).await.unwrap();
+        assert!(validate_token(&jwt_service, None, &token).await.is_ok());
+        assert!(matches!(
+            validate_token(&jwt_service, Some(&denylist), &token).await,
+            Err(AuthError::TokenRevoked)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_inserts_auth_context() {
+        let jwt_service = JwtService::new(b"test-secret-key").unwrap();
+        let mut claims = Claims::new("user123", "tenant456");
+        claims.roles = vec!["admin".to_string()];
+        let token = jwt_service.create_token(&claims).unwrap();
+        let middleware = AuthMiddleware::new(Arc::new(jwt_service));
+
+        let (mut parts, _) = Request::builder()
+            .header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(())
+            .unwrap()
+            .into_parts();
+        middleware.check(&mut parts).await.unwrap();
+
+        let context = parts.extensions.get::<crate::types::AuthContext>().unwrap();
+        assert_eq!(context.user_id, "user123");
+        assert_eq!(context.tenant_id, "tenant456");
+        assert_eq!(context.roles, vec!["admin".to_string()]);
+        assert!(parts.extensions.get::<Claims>().is_some());
+    }
+}
@@ -4,15 +4,29 @@
 //! - `layers`: Core authentication middleware layers
 //! - `extractors`: Request extractors for authenticated users
 //! - `guards`: Authorization guards for roles and permissions
+//! - `authorized`: Typed role and permission extractors
+//! - `services`: Tower `Layer`/`Service` implementations of the guards
+//! - `sessions`: Cookie session resolution into `AuthContext`
+//! - `api_keys`: API key resolution into `AuthContext`
 
 pub mod layers;
 pub mod extractors;
 pub mod guards;
+pub mod authorized;
+pub mod services;
+pub mod sessions;
+#[cfg(feature = "api-keys")]
+pub mod api_keys;
 
 // Re-export for backward compatibility and convenience
 pub use layers::{AuthMiddleware, CorsAuthLayer};
 pub use extractors::{AuthenticatedUser, OptionalUser, extract_token_from_header};
-pub use guards::{RoleGuard, PermissionGuard, TenantGuard};
+pub use guards::{RoleGuard, PermissionGuard, TenantGuard, TENANT_HEADER};
+pub use services::{GuardLayer, GuardService, JsonRejection, Rejection, RequestGuard};
+pub use authorized::{Authorized, HasPermission, RoleRequirement, PermissionRequirement, Admin, TenantAdmin};
+pub use sessions::{SessionMiddleware, extract_cookie, DEFAULT_SESSION_COOKIE};
+#[cfg(feature = "api-keys")]
+pub use api_keys::{ApiKeyMiddleware, extract_api_key, API_KEY_HEADER};
 
 // Re-export types needed by middleware
 pub use crate::{Claims, AuthError, MiddlewareFn};
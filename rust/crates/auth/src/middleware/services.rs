@@ -0,0 +1,293 @@
+//! Tower `Layer` and `Service` implementations of the auth middleware.
+//!
+//! `AuthMiddleware`, `SessionMiddleware`, `ApiKeyMiddleware`, `RoleGuard`,
+//! `PermissionGuard` and `TenantGuard` are `tower::Layer`s, so they compose
+//! with `ServiceBuilder` as well as axum routers:
+//!
+//! ```rust,ignore
+//! let service = ServiceBuilder::new()
+//!     .layer(AuthMiddleware::new(jwt_service))
+//!     .layer(RoleGuard::require_admin())
+//!     .service(inner);
+//! ```
+//!
+//! Used directly as layers, guards reject with the same status and JSON body
+//! as the axum middleware, which needs a response body implementing
+//! `From<String>`. Stacks whose bodies don't (tonic's `BoxBody`, hyper's
+//! `Incoming`) wrap the guard in a [`GuardLayer`] with their own rejection:
+//!
+//! ```rust,ignore
+//! let layer = GuardLayer::new(AuthMiddleware::new(jwt_service))
+//!     .with_rejection(|error: AuthError| {
+//!         let mut response = http::Response::new(BoxBody::default());
+//!         *response.status_mut() = error.to_http_status();
+//!         response
+//!     });
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use http::request::Parts;
+use tower::{Layer, Service};
+use crate::{AuthError, MiddlewareFn};
+use super::guards::{PermissionGuard, RoleGuard, TenantGuard};
+use super::layers::AuthMiddleware;
+use super::sessions::SessionMiddleware;
+
+/// Check run against a request before it reaches the inner service
+///
+/// Guards may add extensions, such as the authenticated `Claims`.
+#[async_trait::async_trait]
+pub trait RequestGuard: Send + Sync + 'static {
+    async fn check(&self, parts: &mut Parts) -> Result<(), AuthError>;
+}
+
+/// Builds the response for a request a guard rejected
+///
+/// Implemented by [`JsonRejection`] and by closures
+/// `Fn(AuthError) -> http::Response<B>`.
+pub trait Rejection<B>: Send + Sync + 'static {
+    fn reject(&self, error: AuthError) -> http::Response<B>;
+}
+
+impl<B, F> Rejection<B> for F
+where
+    F: Fn(AuthError) -> http::Response<B> + Send + Sync + 'static,
+{
+    fn reject(&self, error: AuthError) -> http::Response<B> {
+        self(error)
+    }
+}
+
+/// Rejection with the status and JSON body of the axum middleware
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonRejection;
+
+impl<B: From<String>> Rejection<B> for JsonRejection {
+    fn reject(&self, error: AuthError) -> http::Response<B> {
+        let mut response = http::Response::new(B::from(error.to_response_body().to_string()));
+        *response.status_mut() = error.to_http_status();
+        response.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static("application/json"),
+        );
+        response
+    }
+}
+
+/// Layer running a [`RequestGuard`] with a custom [`Rejection`]
+pub struct GuardLayer<G, R = JsonRejection> {
+    guard: Arc<G>,
+    rejection: Arc<R>,
+}
+
+impl<G, R> Clone for GuardLayer<G, R> {
+    fn clone(&self) -> Self {
+        Self {
+            guard: self.guard.clone(),
+            rejection: self.rejection.clone(),
+        }
+    }
+}
+
+impl<G> GuardLayer<G> {
+    /// Create a layer rejecting with [`JsonRejection`]
+    pub fn new(guard: G) -> Self {
+        Self { guard: Arc::new(guard), rejection: Arc::new(JsonRejection) }
+    }
+}
+
+impl<G, R> GuardLayer<G, R> {
+    /// Build rejections with `rejection` instead
+    pub fn with_rejection<R2>(self, rejection: R2) -> GuardLayer<G, R2> {
+        GuardLayer { guard: self.guard, rejection: Arc::new(rejection) }
+    }
+}
+
+impl<S, G, R> Layer<S> for GuardLayer<G, R> {
+    type Service = GuardService<S, G, R>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GuardService {
+            inner,
+            guard: self.guard.clone(),
+            rejection: self.rejection.clone(),
+        }
+    }
+}
+
+/// Service running a [`RequestGuard`] before the inner service
+pub struct GuardService<S, G, R = JsonRejection> {
+    inner: S,
+    guard: Arc<G>,
+    rejection: Arc<R>,
+}
+
+impl<S: Clone, G, R> Clone for GuardService<S, G, R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            guard: self.guard.clone(),
+            rejection: self.rejection.clone(),
+        }
+    }
+}
+
+impl<S, G> GuardService<S, G> {
+    /// Wrap a service with a guard
+    pub fn new(inner: S, guard: G) -> Self {
+        GuardLayer::new(guard).layer(inner)
+    }
+}
+
+impl<S, G, R, ReqBody, ResBody> Service<http::Request<ReqBody>> for GuardService<S, G, R>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send,
+    G: RequestGuard,
+    R: Rejection<ResBody>,
+    ReqBody: Send + 'static,
+{
+    type Response = http::Response<ResBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<ReqBody>) -> Self::Future {
+        // Use the instance that was polled ready and leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let guard = self.guard.clone();
+        let rejection = self.rejection.clone();
+
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+            match guard.check(&mut parts).await {
+                Ok(()) => inner.call(http::Request::from_parts(parts, body)).await,
+                Err(error) => Ok(rejection.reject(error)),
+            }
+        })
+    }
+}
+
+/// Axum middleware function running a guard
+pub(crate) fn guard_middleware<G: RequestGuard>(guard: G) -> MiddlewareFn {
+    let guard = Arc::new(guard);
+
+    Box::new(move |req: Request, next: Next| {
+        let guard = guard.clone();
+
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+            match guard.check(&mut parts).await {
+                Ok(()) => next.run(Request::from_parts(parts, body)).await,
+                Err(error) => error.into_response(),
+            }
+        })
+    })
+}
+
+macro_rules! impl_guard_layer {
+    ($($guard:ty),+) => {
+        $(
+            impl<S> Layer<S> for $guard {
+                type Service = GuardService<S, $guard>;
+
+                fn layer(&self, inner: S) -> Self::Service {
+                    GuardService::new(inner, self.clone())
+                }
+            }
+        )+
+    };
+}
+
+impl_guard_layer!(AuthMiddleware, SessionMiddleware, RoleGuard, PermissionGuard, TenantGuard);
+#[cfg(feature = "api-keys")]
+impl_guard_layer!(super::api_keys::ApiKeyMiddleware);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use tower::{service_fn, ServiceBuilder, ServiceExt};
+    use crate::{Claims, JwtService};
+
+    async fn echo_user(req: http::Request<String>) -> Result<http::Response<String>, Infallible> {
+        let user = req.extensions().get::<Claims>().map(|claims| claims.sub.clone()).unwrap_or_default();
+        Ok(http::Response::new(user))
+    }
+
+    fn request(token: Option<&str>) -> http::Request<String> {
+        let mut builder = http::Request::builder().uri("/");
+        if let Some(token) = token {
+            builder = builder.header("authorization", format!("Bearer {}", token));
+        }
+        builder.body(String::new()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_auth_and_role_layers_compose() {
+        let jwt_service = Arc::new(JwtService::new(b"test-secret-key").unwrap());
+        let service = ServiceBuilder::new()
+            .layer(AuthMiddleware::new(jwt_service.clone()))
+            .layer(RoleGuard::require_admin())
+            .service(service_fn(echo_user));
+
+        let response = service.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(response.status(), http::StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(body["error"]["code"], "AUTHENTICATION_ERROR");
+
+        let user = jwt_service.create_access_token("user123", "tenant1", vec!["user".to_string()]).unwrap();
+        let response = service.clone().oneshot(request(Some(&user))).await.unwrap();
+        assert_eq!(response.status(), http::StatusCode::FORBIDDEN);
+
+        let admin = jwt_service.create_access_token("admin1", "tenant1", vec!["admin".to_string()]).unwrap();
+        let response = service.oneshot(request(Some(&admin))).await.unwrap();
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(response.body(), "admin1");
+    }
+
+    #[tokio::test]
+    async fn test_permission_guard_service() {
+        let service = PermissionGuard::new(vec!["users:read".to_string()]).layer(service_fn(echo_user));
+        let mut claims = Claims::new("user123", "tenant1");
+
+        let mut req = request(None);
+        req.extensions_mut().insert(claims.clone());
+        assert_eq!(service.clone().oneshot(req).await.unwrap().status(), http::StatusCode::FORBIDDEN);
+
+        claims.permissions = vec!["users:read".to_string()];
+        let mut req = request(None);
+        req.extensions_mut().insert(claims);
+        assert_eq!(service.oneshot(req).await.unwrap().status(), http::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_custom_rejection() {
+        // A body type without `From<String>`
+        #[derive(Debug, PartialEq)]
+        struct Empty;
+
+        let rejection = |error: AuthError| {
+            let mut response = http::Response::new(Empty);
+            *response.status_mut() = error.to_http_status();
+            response
+        };
+        let service = ServiceBuilder::new()
+            .layer(GuardLayer::new(RoleGuard::require_admin()).with_rejection(rejection))
+            .service(service_fn(|_: http::Request<String>| async { Ok::<_, Infallible>(http::Response::new(Empty)) }));
+
+        let response = service.oneshot(request(None)).await.unwrap();
+        assert_eq!(response.status(), http::StatusCode::UNAUTHORIZED);
+        assert_eq!(response.body(), &Empty);
+    }
+}
@@ -0,0 +1,175 @@
+//! Cookie session middleware for Axum.
+//!
+//! This module resolves a session cookie through a `SessionManager` and
+//! exposes the session to handlers as an `AuthContext` request extension.
+
+use std::sync::Arc;
+use axum::http::{header, HeaderMap};
+use http::request::Parts;
+use crate::sessions::SessionManager;
+use crate::types::{AuthContext, UserSession};
+use crate::{AuthError, MiddlewareFn};
+use super::services::{guard_middleware, RequestGuard};
+
+/// Default name of the session cookie
+pub const DEFAULT_SESSION_COOKIE: &str = "session_id";
+
+/// Extract a cookie value from the Cookie headers
+pub fn extract_cookie(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+/// Session cookie authentication middleware
+#[derive(Clone)]
+pub struct SessionMiddleware {
+    sessions: Arc<SessionManager>,
+    cookie_name: String,
+    optional_auth: bool,
+}
+
+impl SessionMiddleware {
+    /// Create new middleware requiring a valid session
+    pub fn new(sessions: Arc<SessionManager>) -> Self {
+        Self {
+            sessions,
+            cookie_name: DEFAULT_SESSION_COOKIE.to_string(),
+            optional_auth: false,
+        }
+    }
+
+    /// Create new middleware that lets requests without a session through
+    pub fn optional(sessions: Arc<SessionManager>) -> Self {
+        Self {
+            optional_auth: true,
+            ..Self::new(sessions)
+        }
+    }
+
+    /// Use a custom cookie name
+    pub fn with_cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.cookie_name = name.into();
+        self
+    }
+
+    /// `Set-Cookie` value that starts a session in the browser
+    ///
+    /// The cookie has no `Max-Age`; the server-side expiry is authoritative.
+    pub fn session_cookie(&self, session: &UserSession) -> String {
+        format!("{}={}; Path=/; HttpOnly; Secure; SameSite=Lax", self.cookie_name, session.session_id)
+    }
+
+    /// `Set-Cookie` value that removes the session cookie
+    pub fn clear_cookie(&self) -> String {
+        format!("{}=; Path=/; HttpOnly; Secure; SameSite=Lax; Max-Age=0", self.cookie_name)
+    }
+
+    /// Convert to Axum middleware function
+    ///
+    /// Resolving a session slides its expiry; the `UserSession` and its
+    /// `AuthContext` are both added to the request extensions.
+    pub fn into_layer(self) -> MiddlewareFn {
+        guard_middleware(self)
+    }
+}
+
+#[async_trait::async_trait]
+impl RequestGuard for SessionMiddleware {
+    async fn check(&self, parts: &mut Parts) -> Result<(), AuthError> {
+        let Some(session_id) = extract_cookie(&parts.headers, &self.cookie_name) else {
+            return if self.optional_auth { Ok(()) } else { Err(AuthError::MissingToken) };
+        };
+
+        match self.sessions.resolve_session(&session_id).await? {
+            Some(session) => {
+                parts.extensions.insert(AuthContext::from_session(&session));
+                parts.extensions.insert(session);
+                Ok(())
+            }
+            None if self.optional_auth => Ok(()),
+            None => Err(AuthError::SessionExpired),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, extract::Request, http::StatusCode, middleware::Next, routing::get, Extension, Router};
+    use tower::ServiceExt;
+    use crate::sessions::SessionConfig;
+
+    fn app(middleware: SessionMiddleware) -> Router {
+        let layer = Arc::new(middleware.into_layer());
+        Router::new()
+            .route("/me", get(|context: Option<Extension<AuthContext>>| async move {
+                context.map(|Extension(context)| context.user_id).unwrap_or_default()
+            }))
+            .layer(axum::middleware::from_fn(move |req: Request, next: Next| {
+                let layer = layer.clone();
+                async move { layer(req, next).await }
+            }))
+    }
+
+    fn request(cookie: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder().uri("/me");
+        if let Some(cookie) = cookie {
+            builder = builder.header("cookie", cookie);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    async fn body_text(response: axum::response::Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_extract_cookie() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, "theme=dark; session_id=abc123".parse().unwrap());
+        assert_eq!(extract_cookie(&headers, "session_id").as_deref(), Some("abc123"));
+        assert!(extract_cookie(&headers, "missing").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_session_resolves_auth_context() {
+        let sessions = Arc::new(SessionManager::new(SessionConfig::default()));
+        let session = sessions
+            .create_session(UserSession::new("user123".to_string(), "tenant1".to_string()))
+            .await
+            .unwrap();
+        let middleware = SessionMiddleware::new(sessions.clone());
+        let cookie = middleware.session_cookie(&session);
+        assert!(cookie.contains("HttpOnly"));
+        let app = app(middleware);
+
+        let cookie = format!("theme=dark; session_id={}", session.session_id);
+        let response = app.clone().oneshot(request(Some(&cookie))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_text(response).await, "user123");
+
+        let response = app.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        sessions.terminate_session(&session.session_id).await.unwrap();
+        let response = app.oneshot(request(Some(&cookie))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_optional_session() {
+        let sessions = Arc::new(SessionManager::new(SessionConfig::default()));
+        let app = app(SessionMiddleware::optional(sessions));
+
+        let response = app.oneshot(request(Some("session_id=unknown"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_text(response).await, "");
+    }
+}
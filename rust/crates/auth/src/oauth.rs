@@ -0,0 +1,21 @@
+//! OAuth 2.0 authorization server
+//!
+//! This module builds the authorization-code grant on top of `PkceHandler`:
+//! - `clients`: Client registry with redirect URI validation
+//! - `codes`: Single-use, short-lived authorization code storage
+//! - `server`: Code issuance, exchange for `AuthTokens` and revocation
+//! - `router`: Axum token and revocation endpoints
+
+pub mod types;
+pub mod clients;
+pub mod codes;
+pub mod server;
+#[cfg(feature = "axum")]
+pub mod router;
+
+pub use types::*;
+pub use clients::{OAuthClient, ClientRegistry};
+pub use codes::{AuthorizationCode, AuthorizationCodeStore, InMemoryAuthorizationCodeStore, DEFAULT_CODE_TTL};
+pub use server::AuthorizationServer;
+#[cfg(feature = "axum")]
+pub use router::{oauth_router, REVOCATION_PATH, TOKEN_PATH};
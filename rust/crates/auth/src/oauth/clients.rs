@@ -0,0 +1,297 @@
+//! OAuth client registry
+//!
+//! Clients are registered with their exact redirect URIs. Confidential
+//! clients store an Argon2 hash of their secret; public clients (SPAs,
+//! native apps) authenticate with PKCE alone.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use crate::security::CryptoUtils;
+use crate::types::{AuthResult, AuthError};
+
+/// Registered OAuth client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthClient {
+    /// Client identifier
+    pub client_id: String,
+
+    /// Human-readable client name
+    pub name: Option<String>,
+
+    /// Argon2 hash of the client secret (None for public clients)
+    pub client_secret_hash: Option<String>,
+
+    /// Registered redirect URIs
+    pub redirect_uris: Vec<String>,
+
+    /// Scopes the client may request (empty allows any scope)
+    pub allowed_scopes: Vec<String>,
+}
+
+impl OAuthClient {
+    /// Create a public client that authenticates with PKCE only
+    pub fn public(client_id: impl Into<String>, redirect_uris: Vec<String>) -> AuthResult<Self> {
+        for uri in &redirect_uris {
+            validate_registered_uri(uri)?;
+        }
+
+        Ok(Self {
+            client_id: client_id.into(),
+            name: None,
+            client_secret_hash: None,
+            redirect_uris,
+            allowed_scopes: Vec::new(),
+        })
+    }
+
+    /// Create a confidential client with a secret
+    pub fn confidential(client_id: impl Into<String>, client_secret: &str, redirect_uris: Vec<String>) -> AuthResult<Self> {
+        let mut client = Self::public(client_id, redirect_uris)?;
+        client.client_secret_hash = Some(CryptoUtils::hash_password(client_secret)?);
+        Ok(client)
+    }
+
+    /// Set the client name
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Restrict the scopes the client may request
+    pub fn with_scopes(mut self, scopes: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.allowed_scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Check if the client must authenticate with a secret
+    pub fn is_confidential(&self) -> bool {
+        self.client_secret_hash.is_some()
+    }
+
+    /// Check if a redirect URI is registered for this client
+    ///
+    /// URIs must match exactly, except that the port of a loopback
+    /// redirect URI may vary (RFC 8252 section 7.3).
+    pub fn is_redirect_uri_allowed(&self, redirect_uri: &str) -> bool {
+        self.redirect_uris.iter().any(|registered| {
+            registered == redirect_uri
+                || matches!(
+                    (strip_loopback_port(registered), strip_loopback_port(redirect_uri)),
+                    (Some(a), Some(b)) if a == b
+                )
+        })
+    }
+
+    /// Check if every requested scope is allowed for this client
+    pub fn is_scope_allowed(&self, scope: Option<&str>) -> bool {
+        if self.allowed_scopes.is_empty() {
+            return true;
+        }
+        scope
+            .unwrap_or_default()
+            .split_whitespace()
+            .all(|requested| self.allowed_scopes.iter().any(|allowed| allowed == requested))
+    }
+
+    /// Verify a client secret
+    pub fn verify_secret(&self, client_secret: &str) -> bool {
+        match &self.client_secret_hash {
+            Some(hash) => CryptoUtils::verify_password(client_secret, hash).is_ok_and(|verification| verification.matches),
+            None => false,
+        }
+    }
+}
+
+fn validate_registered_uri(uri: &str) -> AuthResult<()> {
+    if uri.contains('#') {
+        return Err(AuthError::InvalidRequest("Redirect URI must not contain a fragment".to_string()));
+    }
+    let is_https = uri.starts_with("https://");
+    let is_loopback = strip_loopback_port(uri).is_some();
+    let is_custom_scheme = uri.split_once(':').is_some_and(|(scheme, _)| scheme.contains('.') && !scheme.contains('/'));
+    if !(is_https || is_loopback || is_custom_scheme) {
+        return Err(AuthError::InvalidRequest(format!(
+            "Redirect URI must use https, a loopback address or a private-use scheme: {}",
+            uri
+        )));
+    }
+    Ok(())
+}
+
+/// Strip the port from an `http://127.0.0.1` or `http://[::1]` URI
+fn strip_loopback_port(uri: &str) -> Option<String> {
+    let rest = uri.strip_prefix("http://")?;
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let host = match authority.strip_prefix("[::1]") {
+        Some(port) if port.is_empty() || port.starts_with(':') => "[::1]",
+        _ => match authority.split_once(':') {
+            Some(("127.0.0.1", _)) => "127.0.0.1",
+            None if authority == "127.0.0.1" => "127.0.0.1",
+            _ => return None,
+        },
+    };
+    Some(format!("http://{}{}", host, path))
+}
+
+/// How long a successfully verified client secret is remembered
+const VERIFIED_SECRET_TTL: Duration = Duration::from_secs(60);
+
+/// Client secret that recently passed Argon2 verification
+#[derive(Debug)]
+struct VerifiedSecret {
+    /// SHA-256 of the presented secret
+    digest: String,
+    /// Stored hash it was verified against, so a re-registered secret is not matched
+    secret_hash: Option<String>,
+    expires_at: Instant,
+}
+
+/// In-memory registry of OAuth clients
+#[derive(Debug, Default)]
+pub struct ClientRegistry {
+    clients: RwLock<HashMap<String, OAuthClient>>,
+    verified: Mutex<HashMap<String, VerifiedSecret>>,
+}
+
+impl ClientRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register or replace a client
+    pub fn register(&self, client: OAuthClient) {
+        self.verified.lock().unwrap().remove(&client.client_id);
+        let mut clients = self.clients.write().unwrap();
+        clients.insert(client.client_id.clone(), client);
+    }
+
+    /// Remove a client, returning it if it was registered
+    pub fn remove(&self, client_id: &str) -> Option<OAuthClient> {
+        self.verified.lock().unwrap().remove(client_id);
+        self.clients.write().unwrap().remove(client_id)
+    }
+
+    /// Look up a client
+    pub fn get(&self, client_id: &str) -> Option<OAuthClient> {
+        self.clients.read().unwrap().get(client_id).cloned()
+    }
+
+    /// Authenticate a client at the token endpoint
+    ///
+    /// Confidential clients must present their secret; public clients must not.
+    pub async fn authenticate(&self, client_id: &str, client_secret: Option<&str>) -> Option<OAuthClient> {
+        let client = self.get(client_id)?;
+        let authenticated = match client_secret {
+            Some(secret) => self.verify_secret(&client, secret).await,
+            None => !client.is_confidential(),
+        };
+        authenticated.then_some(client)
+    }
+
+    /// Verify a client secret without blocking the async runtime
+    ///
+    /// Argon2 is slow by design, so verification runs on the blocking pool.
+    /// A secret that verified successfully is remembered by its SHA-256 for
+    /// a minute, so clients calling the token endpoint repeatedly are not
+    /// verified from scratch each time.
+    async fn verify_secret(&self, client: &OAuthClient, secret: &str) -> bool {
+        if !client.is_confidential() {
+            return false;
+        }
+
+        let digest = CryptoUtils::sha256_hash(secret.as_bytes());
+        if self.is_recently_verified(client, &digest) {
+            return true;
+        }
+
+        let verifying = client.clone();
+        let secret = secret.to_string();
+        let verified = tokio::task::spawn_blocking(move || verifying.verify_secret(&secret))
+            .await
+            .unwrap_or(false);
+        if verified {
+            self.verified.lock().unwrap().insert(client.client_id.clone(), VerifiedSecret {
+                digest,
+                secret_hash: client.client_secret_hash.clone(),
+                expires_at: Instant::now() + VERIFIED_SECRET_TTL,
+            });
+        }
+        verified
+    }
+
+    fn is_recently_verified(&self, client: &OAuthClient, digest: &str) -> bool {
+        self.verified.lock().unwrap().get(&client.client_id).is_some_and(|verified| {
+            verified.expires_at > Instant::now()
+                && verified.secret_hash == client.client_secret_hash
+                && CryptoUtils::constant_time_eq(verified.digest.as_bytes(), digest.as_bytes())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redirect_uri_validation() {
+        let client = OAuthClient::public(
+            "spa",
+            vec!["https://app.example.com/callback".to_string(), "http://127.0.0.1/callback".to_string()],
+        ).unwrap();
+
+        assert!(client.is_redirect_uri_allowed("https://app.example.com/callback"));
+        assert!(!client.is_redirect_uri_allowed("https://app.example.com/callback/evil"));
+        assert!(!client.is_redirect_uri_allowed("https://evil.example.com/callback"));
+        assert!(client.is_redirect_uri_allowed("http://127.0.0.1:53817/callback"));
+        assert!(!client.is_redirect_uri_allowed("http://127.0.0.1:53817/other"));
+
+        assert!(OAuthClient::public("bad", vec!["http://app.example.com/callback".to_string()]).is_err());
+        assert!(OAuthClient::public("bad", vec!["https://app.example.com/#frag".to_string()]).is_err());
+        assert!(OAuthClient::public("native", vec!["com.example.app:/callback".to_string()]).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_client_authentication() {
+        let registry = ClientRegistry::new();
+        registry.register(OAuthClient::confidential("backend", "s3cret", vec!["https://api.example.com/cb".to_string()]).unwrap());
+        registry.register(OAuthClient::public("spa", vec!["https://app.example.com/cb".to_string()]).unwrap());
+
+        assert!(registry.authenticate("backend", Some("s3cret")).await.is_some());
+        assert!(registry.authenticate("backend", Some("wrong")).await.is_none());
+        assert!(registry.authenticate("backend", None).await.is_none());
+        assert!(registry.authenticate("spa", None).await.is_some());
+        assert!(registry.authenticate("spa", Some("anything")).await.is_none());
+        assert!(registry.authenticate("unknown", None).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_verified_secrets_are_cached_per_registration() {
+        let registry = ClientRegistry::new();
+        let redirect_uris = vec!["https://api.example.com/cb".to_string()];
+        registry.register(OAuthClient::confidential("backend", "s3cret", redirect_uris.clone()).unwrap());
+
+        assert!(registry.authenticate("backend", Some("s3cret")).await.is_some());
+        assert!(registry.verified.lock().unwrap().contains_key("backend"));
+        assert!(registry.authenticate("backend", Some("s3cret")).await.is_some());
+        assert!(registry.authenticate("backend", Some("wrong")).await.is_none());
+
+        // Rotating the secret drops the remembered one
+        registry.register(OAuthClient::confidential("backend", "rotated", redirect_uris).unwrap());
+        assert!(registry.authenticate("backend", Some("s3cret")).await.is_none());
+        assert!(registry.authenticate("backend", Some("rotated")).await.is_some());
+    }
+
+    #[test]
+    fn test_scope_restrictions() {
+        let client = OAuthClient::public("spa", vec!["https://app.example.com/cb".to_string()])
+            .unwrap()
+            .with_scopes(["read", "write"]);
+
+        assert!(client.is_scope_allowed(Some("read write")));
+        assert!(client.is_scope_allowed(None));
+        assert!(!client.is_scope_allowed(Some("read admin")));
+    }
+}
@@ -0,0 +1,133 @@
+//! Authorization code storage
+//!
+//! Codes are single use and short lived. Stores key codes by their SHA-256
+//! hash so that the stored values cannot be redeemed if leaked.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use crate::pkce::PkceMethod;
+use crate::security::CryptoUtils;
+use crate::types::AuthResult;
+
+/// Default authorization code lifetime (seconds)
+pub const DEFAULT_CODE_TTL: u64 = 60;
+
+/// Authorization code grant awaiting exchange
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorizationCode {
+    /// Client the code was issued to
+    pub client_id: String,
+
+    /// Redirect URI used in the authorization request
+    pub redirect_uri: String,
+
+    /// Authenticated user
+    pub user_id: String,
+
+    /// User's tenant
+    pub tenant_id: String,
+
+    /// User's roles at the time of authorization
+    pub roles: Vec<String>,
+
+    /// Granted scope
+    pub scope: Option<String>,
+
+    /// PKCE code challenge
+    pub code_challenge: String,
+
+    /// PKCE code challenge method
+    pub code_challenge_method: PkceMethod,
+
+    /// Expiration timestamp
+    pub expires_at: u64,
+}
+
+/// Storage for pending authorization codes
+#[async_trait::async_trait]
+pub trait AuthorizationCodeStore: Send + Sync {
+    /// Store a grant under the hash of its code
+    async fn insert_code(&self, code_hash: String, grant: AuthorizationCode) -> AuthResult<()>;
+
+    /// Remove and return a grant, so each code can be exchanged only once
+    async fn take_code(&self, code_hash: &str) -> AuthResult<Option<AuthorizationCode>>;
+
+    /// Delete codes that expired before `now`, returning the number removed
+    async fn delete_expired(&self, now: u64) -> AuthResult<usize>;
+}
+
+/// In-memory authorization code store
+#[derive(Default)]
+pub struct InMemoryAuthorizationCodeStore {
+    codes: Mutex<HashMap<String, AuthorizationCode>>,
+}
+
+impl InMemoryAuthorizationCodeStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl AuthorizationCodeStore for InMemoryAuthorizationCodeStore {
+    async fn insert_code(&self, code_hash: String, grant: AuthorizationCode) -> AuthResult<()> {
+        self.codes.lock().unwrap().insert(code_hash, grant);
+        Ok(())
+    }
+
+    async fn take_code(&self, code_hash: &str) -> AuthResult<Option<AuthorizationCode>> {
+        Ok(self.codes.lock().unwrap().remove(code_hash))
+    }
+
+    async fn delete_expired(&self, now: u64) -> AuthResult<usize> {
+        let mut codes = self.codes.lock().unwrap();
+        let initial_count = codes.len();
+        codes.retain(|_, grant| grant.expires_at > now);
+        Ok(initial_count - codes.len())
+    }
+}
+
+/// Hash a code for storage lookups
+pub(crate) fn hash_code(code: &str) -> String {
+    CryptoUtils::sha256_hash(code.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grant(expires_at: u64) -> AuthorizationCode {
+        AuthorizationCode {
+            client_id: "spa".to_string(),
+            redirect_uri: "https://app.example.com/cb".to_string(),
+            user_id: "user123".to_string(),
+            tenant_id: "tenant456".to_string(),
+            roles: vec![],
+            scope: None,
+            code_challenge: "challenge".to_string(),
+            code_challenge_method: PkceMethod::S256,
+            expires_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_codes_are_single_use() {
+        let store = InMemoryAuthorizationCodeStore::new();
+        store.insert_code(hash_code("abc"), grant(u64::MAX)).await.unwrap();
+
+        assert!(store.take_code(&hash_code("abc")).await.unwrap().is_some());
+        assert!(store.take_code(&hash_code("abc")).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delete_expired() {
+        let store = InMemoryAuthorizationCodeStore::new();
+        store.insert_code(hash_code("old"), grant(10)).await.unwrap();
+        store.insert_code(hash_code("new"), grant(u64::MAX)).await.unwrap();
+
+        assert_eq!(store.delete_expired(100).await.unwrap(), 1);
+        assert!(store.take_code(&hash_code("new")).await.unwrap().is_some());
+    }
+}
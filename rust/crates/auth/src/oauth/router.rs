@@ -0,0 +1,308 @@
+//! Token and revocation endpoint router
+
+use std::sync::Arc;
+use axum::{
+    extract::{rejection::FormRejection, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    routing::post,
+    Form, Json, Router,
+};
+use base64::Engine;
+use serde::Deserialize;
+use crate::pkce::PkceTokenRequest;
+
+use super::server::AuthorizationServer;
+use super::types::OAuthError;
+
+/// Path of the token endpoint
+pub const TOKEN_PATH: &str = "/oauth/token";
+
+/// Path of the revocation endpoint
+pub const REVOCATION_PATH: &str = "/oauth/revoke";
+
+/// Token endpoint form fields (RFC 6749 section 4.1.3)
+#[derive(Debug, Deserialize)]
+struct TokenForm {
+    grant_type: Option<String>,
+    code: Option<String>,
+    redirect_uri: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    code_verifier: Option<String>,
+}
+
+/// Revocation endpoint form fields (RFC 7009 section 2.1)
+///
+/// `token_type_hint` is accepted but unused, since tokens are self-describing.
+#[derive(Debug, Deserialize)]
+struct RevocationForm {
+    token: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+}
+
+/// Router serving the OAuth token endpoint at `/oauth/token` and the
+/// revocation endpoint at `/oauth/revoke`
+///
+/// Clients authenticate with HTTP Basic or `client_secret` in the form body;
+/// public clients send only `client_id`.
+pub fn oauth_router(server: Arc<AuthorizationServer>) -> Router {
+    Router::new()
+        .route(TOKEN_PATH, post(token_endpoint))
+        .route(REVOCATION_PATH, post(revocation_endpoint))
+        .with_state(server)
+}
+
+async fn token_endpoint(
+    State(server): State<Arc<AuthorizationServer>>,
+    headers: HeaderMap,
+    form: Result<Form<TokenForm>, FormRejection>,
+) -> Response {
+    match handle_token_request(&server, &headers, form).await {
+        Ok(response) => {
+            let mut response = Json(response).into_response();
+            let headers = response.headers_mut();
+            headers.insert(http::header::CACHE_CONTROL, http::HeaderValue::from_static("no-store"));
+            headers.insert(http::header::PRAGMA, http::HeaderValue::from_static("no-cache"));
+            response
+        }
+        Err(error) => error.into_response(),
+    }
+}
+
+async fn handle_token_request(
+    server: &AuthorizationServer,
+    headers: &HeaderMap,
+    form: Result<Form<TokenForm>, FormRejection>,
+) -> Result<super::types::OAuthTokenResponse, OAuthError> {
+    let Form(form) = form.map_err(|e| OAuthError::InvalidRequest(e.body_text()))?;
+    let (client_id, client_secret) = client_credentials(headers, form.client_id, form.client_secret)?;
+
+    let request = PkceTokenRequest {
+        grant_type: form.grant_type.ok_or_else(|| missing("grant_type"))?,
+        code: form.code.ok_or_else(|| missing("code"))?,
+        redirect_uri: form.redirect_uri.ok_or_else(|| missing("redirect_uri"))?,
+        client_id,
+        code_verifier: form.code_verifier.ok_or_else(|| missing("code_verifier"))?,
+    };
+
+    server.exchange_code(&request, client_secret.as_deref()).await
+}
+
+async fn revocation_endpoint(
+    State(server): State<Arc<AuthorizationServer>>,
+    headers: HeaderMap,
+    form: Result<Form<RevocationForm>, FormRejection>,
+) -> Response {
+    match handle_revocation_request(&server, &headers, form).await {
+        Ok(()) => {
+            let mut response = http::StatusCode::OK.into_response();
+            response.headers_mut().insert(http::header::CACHE_CONTROL, http::HeaderValue::from_static("no-store"));
+            response
+        }
+        Err(error) => error.into_response(),
+    }
+}
+
+async fn handle_revocation_request(
+    server: &AuthorizationServer,
+    headers: &HeaderMap,
+    form: Result<Form<RevocationForm>, FormRejection>,
+) -> Result<(), OAuthError> {
+    let Form(form) = form.map_err(|e| OAuthError::InvalidRequest(e.body_text()))?;
+    let (client_id, client_secret) = client_credentials(headers, form.client_id, form.client_secret)?;
+    let token = form.token.ok_or_else(|| missing("token"))?;
+
+    server.revoke(&token, &client_id, client_secret.as_deref()).await
+}
+
+/// Resolve client credentials from the Authorization header or form body
+fn client_credentials(
+    headers: &HeaderMap,
+    form_client_id: Option<String>,
+    form_client_secret: Option<String>,
+) -> Result<(String, Option<String>), OAuthError> {
+    match (basic_credentials(headers)?, form_client_id) {
+        (Some((id, _)), Some(form_id)) if id != form_id => {
+            Err(OAuthError::InvalidRequest("client_id does not match Authorization header".to_string()))
+        }
+        (Some((id, secret)), _) => Ok((id, Some(secret))),
+        (None, Some(id)) => Ok((id, form_client_secret)),
+        (None, None) => Err(OAuthError::InvalidClient),
+    }
+}
+
+fn missing(field: &str) -> OAuthError {
+    OAuthError::InvalidRequest(format!("Missing {}", field))
+}
+
+/// Parse `Authorization: Basic` client credentials
+///
+/// The client ID and secret are form-urlencoded before being joined
+/// (RFC 6749 section 2.3.1), so each is decoded after splitting.
+pub(crate) fn basic_credentials(headers: &HeaderMap) -> Result<Option<(String, String)>, OAuthError> {
+    let Some(value) = headers.get(http::header::AUTHORIZATION) else {
+        return Ok(None);
+    };
+    let encoded = value
+        .to_str()
+        .ok()
+        .and_then(|value| value.strip_prefix("Basic "))
+        .ok_or(OAuthError::InvalidClient)?;
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or(OAuthError::InvalidClient)?;
+    let (id, secret) = decoded.split_once(':').ok_or(OAuthError::InvalidClient)?;
+    let id = form_urldecode(id).ok_or(OAuthError::InvalidClient)?;
+    let secret = form_urldecode(secret).ok_or(OAuthError::InvalidClient)?;
+    Ok(Some((id, secret)))
+}
+
+/// Decode an `application/x-www-form-urlencoded` value
+fn form_urldecode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [input.next()?, input.next()?];
+                let hex = std::str::from_utf8(&hex).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+    use crate::oauth::server::tests::{authorize, server, REDIRECT_URI};
+
+    fn token_request(body: String, authorization: Option<&str>) -> Request<Body> {
+        form_request(TOKEN_PATH, body, authorization)
+    }
+
+    fn form_request(uri: &str, body: String, authorization: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/x-www-form-urlencoded");
+        if let Some(authorization) = authorization {
+            builder = builder.header("authorization", authorization);
+        }
+        builder.body(Body::from(body)).unwrap()
+    }
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_token_endpoint_public_client() {
+        let server = Arc::new(server());
+        let (authorization, pkce) = authorize(&server, "spa").await;
+        let app = oauth_router(server);
+
+        let body = format!(
+            "grant_type=authorization_code&code={}&redirect_uri={}&client_id=spa&code_verifier={}",
+            authorization.code, REDIRECT_URI, pkce.verifier()
+        );
+        let response = app.clone().oneshot(token_request(body.clone(), None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["cache-control"], "no-store");
+        let json = json_body(response).await;
+        assert_eq!(json["token_type"], "Bearer");
+        assert_eq!(json["scope"], "read");
+        assert!(json["access_token"].as_str().is_some());
+        assert!(json["refresh_token"].as_str().is_some());
+
+        let response = app.oneshot(token_request(body, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(json_body(response).await["error"], "invalid_grant");
+    }
+
+    #[tokio::test]
+    async fn test_token_endpoint_basic_auth() {
+        let server = Arc::new(server());
+        let (authorization, pkce) = authorize(&server, "backend").await;
+        let app = oauth_router(server);
+
+        let body = format!(
+            "grant_type=authorization_code&code={}&redirect_uri={}&code_verifier={}",
+            authorization.code, REDIRECT_URI, pkce.verifier()
+        );
+        let wrong = format!("Basic {}", base64::engine::general_purpose::STANDARD.encode("backend:wrong"));
+        let response = app.clone().oneshot(token_request(body.clone(), Some(&wrong))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()["www-authenticate"], "Basic");
+
+        let right = format!("Basic {}", base64::engine::general_purpose::STANDARD.encode("backend:s3cret"));
+        let response = app.oneshot(token_request(body, Some(&right))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_basic_credentials_are_form_urldecoded() {
+        let mut headers = HeaderMap::new();
+        let encoded = base64::engine::general_purpose::STANDARD.encode("my%3Aclient:s3cret%2B+%25");
+        headers.insert(http::header::AUTHORIZATION, format!("Basic {}", encoded).parse().unwrap());
+        assert_eq!(
+            basic_credentials(&headers).unwrap(),
+            Some(("my:client".to_string(), "s3cret+ %".to_string()))
+        );
+
+        let invalid = base64::engine::general_purpose::STANDARD.encode("client:bad%2");
+        headers.insert(http::header::AUTHORIZATION, format!("Basic {}", invalid).parse().unwrap());
+        assert!(matches!(basic_credentials(&headers), Err(OAuthError::InvalidClient)));
+    }
+
+    #[tokio::test]
+    async fn test_token_endpoint_rejects_unsupported_grant() {
+        let app = oauth_router(Arc::new(server()));
+        let body = "grant_type=password&code=x&redirect_uri=x&client_id=spa&code_verifier=x".to_string();
+
+        let response = app.oneshot(token_request(body, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(json_body(response).await["error"], "unsupported_grant_type");
+    }
+
+    #[tokio::test]
+    async fn test_revocation_endpoint() {
+        let server = Arc::new(server());
+        let (authorization, pkce) = authorize(&server, "spa").await;
+        let denylist = server.denylist();
+        let app = oauth_router(server);
+
+        let body = format!(
+            "grant_type=authorization_code&code={}&redirect_uri={}&client_id=spa&code_verifier={}",
+            authorization.code, REDIRECT_URI, pkce.verifier()
+        );
+        let response = app.clone().oneshot(token_request(body, None)).await.unwrap();
+        let access_token = json_body(response).await["access_token"].as_str().unwrap().to_string();
+
+        let body = format!("token={}&token_type_hint=access_token&client_id=spa", access_token);
+        let response = app.clone().oneshot(form_request(REVOCATION_PATH, body, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let jti = crate::JwtService::new(b"test-secret-key").unwrap().validate_token(&access_token).unwrap().jti.unwrap();
+        assert!(denylist.is_revoked(&jti).await.unwrap());
+
+        // Unknown tokens are not an error
+        let body = "token=unknown&client_id=spa".to_string();
+        let response = app.clone().oneshot(form_request(REVOCATION_PATH, body, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = "client_id=spa".to_string();
+        let response = app.oneshot(form_request(REVOCATION_PATH, body, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
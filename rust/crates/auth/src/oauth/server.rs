@@ -0,0 +1,413 @@
+//! OAuth 2.0 authorization-code grant with PKCE
+//!
+//! Services authenticate the user and collect consent themselves, then call
+//! `AuthorizationServer::authorize` to mint a code. The token endpoint
+//! exchanges that code for access and refresh tokens.
+
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::pkce::{PkceAuthorizationRequest, PkceHandler, PkceTokenRequest};
+use crate::refresh_tokens::RefreshTokenManager;
+use crate::revocation::{InMemoryTokenDenylist, TokenDenylist};
+use crate::security::CryptoUtils;
+use crate::types::AuthTokens;
+use crate::{Claims, JwtService};
+#[cfg(feature = "observability")]
+use cloudshuttle_observability::audit::{audit_auth, AuditResult};
+
+use super::clients::ClientRegistry;
+use super::codes::{hash_code, AuthorizationCode, AuthorizationCodeStore, InMemoryAuthorizationCodeStore, DEFAULT_CODE_TTL};
+use super::types::{AuthorizationResponse, OAuthError, OAuthResult, OAuthTokenResponse};
+
+/// OAuth authorization server
+pub struct AuthorizationServer {
+    clients: Arc<ClientRegistry>,
+    codes: Arc<dyn AuthorizationCodeStore>,
+    jwt_service: JwtService,
+    refresh_tokens: Arc<RefreshTokenManager>,
+    denylist: Arc<dyn TokenDenylist>,
+    issuer: String,
+    audience: String,
+    access_token_ttl: u64,
+    code_ttl: u64,
+}
+
+impl AuthorizationServer {
+    /// Create a server with an in-memory code store
+    pub fn new(clients: Arc<ClientRegistry>, jwt_service: JwtService, refresh_tokens: Arc<RefreshTokenManager>) -> Self {
+        Self::with_code_store(clients, Arc::new(InMemoryAuthorizationCodeStore::new()), jwt_service, refresh_tokens)
+    }
+
+    /// Create a server backed by a custom code store
+    pub fn with_code_store(
+        clients: Arc<ClientRegistry>,
+        codes: Arc<dyn AuthorizationCodeStore>,
+        jwt_service: JwtService,
+        refresh_tokens: Arc<RefreshTokenManager>,
+    ) -> Self {
+        Self {
+            clients,
+            codes,
+            jwt_service,
+            refresh_tokens,
+            denylist: Arc::new(InMemoryTokenDenylist::new()),
+            issuer: "cloudshuttle".to_string(),
+            audience: "cloudshuttle-api".to_string(),
+            access_token_ttl: 3600,
+            code_ttl: DEFAULT_CODE_TTL,
+        }
+    }
+
+    /// Configure the issuer of access tokens
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = issuer.into();
+        self
+    }
+
+    /// Configure the audience of access tokens
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = audience.into();
+        self
+    }
+
+    /// Configure access token lifetime in seconds
+    pub fn with_access_token_ttl(mut self, seconds: u64) -> Self {
+        self.access_token_ttl = seconds;
+        self
+    }
+
+    /// Configure authorization code lifetime in seconds
+    pub fn with_code_ttl(mut self, seconds: u64) -> Self {
+        self.code_ttl = seconds;
+        self
+    }
+
+    /// Record revoked access tokens in a custom denylist
+    pub fn with_denylist(mut self, denylist: Arc<dyn TokenDenylist>) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    /// Get the client registry
+    pub fn clients(&self) -> &ClientRegistry {
+        &self.clients
+    }
+
+    /// Get the denylist of revoked access tokens
+    ///
+    /// Share it with `TokenIntrospection` and `AuthMiddleware` so that
+    /// revoked tokens are rejected before they expire.
+    pub fn denylist(&self) -> Arc<dyn TokenDenylist> {
+        self.denylist.clone()
+    }
+
+    /// Issue an authorization code for an authenticated user
+    ///
+    /// `InvalidClient` and redirect URI errors must be shown to the user
+    /// rather than redirected, since the redirect target is untrusted.
+    pub async fn authorize(&self, request: &PkceAuthorizationRequest, user: &Claims) -> OAuthResult<AuthorizationResponse> {
+        let client = self.clients.get(&request.client_id).ok_or(OAuthError::InvalidClient)?;
+
+        if !client.is_redirect_uri_allowed(&request.redirect_uri) {
+            return Err(OAuthError::InvalidRequest("Redirect URI is not registered for this client".to_string()));
+        }
+        if !client.is_scope_allowed(request.scope.as_deref()) {
+            return Err(OAuthError::InvalidScope("Requested scope is not allowed for this client".to_string()));
+        }
+        if request.code_challenge.is_empty() {
+            return Err(OAuthError::InvalidRequest("code_challenge is required".to_string()));
+        }
+
+        let code = CryptoUtils::generate_secure_token(32)?;
+        let grant = AuthorizationCode {
+            client_id: client.client_id,
+            redirect_uri: request.redirect_uri.clone(),
+            user_id: user.sub.clone(),
+            tenant_id: user.tenant_id.clone(),
+            roles: user.roles.clone(),
+            scope: request.scope.clone(),
+            code_challenge: request.code_challenge.clone(),
+            code_challenge_method: request.code_challenge_method,
+            expires_at: current_timestamp() + self.code_ttl,
+        };
+        self.codes.insert_code(hash_code(&code), grant).await?;
+
+        #[cfg(feature = "observability")]
+        audit_auth("oauth_code_issued", Some(&user.sub), AuditResult::Success);
+
+        Ok(AuthorizationResponse {
+            code,
+            state: request.state.clone(),
+            redirect_uri: request.redirect_uri.clone(),
+        })
+    }
+
+    /// Exchange an authorization code for tokens
+    ///
+    /// Confidential clients must pass their secret; public clients rely on
+    /// the PKCE verifier alone.
+    pub async fn exchange_code(&self, request: &PkceTokenRequest, client_secret: Option<&str>) -> OAuthResult<OAuthTokenResponse> {
+        if request.grant_type != "authorization_code" {
+            return Err(OAuthError::UnsupportedGrantType);
+        }
+
+        let client = self.clients
+            .authenticate(&request.client_id, client_secret)
+            .await
+            .ok_or(OAuthError::InvalidClient)?;
+
+        // Removing the code first makes it single use even if validation fails
+        let grant = self.codes
+            .take_code(&hash_code(&request.code))
+            .await?
+            .ok_or_else(|| OAuthError::InvalidGrant("Invalid authorization code".to_string()))?;
+
+        if grant.expires_at <= current_timestamp() {
+            return Err(OAuthError::InvalidGrant("Authorization code expired".to_string()));
+        }
+        if grant.client_id != client.client_id {
+            return Err(OAuthError::InvalidGrant("Authorization code was issued to another client".to_string()));
+        }
+        if grant.redirect_uri != request.redirect_uri {
+            return Err(OAuthError::InvalidGrant("Redirect URI does not match authorization request".to_string()));
+        }
+
+        let verified = PkceHandler::validate_challenge(&request.code_verifier, &grant.code_challenge, grant.code_challenge_method)
+            .map_err(|e| OAuthError::InvalidRequest(e.to_string()))?;
+        if !verified {
+            return Err(OAuthError::InvalidGrant("PKCE verification failed".to_string()));
+        }
+
+        let response = self.issue_tokens(&grant).await?;
+
+        #[cfg(feature = "observability")]
+        audit_auth("oauth_code_exchanged", Some(&grant.user_id), AuditResult::Success);
+
+        Ok(response)
+    }
+
+    /// Revoke an access or refresh token (RFC 7009)
+    ///
+    /// The token type is read from the token itself. Tokens that are
+    /// invalid, expired or unknown are ignored, since the client's aim of
+    /// invalidating them is already met.
+    pub async fn revoke(&self, token: &str, client_id: &str, client_secret: Option<&str>) -> OAuthResult<()> {
+        let client = self.clients
+            .authenticate(client_id, client_secret)
+            .await
+            .ok_or(OAuthError::InvalidClient)?;
+
+        let Ok(claims) = self.jwt_service.validate_token(token) else {
+            return Ok(());
+        };
+
+        if claims.custom.get("type").and_then(|v| v.as_str()) == Some("refresh") {
+            // Refresh tokens issued here record the client as their device
+            let Some(record) = self.refresh_tokens.get_token_record(token).await? else {
+                return Ok(());
+            };
+            if record.device_id.as_deref() != Some(client.client_id.as_str()) {
+                return Err(OAuthError::UnauthorizedClient);
+            }
+            self.refresh_tokens.revoke_token(token).await?;
+        } else {
+            if claims.custom.get("client_id").and_then(|v| v.as_str()) != Some(client.client_id.as_str()) {
+                return Err(OAuthError::UnauthorizedClient);
+            }
+            self.denylist.revoke_claims(&claims).await?;
+        }
+
+        #[cfg(feature = "observability")]
+        audit_auth("oauth_token_revoked", Some(&claims.sub), AuditResult::Success);
+
+        Ok(())
+    }
+
+    async fn issue_tokens(&self, grant: &AuthorizationCode) -> OAuthResult<OAuthTokenResponse> {
+        let mut claims = Claims::new(grant.user_id.clone(), grant.tenant_id.clone());
+        claims.roles = grant.roles.clone();
+        claims.iss = Some(self.issuer.clone());
+        claims.aud = Some(self.audience.clone());
+        claims.exp = current_timestamp() + self.access_token_ttl;
+        claims.custom.insert("client_id".to_string(), serde_json::Value::String(grant.client_id.clone()));
+        if let Some(scope) = &grant.scope {
+            claims.custom.insert("scope".to_string(), serde_json::Value::String(scope.clone()));
+        }
+
+        let access_token = self.jwt_service.create_token(&claims)?;
+        let refresh_token = self.refresh_tokens
+            .create_refresh_token(&grant.user_id, Some(grant.client_id.clone()), None, None)
+            .await?;
+
+        Ok(OAuthTokenResponse {
+            tokens: AuthTokens::new(
+                access_token,
+                refresh_token,
+                self.access_token_ttl,
+                self.refresh_tokens.config().max_lifetime,
+            ),
+            scope: grant.scope.clone(),
+        })
+    }
+}
+
+fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::oauth::OAuthClient;
+    use crate::pkce::{PkceMethod, PkcePair};
+    use crate::refresh_tokens::RefreshTokenConfig;
+
+    pub(crate) const REDIRECT_URI: &str = "https://app.example.com/callback";
+
+    pub(crate) fn server() -> AuthorizationServer {
+        let jwt_service = JwtService::new(b"test-secret-key").unwrap();
+        let refresh_tokens = Arc::new(RefreshTokenManager::new(jwt_service.clone(), RefreshTokenConfig::default()));
+        let clients = Arc::new(ClientRegistry::new());
+        clients.register(OAuthClient::public("spa", vec![REDIRECT_URI.to_string()]).unwrap().with_scopes(["read", "write"]));
+        clients.register(OAuthClient::confidential("backend", "s3cret", vec![REDIRECT_URI.to_string()]).unwrap());
+        AuthorizationServer::new(clients, jwt_service, refresh_tokens)
+    }
+
+    pub(crate) async fn authorize(server: &AuthorizationServer, client_id: &str) -> (AuthorizationResponse, PkcePair) {
+        let pkce = PkceHandler::generate().unwrap();
+        let request = PkceAuthorizationRequest {
+            client_id: client_id.to_string(),
+            redirect_uri: REDIRECT_URI.to_string(),
+            scope: Some("read".to_string()),
+            state: Some("xyz".to_string()),
+            code_challenge: pkce.code_challenge.clone(),
+            code_challenge_method: PkceMethod::S256,
+        };
+        let mut user = Claims::new("user123", "tenant456");
+        user.roles = vec!["user".to_string()];
+        (server.authorize(&request, &user).await.unwrap(), pkce)
+    }
+
+    fn token_request(client_id: &str, code: &str, code_verifier: &str) -> PkceTokenRequest {
+        PkceTokenRequest {
+            grant_type: "authorization_code".to_string(),
+            code: code.to_string(),
+            redirect_uri: REDIRECT_URI.to_string(),
+            client_id: client_id.to_string(),
+            code_verifier: code_verifier.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_authorization_code_flow() {
+        let server = server();
+        let (authorization, pkce) = authorize(&server, "spa").await;
+        assert!(authorization.redirect_url().ends_with("&state=xyz"));
+
+        let response = server.exchange_code(&token_request("spa", &authorization.code, pkce.verifier()), None).await.unwrap();
+        assert_eq!(response.scope.as_deref(), Some("read"));
+        assert_eq!(response.tokens.token_type, "Bearer");
+
+        let claims = server.jwt_service.validate_token(&response.tokens.access_token).unwrap();
+        assert_eq!(claims.sub, "user123");
+        assert_eq!(claims.roles, vec!["user".to_string()]);
+        assert_eq!(claims.custom.get("client_id"), Some(&serde_json::json!("spa")));
+    }
+
+    #[tokio::test]
+    async fn test_code_is_single_use() {
+        let server = server();
+        let (authorization, pkce) = authorize(&server, "spa").await;
+        let request = token_request("spa", &authorization.code, pkce.verifier());
+
+        assert!(server.exchange_code(&request, None).await.is_ok());
+        assert!(matches!(server.exchange_code(&request, None).await, Err(OAuthError::InvalidGrant(_))));
+    }
+
+    #[tokio::test]
+    async fn test_code_bound_to_verifier_client_and_redirect() {
+        let server = server();
+
+        let (authorization, _) = authorize(&server, "spa").await;
+        let wrong_verifier = PkceHandler::generate().unwrap();
+        let result = server.exchange_code(&token_request("spa", &authorization.code, wrong_verifier.verifier()), None).await;
+        assert!(matches!(result, Err(OAuthError::InvalidGrant(_))));
+
+        let (authorization, pkce) = authorize(&server, "spa").await;
+        let result = server.exchange_code(&token_request("backend", &authorization.code, pkce.verifier()), Some("s3cret")).await;
+        assert!(matches!(result, Err(OAuthError::InvalidGrant(_))));
+
+        let (authorization, pkce) = authorize(&server, "spa").await;
+        let mut request = token_request("spa", &authorization.code, pkce.verifier());
+        request.redirect_uri = "https://app.example.com/other".to_string();
+        assert!(matches!(server.exchange_code(&request, None).await, Err(OAuthError::InvalidGrant(_))));
+    }
+
+    #[tokio::test]
+    async fn test_confidential_client_requires_secret() {
+        let server = server();
+        let (authorization, pkce) = authorize(&server, "backend").await;
+        let request = token_request("backend", &authorization.code, pkce.verifier());
+
+        assert!(matches!(server.exchange_code(&request, None).await, Err(OAuthError::InvalidClient)));
+        assert!(server.exchange_code(&request, Some("s3cret")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_access_token() {
+        let server = server();
+        let (authorization, pkce) = authorize(&server, "spa").await;
+        let response = server.exchange_code(&token_request("spa", &authorization.code, pkce.verifier()), None).await.unwrap();
+        let claims = server.jwt_service.validate_token(&response.tokens.access_token).unwrap();
+
+        let result = server.revoke(&response.tokens.access_token, "backend", Some("s3cret")).await;
+        assert!(matches!(result, Err(OAuthError::UnauthorizedClient)));
+        assert!(!server.denylist().is_claims_revoked(&claims).await.unwrap());
+
+        server.revoke(&response.tokens.access_token, "spa", None).await.unwrap();
+        assert!(server.denylist().is_claims_revoked(&claims).await.unwrap());
+
+        server.revoke("not-a-token", "spa", None).await.unwrap();
+        assert!(matches!(server.revoke("not-a-token", "backend", None).await, Err(OAuthError::InvalidClient)));
+    }
+
+    #[tokio::test]
+    async fn test_revoke_refresh_token() {
+        let server = server();
+        let (authorization, pkce) = authorize(&server, "spa").await;
+        let response = server.exchange_code(&token_request("spa", &authorization.code, pkce.verifier()), None).await.unwrap();
+        let refresh_token = response.tokens.refresh_token;
+
+        let result = server.revoke(&refresh_token, "backend", Some("s3cret")).await;
+        assert!(matches!(result, Err(OAuthError::UnauthorizedClient)));
+
+        server.revoke(&refresh_token, "spa", None).await.unwrap();
+        let record = server.refresh_tokens.get_token_record(&refresh_token).await.unwrap().unwrap();
+        assert!(record.revoked);
+    }
+
+    #[tokio::test]
+    async fn test_authorize_rejects_unregistered_redirect_and_scope() {
+        let server = server();
+        let user = Claims::new("user123", "tenant456");
+        let mut request = PkceAuthorizationRequest {
+            client_id: "spa".to_string(),
+            redirect_uri: "https://evil.example.com/callback".to_string(),
+            scope: None,
+            state: None,
+            code_challenge: PkceHandler::generate().unwrap().code_challenge,
+            code_challenge_method: PkceMethod::S256,
+        };
+        assert!(matches!(server.authorize(&request, &user).await, Err(OAuthError::InvalidRequest(_))));
+
+        request.redirect_uri = REDIRECT_URI.to_string();
+        request.scope = Some("admin".to_string());
+        assert!(matches!(server.authorize(&request, &user).await, Err(OAuthError::InvalidScope(_))));
+
+        request.client_id = "unknown".to_string();
+        assert!(matches!(server.authorize(&request, &user).await, Err(OAuthError::InvalidClient)));
+    }
+}
@@ -0,0 +1,165 @@
+//! OAuth 2.0 response and error types
+
+use serde::{Deserialize, Serialize};
+use crate::types::{AuthError, AuthTokens};
+
+/// OAuth result type
+pub type OAuthResult<T> = Result<T, OAuthError>;
+
+/// OAuth 2.0 error (RFC 6749 sections 4.1.2.1 and 5.2)
+#[derive(Debug, thiserror::Error)]
+pub enum OAuthError {
+    #[error("invalid_request: {0}")]
+    InvalidRequest(String),
+
+    #[error("invalid_client")]
+    InvalidClient,
+
+    #[error("invalid_grant: {0}")]
+    InvalidGrant(String),
+
+    #[error("unauthorized_client")]
+    UnauthorizedClient,
+
+    #[error("unsupported_grant_type")]
+    UnsupportedGrantType,
+
+    #[error("invalid_scope: {0}")]
+    InvalidScope(String),
+
+    #[error("server_error: {0}")]
+    Server(#[from] AuthError),
+}
+
+impl OAuthError {
+    /// Error code from the OAuth error registry
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::InvalidRequest(_) => "invalid_request",
+            Self::InvalidClient => "invalid_client",
+            Self::InvalidGrant(_) => "invalid_grant",
+            Self::UnauthorizedClient => "unauthorized_client",
+            Self::UnsupportedGrantType => "unsupported_grant_type",
+            Self::InvalidScope(_) => "invalid_scope",
+            Self::Server(_) => "server_error",
+        }
+    }
+
+    /// Human-readable description safe to return to clients
+    pub fn description(&self) -> Option<String> {
+        match self {
+            Self::InvalidRequest(msg) | Self::InvalidGrant(msg) | Self::InvalidScope(msg) => Some(msg.clone()),
+            _ => None,
+        }
+    }
+
+    /// Convert error to HTTP status code
+    pub fn to_http_status(&self) -> http::StatusCode {
+        match self {
+            Self::InvalidClient => http::StatusCode::UNAUTHORIZED,
+            Self::Server(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            _ => http::StatusCode::BAD_REQUEST,
+        }
+    }
+
+    /// Error response body
+    pub fn to_response_body(&self) -> OAuthErrorResponse {
+        OAuthErrorResponse {
+            error: self.error_code().to_string(),
+            error_description: self.description(),
+        }
+    }
+}
+
+/// OAuth error response body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthErrorResponse {
+    pub error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_description: Option<String>,
+}
+
+#[cfg(feature = "axum")]
+impl axum::response::IntoResponse for OAuthError {
+    fn into_response(self) -> axum::response::Response {
+        let mut response = (self.to_http_status(), axum::Json(self.to_response_body())).into_response();
+        let headers = response.headers_mut();
+        headers.insert(http::header::CACHE_CONTROL, http::HeaderValue::from_static("no-store"));
+        if matches!(self, Self::InvalidClient) {
+            headers.insert(http::header::WWW_AUTHENTICATE, http::HeaderValue::from_static("Basic"));
+        }
+        response
+    }
+}
+
+/// Successful authorization, to be sent back to the client's redirect URI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorizationResponse {
+    /// Single-use authorization code
+    pub code: String,
+
+    /// State echoed from the authorization request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+
+    /// Validated redirect URI
+    pub redirect_uri: String,
+}
+
+impl AuthorizationResponse {
+    /// Build the redirect URL carrying the code and state
+    pub fn redirect_url(&self) -> String {
+        let separator = if self.redirect_uri.contains('?') { '&' } else { '?' };
+        let mut url = format!("{}{}code={}", self.redirect_uri, separator, query_encode(&self.code));
+        if let Some(state) = &self.state {
+            url.push_str("&state=");
+            url.push_str(&query_encode(state));
+        }
+        url
+    }
+}
+
+/// Token endpoint response (RFC 6749 section 5.1)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthTokenResponse {
+    #[serde(flatten)]
+    pub tokens: AuthTokens,
+
+    /// Granted scope
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+fn query_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redirect_url() {
+        let response = AuthorizationResponse {
+            code: "abc".to_string(),
+            state: Some("x y&z".to_string()),
+            redirect_uri: "https://app.example.com/cb?tab=1".to_string(),
+        };
+        assert_eq!(response.redirect_url(), "https://app.example.com/cb?tab=1&code=abc&state=x%20y%26z");
+    }
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(OAuthError::InvalidClient.to_http_status(), http::StatusCode::UNAUTHORIZED);
+        let body = OAuthError::InvalidGrant("Code expired".to_string()).to_response_body();
+        assert_eq!(body.error, "invalid_grant");
+        assert_eq!(body.error_description.as_deref(), Some("Code expired"));
+        assert!(OAuthError::Server(AuthError::ServiceUnavailable).description().is_none());
+    }
+}
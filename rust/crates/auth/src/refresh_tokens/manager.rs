@@ -3,43 +3,86 @@
 //! This module contains the core RefreshTokenManager struct and its
 //! methods for managing refresh token lifecycle, rotation, and security.
 
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use crate::types::{AuthResult, AuthError};
 #[cfg(feature = "observability")]
 use cloudshuttle_observability::audit::{audit_auth, AuditResult};
-use crate::{Claims, JwtService};
+use crate::{Claims, JwtService, SecurityEvent};
 
+use super::store::{InMemoryRefreshTokenStore, RefreshTokenStore};
 use super::types::*;
 
+/// Revocation reason recorded when a token is exchanged for its successor
+const ROTATED: &str = "Rotated";
+
+/// Callback invoked when the manager raises a security event
+pub type SecurityEventHandler = Arc<dyn Fn(SecurityEvent) + Send + Sync>;
+
 /// Refresh token manager with security features
 pub struct RefreshTokenManager {
     jwt_service: JwtService,
     config: RefreshTokenConfig,
-    token_store: Arc<Mutex<HashMap<String, RefreshTokenRecord>>>,
+    store: Arc<dyn RefreshTokenStore>,
+    security_event_handler: Option<SecurityEventHandler>,
 }
 
 impl RefreshTokenManager {
-    /// Create a new refresh token manager
+    /// Create a new refresh token manager backed by an in-memory store
     pub fn new(jwt_service: JwtService, config: RefreshTokenConfig) -> Self {
+        Self::with_store(jwt_service, config, Arc::new(InMemoryRefreshTokenStore::new()))
+    }
+
+    /// Create a new refresh token manager backed by the given store
+    pub fn with_store(
+        jwt_service: JwtService,
+        config: RefreshTokenConfig,
+        store: Arc<dyn RefreshTokenStore>,
+    ) -> Self {
         Self {
             jwt_service,
             config,
-            token_store: Arc::new(Mutex::new(HashMap::new())),
+            store,
+            security_event_handler: None,
         }
     }
 
-    /// Create a new refresh token for a user
-    pub fn create_refresh_token(
+    /// Register a handler that receives security events such as token reuse
+    pub fn with_security_event_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(SecurityEvent) + Send + Sync + 'static,
+    {
+        self.security_event_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Get the refresh token policy
+    pub fn config(&self) -> &RefreshTokenConfig {
+        &self.config
+    }
+
+    /// Create a new refresh token for a user, starting a new token family
+    pub async fn create_refresh_token(
         &self,
         user_id: &str,
         device_id: Option<String>,
         ip_address: Option<String>,
         user_agent: Option<String>,
+    ) -> AuthResult<String> {
+        let family_id = format!("fam_{}", uuid::Uuid::new_v4());
+        self.issue_refresh_token(user_id, family_id, device_id, ip_address, user_agent).await
+    }
+
+    /// Issue a refresh token within an existing token family
+    async fn issue_refresh_token(
+        &self,
+        user_id: &str,
+        family_id: String,
+        device_id: Option<String>,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
     ) -> AuthResult<String> {
         // Check token limit per user
-        self.enforce_token_limit(user_id)?;
+        self.enforce_token_limit(user_id).await?;
 
         // Generate unique token ID
         let token_id = format!("rt_{}_{}", user_id, uuid::Uuid::new_v4());
@@ -48,6 +91,7 @@ impl RefreshTokenManager {
         let mut claims = Claims::new(user_id.to_string(), "refresh".to_string());
         claims.custom.insert("token_id".to_string(), serde_json::Value::String(token_id.clone()));
         claims.custom.insert("type".to_string(), serde_json::Value::String("refresh".to_string()));
+        claims.custom.insert("family_id".to_string(), serde_json::Value::String(family_id.clone()));
 
         // Set expiration
         let now = Self::current_timestamp();
@@ -60,7 +104,7 @@ impl RefreshTokenManager {
         let record = RefreshTokenRecord {
             token_id: token_id.clone(),
             user_id: user_id.to_string(),
-            family_id: self.config.family_id.clone(),
+            family_id: Some(family_id),
             created_at: now,
             expires_at: claims.exp,
             device_id,
@@ -70,14 +114,13 @@ impl RefreshTokenManager {
             revocation_reason: None,
         };
 
-        let mut store = self.token_store.lock().unwrap();
-        store.insert(token_id, record);
+        self.store.insert_token(record).await?;
 
         Ok(refresh_token)
     }
 
     /// Use a refresh token to generate new tokens
-    pub fn refresh_tokens(&self, request: RefreshTokenRequest) -> AuthResult<RefreshTokenResponse> {
+    pub async fn refresh_tokens(&self, request: RefreshTokenRequest) -> AuthResult<RefreshTokenResponse> {
         // Validate the refresh token
         let claims = self.jwt_service.validate_token(&request.refresh_token)?;
 
@@ -95,14 +138,16 @@ impl RefreshTokenManager {
         }
 
         // Check if token is stored and valid
-        let mut store = self.token_store.lock().unwrap();
-        let record = store.get(token_id)
-            .ok_or_else(|| AuthError::TokenNotFound)?;
+        let record = self.store.get_token(token_id).await?
+            .ok_or(AuthError::TokenNotFound)?;
 
+        // Only a rotated token being presented again indicates theft; tokens revoked
+        // on logout, by limit eviction or manually are simply no longer valid
         if record.revoked {
-            #[cfg(feature = "observability")]
-            audit_auth("refresh_token_revoked", Some(&claims.sub), AuditResult::Failure);
-            return Err(AuthError::TokenRevoked);
+            return Err(match record.revocation_reason.as_deref() {
+                Some(ROTATED) => self.handle_token_reuse(&record).await,
+                _ => AuthError::TokenRevoked,
+            });
         }
 
         if Self::current_timestamp() > record.expires_at {
@@ -118,19 +163,21 @@ impl RefreshTokenManager {
 
         // Handle token rotation
         if self.config.rotation_enabled {
-            // Revoke the old token
-            if let Some(record) = store.get_mut(token_id) {
-                record.revoked = true;
-                record.revocation_reason = Some("Rotated".to_string());
+            // Revoke the old token; losing this race means the token was presented twice
+            if !self.store.revoke_token(token_id, ROTATED).await? {
+                return Err(self.handle_token_reuse(&record).await);
             }
 
-            // Create new refresh token
-            refresh_token = Some(self.create_refresh_token(
+            // Create new refresh token in the same family
+            let family_id = record.family_id.clone()
+                .unwrap_or_else(|| format!("fam_{}", uuid::Uuid::new_v4()));
+            refresh_token = Some(self.issue_refresh_token(
                 &claims.sub,
+                family_id,
                 request.device_id,
                 None, // IP address not provided in refresh request
                 None, // User agent not provided in refresh request
-            )?);
+            ).await?);
         }
 
         #[cfg(feature = "observability")]
@@ -146,18 +193,14 @@ impl RefreshTokenManager {
     }
 
     /// Revoke a specific refresh token
-    pub fn revoke_token(&self, refresh_token: &str) -> AuthResult<()> {
+    pub async fn revoke_token(&self, refresh_token: &str) -> AuthResult<()> {
         let claims = self.jwt_service.extract_claims_unchecked(refresh_token)?;
 
         let token_id = claims.custom.get("token_id")
             .and_then(|v| v.as_str())
             .ok_or_else(|| AuthError::InvalidToken("Missing token ID".to_string()))?;
 
-        let mut store = self.token_store.lock().unwrap();
-        if let Some(record) = store.get_mut(token_id) {
-            record.revoked = true;
-            record.revocation_reason = Some("Manually revoked".to_string());
-
+        if self.store.revoke_token(token_id, "Manually revoked").await? {
             #[cfg(feature = "observability")]
             audit_auth("refresh_token_revoked", Some(&claims.sub), AuditResult::Success);
         }
@@ -165,62 +208,69 @@ impl RefreshTokenManager {
         Ok(())
     }
 
-    /// Revoke all refresh tokens for a user
-    pub fn revoke_all_user_tokens(&self, user_id: &str) -> AuthResult<usize> {
-        let mut store = self.token_store.lock().unwrap();
-        let mut revoked_count = 0;
-
-        for record in store.values_mut() {
-            if record.user_id == user_id {
-                record.revoked = true;
-                record.revocation_reason = Some("User revocation".to_string());
-                revoked_count += 1;
-            }
+    /// Look up the stored record of a refresh token
+    pub async fn get_token_record(&self, refresh_token: &str) -> AuthResult<Option<RefreshTokenRecord>> {
+        let claims = self.jwt_service.extract_claims_unchecked(refresh_token)?;
+
+        match claims.custom.get("token_id").and_then(|v| v.as_str()) {
+            Some(token_id) => self.store.get_token(token_id).await,
+            None => Ok(None),
         }
+    }
 
-        Ok(revoked_count)
+    /// Revoke all refresh tokens for a user
+    pub async fn revoke_all_user_tokens(&self, user_id: &str) -> AuthResult<usize> {
+        self.store.revoke_user_tokens(user_id, "User revocation").await
     }
 
     /// Revoke all tokens in a family
-    pub fn revoke_token_family(&self, family_id: &str) -> AuthResult<usize> {
-        let mut store = self.token_store.lock().unwrap();
-        let mut revoked_count = 0;
-
-        for record in store.values_mut() {
-            if record.family_id.as_ref() == Some(&family_id.to_string()) {
-                record.revoked = true;
-                record.revocation_reason = Some("Family revocation".to_string());
-                revoked_count += 1;
-            }
-        }
-
-        Ok(revoked_count)
+    pub async fn revoke_token_family(&self, family_id: &str) -> AuthResult<usize> {
+        self.store.revoke_family(family_id, "Family revocation").await
     }
 
     /// Get active tokens for a user
-    pub fn get_user_active_tokens(&self, user_id: &str) -> Vec<RefreshTokenRecord> {
-        let store = self.token_store.lock().unwrap();
-        let now = Self::current_timestamp();
-
-        store.values()
-            .filter(|record|
-                record.user_id == user_id &&
-                !record.revoked &&
-                record.expires_at > now
-            )
-            .cloned()
-            .collect()
+    pub async fn get_user_active_tokens(&self, user_id: &str) -> AuthResult<Vec<RefreshTokenRecord>> {
+        self.store.active_user_tokens(user_id, Self::current_timestamp()).await
     }
 
     /// Clean up expired tokens
-    pub fn cleanup_expired_tokens(&self) -> usize {
-        let mut store = self.token_store.lock().unwrap();
-        let now = Self::current_timestamp();
-        let initial_count = store.len();
+    pub async fn cleanup_expired_tokens(&self) -> AuthResult<usize> {
+        self.store.delete_expired(Self::current_timestamp()).await
+    }
+
+    /// Respond to a revoked refresh token being presented again
+    ///
+    /// Unless `revoke_on_security_event` is disabled, the whole family is
+    /// revoked so that neither the attacker nor the legitimate client can keep
+    /// refreshing, forcing a new login.
+    async fn handle_token_reuse(&self, record: &RefreshTokenRecord) -> AuthError {
+        tracing::warn!(
+            user_id = %record.user_id,
+            token_id = %record.token_id,
+            family_id = ?record.family_id,
+            "Refresh token reuse detected"
+        );
+
+        #[cfg(feature = "observability")]
+        audit_auth("refresh_token_reuse_detected", Some(&record.user_id), AuditResult::Failure);
 
-        store.retain(|_, record| record.expires_at > now);
+        if self.config.revoke_on_security_event {
+            if let Some(family_id) = &record.family_id {
+                if let Err(error) = self.store.revoke_family(family_id, "Reuse detected").await {
+                    return error;
+                }
+            }
+        }
 
-        initial_count - store.len()
+        if let Some(handler) = &self.security_event_handler {
+            handler(SecurityEvent::refresh_token_reuse(
+                record.user_id.clone(),
+                record.family_id.clone(),
+                record.ip_address.clone(),
+            ));
+        }
+
+        AuthError::TokenReuseDetected
     }
 
     /// Get current timestamp
@@ -232,8 +282,8 @@ impl RefreshTokenManager {
     }
 
     /// Enforce token limit per user
-    fn enforce_token_limit(&self, user_id: &str) -> AuthResult<()> {
-        let active_tokens = self.get_user_active_tokens(user_id);
+    async fn enforce_token_limit(&self, user_id: &str) -> AuthResult<()> {
+        let active_tokens = self.get_user_active_tokens(user_id).await?;
 
         if active_tokens.len() >= self.config.max_tokens_per_user {
             // Revoke the oldest token to make room
@@ -243,11 +293,7 @@ impl RefreshTokenManager {
                 .map(|record| record.token_id.clone());
 
             if let Some(token_id) = oldest_token_id {
-                let mut store = self.token_store.lock().unwrap();
-                if let Some(record) = store.get_mut(&token_id) {
-                    record.revoked = true;
-                    record.revocation_reason = Some("Limit exceeded".to_string());
-                }
+                self.store.revoke_token(&token_id, "Limit exceeded").await?;
             }
         }
 
@@ -260,8 +306,8 @@ mod tests {
     use super::*;
     use crate::JwtService;
 
-    #[test]
-    fn test_create_refresh_token() {
+    #[tokio::test]
+    async fn test_create_refresh_token() {
         let jwt_service = JwtService::new(b"test-secret-key").unwrap();
         let config = RefreshTokenConfig::default();
         let manager = RefreshTokenManager::new(jwt_service, config);
@@ -271,19 +317,19 @@ mod tests {
             Some("device456".to_string()),
             Some("127.0.0.1".to_string()),
             Some("Test Browser".to_string()),
-        ).unwrap();
+        ).await.unwrap();
 
         assert!(!token.is_empty());
 
         // Verify token was stored
-        let active_tokens = manager.get_user_active_tokens("user123");
+        let active_tokens = manager.get_user_active_tokens("user123").await.unwrap();
         assert_eq!(active_tokens.len(), 1);
         assert_eq!(active_tokens[0].user_id, "user123");
         assert_eq!(active_tokens[0].device_id, Some("device456".to_string()));
     }
 
-    #[test]
-    fn test_refresh_tokens_with_rotation() {
+    #[tokio::test]
+    async fn test_refresh_tokens_with_rotation() {
         let jwt_service = JwtService::new(b"test-secret-key").unwrap();
         let config = RefreshTokenConfig {
             rotation_enabled: true,
@@ -292,7 +338,7 @@ mod tests {
         let manager = RefreshTokenManager::new(jwt_service.clone(), config);
 
         // Create initial refresh token
-        let refresh_token = manager.create_refresh_token("user123", None, None, None).unwrap();
+        let refresh_token = manager.create_refresh_token("user123", None, None, None).await.unwrap();
 
         // Use it to refresh
         let request = RefreshTokenRequest {
@@ -301,7 +347,7 @@ mod tests {
             scope: None,
         };
 
-        let response = manager.refresh_tokens(request).unwrap();
+        let response = manager.refresh_tokens(request).await.unwrap();
 
         assert!(!response.access_token.is_empty());
         assert!(response.refresh_token.is_some()); // New token due to rotation
@@ -312,9 +358,128 @@ mod tests {
         let claims = jwt_service.extract_claims_unchecked(&refresh_token).unwrap();
         let token_id = claims.custom["token_id"].as_str().unwrap();
 
-        let store = manager.token_store.lock().unwrap();
-        let record = store.get(token_id).unwrap();
+        let record = manager.store.get_token(token_id).await.unwrap().unwrap();
         assert!(record.revoked);
         assert_eq!(record.revocation_reason, Some("Rotated".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_rotated_token_cannot_be_reused() {
+        let jwt_service = JwtService::new(b"test-secret-key").unwrap();
+        let store = Arc::new(InMemoryRefreshTokenStore::new());
+        let manager = RefreshTokenManager::with_store(jwt_service, RefreshTokenConfig::default(), store);
+
+        let refresh_token = manager.create_refresh_token("user123", None, None, None).await.unwrap();
+        let request = || RefreshTokenRequest {
+            refresh_token: refresh_token.clone(),
+            device_id: None,
+            scope: None,
+        };
+
+        assert!(manager.refresh_tokens(request()).await.is_ok());
+        assert!(matches!(manager.refresh_tokens(request()).await, Err(AuthError::TokenReuseDetected)));
+    }
+
+    #[tokio::test]
+    async fn test_each_login_starts_new_family() {
+        let jwt_service = JwtService::new(b"test-secret-key").unwrap();
+        let manager = RefreshTokenManager::new(jwt_service, RefreshTokenConfig::default());
+
+        manager.create_refresh_token("user123", None, None, None).await.unwrap();
+        manager.create_refresh_token("user123", None, None, None).await.unwrap();
+
+        let tokens = manager.get_user_active_tokens("user123").await.unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert!(tokens[0].family_id.is_some());
+        assert_ne!(tokens[0].family_id, tokens[1].family_id);
+    }
+
+    #[tokio::test]
+    async fn test_reuse_revokes_family_and_emits_event() {
+        let jwt_service = JwtService::new(b"test-secret-key").unwrap();
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let captured = events.clone();
+        let manager = RefreshTokenManager::new(jwt_service, RefreshTokenConfig::default())
+            .with_security_event_handler(move |event| captured.lock().unwrap().push(event));
+
+        let original = manager.create_refresh_token("user123", None, None, None).await.unwrap();
+        let other_login = manager.create_refresh_token("user123", None, None, None).await.unwrap();
+        let request = |refresh_token: &str| RefreshTokenRequest {
+            refresh_token: refresh_token.to_string(),
+            device_id: None,
+            scope: None,
+        };
+
+        let rotated = manager.refresh_tokens(request(&original)).await.unwrap()
+            .refresh_token.unwrap();
+
+        // Replaying the rotated token compromises the family
+        let result = manager.refresh_tokens(request(&original)).await;
+        assert!(matches!(result, Err(AuthError::TokenReuseDetected)));
+
+        // The successor issued to the legitimate client is revoked too
+        assert!(manager.refresh_tokens(request(&rotated)).await.is_err());
+
+        // Tokens from other logins are unaffected
+        assert!(manager.refresh_tokens(request(&other_login)).await.is_ok());
+
+        let events = events.lock().unwrap();
+        assert!(!events.is_empty());
+        assert!(matches!(events[0].event_type, crate::SecurityEventType::SuspiciousActivity));
+        assert_eq!(events[0].user_id.as_deref(), Some("user123"));
+    }
+
+    #[tokio::test]
+    async fn test_revoked_token_is_not_treated_as_reuse() {
+        let jwt_service = JwtService::new(b"test-secret-key").unwrap();
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let captured = events.clone();
+        let manager = RefreshTokenManager::new(jwt_service, RefreshTokenConfig::default())
+            .with_security_event_handler(move |event| captured.lock().unwrap().push(event));
+
+        // A client retrying after logout
+        let logged_out = manager.create_refresh_token("user123", None, None, None).await.unwrap();
+        manager.revoke_token(&logged_out).await.unwrap();
+        let request = RefreshTokenRequest {
+            refresh_token: logged_out.clone(),
+            device_id: None,
+            scope: None,
+        };
+        assert!(matches!(manager.refresh_tokens(request).await, Err(AuthError::TokenRevoked)));
+
+        // Tokens revoked with all of a user's sessions
+        let other = manager.create_refresh_token("user123", None, None, None).await.unwrap();
+        manager.revoke_all_user_tokens("user123").await.unwrap();
+        let request = RefreshTokenRequest {
+            refresh_token: other,
+            device_id: None,
+            scope: None,
+        };
+        assert!(matches!(manager.refresh_tokens(request).await, Err(AuthError::TokenRevoked)));
+
+        assert!(events.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reuse_keeps_family_when_revocation_disabled() {
+        let jwt_service = JwtService::new(b"test-secret-key").unwrap();
+        let config = RefreshTokenConfig {
+            revoke_on_security_event: false,
+            ..Default::default()
+        };
+        let manager = RefreshTokenManager::new(jwt_service, config);
+        let request = |refresh_token: &str| RefreshTokenRequest {
+            refresh_token: refresh_token.to_string(),
+            device_id: None,
+            scope: None,
+        };
+
+        let original = manager.create_refresh_token("user123", None, None, None).await.unwrap();
+        let rotated = manager.refresh_tokens(request(&original)).await.unwrap()
+            .refresh_token.unwrap();
+
+        // Reuse is still reported, but the successor keeps working
+        assert!(matches!(manager.refresh_tokens(request(&original)).await, Err(AuthError::TokenReuseDetected)));
+        assert!(manager.refresh_tokens(request(&rotated)).await.is_ok());
+    }
 }
@@ -5,7 +5,13 @@
 
 pub mod types;
 pub mod manager;
+pub mod store;
+#[cfg(feature = "database")]
+pub mod postgres;
 
 // Re-export public types and functions
 pub use types::*;
 pub use manager::*;
+pub use store::*;
+#[cfg(feature = "database")]
+pub use postgres::*;
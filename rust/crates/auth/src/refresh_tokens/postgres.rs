@@ -0,0 +1,258 @@
+//! PostgreSQL refresh token store.
+//!
+//! This module provides a `RefreshTokenStore` backed by a shared Postgres
+//! table so refresh tokens survive restarts and are visible to every replica.
+
+use cloudshuttle_database::{DatabaseConnection, Migration, MigrationBuilder};
+use sqlx::PgPool;
+use crate::types::{AuthResult, AuthError};
+
+use super::store::RefreshTokenStore;
+use super::types::RefreshTokenRecord;
+
+/// Refresh token store persisted in the `refresh_tokens` table
+#[derive(Clone)]
+pub struct PostgresRefreshTokenStore {
+    pool: PgPool,
+}
+
+impl PostgresRefreshTokenStore {
+    /// Create a store using an existing connection pool
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a store sharing the pool of a database connection
+    pub fn from_connection(connection: &DatabaseConnection) -> Self {
+        Self::new(connection.pool().clone())
+    }
+
+    /// Migration creating the `refresh_tokens` table and its indexes
+    pub fn migration() -> Migration {
+        MigrationBuilder::new("auth_0001", "create_refresh_tokens")
+            .description("Create refresh token storage for cloudshuttle-auth")
+            .up_sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS refresh_tokens (
+                    token_id VARCHAR(255) PRIMARY KEY,
+                    user_id VARCHAR(255) NOT NULL,
+                    family_id VARCHAR(255),
+                    created_at BIGINT NOT NULL,
+                    expires_at BIGINT NOT NULL,
+                    device_id VARCHAR(255),
+                    ip_address VARCHAR(64),
+                    user_agent TEXT,
+                    revoked BOOLEAN NOT NULL DEFAULT FALSE,
+                    revocation_reason TEXT
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_refresh_tokens_user_id ON refresh_tokens(user_id);
+                CREATE INDEX IF NOT EXISTS idx_refresh_tokens_family_id ON refresh_tokens(family_id);
+                CREATE INDEX IF NOT EXISTS idx_refresh_tokens_expires_at ON refresh_tokens(expires_at);
+                "#,
+            )
+            .down_sql("DROP TABLE IF EXISTS refresh_tokens")
+            .build()
+    }
+}
+
+/// Row representation of a refresh token record
+#[derive(sqlx::FromRow)]
+struct RefreshTokenRow {
+    token_id: String,
+    user_id: String,
+    family_id: Option<String>,
+    created_at: i64,
+    expires_at: i64,
+    device_id: Option<String>,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    revoked: bool,
+    revocation_reason: Option<String>,
+}
+
+impl From<RefreshTokenRow> for RefreshTokenRecord {
+    fn from(row: RefreshTokenRow) -> Self {
+        Self {
+            token_id: row.token_id,
+            user_id: row.user_id,
+            family_id: row.family_id,
+            created_at: row.created_at as u64,
+            expires_at: row.expires_at as u64,
+            device_id: row.device_id,
+            ip_address: row.ip_address,
+            user_agent: row.user_agent,
+            revoked: row.revoked,
+            revocation_reason: row.revocation_reason,
+        }
+    }
+}
+
+fn database_error(error: sqlx::Error) -> AuthError {
+    AuthError::DatabaseError(error.to_string())
+}
+
+#[async_trait::async_trait]
+impl RefreshTokenStore for PostgresRefreshTokenStore {
+    async fn insert_token(&self, record: RefreshTokenRecord) -> AuthResult<()> {
+        sqlx::query(
+            "INSERT INTO refresh_tokens (token_id, user_id, family_id, created_at, expires_at, device_id, ip_address, user_agent, revoked, revocation_reason) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)"
+        )
+        .bind(&record.token_id)
+        .bind(&record.user_id)
+        .bind(&record.family_id)
+        .bind(record.created_at as i64)
+        .bind(record.expires_at as i64)
+        .bind(&record.device_id)
+        .bind(&record.ip_address)
+        .bind(&record.user_agent)
+        .bind(record.revoked)
+        .bind(&record.revocation_reason)
+        .execute(&self.pool)
+        .await
+        .map_err(database_error)?;
+
+        Ok(())
+    }
+
+    async fn get_token(&self, token_id: &str) -> AuthResult<Option<RefreshTokenRecord>> {
+        let row = sqlx::query_as::<_, RefreshTokenRow>("SELECT * FROM refresh_tokens WHERE token_id = $1")
+            .bind(token_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(database_error)?;
+
+        Ok(row.map(Into::into))
+    }
+
+    async fn revoke_token(&self, token_id: &str, reason: &str) -> AuthResult<bool> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked = TRUE, revocation_reason = $2 WHERE token_id = $1 AND revoked = FALSE"
+        )
+        .bind(token_id)
+        .bind(reason)
+        .execute(&self.pool)
+        .await
+        .map_err(database_error)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn revoke_user_tokens(&self, user_id: &str, reason: &str) -> AuthResult<usize> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked = TRUE, revocation_reason = $2 WHERE user_id = $1 AND revoked = FALSE"
+        )
+        .bind(user_id)
+        .bind(reason)
+        .execute(&self.pool)
+        .await
+        .map_err(database_error)?;
+
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn revoke_family(&self, family_id: &str, reason: &str) -> AuthResult<usize> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked = TRUE, revocation_reason = $2 WHERE family_id = $1 AND revoked = FALSE"
+        )
+        .bind(family_id)
+        .bind(reason)
+        .execute(&self.pool)
+        .await
+        .map_err(database_error)?;
+
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn active_user_tokens(&self, user_id: &str, now: u64) -> AuthResult<Vec<RefreshTokenRecord>> {
+        let rows = sqlx::query_as::<_, RefreshTokenRow>(
+            "SELECT * FROM refresh_tokens WHERE user_id = $1 AND revoked = FALSE AND expires_at > $2 ORDER BY created_at"
+        )
+        .bind(user_id)
+        .bind(now as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(database_error)?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn delete_expired(&self, now: u64) -> AuthResult<usize> {
+        let result = sqlx::query("DELETE FROM refresh_tokens WHERE expires_at <= $1")
+            .bind(now as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(database_error)?;
+
+        Ok(result.rows_affected() as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cloudshuttle_database::test_util;
+
+    async fn test_store() -> PostgresRefreshTokenStore {
+        PostgresRefreshTokenStore::new(test_util::migrated_pool(&PostgresRefreshTokenStore::migration()).await)
+    }
+
+    fn record(user_id: &str, family_id: &str, expires_at: u64) -> RefreshTokenRecord {
+        RefreshTokenRecord {
+            token_id: format!("rt_{}", uuid::Uuid::new_v4()),
+            user_id: user_id.to_string(),
+            family_id: Some(family_id.to_string()),
+            created_at: 1,
+            expires_at,
+            device_id: Some("device".to_string()),
+            ip_address: None,
+            user_agent: None,
+            revoked: false,
+            revocation_reason: None,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_migration_up_and_down() {
+        test_util::assert_migration_reversible(&PostgresRefreshTokenStore::migration(), "refresh_tokens").await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_postgres_store_roundtrip() {
+        let store = test_store().await;
+        let user_id = format!("user_{}", uuid::Uuid::new_v4());
+        let family_id = uuid::Uuid::new_v4().to_string();
+
+        let first = record(&user_id, &family_id, u64::MAX / 4);
+        let second = record(&user_id, &family_id, u64::MAX / 4);
+        store.insert_token(first.clone()).await.unwrap();
+        store.insert_token(second.clone()).await.unwrap();
+
+        let loaded = store.get_token(&first.token_id).await.unwrap().unwrap();
+        assert_eq!(loaded.user_id, user_id);
+        assert_eq!(loaded.device_id, Some("device".to_string()));
+
+        assert!(store.revoke_token(&first.token_id, "Rotated").await.unwrap());
+        assert!(!store.revoke_token(&first.token_id, "Rotated").await.unwrap());
+        assert_eq!(store.active_user_tokens(&user_id, 2).await.unwrap().len(), 1);
+
+        assert_eq!(store.revoke_family(&family_id, "Family revocation").await.unwrap(), 1);
+        assert!(store.active_user_tokens(&user_id, 2).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_postgres_store_delete_expired() {
+        let store = test_store().await;
+        let user_id = format!("user_{}", uuid::Uuid::new_v4());
+
+        let expired = record(&user_id, "fam", 5);
+        store.insert_token(expired.clone()).await.unwrap();
+
+        assert!(store.delete_expired(10).await.unwrap() >= 1);
+        assert!(store.get_token(&expired.token_id).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,184 @@
+//! Refresh token storage backends.
+//!
+//! This module defines the `RefreshTokenStore` trait used by the
+//! RefreshTokenManager to persist token metadata, along with the
+//! default in-memory implementation.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use crate::types::AuthResult;
+
+use super::types::RefreshTokenRecord;
+
+/// Persistent storage for refresh token metadata
+///
+/// Implementations must make `revoke_token` atomic so that concurrent
+/// rotations of the same token across replicas cannot both succeed.
+#[async_trait::async_trait]
+pub trait RefreshTokenStore: Send + Sync {
+    /// Store a newly issued token record
+    async fn insert_token(&self, record: RefreshTokenRecord) -> AuthResult<()>;
+
+    /// Look up a token record by its ID
+    async fn get_token(&self, token_id: &str) -> AuthResult<Option<RefreshTokenRecord>>;
+
+    /// Revoke a single token, returning `true` if it was active before the call
+    async fn revoke_token(&self, token_id: &str, reason: &str) -> AuthResult<bool>;
+
+    /// Revoke all active tokens for a user, returning the number revoked
+    async fn revoke_user_tokens(&self, user_id: &str, reason: &str) -> AuthResult<usize>;
+
+    /// Revoke all active tokens in a family, returning the number revoked
+    async fn revoke_family(&self, family_id: &str, reason: &str) -> AuthResult<usize>;
+
+    /// List unrevoked, unexpired tokens for a user
+    async fn active_user_tokens(&self, user_id: &str, now: u64) -> AuthResult<Vec<RefreshTokenRecord>>;
+
+    /// Delete tokens that expired before `now`, returning the number removed
+    async fn delete_expired(&self, now: u64) -> AuthResult<usize>;
+}
+
+/// In-memory refresh token store for single-instance deployments and testing
+#[derive(Default)]
+pub struct InMemoryRefreshTokenStore {
+    tokens: Mutex<HashMap<String, RefreshTokenRecord>>,
+}
+
+impl InMemoryRefreshTokenStore {
+    /// Create an empty in-memory store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn revoke_matching<F>(&self, reason: &str, predicate: F) -> usize
+    where
+        F: Fn(&RefreshTokenRecord) -> bool,
+    {
+        let mut tokens = self.tokens.lock().unwrap();
+        let mut revoked_count = 0;
+
+        for record in tokens.values_mut() {
+            if !record.revoked && predicate(record) {
+                record.revoked = true;
+                record.revocation_reason = Some(reason.to_string());
+                revoked_count += 1;
+            }
+        }
+
+        revoked_count
+    }
+}
+
+#[async_trait::async_trait]
+impl RefreshTokenStore for InMemoryRefreshTokenStore {
+    async fn insert_token(&self, record: RefreshTokenRecord) -> AuthResult<()> {
+        let mut tokens = self.tokens.lock().unwrap();
+        tokens.insert(record.token_id.clone(), record);
+        Ok(())
+    }
+
+    async fn get_token(&self, token_id: &str) -> AuthResult<Option<RefreshTokenRecord>> {
+        let tokens = self.tokens.lock().unwrap();
+        Ok(tokens.get(token_id).cloned())
+    }
+
+    async fn revoke_token(&self, token_id: &str, reason: &str) -> AuthResult<bool> {
+        let mut tokens = self.tokens.lock().unwrap();
+        match tokens.get_mut(token_id) {
+            Some(record) if !record.revoked => {
+                record.revoked = true;
+                record.revocation_reason = Some(reason.to_string());
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn revoke_user_tokens(&self, user_id: &str, reason: &str) -> AuthResult<usize> {
+        Ok(self.revoke_matching(reason, |record| record.user_id == user_id))
+    }
+
+    async fn revoke_family(&self, family_id: &str, reason: &str) -> AuthResult<usize> {
+        Ok(self.revoke_matching(reason, |record| record.family_id.as_deref() == Some(family_id)))
+    }
+
+    async fn active_user_tokens(&self, user_id: &str, now: u64) -> AuthResult<Vec<RefreshTokenRecord>> {
+        let tokens = self.tokens.lock().unwrap();
+        Ok(tokens.values()
+            .filter(|record|
+                record.user_id == user_id &&
+                !record.revoked &&
+                record.expires_at > now
+            )
+            .cloned()
+            .collect())
+    }
+
+    async fn delete_expired(&self, now: u64) -> AuthResult<usize> {
+        let mut tokens = self.tokens.lock().unwrap();
+        let initial_count = tokens.len();
+        tokens.retain(|_, record| record.expires_at > now);
+        Ok(initial_count - tokens.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(token_id: &str, user_id: &str, family_id: &str, expires_at: u64) -> RefreshTokenRecord {
+        RefreshTokenRecord {
+            token_id: token_id.to_string(),
+            user_id: user_id.to_string(),
+            family_id: Some(family_id.to_string()),
+            created_at: 0,
+            expires_at,
+            device_id: None,
+            ip_address: None,
+            user_agent: None,
+            revoked: false,
+            revocation_reason: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_revoke_token_is_single_use() {
+        let store = InMemoryRefreshTokenStore::new();
+        store.insert_token(record("rt1", "user1", "fam1", 100)).await.unwrap();
+
+        assert!(store.revoke_token("rt1", "Rotated").await.unwrap());
+        assert!(!store.revoke_token("rt1", "Rotated").await.unwrap());
+        assert!(!store.revoke_token("missing", "Rotated").await.unwrap());
+
+        let stored = store.get_token("rt1").await.unwrap().unwrap();
+        assert!(stored.revoked);
+        assert_eq!(stored.revocation_reason, Some("Rotated".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_family_and_user_revocation() {
+        let store = InMemoryRefreshTokenStore::new();
+        store.insert_token(record("rt1", "user1", "fam1", 100)).await.unwrap();
+        store.insert_token(record("rt2", "user1", "fam1", 100)).await.unwrap();
+        store.insert_token(record("rt3", "user1", "fam2", 100)).await.unwrap();
+        store.insert_token(record("rt4", "user2", "fam3", 100)).await.unwrap();
+
+        assert_eq!(store.revoke_family("fam1", "Family revocation").await.unwrap(), 2);
+        assert_eq!(store.active_user_tokens("user1", 50).await.unwrap().len(), 1);
+
+        assert_eq!(store.revoke_user_tokens("user1", "User revocation").await.unwrap(), 1);
+        assert!(store.active_user_tokens("user1", 50).await.unwrap().is_empty());
+        assert_eq!(store.active_user_tokens("user2", 50).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_delete_expired() {
+        let store = InMemoryRefreshTokenStore::new();
+        store.insert_token(record("rt1", "user1", "fam1", 10)).await.unwrap();
+        store.insert_token(record("rt2", "user1", "fam1", 100)).await.unwrap();
+
+        assert_eq!(store.delete_expired(50).await.unwrap(), 1);
+        assert!(store.get_token("rt1").await.unwrap().is_none());
+        assert!(store.get_token("rt2").await.unwrap().is_some());
+    }
+}
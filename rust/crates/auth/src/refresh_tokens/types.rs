@@ -19,9 +19,6 @@ pub struct RefreshTokenConfig {
 
     /// Whether to revoke all tokens when a security event occurs
     pub revoke_on_security_event: bool,
-
-    /// Family ID for token grouping (for cascade revocation)
-    pub family_id: Option<String>,
 }
 
 impl Default for RefreshTokenConfig {
@@ -31,7 +28,6 @@ impl Default for RefreshTokenConfig {
             rotation_enabled: true,
             max_tokens_per_user: 5,
             revoke_on_security_event: true,
-            family_id: None,
         }
     }
 }
@@ -45,7 +41,7 @@ pub struct RefreshTokenRecord {
     /// User ID associated with the token
     pub user_id: String,
 
-    /// Family ID for cascade operations
+    /// Family ID shared by all tokens rotated from the same login
     pub family_id: Option<String>,
 
     /// Token creation timestamp
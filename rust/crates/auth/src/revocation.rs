@@ -0,0 +1,14 @@
+//! Access token revocation
+//!
+//! JWT access tokens are self-contained, so revoking one before it expires
+//! means remembering its `jti` until then:
+//! - `denylist`: `TokenDenylist` trait and in-memory implementation
+//! - `postgres`: Postgres-backed denylist shared across replicas
+
+pub mod denylist;
+#[cfg(feature = "database")]
+pub mod postgres;
+
+pub use denylist::{TokenDenylist, InMemoryTokenDenylist};
+#[cfg(feature = "database")]
+pub use postgres::PostgresTokenDenylist;
@@ -0,0 +1,141 @@
+//! Token denylist keyed by `jti`
+//!
+//! Entries only need to outlive the token they deny: once a token has
+//! expired, signature validation rejects it anyway.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::types::AuthResult;
+use crate::Claims;
+
+/// Storage for revoked token identifiers
+#[async_trait::async_trait]
+pub trait TokenDenylist: Send + Sync {
+    /// Deny a token id until `expires_at`
+    async fn revoke(&self, jti: &str, expires_at: u64) -> AuthResult<()>;
+
+    /// Check if a token id is currently denied
+    async fn is_revoked(&self, jti: &str) -> AuthResult<bool>;
+
+    /// Delete entries that expired before `now`, returning the number removed
+    async fn delete_expired(&self, now: u64) -> AuthResult<usize>;
+
+    /// Deny a token for the rest of its lifetime
+    ///
+    /// Returns false if the token has no `jti` or has already expired.
+    async fn revoke_claims(&self, claims: &Claims) -> AuthResult<bool> {
+        match &claims.jti {
+            Some(jti) if claims.exp > current_timestamp() => {
+                self.revoke(jti, claims.exp).await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Check if the token carrying these claims has been revoked
+    async fn is_claims_revoked(&self, claims: &Claims) -> AuthResult<bool> {
+        match &claims.jti {
+            Some(jti) => self.is_revoked(jti).await,
+            None => Ok(false),
+        }
+    }
+}
+
+/// In-memory token denylist
+///
+/// Each entry lives for the remaining lifetime of the token it denies and
+/// is dropped lazily on lookup or by `delete_expired`.
+#[derive(Default)]
+pub struct InMemoryTokenDenylist {
+    entries: Mutex<HashMap<String, u64>>,
+}
+
+impl InMemoryTokenDenylist {
+    /// Create an empty denylist
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of entries, including any not yet cleaned up
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Check if the denylist has no entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenDenylist for InMemoryTokenDenylist {
+    async fn revoke(&self, jti: &str, expires_at: u64) -> AuthResult<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.entry(jti.to_string()).or_insert(expires_at);
+        *entry = (*entry).max(expires_at);
+        Ok(())
+    }
+
+    async fn is_revoked(&self, jti: &str) -> AuthResult<bool> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(jti) {
+            Some(&expires_at) if expires_at > current_timestamp() => Ok(true),
+            Some(_) => {
+                entries.remove(jti);
+                Ok(false)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn delete_expired(&self, now: u64) -> AuthResult<usize> {
+        let mut entries = self.entries.lock().unwrap();
+        let initial_count = entries.len();
+        entries.retain(|_, expires_at| *expires_at > now);
+        Ok(initial_count - entries.len())
+    }
+}
+
+fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_revoke_claims() {
+        let denylist = InMemoryTokenDenylist::new();
+        let claims = Claims::new("user123", "tenant456");
+
+        assert!(!denylist.is_claims_revoked(&claims).await.unwrap());
+        assert!(denylist.revoke_claims(&claims).await.unwrap());
+        assert!(denylist.is_claims_revoked(&claims).await.unwrap());
+        assert!(!denylist.is_claims_revoked(&Claims::new("user123", "tenant456")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_entries_expire_with_token() {
+        let denylist = InMemoryTokenDenylist::new();
+
+        let mut expired = Claims::new("user123", "tenant456");
+        expired.exp = current_timestamp() - 1;
+        assert!(!denylist.revoke_claims(&expired).await.unwrap());
+        assert!(denylist.is_empty());
+
+        denylist.revoke("stale", current_timestamp() - 1).await.unwrap();
+        assert!(!denylist.is_revoked("stale").await.unwrap());
+        assert!(denylist.is_empty());
+
+        denylist.revoke("old", 10).await.unwrap();
+        denylist.revoke("new", u64::MAX).await.unwrap();
+        assert_eq!(denylist.delete_expired(100).await.unwrap(), 1);
+        assert!(denylist.is_revoked("new").await.unwrap());
+    }
+}
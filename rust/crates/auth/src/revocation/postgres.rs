@@ -0,0 +1,132 @@
+//! PostgreSQL token denylist.
+//!
+//! This module provides a `TokenDenylist` backed by a shared Postgres table
+//! so a token revoked on one replica is rejected by all of them.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+use cloudshuttle_database::{DatabaseConnection, Migration, MigrationBuilder};
+use sqlx::PgPool;
+use crate::types::{AuthResult, AuthError};
+
+use super::denylist::TokenDenylist;
+
+/// Token denylist persisted in the `revoked_tokens` table
+#[derive(Clone)]
+pub struct PostgresTokenDenylist {
+    pool: PgPool,
+}
+
+impl PostgresTokenDenylist {
+    /// Create a denylist using an existing connection pool
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a denylist sharing the pool of a database connection
+    pub fn from_connection(connection: &DatabaseConnection) -> Self {
+        Self::new(connection.pool().clone())
+    }
+
+    /// Migration creating the `revoked_tokens` table and its indexes
+    pub fn migration() -> Migration {
+        MigrationBuilder::new("auth_0002", "create_revoked_tokens")
+            .description("Create access token denylist for cloudshuttle-auth")
+            .up_sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS revoked_tokens (
+                    jti VARCHAR(255) PRIMARY KEY,
+                    expires_at BIGINT NOT NULL
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_revoked_tokens_expires_at ON revoked_tokens(expires_at);
+                "#,
+            )
+            .down_sql("DROP TABLE IF EXISTS revoked_tokens")
+            .build()
+    }
+}
+
+fn database_error(error: sqlx::Error) -> AuthError {
+    AuthError::DatabaseError(error.to_string())
+}
+
+fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[async_trait::async_trait]
+impl TokenDenylist for PostgresTokenDenylist {
+    async fn revoke(&self, jti: &str, expires_at: u64) -> AuthResult<()> {
+        sqlx::query(
+            "INSERT INTO revoked_tokens (jti, expires_at) VALUES ($1, $2) \
+             ON CONFLICT (jti) DO UPDATE SET expires_at = GREATEST(revoked_tokens.expires_at, EXCLUDED.expires_at)"
+        )
+        .bind(jti)
+        .bind(expires_at as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(database_error)?;
+
+        Ok(())
+    }
+
+    async fn is_revoked(&self, jti: &str) -> AuthResult<bool> {
+        let revoked: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM revoked_tokens WHERE jti = $1 AND expires_at > $2)"
+        )
+        .bind(jti)
+        .bind(current_timestamp() as i64)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(database_error)?;
+
+        Ok(revoked)
+    }
+
+    async fn delete_expired(&self, now: u64) -> AuthResult<usize> {
+        let result = sqlx::query("DELETE FROM revoked_tokens WHERE expires_at <= $1")
+            .bind(now as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(database_error)?;
+
+        Ok(result.rows_affected() as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cloudshuttle_database::test_util;
+
+    async fn test_denylist() -> PostgresTokenDenylist {
+        PostgresTokenDenylist::new(test_util::migrated_pool(&PostgresTokenDenylist::migration()).await)
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_migration_up_and_down() {
+        test_util::assert_migration_reversible(&PostgresTokenDenylist::migration(), "revoked_tokens").await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_postgres_denylist_roundtrip() {
+        let denylist = test_denylist().await;
+        let jti = uuid::Uuid::new_v4().to_string();
+        let expired = uuid::Uuid::new_v4().to_string();
+
+        assert!(!denylist.is_revoked(&jti).await.unwrap());
+        denylist.revoke(&jti, current_timestamp() + 60).await.unwrap();
+        denylist.revoke(&jti, current_timestamp() + 30).await.unwrap();
+        assert!(denylist.is_revoked(&jti).await.unwrap());
+
+        denylist.revoke(&expired, 5).await.unwrap();
+        assert!(!denylist.is_revoked(&expired).await.unwrap());
+        assert!(denylist.delete_expired(10).await.unwrap() >= 1);
+        assert!(denylist.is_revoked(&jti).await.unwrap());
+    }
+}
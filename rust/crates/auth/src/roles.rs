@@ -0,0 +1,252 @@
+//! Role hierarchy and permission inheritance
+//!
+//! Roles may inherit other roles and grant permissions. Effective roles and
+//! permissions are resolved either when a token is issued, so exact-match
+//! checks such as `Claims::has_role` see inherited roles, or at check time
+//! by the middleware guards. Permission checks accept wildcard grants such
+//! as `documents:*`, see [`crate::authorization::permissions`].
+
+use std::collections::{BTreeSet, HashMap};
+use serde::{Deserialize, Serialize};
+use crate::authorization::permissions::permission_matches;
+use crate::types::{AuthResult, AuthError};
+use crate::Claims;
+
+/// Role definition
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Role {
+    pub name: String,
+
+    /// Roles whose permissions this role inherits
+    #[serde(default)]
+    pub inherits: Vec<String>,
+
+    /// Permissions granted directly by this role
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+impl Role {
+    /// Create a role with no parents or permissions
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Inherit from other roles
+    pub fn inherits(mut self, roles: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.inherits = roles.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Grant permissions
+    pub fn with_permissions(mut self, permissions: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.permissions = permissions.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// Roles and permissions a user holds once inheritance is resolved
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectiveAccess {
+    pub roles: BTreeSet<String>,
+    pub permissions: BTreeSet<String>,
+}
+
+/// Registry of roles forming an acyclic inheritance graph
+#[derive(Debug, Clone, Default)]
+pub struct RoleRegistry {
+    roles: HashMap<String, Role>,
+}
+
+impl RoleRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a registry from role definitions given in any order
+    ///
+    /// Fails if a role inherits from an unknown role or inheritance forms a cycle.
+    pub fn from_roles(roles: impl IntoIterator<Item = Role>) -> AuthResult<Self> {
+        let registry = Self {
+            roles: roles.into_iter().map(|role| (role.name.clone(), role)).collect(),
+        };
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    /// Add or replace a role, rejecting unknown parents and cycles
+    pub fn add_role(&mut self, role: Role) -> AuthResult<()> {
+        let name = role.name.clone();
+        let previous = self.roles.insert(name.clone(), role);
+
+        if let Err(error) = self.validate() {
+            match previous {
+                Some(previous) => self.roles.insert(name, previous),
+                None => self.roles.remove(&name),
+            };
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Look up a role
+    pub fn get(&self, name: &str) -> Option<&Role> {
+        self.roles.get(name)
+    }
+
+    /// Resolve assigned roles and direct permissions into effective access
+    ///
+    /// Roles missing from the registry are kept but grant nothing.
+    pub fn effective_access(&self, roles: &[String], permissions: &[String]) -> EffectiveAccess {
+        let mut access = EffectiveAccess {
+            roles: BTreeSet::new(),
+            permissions: permissions.iter().cloned().collect(),
+        };
+
+        let mut pending: Vec<&str> = roles.iter().map(String::as_str).collect();
+        while let Some(name) = pending.pop() {
+            if !access.roles.insert(name.to_string()) {
+                continue;
+            }
+            if let Some(role) = self.roles.get(name) {
+                access.permissions.extend(role.permissions.iter().cloned());
+                pending.extend(role.inherits.iter().map(String::as_str));
+            }
+        }
+
+        access
+    }
+
+    /// Check if assigned roles include `role` directly or through inheritance
+    pub fn has_role(&self, roles: &[String], role: &str) -> bool {
+        self.effective_access(roles, &[]).roles.contains(role)
+    }
+
+    /// Check if roles or direct permissions grant `permission`, honouring wildcards
+    pub fn has_permission(&self, roles: &[String], permissions: &[String], permission: &str) -> bool {
+        self.effective_access(roles, permissions)
+            .permissions
+            .iter()
+            .any(|pattern| permission_matches(pattern, permission))
+    }
+
+    /// Replace a token's roles and permissions with their effective sets
+    pub fn resolve_claims(&self, claims: &mut Claims) {
+        let access = self.effective_access(&claims.roles, &claims.permissions);
+        claims.roles = access.roles.into_iter().collect();
+        claims.permissions = access.permissions.into_iter().collect();
+    }
+
+    fn validate(&self) -> AuthResult<()> {
+        for role in self.roles.values() {
+            if let Some(parent) = role.inherits.iter().find(|parent| !self.roles.contains_key(*parent)) {
+                return Err(invalid(format!("role '{}' inherits unknown role '{}'", role.name, parent)));
+            }
+        }
+
+        // Depth-first search; a role met again while still on the path closes a cycle
+        let mut finished = BTreeSet::new();
+        for name in self.roles.keys() {
+            let mut path = Vec::new();
+            self.check_cycles(name, &mut path, &mut finished)?;
+        }
+        Ok(())
+    }
+
+    fn check_cycles<'a>(&'a self, name: &'a str, path: &mut Vec<&'a str>, finished: &mut BTreeSet<&'a str>) -> AuthResult<()> {
+        if finished.contains(name) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|visited| *visited == name) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name);
+            return Err(invalid(format!("inheritance cycle {}", cycle.join(" -> "))));
+        }
+
+        path.push(name);
+        for parent in &self.roles[name].inherits {
+            self.check_cycles(parent, path, finished)?;
+        }
+        path.pop();
+        finished.insert(name);
+        Ok(())
+    }
+}
+
+fn invalid(message: String) -> AuthError {
+    AuthError::InvalidConfiguration(format!("Invalid role hierarchy: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> RoleRegistry {
+        RoleRegistry::from_roles([
+            Role::new("admin").inherits(["editor", "user_manager"]).with_permissions(["settings:write"]),
+            Role::new("editor").inherits(["viewer"]).with_permissions(["documents:write"]),
+            Role::new("viewer").with_permissions(["documents:read"]),
+            Role::new("user_manager").with_permissions(["users:write"]),
+        ]).unwrap()
+    }
+
+    #[test]
+    fn test_permission_inheritance() {
+        let registry = registry();
+        let access = registry.effective_access(&["admin".to_string()], &["billing:read".to_string()]);
+
+        let roles: Vec<_> = access.roles.iter().map(String::as_str).collect();
+        assert_eq!(roles, vec!["admin", "editor", "user_manager", "viewer"]);
+        let permissions: Vec<_> = access.permissions.iter().map(String::as_str).collect();
+        assert_eq!(permissions, vec!["billing:read", "documents:read", "documents:write", "settings:write", "users:write"]);
+
+        let editor = vec!["editor".to_string()];
+        assert!(registry.has_role(&editor, "viewer"));
+        assert!(!registry.has_role(&editor, "admin"));
+        assert!(registry.has_permission(&editor, &[], "documents:read"));
+        assert!(!registry.has_permission(&editor, &[], "users:write"));
+    }
+
+    #[test]
+    fn test_has_permission_honours_wildcards() {
+        let registry = RoleRegistry::from_roles([
+            Role::new("owner").with_permissions(["documents:*"]),
+        ]).unwrap();
+        let owner = vec!["owner".to_string()];
+
+        assert!(registry.has_permission(&owner, &[], "documents:publish"));
+        assert!(registry.has_permission(&[], &["*:read".to_string()], "users:read"));
+        assert!(!registry.has_permission(&owner, &[], "users:read"));
+    }
+
+    #[test]
+    fn test_resolve_claims() {
+        let mut claims = Claims::new("user123", "tenant1");
+        claims.roles = vec!["editor".to_string(), "unregistered".to_string()];
+        registry().resolve_claims(&mut claims);
+
+        assert!(claims.has_role("viewer"));
+        assert!(claims.has_role("unregistered"));
+        assert!(claims.has_permission("documents:read"));
+    }
+
+    #[test]
+    fn test_rejects_cycles_and_unknown_parents() {
+        let result = RoleRegistry::from_roles([
+            Role::new("a").inherits(["b"]),
+            Role::new("b").inherits(["c"]),
+            Role::new("c").inherits(["a"]),
+        ]);
+        assert!(matches!(result, Err(AuthError::InvalidConfiguration(message)) if message.contains("cycle")));
+        assert!(RoleRegistry::from_roles([Role::new("a").inherits(["missing"])]).is_err());
+
+        let mut registry = registry();
+        assert!(registry.add_role(Role::new("viewer").inherits(["admin"])).is_err());
+        assert!(registry.get("viewer").unwrap().inherits.is_empty());
+        assert!(registry.add_role(Role::new("auditor").inherits(["viewer"])).is_ok());
+    }
+}
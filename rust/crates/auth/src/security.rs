@@ -4,20 +4,24 @@
 //! - `password_policy`: Password strength validation and entropy calculation
 //! - `input_sanitization`: XSS/SQL injection prevention and input validation
 //! - `encryption`: Cryptographic operations and secure token generation
-//! - `rate_limiting`: Request throttling and rate limiting (currently stubbed)
+//! - `rate_limiting`: Request throttling and account lockout
+//! - `blocklist`: Common, breached and context-derived password rejection
 
 pub mod password_policy;
 pub mod input_sanitization;
 pub mod encryption;
 pub mod rate_limiting;
+pub mod blocklist;
 
 // Re-export for backward compatibility
 pub use password_policy::{PasswordPolicy, PasswordStrength};
 pub use input_sanitization::InputSanitizer;
 pub use encryption::CryptoUtils;
-pub use rate_limiting::{RateLimiter, check_rate_limit};
+pub use rate_limiting::{RateLimiter, AccountLockout, check_rate_limit};
+pub use blocklist::{PasswordBlocklist, CommonPasswords, PwnedPasswordRanges, PasswordContext};
 
 use crate::types::{AuthResult, AuthError};
+use cloudshuttle_crypto::PasswordVerification;
 
 /// Main security validator - orchestrates all security modules
 pub struct SecurityValidator;
@@ -47,7 +51,7 @@ impl SecurityValidator {
     }
 
     /// Verify password (delegates to CryptoUtils)
-    pub fn verify_password(password: &str, hash: &str) -> AuthResult<bool> {
+    pub fn verify_password(password: &str, hash: &str) -> AuthResult<PasswordVerification> {
         CryptoUtils::verify_password(password, hash)
     }
 
@@ -122,8 +126,8 @@ mod tests {
         assert_ne!(hash, password);
 
         // Should be able to verify
-        assert!(SecurityValidator::verify_password(password, &hash).unwrap());
-        assert!(!SecurityValidator::verify_password("wrongpassword", &hash).unwrap());
+        assert!(SecurityValidator::verify_password(password, &hash).unwrap().matches);
+        assert!(!SecurityValidator::verify_password("wrongpassword", &hash).unwrap().matches);
     }
 
     #[test]
@@ -0,0 +1,281 @@
+//! Common and breached password blocklists
+//!
+//! `PasswordPolicy` consults these after its character rules:
+//! - `CommonPasswords`: an embedded list of common passwords
+//! - `PwnedPasswordRanges`: a local directory of HIBP-style SHA-1 range files
+//! - `PasswordContext`: user details a password must not contain
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use ring::digest;
+use crate::types::{AuthResult, AuthError};
+
+/// Source of passwords that must not be used
+///
+/// Checks are synchronous. The provided blocklists answer from memory once
+/// loaded; only the first `PwnedPasswordRanges` check reads from disk.
+pub trait PasswordBlocklist: Send + Sync + fmt::Debug {
+    /// Check a password, returning the reason it is rejected
+    fn check(&self, password: &str) -> AuthResult<Option<String>>;
+}
+
+static EMBEDDED_PASSWORDS: OnceLock<HashSet<String>> = OnceLock::new();
+
+/// Embedded list of common passwords, compared case-insensitively
+///
+/// The list holds 10,000 frequently used passwords and their usual variants
+/// (appended digits, years and symbols, character substitutions, keyboard
+/// and digit sequences). Entries are at least 8 characters long, the default
+/// `PasswordPolicy::min_length`, as shorter ones are rejected by length alone.
+/// It is parsed once, on first use.
+#[derive(Clone, Default)]
+pub struct CommonPasswords {
+    extra: HashSet<String>,
+}
+
+impl CommonPasswords {
+    /// Blocklist of the embedded passwords
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Block additional passwords, such as product or company names
+    pub fn with_passwords(mut self, passwords: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        self.extra.extend(passwords.into_iter().map(|password| password.as_ref().to_lowercase()));
+        self
+    }
+
+    /// Check if a password is on the list
+    pub fn contains(&self, password: &str) -> bool {
+        let password = password.to_lowercase();
+        Self::embedded().contains(&password) || self.extra.contains(&password)
+    }
+
+    fn embedded() -> &'static HashSet<String> {
+        EMBEDDED_PASSWORDS.get_or_init(|| {
+            include_str!("common_passwords.txt")
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_lowercase)
+                .collect()
+        })
+    }
+}
+
+impl fmt::Debug for CommonPasswords {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CommonPasswords")
+            .field("embedded", &Self::embedded().len())
+            .field("extra", &self.extra.len())
+            .finish()
+    }
+}
+
+impl PasswordBlocklist for CommonPasswords {
+    fn check(&self, password: &str) -> AuthResult<Option<String>> {
+        Ok(self
+            .contains(password)
+            .then(|| "Password is too common, please choose a different one".to_string()))
+    }
+}
+
+/// Breached passwords from a local copy of the Pwned Passwords range files
+///
+/// The directory holds one file per 5-character SHA-1 prefix (`21BD1` or
+/// `21BD1.txt`), each line being `SUFFIX:COUNT` as served by the range API.
+/// Missing prefix files mean no known breaches. Every range file is read
+/// into memory on the first check, or by `load`, and kept for the life of
+/// the blocklist and its clones, so ship a copy trimmed to the hashes worth
+/// blocking (for example those above a breach count) rather than the full
+/// corpus.
+#[derive(Debug, Clone)]
+pub struct PwnedPasswordRanges {
+    dir: PathBuf,
+    min_count: u64,
+    hashes: Arc<OnceLock<HashMap<String, u64>>>,
+}
+
+impl PwnedPasswordRanges {
+    /// Use the range files in a directory
+    pub fn from_dir(dir: impl Into<PathBuf>) -> AuthResult<Self> {
+        let dir = dir.into();
+        if !dir.is_dir() {
+            return Err(AuthError::InternalError(format!("Password range directory not found: {}", dir.display())));
+        }
+        Ok(Self { dir, min_count: 1, hashes: Arc::default() })
+    }
+
+    /// Only reject passwords seen in at least this many breaches
+    pub fn with_min_count(mut self, min_count: u64) -> Self {
+        self.min_count = min_count.max(1);
+        self
+    }
+
+    /// Number of times a password appears in the breach corpus
+    pub fn breach_count(&self, password: &str) -> AuthResult<u64> {
+        Ok(self.load()?.get(&sha1_hex(password)).copied().unwrap_or(0))
+    }
+
+    /// Read the range files now rather than on the first check
+    ///
+    /// Blocking file I/O; call it at startup, or from async code through
+    /// `tokio::task::spawn_blocking`. A failed load is retried on the next
+    /// call.
+    pub fn load(&self) -> AuthResult<&HashMap<String, u64>> {
+        if let Some(hashes) = self.hashes.get() {
+            return Ok(hashes);
+        }
+        let hashes = read_ranges(&self.dir)?;
+        Ok(self.hashes.get_or_init(|| hashes))
+    }
+}
+
+/// Read every range file in a directory into full uppercase hashes
+fn read_ranges(dir: &Path) -> AuthResult<HashMap<String, u64>> {
+    let read_error = |e: std::io::Error| AuthError::InternalError(format!("Failed to read password ranges in {}: {}", dir.display(), e));
+
+    let mut hashes = HashMap::new();
+    for entry in std::fs::read_dir(dir).map_err(read_error)? {
+        let path = entry.map_err(read_error)?.path();
+        let Some(prefix) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.strip_suffix(".txt").unwrap_or(name))
+            .filter(|prefix| prefix.len() == 5 && prefix.chars().all(|c| c.is_ascii_hexdigit()))
+            .map(str::to_ascii_uppercase)
+        else {
+            continue;
+        };
+
+        let contents = std::fs::read_to_string(&path).map_err(read_error)?;
+        for (suffix, count) in parse_range(&contents) {
+            hashes.insert(format!("{}{}", prefix, suffix.to_ascii_uppercase()), count);
+        }
+    }
+    Ok(hashes)
+}
+
+impl PasswordBlocklist for PwnedPasswordRanges {
+    fn check(&self, password: &str) -> AuthResult<Option<String>> {
+        Ok((self.breach_count(password)? >= self.min_count)
+            .then(|| "Password has appeared in a data breach, please choose a different one".to_string()))
+    }
+}
+
+/// Parse `SUFFIX:COUNT` lines, skipping malformed ones
+fn parse_range(contents: &str) -> impl Iterator<Item = (&str, u64)> {
+    contents.lines().filter_map(|line| {
+        let (suffix, count) = line.trim().split_once(':')?;
+        Some((suffix, count.trim().parse().ok()?))
+    })
+}
+
+/// Uppercase hex SHA-1, as used by Pwned Passwords
+fn sha1_hex(password: &str) -> String {
+    let digest = digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, password.as_bytes());
+    hex::encode_upper(digest.as_ref())
+}
+
+/// Details about the user a password must not contain
+#[derive(Debug, Clone, Default)]
+pub struct PasswordContext {
+    pub username: Option<String>,
+    pub email: Option<String>,
+}
+
+/// Shortest username or email part worth checking for
+const MIN_CONTEXT_LENGTH: usize = 3;
+
+impl PasswordContext {
+    /// Create an empty context
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject passwords containing the username
+    pub fn with_username(mut self, username: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self
+    }
+
+    /// Reject passwords containing the email address or its local part
+    pub fn with_email(mut self, email: impl Into<String>) -> Self {
+        self.email = Some(email.into());
+        self
+    }
+
+    /// Reasons the password is rejected given this context
+    pub fn check(&self, password: &str) -> Vec<String> {
+        let password = password.to_lowercase();
+        let contains = |value: &str| {
+            let value = value.trim().to_lowercase();
+            value.chars().count() >= MIN_CONTEXT_LENGTH && password.contains(&value)
+        };
+
+        let mut errors = Vec::new();
+        if self.username.as_deref().is_some_and(contains) {
+            errors.push("Password must not contain your username".to_string());
+        }
+        if let Some(email) = &self.email {
+            let local_part = email.split('@').next().unwrap_or_default();
+            if contains(email) || contains(local_part) {
+                errors.push("Password must not contain your email address".to_string());
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_common_passwords() {
+        let blocklist = CommonPasswords::new().with_passwords(["CloudShuttle2024"]);
+        assert!(blocklist.contains("Password1"));
+        assert!(blocklist.contains("cloudshuttle2024"));
+        assert!(!blocklist.contains("correct horse battery staple"));
+        assert!(blocklist.check("qwerty123").unwrap().is_some());
+        assert!(blocklist.contains("summer2024"));
+        assert!(blocklist.contains("p@$$w0rd"));
+    }
+
+    #[test]
+    fn test_common_passwords_are_not_shorter_than_min_length() {
+        let min_length = crate::types::PasswordPolicy::default().min_length;
+        assert!(CommonPasswords::embedded().len() >= 10_000);
+        assert!(CommonPasswords::embedded().iter().all(|password| password.len() >= min_length));
+    }
+
+    #[test]
+    fn test_pwned_password_ranges() {
+        let dir = std::env::temp_dir().join(format!("pwned-ranges-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // SHA-1("password") = 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+        std::fs::write(dir.join("5BAA6.txt"), "003D68EB55068C33ACE09247EE4C639306B:3\r\n1E4C9B93F3F0682250B6CF8331B7EE68FD8:9659365\r\n").unwrap();
+
+        let ranges = PwnedPasswordRanges::from_dir(&dir).unwrap();
+        assert_eq!(ranges.breach_count("password").unwrap(), 9659365);
+        assert_eq!(ranges.breach_count("a much less common passphrase").unwrap(), 0);
+        assert!(ranges.check("password").unwrap().is_some());
+        assert!(ranges.clone().with_min_count(10_000_000).check("password").unwrap().is_none());
+
+        // The ranges were loaded once and no longer touch the disk
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(ranges.breach_count("password").unwrap(), 9659365);
+        assert!(PwnedPasswordRanges::from_dir(&dir).is_err());
+    }
+
+    #[test]
+    fn test_password_context() {
+        let context = PasswordContext::new().with_username("jdoe").with_email("jane.doe@example.com");
+        assert_eq!(context.check("MyJDoe!Pass1").len(), 1);
+        assert_eq!(context.check("jane.doe2024!").len(), 1);
+        assert!(context.check("Unrelated!Pass1").is_empty());
+        assert!(PasswordContext::new().with_username("jd").check("jdjdjd").is_empty());
+    }
+}
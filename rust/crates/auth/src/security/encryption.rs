@@ -4,35 +4,28 @@ use crate::types::{AuthResult, AuthError};
 use ring::rand::SecureRandom;
 use ring::rand::SystemRandom;
 use base64::Engine;
+use subtle::ConstantTimeEq;
+use cloudshuttle_crypto::{PasswordHasher, PasswordVerification};
 
 /// Cryptographic utilities
 pub struct CryptoUtils;
 
 impl CryptoUtils {
-    /// Hash password using Argon2
+    /// Hash password with the default `cloudshuttle_crypto::PasswordHasher` (Argon2id)
     pub fn hash_password(password: &str) -> AuthResult<String> {
-        use argon2::{Argon2, PasswordHasher};
-        use argon2::password_hash::{rand_core::OsRng, SaltString};
-
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-
-        let password_hash = argon2.hash_password(password.as_bytes(), &salt)
-            .map_err(|_| AuthError::ExternalServiceError("Password hashing failed".to_string()))?;
-
-        Ok(password_hash.to_string())
+        PasswordHasher::default()
+            .hash(password)
+            .map_err(|_| AuthError::ExternalServiceError("Password hashing failed".to_string()))
     }
 
-    /// Verify password against hash
-    pub fn verify_password(password: &str, hash: &str) -> AuthResult<bool> {
-        use argon2::{Argon2, PasswordVerifier};
-        use argon2::password_hash::PasswordHash;
-
-        let parsed_hash = PasswordHash::new(hash)
-            .map_err(|_| AuthError::InvalidCredentials)?;
-
-        let argon2 = Argon2::default();
-        Ok(argon2.verify_password(password.as_bytes(), &parsed_hash).is_ok())
+    /// Verify password against an Argon2 or bcrypt hash
+    ///
+    /// When `needs_rehash` is set on a match, replace the stored hash with
+    /// `hash_password` so bcrypt and outdated Argon2 hashes get upgraded.
+    pub fn verify_password(password: &str, hash: &str) -> AuthResult<PasswordVerification> {
+        PasswordHasher::default()
+            .verify(password, hash)
+            .map_err(|_| AuthError::InvalidCredentials)
     }
 
     /// Generate secure random token
@@ -58,6 +51,14 @@ impl CryptoUtils {
         let digest = digest::digest(&digest::SHA256, data);
         hex::encode(digest.as_ref())
     }
+
+    /// Compare byte strings in time independent of where they differ
+    ///
+    /// Only the lengths may leak, so compare fixed-length values such as
+    /// digests or codes.
+    pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+        a.ct_eq(b).into()
+    }
 }
 
 #[cfg(test)]
@@ -73,8 +74,15 @@ mod tests {
         assert_ne!(hash, password);
 
         // Should be able to verify
-        assert!(CryptoUtils::verify_password(password, &hash).unwrap());
-        assert!(!CryptoUtils::verify_password("wrongpassword", &hash).unwrap());
+        let verification = CryptoUtils::verify_password(password, &hash).unwrap();
+        assert!(verification.matches && !verification.needs_rehash);
+        assert!(!CryptoUtils::verify_password("wrongpassword", &hash).unwrap().matches);
+
+        // Hashes with outdated parameters verify and are flagged for an upgrade
+        let weak = cloudshuttle_crypto::PasswordHashConfig { memory_kib: 1024, iterations: 1, parallelism: 1 };
+        let weak_hash = PasswordHasher::new(weak).unwrap().hash(password).unwrap();
+        let verification = CryptoUtils::verify_password(password, &weak_hash).unwrap();
+        assert!(verification.matches && verification.needs_rehash);
     }
 
     #[test]
@@ -104,4 +112,11 @@ mod tests {
         // Should be valid hex
         assert!(hash1.chars().all(|c| c.is_ascii_hexdigit()));
     }
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(CryptoUtils::constant_time_eq(b"123456", b"123456"));
+        assert!(!CryptoUtils::constant_time_eq(b"123456", b"123457"));
+        assert!(!CryptoUtils::constant_time_eq(b"123456", b"1234567"));
+        assert!(CryptoUtils::constant_time_eq(b"", b""));
+    }
 }
@@ -1,63 +1,280 @@
 //! Rate limiting and request throttling
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, Instant};
+use crate::types::{AuthResult, AuthError, LockoutPolicy, LoginAttempt};
 
-/// Rate limiter for request throttling
+/// Request log for a single key within its sliding window
+#[derive(Debug)]
+struct SlidingWindow {
+    window: Duration,
+    requests: VecDeque<Instant>,
+}
+
+impl SlidingWindow {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            requests: VecDeque::new(),
+        }
+    }
+
+    /// Drop requests that have fallen out of the window
+    fn prune(&mut self, now: Instant) {
+        while let Some(&oldest) = self.requests.front() {
+            if now.duration_since(oldest) >= self.window {
+                self.requests.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+/// Thread-safe sliding window log rate limiter
 pub struct RateLimiter {
-    attempts: HashMap<String, Vec<Instant>>,
+    attempts: Mutex<HashMap<String, SlidingWindow>>,
     max_attempts: u32,
     window_duration: Duration,
+    last_cleanup: Mutex<Instant>,
 }
 
 impl RateLimiter {
     /// Create new rate limiter
     pub fn new(max_attempts: u32, window_seconds: u64) -> Self {
         Self {
-            attempts: HashMap::new(),
+            attempts: Mutex::new(HashMap::new()),
             max_attempts,
             window_duration: Duration::from_secs(window_seconds),
+            last_cleanup: Mutex::new(Instant::now()),
         }
     }
 
-    /// Check if request is allowed
-    pub fn check_rate_limit(&mut self, _key: &str, _max_requests: u32, _window_seconds: u64) -> bool {
-        // TODO: Implement proper rate limiting with persistent storage
-        // In production, this would use Redis or similar distributed store
-        // For now, return true (allow) to avoid breaking functionality
+    /// Check if request is allowed using the limiter's default limits
+    pub fn check(&self, key: &str) -> bool {
+        self.check_rate_limit(key, self.max_attempts, self.window_duration.as_secs())
+    }
+
+    /// Check if request is allowed, recording it when it is
+    pub fn check_rate_limit(&self, key: &str, max_requests: u32, window_seconds: u64) -> bool {
+        let now = Instant::now();
+        self.maybe_cleanup(now);
 
-        // Basic in-memory implementation (not suitable for production)
-        // This is a placeholder - proper implementation needed
+        let mut attempts = self.attempts.lock().unwrap();
+        let entry = attempts
+            .entry(key.to_string())
+            .or_insert_with(|| SlidingWindow::new(Duration::from_secs(window_seconds)));
+        entry.window = Duration::from_secs(window_seconds);
+        entry.prune(now);
 
+        if entry.requests.len() >= max_requests as usize {
+            return false;
+        }
+
+        entry.requests.push_back(now);
         true
     }
 
+    /// Record a request unconditionally, returning the count within the window
+    pub fn record(&self, key: &str, window_seconds: u64) -> u32 {
+        let now = Instant::now();
+        self.maybe_cleanup(now);
+
+        let mut attempts = self.attempts.lock().unwrap();
+        let entry = attempts
+            .entry(key.to_string())
+            .or_insert_with(|| SlidingWindow::new(Duration::from_secs(window_seconds)));
+        entry.window = Duration::from_secs(window_seconds);
+        entry.prune(now);
+        entry.requests.push_back(now);
+
+        entry.requests.len() as u32
+    }
+
+    /// Time until the oldest request in the window expires
+    pub fn retry_after(&self, key: &str) -> Option<Duration> {
+        let attempts = self.attempts.lock().unwrap();
+        let entry = attempts.get(key)?;
+        let oldest = entry.requests.front()?;
+        Some(entry.window.saturating_sub(Instant::now().duration_since(*oldest)))
+    }
+
     /// Clean up expired entries
-    pub fn cleanup(&mut self) {
+    pub fn cleanup(&self) {
         let now = Instant::now();
-        self.attempts.retain(|_, attempts| {
-            attempts.retain(|&time| now.duration_since(time) < self.window_duration);
-            !attempts.is_empty()
+        let mut attempts = self.attempts.lock().unwrap();
+        attempts.retain(|_, entry| {
+            entry.prune(now);
+            !entry.requests.is_empty()
         });
     }
 
     /// Get current request count for a key
     pub fn get_request_count(&self, key: &str) -> u32 {
-        self.attempts.get(key).map(|attempts| attempts.len() as u32).unwrap_or(0)
+        let now = Instant::now();
+        let mut attempts = self.attempts.lock().unwrap();
+        attempts.get_mut(key)
+            .map(|entry| {
+                entry.prune(now);
+                entry.requests.len() as u32
+            })
+            .unwrap_or(0)
     }
 
     /// Reset rate limit for a key
-    pub fn reset(&mut self, key: &str) {
-        self.attempts.remove(key);
+    pub fn reset(&self, key: &str) {
+        self.attempts.lock().unwrap().remove(key);
     }
+
+    /// Expire idle keys at most once per default window
+    fn maybe_cleanup(&self, now: Instant) {
+        let mut last_cleanup = self.last_cleanup.lock().unwrap();
+        if now.duration_since(*last_cleanup) >= self.window_duration {
+            *last_cleanup = now;
+            drop(last_cleanup);
+            self.cleanup();
+        }
+    }
+}
+
+/// Process-wide limiter backing the free `check_rate_limit` function
+fn global_limiter() -> &'static RateLimiter {
+    static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+    LIMITER.get_or_init(|| RateLimiter::new(100, 60))
 }
 
 /// Simple rate limiting check (compatibility function)
+///
+/// Uses a process-wide limiter; services running several replicas should
+/// use a shared store such as the API crate's rate limiting middleware.
 pub fn check_rate_limit(key: &str, max_requests: u32, window_seconds: u64) -> bool {
-    // In production, this would use Redis or similar
-    // For now, return true (allow) - implement proper rate limiting in production
-    let _ = (key, max_requests, window_seconds);
-    true
+    global_limiter().check_rate_limit(key, max_requests, window_seconds)
+}
+
+/// Lockout state for a single account
+#[derive(Debug, Clone)]
+struct LockoutState {
+    locked_until: Option<chrono::DateTime<chrono::Utc>>,
+    lockout_count: u32,
+}
+
+/// Tracks failed logins and applies a `LockoutPolicy`
+///
+/// Failures are counted in a sliding window of `reset_after_minutes`. Once
+/// `max_attempts` is reached the account is locked for
+/// `calculate_lockout_duration(n)`, where `n` is the number of consecutive
+/// lockouts since the last successful login.
+pub struct AccountLockout {
+    policy: LockoutPolicy,
+    failures: RateLimiter,
+    lockouts: Mutex<HashMap<String, LockoutState>>,
+}
+
+impl AccountLockout {
+    /// Create a lockout tracker for the given policy
+    pub fn new(policy: LockoutPolicy) -> Self {
+        let window_seconds = policy.reset_window_minutes() as u64 * 60;
+        Self {
+            failures: RateLimiter::new(policy.max_attempts, window_seconds),
+            policy,
+            lockouts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the lockout policy
+    pub fn policy(&self) -> &LockoutPolicy {
+        &self.policy
+    }
+
+    /// Fail with `AccountLocked` if the user is currently locked out
+    pub fn check_account(&self, user_id: &str) -> AuthResult<()> {
+        if self.is_locked(user_id) {
+            return Err(AuthError::AccountLocked);
+        }
+        Ok(())
+    }
+
+    /// Check if the user is currently locked out
+    pub fn is_locked(&self, user_id: &str) -> bool {
+        self.locked_until(user_id).is_some()
+    }
+
+    /// Get the end of the current lockout, if any
+    pub fn locked_until(&self, user_id: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+        let now = chrono::Utc::now();
+        let mut lockouts = self.lockouts.lock().unwrap();
+        let state = lockouts.get_mut(user_id)?;
+
+        match state.locked_until {
+            Some(until) if until > now => Some(until),
+            Some(_) => {
+                state.locked_until = None;
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Record a login attempt
+    ///
+    /// Successful attempts clear the failure history. A failure that reaches
+    /// the policy threshold locks the account and returns `AccountLocked`.
+    pub fn record_attempt(&self, attempt: &LoginAttempt) -> AuthResult<()> {
+        if attempt.success {
+            self.unlock(&attempt.user_id);
+            return Ok(());
+        }
+
+        self.check_account(&attempt.user_id)?;
+
+        let window_seconds = self.policy.reset_window_minutes() as u64 * 60;
+        let failures = self.failures.record(&attempt.user_id, window_seconds);
+        if failures < self.policy.max_attempts {
+            return Ok(());
+        }
+
+        self.failures.reset(&attempt.user_id);
+
+        let mut lockouts = self.lockouts.lock().unwrap();
+        let state = lockouts.entry(attempt.user_id.clone()).or_insert(LockoutState {
+            locked_until: None,
+            lockout_count: 0,
+        });
+        state.lockout_count += 1;
+        state.locked_until = Some(attempt.timestamp + self.policy.calculate_lockout_duration(state.lockout_count));
+
+        tracing::warn!(
+            user_id = %attempt.user_id,
+            ip_address = %attempt.ip_address,
+            lockout_count = state.lockout_count,
+            "Account locked after repeated login failures"
+        );
+
+        Err(AuthError::AccountLocked)
+    }
+
+    /// Get the number of failures in the current window
+    pub fn failure_count(&self, user_id: &str) -> u32 {
+        self.failures.get_request_count(user_id)
+    }
+
+    /// Clear failures and any lockout for a user
+    pub fn unlock(&self, user_id: &str) {
+        self.failures.reset(user_id);
+        self.lockouts.lock().unwrap().remove(user_id);
+    }
+
+    /// Clean up expired failure windows and lockouts
+    pub fn cleanup(&self) {
+        self.failures.cleanup();
+
+        let now = chrono::Utc::now();
+        let reset_window = chrono::Duration::minutes(self.policy.reset_window_minutes() as i64);
+        self.lockouts.lock().unwrap().retain(|_, state| {
+            state.locked_until.is_some_and(|until| until + reset_window > now)
+        });
+    }
 }
 
 #[cfg(test)]
@@ -73,11 +290,11 @@ mod tests {
 
     #[test]
     fn test_rate_limiter_basic_functionality() {
-        let mut limiter = RateLimiter::new(5, 60);
+        let limiter = RateLimiter::new(5, 60);
 
-        // Currently always returns true (stub implementation)
         assert!(limiter.check_rate_limit("test_key", 5, 60));
-        assert!(check_rate_limit("test_key", 5, 60));
+        assert!(check_rate_limit("test_basic_key", 5, 60));
+        assert_eq!(limiter.get_request_count("test_key"), 1);
     }
 
     #[test]
@@ -87,16 +304,90 @@ mod tests {
         assert_eq!(limiter.get_request_count("nonexistent"), 0);
     }
 
-    // TODO: Add tests for actual rate limiting logic once implemented
-    // #[test]
-    // fn test_rate_limiting_enforcement() {
-    //     let mut limiter = RateLimiter::new(2, 1); // 2 requests per second
-    //
-    //     // First two requests should be allowed
-    //     assert!(limiter.check_rate_limit("test", 2, 1));
-    //     assert!(limiter.check_rate_limit("test", 2, 1));
-    //
-    //     // Third request should be denied
-    //     assert!(!limiter.check_rate_limit("test", 2, 1));
-    // }
+    #[test]
+    fn test_rate_limiting_enforcement() {
+        let limiter = RateLimiter::new(2, 60);
+
+        // First two requests should be allowed
+        assert!(limiter.check("test"));
+        assert!(limiter.check("test"));
+
+        // Third request should be denied
+        assert!(!limiter.check("test"));
+        assert!(limiter.retry_after("test").is_some());
+
+        // Other keys are independent
+        assert!(limiter.check("other"));
+
+        limiter.reset("test");
+        assert!(limiter.check("test"));
+    }
+
+    #[test]
+    fn test_sliding_window_expiry() {
+        let limiter = RateLimiter::new(1, 0);
+
+        // A zero-length window never holds on to requests
+        assert!(limiter.check_rate_limit("key", 1, 0));
+        assert!(limiter.check_rate_limit("key", 1, 0));
+
+        limiter.cleanup();
+        assert_eq!(limiter.get_request_count("key"), 0);
+    }
+
+    #[test]
+    fn test_global_rate_limit() {
+        assert!(check_rate_limit("test_global_key", 2, 60));
+        assert!(check_rate_limit("test_global_key", 2, 60));
+        assert!(!check_rate_limit("test_global_key", 2, 60));
+    }
+
+    #[test]
+    fn test_account_lockout_after_failures() {
+        let policy = LockoutPolicy {
+            max_attempts: 3,
+            ..Default::default()
+        };
+        let lockout = AccountLockout::new(policy.clone());
+        let failure = || LoginAttempt::failure("user1".to_string(), "127.0.0.1".to_string(), "bad_password".to_string());
+
+        assert!(lockout.record_attempt(&failure()).is_ok());
+        assert!(lockout.record_attempt(&failure()).is_ok());
+        assert_eq!(lockout.failure_count("user1"), 2);
+
+        assert!(matches!(lockout.record_attempt(&failure()), Err(AuthError::AccountLocked)));
+        assert!(matches!(lockout.check_account("user1"), Err(AuthError::AccountLocked)));
+        assert!(lockout.check_account("user2").is_ok());
+
+        let locked_until = lockout.locked_until("user1").unwrap();
+        let expected = policy.calculate_lockout_duration(1);
+        assert!(locked_until - chrono::Utc::now() <= expected);
+        assert!(locked_until - chrono::Utc::now() > expected - chrono::Duration::minutes(1));
+    }
+
+    #[test]
+    fn test_successful_login_clears_failures() {
+        let lockout = AccountLockout::new(LockoutPolicy::default());
+        let failure = LoginAttempt::failure("user1".to_string(), "127.0.0.1".to_string(), "bad_password".to_string());
+
+        lockout.record_attempt(&failure).unwrap();
+        lockout.record_attempt(&LoginAttempt::success("user1".to_string(), "127.0.0.1".to_string())).unwrap();
+
+        assert_eq!(lockout.failure_count("user1"), 0);
+        assert!(!lockout.is_locked("user1"));
+    }
+
+    #[test]
+    fn test_lockout_expiry() {
+        let lockout = AccountLockout::new(LockoutPolicy {
+            max_attempts: 1,
+            lockout_duration_minutes: 0,
+            progressive_lockout: false,
+            ..Default::default()
+        });
+        let failure = LoginAttempt::failure("user1".to_string(), "127.0.0.1".to_string(), "bad_password".to_string());
+
+        assert!(lockout.record_attempt(&failure).is_err());
+        assert!(!lockout.is_locked("user1"));
+    }
 }
@@ -0,0 +1,13 @@
+//! Server-side session management
+//!
+//! This module builds session lifecycles on top of `SessionStore`:
+//! - `manager`: Sliding expiry and per-user concurrent session limits
+//! - `postgres`: Postgres-backed `SessionStore`
+
+pub mod manager;
+#[cfg(feature = "database")]
+pub mod postgres;
+
+pub use manager::{SessionManager, SessionConfig};
+#[cfg(feature = "database")]
+pub use postgres::PostgresSessionStore;
@@ -0,0 +1,210 @@
+//! Session manager implementation.
+//!
+//! Sessions slide: every resolved request pushes the expiry out by the idle
+//! timeout, up to an optional absolute lifetime measured from creation.
+
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use crate::types::{AuthResult, InMemorySessionStore, SessionStore, UserSession};
+#[cfg(feature = "observability")]
+use cloudshuttle_observability::audit::{audit_auth, AuditResult};
+
+/// Session lifetime policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionConfig {
+    /// Hours of inactivity after which a session expires
+    pub idle_timeout_hours: i64,
+
+    /// Hours after creation after which a session expires regardless of activity
+    pub absolute_timeout_hours: Option<i64>,
+
+    /// Maximum concurrent sessions per user; the oldest is terminated to make room
+    pub max_sessions_per_user: usize,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout_hours: 8,
+            absolute_timeout_hours: Some(24 * 7),
+            max_sessions_per_user: 5,
+        }
+    }
+}
+
+/// Minimum expiry change worth writing back to the store
+const MIN_EXTENSION_SECONDS: i64 = 60;
+
+/// Session manager with sliding expiry
+pub struct SessionManager {
+    config: SessionConfig,
+    store: Arc<dyn SessionStore>,
+}
+
+impl SessionManager {
+    /// Create a new session manager backed by an in-memory store
+    pub fn new(config: SessionConfig) -> Self {
+        Self::with_store(config, Arc::new(InMemorySessionStore::new()))
+    }
+
+    /// Create a new session manager backed by the given store
+    pub fn with_store(config: SessionConfig, store: Arc<dyn SessionStore>) -> Self {
+        Self { config, store }
+    }
+
+    /// Get the session policy
+    pub fn config(&self) -> &SessionConfig {
+        &self.config
+    }
+
+    /// Start a session, terminating the user's oldest session if at the limit
+    ///
+    /// The limit is only enforced atomically if the store overrides
+    /// `SessionStore::create_session_within_limit`, as the in-memory and
+    /// Postgres stores do.
+    pub async fn create_session(&self, mut session: UserSession) -> AuthResult<UserSession> {
+        session.expires_at = session.created_at + chrono::Duration::hours(self.config.idle_timeout_hours);
+        self.clamp_to_absolute_timeout(&mut session);
+        self.store
+            .create_session_within_limit(session.clone(), self.config.max_sessions_per_user)
+            .await?;
+
+        #[cfg(feature = "observability")]
+        audit_auth("session_created", Some(&session.user_id), AuditResult::Success);
+
+        Ok(session)
+    }
+
+    /// Look up an active session and slide its expiry
+    ///
+    /// Expired sessions are deleted and reported as missing.
+    pub async fn resolve_session(&self, session_id: &str) -> AuthResult<Option<UserSession>> {
+        let Some(mut session) = self.store.get_session(session_id).await? else {
+            return Ok(None);
+        };
+
+        if session.is_expired() {
+            self.store.delete_session(session_id).await?;
+            return Ok(None);
+        }
+
+        let previous_expiry = session.expires_at;
+        session.extend(self.config.idle_timeout_hours);
+        self.clamp_to_absolute_timeout(&mut session);
+
+        if (session.expires_at - previous_expiry).num_seconds() >= MIN_EXTENSION_SECONDS {
+            self.store.update_session(&session).await?;
+        } else {
+            session.expires_at = previous_expiry;
+        }
+
+        Ok(Some(session))
+    }
+
+    /// List a user's active sessions, oldest first
+    pub async fn list_user_sessions(&self, user_id: &str) -> AuthResult<Vec<UserSession>> {
+        self.store.list_user_sessions(user_id).await
+    }
+
+    /// End a single session
+    pub async fn terminate_session(&self, session_id: &str) -> AuthResult<()> {
+        self.store.delete_session(session_id).await
+    }
+
+    /// End all of a user's sessions, e.g. after a password change
+    pub async fn terminate_user_sessions(&self, user_id: &str) -> AuthResult<usize> {
+        let count = self.store.delete_user_sessions(user_id).await?;
+
+        #[cfg(feature = "observability")]
+        audit_auth("sessions_terminated", Some(user_id), AuditResult::Success);
+
+        Ok(count)
+    }
+
+    /// Clean up expired sessions
+    pub async fn cleanup_expired_sessions(&self) -> AuthResult<usize> {
+        self.store.cleanup_expired_sessions().await
+    }
+
+    fn clamp_to_absolute_timeout(&self, session: &mut UserSession) {
+        if let Some(hours) = self.config.absolute_timeout_hours {
+            let absolute_expiry = session.created_at + chrono::Duration::hours(hours);
+            session.expires_at = session.expires_at.min(absolute_expiry);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(user_id: &str) -> UserSession {
+        UserSession::new(user_id.to_string(), "tenant1".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_sliding_expiry() {
+        let store = Arc::new(InMemorySessionStore::new());
+        let manager = SessionManager::with_store(SessionConfig::default(), store.clone());
+        let mut created = manager.create_session(session("user123")).await.unwrap();
+        assert!(created.time_remaining() > chrono::Duration::hours(7));
+
+        // Simulate an hour of inactivity
+        created.expires_at -= chrono::Duration::hours(1);
+        store.update_session(&created).await.unwrap();
+
+        let resolved = manager.resolve_session(&created.session_id).await.unwrap().unwrap();
+        assert!(resolved.expires_at > created.expires_at + chrono::Duration::minutes(59));
+        let stored = store.get_session(&created.session_id).await.unwrap().unwrap();
+        assert_eq!(stored.expires_at, resolved.expires_at);
+    }
+
+    #[tokio::test]
+    async fn test_absolute_timeout_caps_sliding() {
+        let manager = SessionManager::new(SessionConfig {
+            absolute_timeout_hours: Some(1),
+            ..Default::default()
+        });
+        let created = manager.create_session(session("user123")).await.unwrap();
+        assert_eq!(created.expires_at, created.created_at + chrono::Duration::hours(1));
+
+        let resolved = manager.resolve_session(&created.session_id).await.unwrap().unwrap();
+        assert_eq!(resolved.expires_at, created.expires_at);
+    }
+
+    #[tokio::test]
+    async fn test_expired_sessions_are_removed() {
+        let store = Arc::new(InMemorySessionStore::new());
+        let manager = SessionManager::with_store(SessionConfig::default(), store.clone());
+        let expired = session("user123").with_expiry(-1);
+        store.create_session(expired.clone()).await.unwrap();
+
+        assert!(manager.resolve_session(&expired.session_id).await.unwrap().is_none());
+        assert!(store.get_session(&expired.session_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_session_limit_terminates_oldest() {
+        let manager = SessionManager::new(SessionConfig {
+            max_sessions_per_user: 2,
+            ..Default::default()
+        });
+        // Distinct creation times, so "oldest" can't tie
+        let created = |minutes_ago| {
+            let mut session = session("user123");
+            session.created_at -= chrono::Duration::minutes(minutes_ago);
+            session
+        };
+        let first = manager.create_session(created(3)).await.unwrap();
+        let second = manager.create_session(created(2)).await.unwrap();
+        let third = manager.create_session(created(1)).await.unwrap();
+
+        let sessions = manager.list_user_sessions("user123").await.unwrap();
+        let ids: Vec<_> = sessions.iter().map(|s| s.session_id.as_str()).collect();
+        assert_eq!(ids, vec![second.session_id.as_str(), third.session_id.as_str()]);
+        assert!(manager.resolve_session(&first.session_id).await.unwrap().is_none());
+
+        assert_eq!(manager.terminate_user_sessions("user123").await.unwrap(), 2);
+        assert!(manager.list_user_sessions("user123").await.unwrap().is_empty());
+    }
+}
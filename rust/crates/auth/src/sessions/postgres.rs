@@ -0,0 +1,304 @@
+//! PostgreSQL session store.
+//!
+//! This module provides a `SessionStore` backed by a shared Postgres table
+//! so sessions survive restarts and are visible to every replica.
+
+use chrono::{DateTime, Utc};
+use cloudshuttle_database::{DatabaseConnection, Migration, MigrationBuilder};
+use sqlx::PgPool;
+use crate::types::{AuthResult, AuthError, SessionStore, UserSession};
+
+/// Session store persisted in the `user_sessions` table
+#[derive(Clone)]
+pub struct PostgresSessionStore {
+    pool: PgPool,
+}
+
+impl PostgresSessionStore {
+    /// Create a store using an existing connection pool
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a store sharing the pool of a database connection
+    pub fn from_connection(connection: &DatabaseConnection) -> Self {
+        Self::new(connection.pool().clone())
+    }
+
+    /// Migration creating the `user_sessions` table and its indexes
+    pub fn migration() -> Migration {
+        MigrationBuilder::new("auth_0003", "create_user_sessions")
+            .description("Create server-side session storage for cloudshuttle-auth")
+            .up_sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS user_sessions (
+                    session_id VARCHAR(255) PRIMARY KEY,
+                    user_id VARCHAR(255) NOT NULL,
+                    tenant_id VARCHAR(255) NOT NULL,
+                    roles TEXT[] NOT NULL DEFAULT '{}',
+                    permissions TEXT[] NOT NULL DEFAULT '{}',
+                    created_at TIMESTAMPTZ NOT NULL,
+                    expires_at TIMESTAMPTZ NOT NULL,
+                    ip_address VARCHAR(64),
+                    user_agent TEXT
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_user_sessions_user_id ON user_sessions(user_id);
+                CREATE INDEX IF NOT EXISTS idx_user_sessions_expires_at ON user_sessions(expires_at);
+                "#,
+            )
+            .down_sql("DROP TABLE IF EXISTS user_sessions")
+            .build()
+    }
+}
+
+/// Row representation of a user session
+#[derive(sqlx::FromRow)]
+struct UserSessionRow {
+    session_id: String,
+    user_id: String,
+    tenant_id: String,
+    roles: Vec<String>,
+    permissions: Vec<String>,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+}
+
+impl From<UserSessionRow> for UserSession {
+    fn from(row: UserSessionRow) -> Self {
+        Self {
+            user_id: row.user_id,
+            tenant_id: row.tenant_id,
+            roles: row.roles,
+            permissions: row.permissions,
+            session_id: row.session_id,
+            created_at: row.created_at,
+            expires_at: row.expires_at,
+            ip_address: row.ip_address,
+            user_agent: row.user_agent,
+        }
+    }
+}
+
+fn database_error(error: sqlx::Error) -> AuthError {
+    AuthError::DatabaseError(error.to_string())
+}
+
+async fn insert_session<'e>(executor: impl sqlx::PgExecutor<'e>, session: &UserSession) -> AuthResult<()> {
+    sqlx::query(
+        "INSERT INTO user_sessions (session_id, user_id, tenant_id, roles, permissions, created_at, expires_at, ip_address, user_agent) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+    )
+    .bind(&session.session_id)
+    .bind(&session.user_id)
+    .bind(&session.tenant_id)
+    .bind(&session.roles)
+    .bind(&session.permissions)
+    .bind(session.created_at)
+    .bind(session.expires_at)
+    .bind(&session.ip_address)
+    .bind(&session.user_agent)
+    .execute(executor)
+    .await
+    .map_err(database_error)?;
+
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl SessionStore for PostgresSessionStore {
+    async fn create_session(&self, session: UserSession) -> AuthResult<String> {
+        insert_session(&self.pool, &session).await?;
+        Ok(session.session_id)
+    }
+
+    async fn get_session(&self, session_id: &str) -> AuthResult<Option<UserSession>> {
+        let row = sqlx::query_as::<_, UserSessionRow>("SELECT * FROM user_sessions WHERE session_id = $1")
+            .bind(session_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(database_error)?;
+
+        Ok(row.map(Into::into))
+    }
+
+    async fn update_session(&self, session: &UserSession) -> AuthResult<()> {
+        sqlx::query(
+            "UPDATE user_sessions SET roles = $2, permissions = $3, expires_at = $4, ip_address = $5, user_agent = $6 \
+             WHERE session_id = $1"
+        )
+        .bind(&session.session_id)
+        .bind(&session.roles)
+        .bind(&session.permissions)
+        .bind(session.expires_at)
+        .bind(&session.ip_address)
+        .bind(&session.user_agent)
+        .execute(&self.pool)
+        .await
+        .map_err(database_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_session(&self, session_id: &str) -> AuthResult<()> {
+        sqlx::query("DELETE FROM user_sessions WHERE session_id = $1")
+            .bind(session_id)
+            .execute(&self.pool)
+            .await
+            .map_err(database_error)?;
+
+        Ok(())
+    }
+
+    async fn cleanup_expired_sessions(&self) -> AuthResult<usize> {
+        let result = sqlx::query("DELETE FROM user_sessions WHERE expires_at <= NOW()")
+            .execute(&self.pool)
+            .await
+            .map_err(database_error)?;
+
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn list_user_sessions(&self, user_id: &str) -> AuthResult<Vec<UserSession>> {
+        let rows = sqlx::query_as::<_, UserSessionRow>(
+            "SELECT * FROM user_sessions WHERE user_id = $1 AND expires_at > NOW() ORDER BY created_at"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(database_error)?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn delete_user_sessions(&self, user_id: &str) -> AuthResult<usize> {
+        let result = sqlx::query("DELETE FROM user_sessions WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(database_error)?;
+
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn create_session_within_limit(&self, session: UserSession, max_sessions: usize) -> AuthResult<String> {
+        if max_sessions == 0 {
+            return self.create_session(session).await;
+        }
+
+        let mut tx = self.pool.begin().await.map_err(database_error)?;
+
+        // Serialize logins per user so concurrent replicas can't both make room
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('user_sessions:' || $1))")
+            .bind(&session.user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(database_error)?;
+
+        sqlx::query(
+            "DELETE FROM user_sessions WHERE session_id IN ( \
+                 SELECT session_id FROM user_sessions WHERE user_id = $1 AND expires_at > NOW() \
+                 ORDER BY created_at DESC OFFSET $2 \
+             )"
+        )
+        .bind(&session.user_id)
+        .bind(max_sessions as i64 - 1)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+        insert_session(&mut *tx, &session).await?;
+        tx.commit().await.map_err(database_error)?;
+
+        Ok(session.session_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cloudshuttle_database::test_util;
+    use std::sync::Arc;
+    use crate::sessions::{SessionConfig, SessionManager};
+
+    async fn test_store() -> PostgresSessionStore {
+        PostgresSessionStore::new(test_util::migrated_pool(&PostgresSessionStore::migration()).await)
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_migration_up_and_down() {
+        test_util::assert_migration_reversible(&PostgresSessionStore::migration(), "user_sessions").await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_postgres_store_roundtrip() {
+        let store = test_store().await;
+        let user_id = format!("user_{}", uuid::Uuid::new_v4());
+
+        let session = UserSession::new(user_id.clone(), "tenant1".to_string())
+            .with_roles(vec!["admin".to_string()])
+            .with_ip_address("127.0.0.1".to_string());
+        let session_id = store.create_session(session.clone()).await.unwrap();
+
+        let mut loaded = store.get_session(&session_id).await.unwrap().unwrap();
+        assert_eq!(loaded.roles, vec!["admin".to_string()]);
+        assert_eq!(loaded.ip_address.as_deref(), Some("127.0.0.1"));
+
+        loaded.extend(4);
+        store.update_session(&loaded).await.unwrap();
+        let extended = store.get_session(&session_id).await.unwrap().unwrap();
+        assert!(extended.time_remaining() > chrono::Duration::hours(3));
+
+        store.create_session(UserSession::new(user_id.clone(), "tenant1".to_string()).with_expiry(-1)).await.unwrap();
+        assert_eq!(store.list_user_sessions(&user_id).await.unwrap().len(), 1);
+        assert!(store.cleanup_expired_sessions().await.unwrap() >= 1);
+
+        assert_eq!(store.delete_user_sessions(&user_id).await.unwrap(), 1);
+        assert!(store.get_session(&session_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_session_manager_with_postgres() {
+        let store = test_store().await;
+        let user_id = format!("user_{}", uuid::Uuid::new_v4());
+        let manager = SessionManager::with_store(
+            SessionConfig { max_sessions_per_user: 1, ..Default::default() },
+            Arc::new(store),
+        );
+
+        let mut first = UserSession::new(user_id.clone(), "tenant1".to_string());
+        first.created_at -= chrono::Duration::minutes(1);
+        let first = manager.create_session(first).await.unwrap();
+        let second = manager.create_session(UserSession::new(user_id.clone(), "tenant1".to_string())).await.unwrap();
+
+        assert!(manager.resolve_session(&first.session_id).await.unwrap().is_none());
+        assert!(manager.resolve_session(&second.session_id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_concurrent_logins_respect_session_limit() {
+        let store = Arc::new(test_store().await);
+        let user_id = format!("user_{}", uuid::Uuid::new_v4());
+        let manager = Arc::new(SessionManager::with_store(
+            SessionConfig { max_sessions_per_user: 2, ..Default::default() },
+            store.clone(),
+        ));
+
+        let logins = (0..10).map(|_| {
+            let manager = manager.clone();
+            let user_id = user_id.clone();
+            tokio::spawn(async move { manager.create_session(UserSession::new(user_id, "tenant1".to_string())).await })
+        });
+        for login in futures::future::join_all(logins).await {
+            login.unwrap().unwrap();
+        }
+
+        assert_eq!(store.list_user_sessions(&user_id).await.unwrap().len(), 2);
+    }
+}
@@ -29,12 +29,18 @@ pub enum AuthError {
     #[error("Token revoked")]
     TokenRevoked,
 
+    #[error("Refresh token reuse detected")]
+    TokenReuseDetected,
+
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
 
     #[error("Internal error: {0}")]
     InternalError(String),
 
+    #[error("Invalid configuration: {0}")]
+    InvalidConfiguration(String),
+
     #[error("Invalid token type: expected {expected}, got {actual}")]
     InvalidTokenType { expected: String, actual: String },
 
@@ -107,7 +113,7 @@ impl AuthError {
     pub fn to_http_status(&self) -> http::StatusCode {
         match self {
             AuthError::TokenExpired | AuthError::RefreshTokenExpired | AuthError::SessionExpired
-            | AuthError::TokenRevoked => {
+            | AuthError::TokenRevoked | AuthError::TokenReuseDetected | AuthError::MissingToken => {
                 http::StatusCode::UNAUTHORIZED
             }
             AuthError::InvalidCredentials
@@ -138,8 +144,10 @@ impl AuthError {
             AuthError::InvalidCredentials => "INVALID_CREDENTIALS",
             AuthError::InvalidToken(_) => "INVALID_TOKEN",
             AuthError::TokenRevoked => "TOKEN_REVOKED",
+            AuthError::TokenReuseDetected => "TOKEN_REUSE_DETECTED",
             AuthError::InvalidRequest(_) => "INVALID_REQUEST",
             AuthError::InternalError(_) => "INTERNAL_ERROR",
+            AuthError::InvalidConfiguration(_) => "INVALID_CONFIGURATION",
             AuthError::InvalidRefreshToken => "INVALID_REFRESH_TOKEN",
             AuthError::RefreshTokenExpired => "REFRESH_TOKEN_EXPIRED",
             AuthError::SessionExpired => "SESSION_EXPIRED",
@@ -181,6 +189,7 @@ impl AuthError {
                 | AuthError::InvalidRefreshToken
                 | AuthError::TokenExpired
                 | AuthError::RefreshTokenExpired
+                | AuthError::TokenReuseDetected
                 | AuthError::SessionExpired
                 | AuthError::InvalidMfaCode
         )
@@ -190,21 +199,23 @@ impl AuthError {
     pub fn is_authz_failure(&self) -> bool {
         matches!(self, AuthError::InsufficientPermissions { .. })
     }
-}
 
-#[cfg(feature = "axum")]
-impl axum::response::IntoResponse for AuthError {
-    fn into_response(self) -> axum::response::Response {
-        let status = self.to_http_status();
-        let body = serde_json::json!({
+    /// JSON error response body
+    pub fn to_response_body(&self) -> serde_json::Value {
+        serde_json::json!({
             "error": {
                 "code": self.error_code(),
                 "message": self.to_string(),
                 "retryable": self.is_retryable()
             }
-        });
+        })
+    }
+}
 
-        (status, axum::Json(body)).into_response()
+#[cfg(feature = "axum")]
+impl axum::response::IntoResponse for AuthError {
+    fn into_response(self) -> axum::response::Response {
+        (self.to_http_status(), axum::Json(self.to_response_body())).into_response()
     }
 }
 
@@ -220,11 +231,18 @@ mod tests {
         assert_eq!(AuthError::ServiceUnavailable.to_http_status(), http::StatusCode::SERVICE_UNAVAILABLE);
     }
 
+    #[test]
+    fn test_missing_token_is_unauthorized() {
+        assert_eq!(AuthError::MissingToken.to_http_status(), http::StatusCode::UNAUTHORIZED);
+    }
+
     #[test]
     fn test_auth_error_codes() {
         assert_eq!(AuthError::InvalidCredentials.error_code(), "INVALID_CREDENTIALS");
         assert_eq!(AuthError::TokenExpired.error_code(), "TOKEN_EXPIRED");
+        assert_eq!(AuthError::TokenReuseDetected.error_code(), "TOKEN_REUSE_DETECTED");
         assert_eq!(AuthError::ServiceUnavailable.error_code(), "SERVICE_UNAVAILABLE");
+        assert_eq!(AuthError::InvalidConfiguration(String::new()).error_code(), "INVALID_CONFIGURATION");
     }
 
     #[test]
@@ -234,6 +252,7 @@ mod tests {
         assert!(AuthError::InsufficientPermissions { required: vec![], actual: vec![] }.is_authz_failure());
         assert!(AuthError::ServiceUnavailable.is_retryable());
         assert!(!AuthError::InvalidCredentials.is_retryable());
+        assert!(!AuthError::InvalidConfiguration(String::new()).is_retryable());
     }
 
     #[test]
@@ -1,6 +1,8 @@
 //! Authentication security policies and configurations
 
+use std::sync::Arc;
 use serde::{Deserialize, Serialize};
+use crate::security::blocklist::{CommonPasswords, PasswordBlocklist, PasswordContext};
 
 /// Password policy configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +16,10 @@ pub struct PasswordPolicy {
     pub max_age_days: Option<u32>,
     pub prevent_reuse_count: Option<u32>,
     pub lockout_on_weak_password: bool,
+
+    /// Additional blocklists, such as breached password ranges
+    #[serde(skip)]
+    pub blocklists: Vec<Arc<dyn PasswordBlocklist>>,
 }
 
 impl Default for PasswordPolicy {
@@ -28,13 +34,27 @@ impl Default for PasswordPolicy {
             max_age_days: Some(90),
             prevent_reuse_count: Some(5),
             lockout_on_weak_password: false,
+            blocklists: Vec::new(),
         }
     }
 }
 
 impl PasswordPolicy {
+    /// Also reject passwords found in a blocklist
+    pub fn with_blocklist(mut self, blocklist: Arc<dyn PasswordBlocklist>) -> Self {
+        self.blocklists.push(blocklist);
+        self
+    }
+
     /// Validate a password against this policy
     pub fn validate_password(&self, password: &str) -> Result<(), Vec<String>> {
+        self.validate_password_with_context(password, &PasswordContext::default())
+    }
+
+    /// Validate a password, also rejecting ones containing the user's details
+    ///
+    /// Blocklists that fail to answer reject the password rather than let it through.
+    pub fn validate_password_with_context(&self, password: &str, context: &PasswordContext) -> Result<(), Vec<String>> {
         let mut errors = Vec::new();
 
         if password.len() < self.min_length {
@@ -61,6 +81,20 @@ impl PasswordPolicy {
             errors.push("Password is too common, please choose a different one".to_string());
         }
 
+        errors.extend(context.check(password));
+
+        for blocklist in &self.blocklists {
+            match blocklist.check(password) {
+                Ok(Some(reason)) => errors.push(reason),
+                Ok(None) => {}
+                Err(e) => {
+                    // The error may name files or services; keep it out of the user's message
+                    tracing::warn!("Password blocklist check failed: {}", e);
+                    errors.push("Password could not be checked, please try again later".to_string());
+                }
+            }
+        }
+
         if errors.is_empty() {
             Ok(())
         } else {
@@ -68,15 +102,9 @@ impl PasswordPolicy {
         }
     }
 
-    /// Check if password is in a list of common passwords
+    /// Check if password is in the embedded list of common passwords
     fn is_common_password(&self, password: &str) -> bool {
-        let common_passwords = [
-            "password", "123456", "123456789", "qwerty", "abc123",
-            "password123", "admin", "letmein", "welcome", "monkey",
-            "1234567890", "password1", "qwerty123", "welcome123"
-        ];
-
-        common_passwords.contains(&password.to_lowercase().as_str())
+        CommonPasswords::new().contains(password)
     }
 
     /// Generate a password strength score (0-100)
@@ -360,6 +388,18 @@ impl SecurityEvent {
         }
     }
 
+    pub fn refresh_token_reuse(user_id: String, family_id: Option<String>, ip_address: Option<String>) -> Self {
+        Self {
+            event_type: SecurityEventType::SuspiciousActivity,
+            user_id: Some(user_id),
+            ip_address,
+            user_agent: None,
+            timestamp: chrono::Utc::now(),
+            details: serde_json::json!({"reason": "refresh_token_reuse", "family_id": family_id}),
+            severity: SecuritySeverity::Critical,
+        }
+    }
+
     pub fn with_user_agent(mut self, user_agent: String) -> Self {
         self.user_agent = Some(user_agent);
         self
@@ -385,6 +425,48 @@ mod tests {
         assert!(policy.validate_password("password").is_err()); // common password
     }
 
+    #[derive(Debug)]
+    struct Breached;
+
+    impl PasswordBlocklist for Breached {
+        fn check(&self, password: &str) -> crate::types::AuthResult<Option<String>> {
+            Ok((password == "Breached123").then(|| "Password has appeared in a data breach".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_password_policy_blocklists_and_context() {
+        let policy = PasswordPolicy::default().with_blocklist(Arc::new(Breached));
+
+        let errors = policy.validate_password("Breached123").unwrap_err();
+        assert_eq!(errors, vec!["Password has appeared in a data breach".to_string()]);
+        assert!(policy.validate_password("Trustno1").is_err());
+
+        let context = PasswordContext::new().with_username("alice").with_email("alice.smith@example.com");
+        let errors = policy.validate_password_with_context("Alice2024xyz", &context).unwrap_err();
+        assert_eq!(errors, vec!["Password must not contain your username".to_string()]);
+        let errors = policy.validate_password_with_context("Alice.Smith99", &context).unwrap_err();
+        assert_eq!(errors, vec!["Password must not contain your username".to_string(), "Password must not contain your email address".to_string()]);
+        assert!(policy.validate_password_with_context("Unrelated2024", &context).is_ok());
+    }
+
+    #[derive(Debug)]
+    struct Unavailable;
+
+    impl PasswordBlocklist for Unavailable {
+        fn check(&self, _password: &str) -> crate::types::AuthResult<Option<String>> {
+            Err(crate::types::AuthError::InternalError("Failed to read /srv/ranges/5BAA6".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_password_policy_hides_blocklist_errors() {
+        let policy = PasswordPolicy::default().with_blocklist(Arc::new(Unavailable));
+
+        let errors = policy.validate_password("ValidPass123").unwrap_err();
+        assert_eq!(errors, vec!["Password could not be checked, please try again later".to_string()]);
+    }
+
     #[test]
     fn test_password_strength_score() {
         let policy = PasswordPolicy::default();
@@ -183,18 +183,53 @@ pub trait SessionStore: Send + Sync {
     async fn update_session(&self, session: &UserSession) -> Result<(), crate::AuthError>;
     async fn delete_session(&self, session_id: &str) -> Result<(), crate::AuthError>;
     async fn cleanup_expired_sessions(&self) -> Result<usize, crate::AuthError>;
+
+    /// List a user's unexpired sessions, oldest first
+    ///
+    /// Not supported by default, so stores written before this method existed
+    /// keep compiling; they can't enforce session limits until they add it.
+    async fn list_user_sessions(&self, _user_id: &str) -> Result<Vec<UserSession>, crate::AuthError> {
+        Err(unsupported("list_user_sessions"))
+    }
+
+    /// Delete all of a user's sessions, returning the number removed
+    ///
+    /// Not supported by default, like `list_user_sessions`.
+    async fn delete_user_sessions(&self, _user_id: &str) -> Result<usize, crate::AuthError> {
+        Err(unsupported("delete_user_sessions"))
+    }
+
+    /// Create a session after deleting the user's oldest sessions so that at
+    /// most `max_sessions` remain; `0` means no limit
+    ///
+    /// The default implementation is not atomic: concurrent logins for the same
+    /// user can leave more than `max_sessions` behind. Stores shared between
+    /// replicas should override it.
+    async fn create_session_within_limit(&self, session: UserSession, max_sessions: usize) -> Result<String, crate::AuthError> {
+        if max_sessions > 0 {
+            let sessions = self.list_user_sessions(&session.user_id).await?;
+            let excess = (sessions.len() + 1).saturating_sub(max_sessions);
+            for old in sessions.iter().take(excess) {
+                self.delete_session(&old.session_id).await?;
+            }
+        }
+        self.create_session(session).await
+    }
+}
+
+fn unsupported(operation: &str) -> crate::AuthError {
+    crate::AuthError::InternalError(format!("Session store does not support {}", operation))
 }
 
 /// In-memory session store for testing/development
+#[derive(Default)]
 pub struct InMemorySessionStore {
-    sessions: std::sync::RwLock<std::collections::HashMap<String, UserSession>>,
+    sessions: tokio::sync::RwLock<std::collections::HashMap<String, UserSession>>,
 }
 
 impl InMemorySessionStore {
     pub fn new() -> Self {
-        Self {
-            sessions: std::sync::RwLock::new(std::collections::HashMap::new()),
-        }
+        Self::default()
     }
 }
 
@@ -202,35 +237,73 @@ impl InMemorySessionStore {
 impl SessionStore for InMemorySessionStore {
     async fn create_session(&self, session: UserSession) -> Result<String, crate::AuthError> {
         let session_id = session.session_id.clone();
-        let mut sessions = self.sessions.write().unwrap();
+        let mut sessions = self.sessions.write().await;
         sessions.insert(session_id.clone(), session);
         Ok(session_id)
     }
 
     async fn get_session(&self, session_id: &str) -> Result<Option<UserSession>, crate::AuthError> {
-        let sessions = self.sessions.read().unwrap();
+        let sessions = self.sessions.read().await;
         Ok(sessions.get(session_id).cloned())
     }
 
     async fn update_session(&self, session: &UserSession) -> Result<(), crate::AuthError> {
-        let mut sessions = self.sessions.write().unwrap();
+        let mut sessions = self.sessions.write().await;
         sessions.insert(session.session_id.clone(), session.clone());
         Ok(())
     }
 
     async fn delete_session(&self, session_id: &str) -> Result<(), crate::AuthError> {
-        let mut sessions = self.sessions.write().unwrap();
+        let mut sessions = self.sessions.write().await;
         sessions.remove(session_id);
         Ok(())
     }
 
     async fn cleanup_expired_sessions(&self) -> Result<usize, crate::AuthError> {
-        let mut sessions = self.sessions.write().unwrap();
+        let mut sessions = self.sessions.write().await;
         let before = sessions.len();
         sessions.retain(|_, session| !session.is_expired());
         let after = sessions.len();
         Ok(before - after)
     }
+
+    async fn list_user_sessions(&self, user_id: &str) -> Result<Vec<UserSession>, crate::AuthError> {
+        let sessions = self.sessions.read().await;
+        let mut user_sessions: Vec<UserSession> = sessions
+            .values()
+            .filter(|session| session.user_id == user_id && !session.is_expired())
+            .cloned()
+            .collect();
+        user_sessions.sort_by_key(|session| session.created_at);
+        Ok(user_sessions)
+    }
+
+    async fn delete_user_sessions(&self, user_id: &str) -> Result<usize, crate::AuthError> {
+        let mut sessions = self.sessions.write().await;
+        let before = sessions.len();
+        sessions.retain(|_, session| session.user_id != user_id);
+        Ok(before - sessions.len())
+    }
+
+    async fn create_session_within_limit(&self, session: UserSession, max_sessions: usize) -> Result<String, crate::AuthError> {
+        let mut sessions = self.sessions.write().await;
+        if max_sessions > 0 {
+            let mut user_sessions: Vec<(String, chrono::DateTime<chrono::Utc>)> = sessions
+                .values()
+                .filter(|existing| existing.user_id == session.user_id && !existing.is_expired())
+                .map(|existing| (existing.session_id.clone(), existing.created_at))
+                .collect();
+            user_sessions.sort_by_key(|(_, created_at)| *created_at);
+            let excess = (user_sessions.len() + 1).saturating_sub(max_sessions);
+            for (session_id, _) in user_sessions.into_iter().take(excess) {
+                sessions.remove(&session_id);
+            }
+        }
+
+        let session_id = session.session_id.clone();
+        sessions.insert(session_id.clone(), session);
+        Ok(session_id)
+    }
 }
 
 #[cfg(test)]
@@ -291,6 +364,60 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_user_sessions() {
+        let store = InMemorySessionStore::new();
+        // Distinct creation times, so the expected order can't tie
+        let mut first = UserSession::new("user123".to_string(), "tenant1".to_string());
+        first.created_at -= chrono::Duration::minutes(2);
+        let mut second = UserSession::new("user123".to_string(), "tenant1".to_string());
+        second.created_at -= chrono::Duration::minutes(1);
+        let expired = UserSession::new("user123".to_string(), "tenant1".to_string()).with_expiry(-1);
+        let other = UserSession::new("user456".to_string(), "tenant1".to_string());
+        for session in [&second, &first, &expired, &other] {
+            store.create_session(session.clone()).await.unwrap();
+        }
+
+        let sessions = store.list_user_sessions("user123").await.unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].session_id, first.session_id);
+
+        assert_eq!(store.delete_user_sessions("user123").await.unwrap(), 3);
+        assert!(store.list_user_sessions("user123").await.unwrap().is_empty());
+        assert!(store.get_session(&other.session_id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_user_session_methods_default_to_unsupported() {
+        // A store implementing only the original methods
+        struct MinimalStore;
+
+        #[async_trait::async_trait]
+        impl SessionStore for MinimalStore {
+            async fn create_session(&self, session: UserSession) -> Result<String, crate::AuthError> {
+                Ok(session.session_id)
+            }
+            async fn get_session(&self, _session_id: &str) -> Result<Option<UserSession>, crate::AuthError> {
+                Ok(None)
+            }
+            async fn update_session(&self, _session: &UserSession) -> Result<(), crate::AuthError> {
+                Ok(())
+            }
+            async fn delete_session(&self, _session_id: &str) -> Result<(), crate::AuthError> {
+                Ok(())
+            }
+            async fn cleanup_expired_sessions(&self) -> Result<usize, crate::AuthError> {
+                Ok(0)
+            }
+        }
+
+        let session = UserSession::new("user123".to_string(), "tenant1".to_string());
+        assert!(MinimalStore.list_user_sessions("user123").await.is_err());
+        assert!(MinimalStore.delete_user_sessions("user123").await.is_err());
+        assert!(MinimalStore.create_session_within_limit(session.clone(), 0).await.is_ok());
+        assert!(MinimalStore.create_session_within_limit(session, 1).await.is_err());
+    }
+
     #[test]
     fn test_session_roles_and_permissions() {
         let session = UserSession::new("user123".to_string(), "tenant1".to_string())
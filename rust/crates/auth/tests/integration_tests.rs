@@ -41,7 +41,7 @@ async fn test_user_registration_and_authentication_flow() {
     assert_eq!(validated_claims.email, Some(email.to_string()));
 
     // Verify password
-    assert!(SecurityValidator::verify_password(password, &password_hash).unwrap());
+    assert!(SecurityValidator::verify_password(password, &password_hash).unwrap().matches);
 
     println!("✅ User registration and authentication flow completed successfully");
 }
@@ -245,18 +245,15 @@ async fn test_input_sanitization_pipeline() {
     }
 }
 
-/// Test rate limiting (mock implementation)
+/// Test rate limiting
 #[tokio::test]
 async fn test_rate_limiting() {
-    // Test rate limiting logic (simplified version)
-    // In real implementation, this would use Redis or similar
-
     let test_keys = vec!["user-1", "user-2", "admin"];
 
     for key in test_keys {
-        // For now, our mock implementation always returns true
+        // First request for each key is within the limit
         let allowed = SecurityValidator::check_rate_limit(key, 10, 60);
-        assert!(allowed, "Rate limiting should allow requests in mock implementation");
+        assert!(allowed, "Rate limiting should allow requests within the limit");
 
         println!("✅ Rate limiting validated for key: {}", key);
     }
@@ -341,7 +338,7 @@ async fn test_complete_authentication_workflow() {
     assert_eq!(validated_claims.sub, user_id);
 
     // 6. Verify password for login
-    assert!(SecurityValidator::verify_password(password, &password_hash).unwrap());
+    assert!(SecurityValidator::verify_password(password, &password_hash).unwrap().matches);
 
     // 7. Log successful authentication
     SecurityAuditor::log_auth_attempt(email, true, Some("127.0.0.1"));
@@ -123,7 +123,7 @@ impl AuthenticationService {
         // Step 4: Generate tokens
         let roles = user.roles.clone();
         let access_token = self.jwt_service.create_access_token(user_id, &user.tenant_id, roles)?;
-        let refresh_token = self.refresh_manager.create_refresh_token(user_id, None, None, None)?;
+        let refresh_token = self.refresh_manager.create_refresh_token(user_id, None, None, None).await?;
 
         // Step 5: Audit successful authentication
         self.audit_logger.log(
@@ -170,7 +170,7 @@ impl AuthenticationService {
             scope: None,
         };
 
-        let response = self.refresh_manager.refresh_tokens(request)?;
+        let response = self.refresh_manager.refresh_tokens(request).await?;
 
         // Audit token refresh
         self.audit_logger.log(
@@ -199,7 +199,7 @@ impl AuthenticationService {
             token_type_hint: Some("access_token".to_string()),
         };
 
-        let response = self.token_introspector.introspect(request)?;
+        let response = self.token_introspector.introspect(request).await?;
 
         Ok(TokenValidationResult {
             is_valid: response.active,
@@ -210,7 +210,7 @@ impl AuthenticationService {
 
     /// Revoke all user tokens (security incident response)
     async fn revoke_all_user_tokens(&self, user_id: &str) -> Result<usize, AuthError> {
-        let count = self.refresh_manager.revoke_all_user_tokens(user_id)?;
+        let count = self.refresh_manager.revoke_all_user_tokens(user_id).await?;
 
         // Audit token revocation
         self.audit_logger.log(
@@ -200,6 +200,14 @@ pub fn jwt_secret() -> ConfigSecret {
         .description("JWT signing secret")
 }
 
+/// Utility function to create an optional password pepper secret
+pub fn password_pepper_secret() -> ConfigSecret {
+    ConfigSecret::plain("")
+        .optional()
+        .env_var("PASSWORD_PEPPER")
+        .description("Secret pepper mixed into password hashes")
+}
+
 /// Utility function to create an API key secret
 pub fn api_key_secret(name: &str) -> ConfigSecret {
     ConfigSecret::plain("")
@@ -254,5 +262,9 @@ mod tests {
 
         let api_secret = api_key_secret("github");
         assert_eq!(api_secret.env_var, Some("GITHUB_API_KEY".to_string()));
+
+        let pepper_secret = password_pepper_secret();
+        assert!(!pepper_secret.required);
+        assert_eq!(pepper_secret.env_var, Some("PASSWORD_PEPPER".to_string()));
     }
 }
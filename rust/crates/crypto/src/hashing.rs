@@ -2,11 +2,14 @@
 //!
 //! This module provides secure password hashing and verification
 //! using the Argon2 algorithm with appropriate security parameters.
+//! [`PasswordHasher`] adds configurable Argon2 parameters, an optional
+//! pepper and verification of legacy bcrypt hashes.
 
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher as ArgonPasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
+    Algorithm, Argon2, Params, Version,
 };
+use serde::{Deserialize, Serialize};
 use std::result::Result as StdResult;
 
 /// Error type for password operations
@@ -25,6 +28,193 @@ pub enum PasswordError {
 /// Result type for password operations
 pub type Result<T> = StdResult<T, PasswordError>;
 
+/// Argon2id cost parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordHashConfig {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    /// Number of passes over memory
+    pub iterations: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl Default for PasswordHashConfig {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// Outcome of verifying a password against a stored hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PasswordVerification {
+    /// Whether the password matched
+    pub matches: bool,
+    /// Whether the hash should be replaced with [`PasswordHasher::hash`]
+    /// (only meaningful when the password matched)
+    pub needs_rehash: bool,
+}
+
+/// Password hashing service
+///
+/// New hashes use Argon2id with the configured parameters. Verification
+/// accepts any Argon2 variant and bcrypt (`$2a$`, `$2b$`, `$2x$`, `$2y$`)
+/// and reports whether the hash should be upgraded, so logins can rehash
+/// on success.
+///
+/// The pepper is an Argon2 secret kept out of the database, for example
+/// `config::secrets::password_pepper_secret()`. Once a pepper is set,
+/// Argon2 hashes created without it are rejected unless
+/// [`allow_unpeppered_legacy`](Self::allow_unpeppered_legacy) is enabled
+/// for a migration period, in which case they verify and are reported as
+/// needing a rehash. bcrypt hashes predate the pepper and are verified
+/// without one.
+#[derive(Clone)]
+pub struct PasswordHasher {
+    config: PasswordHashConfig,
+    params: Params,
+    pepper: Option<Vec<u8>>,
+    allow_unpeppered_legacy: bool,
+}
+
+impl std::fmt::Debug for PasswordHasher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PasswordHasher")
+            .field("config", &self.config)
+            .field("pepper", &self.pepper.as_ref().map(|_| "<redacted>"))
+            .field("allow_unpeppered_legacy", &self.allow_unpeppered_legacy)
+            .finish()
+    }
+}
+
+impl Default for PasswordHasher {
+    fn default() -> Self {
+        Self::new(PasswordHashConfig::default()).expect("default Argon2 parameters are valid")
+    }
+}
+
+impl PasswordHasher {
+    /// Create a hasher with the given Argon2id parameters
+    pub fn new(config: PasswordHashConfig) -> Result<Self> {
+        let params = Params::new(config.memory_kib, config.iterations, config.parallelism, None)
+            .map_err(|e| PasswordError::HashingFailed(format!("Invalid Argon2 parameters: {}", e)))?;
+
+        Ok(Self { config, params, pepper: None, allow_unpeppered_legacy: false })
+    }
+
+    /// Mix a secret pepper into every Argon2 hash
+    pub fn with_pepper(mut self, pepper: impl AsRef<[u8]>) -> Result<Self> {
+        let pepper = pepper.as_ref();
+        if pepper.is_empty() {
+            return Err(PasswordError::HashingFailed("Pepper cannot be empty".to_string()));
+        }
+        self.pepper = Some(pepper.to_vec());
+        Ok(self)
+    }
+
+    /// Accept Argon2 hashes created before the pepper was configured
+    ///
+    /// Off by default: a leaked database must not allow offline guessing
+    /// against unpeppered hashes indefinitely. Enable it while existing users
+    /// are migrated; matching hashes are reported as needing a rehash.
+    pub fn allow_unpeppered_legacy(mut self, allow: bool) -> Self {
+        self.allow_unpeppered_legacy = allow;
+        self
+    }
+
+    /// Get the Argon2id parameters
+    pub fn config(&self) -> &PasswordHashConfig {
+        &self.config
+    }
+
+    /// Hash a password with Argon2id and a random salt
+    pub fn hash(&self, password: &str) -> Result<String> {
+        if password.is_empty() {
+            return Err(PasswordError::HashingFailed("Password cannot be empty".to_string()));
+        }
+
+        let salt = SaltString::generate(&mut OsRng);
+        let password_hash = self
+            .argon2(Algorithm::Argon2id, self.params.clone())?
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| PasswordError::HashingFailed(e.to_string()))?;
+
+        Ok(password_hash.to_string())
+    }
+
+    /// Verify a password against an Argon2 or bcrypt hash
+    pub fn verify(&self, password: &str, hash: &str) -> Result<PasswordVerification> {
+        if is_bcrypt_hash(hash) {
+            let matches = bcrypt::verify(password, hash)
+                .map_err(|e| PasswordError::VerificationFailed(e.to_string()))?;
+            return Ok(PasswordVerification { matches, needs_rehash: true });
+        }
+
+        let parsed_hash = PasswordHash::new(hash)
+            .map_err(|e| PasswordError::InvalidHashFormat(e.to_string()))?;
+        let algorithm = Algorithm::try_from(parsed_hash.algorithm)
+            .map_err(|e| PasswordError::InvalidHashFormat(e.to_string()))?;
+
+        // Parameters are read from the hash itself
+        let matches = verify_argon2(&self.argon2(algorithm, Params::default())?, password, &parsed_hash)?;
+        if !matches && self.pepper.is_some() && self.allow_unpeppered_legacy {
+            // Hashes created before the pepper was introduced
+            let unpeppered = Argon2::new(algorithm, Version::V0x13, Params::default());
+            if verify_argon2(&unpeppered, password, &parsed_hash)? {
+                return Ok(PasswordVerification { matches: true, needs_rehash: true });
+            }
+        }
+
+        Ok(PasswordVerification { matches, needs_rehash: self.needs_rehash(hash) })
+    }
+
+    /// Check if a hash uses an outdated algorithm or parameters
+    ///
+    /// Unparseable hashes are reported as needing a rehash.
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        if is_bcrypt_hash(hash) {
+            return true;
+        }
+        let Ok(parsed_hash) = PasswordHash::new(hash) else {
+            return true;
+        };
+
+        let params = Params::try_from(&parsed_hash);
+        parsed_hash.algorithm != Algorithm::Argon2id.ident()
+            || parsed_hash.version != Some(Version::V0x13.into())
+            || params.map_or(true, |params| {
+                params.m_cost() != self.params.m_cost()
+                    || params.t_cost() != self.params.t_cost()
+                    || params.p_cost() != self.params.p_cost()
+            })
+    }
+
+    fn argon2(&self, algorithm: Algorithm, params: Params) -> Result<Argon2<'_>> {
+        match &self.pepper {
+            Some(pepper) => Argon2::new_with_secret(pepper, algorithm, Version::V0x13, params)
+                .map_err(|e| PasswordError::HashingFailed(e.to_string())),
+            None => Ok(Argon2::new(algorithm, Version::V0x13, params)),
+        }
+    }
+}
+
+fn verify_argon2(argon2: &Argon2<'_>, password: &str, hash: &PasswordHash<'_>) -> Result<bool> {
+    match argon2.verify_password(password.as_bytes(), hash) {
+        Ok(()) => Ok(true),
+        Err(argon2::password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(PasswordError::VerificationFailed(e.to_string())),
+    }
+}
+
+/// Check if a hash is in bcrypt modular crypt format
+fn is_bcrypt_hash(hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2x$", "$2y$"].iter().any(|prefix| hash.starts_with(prefix))
+}
+
 /// Hash a password using Argon2
 ///
 /// This function generates a secure hash of the provided password
@@ -42,23 +232,7 @@ pub type Result<T> = StdResult<T, PasswordError>;
 /// assert!(!hash.is_empty());
 /// ```
 pub fn hash_password(password: &str) -> Result<String> {
-    // Validate password is not empty
-    if password.is_empty() {
-        return Err(PasswordError::HashingFailed("Password cannot be empty".to_string()));
-    }
-
-    // Generate a random salt
-    let salt = SaltString::generate(&mut OsRng);
-
-    // Create Argon2 hasher with default parameters
-    let argon2 = Argon2::default();
-
-    // Hash the password
-    let password_hash = argon2
-        .hash_password(password.as_bytes(), &salt)
-        .map_err(|e| PasswordError::HashingFailed(e.to_string()))?;
-
-    Ok(password_hash.to_string())
+    PasswordHasher::default().hash(password)
 }
 
 /// Verify a password against its hash
@@ -80,19 +254,7 @@ pub fn hash_password(password: &str) -> Result<String> {
 /// assert!(!verify_password("wrong-password", &hash)?);
 /// ```
 pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
-    // Parse the hash
-    let parsed_hash = PasswordHash::new(hash)
-        .map_err(|e| PasswordError::InvalidHashFormat(e.to_string()))?;
-
-    // Create Argon2 verifier with default parameters
-    let argon2 = Argon2::default();
-
-    // Verify the password
-    match argon2.verify_password(password.as_bytes(), &parsed_hash) {
-        Ok(()) => Ok(true),
-        Err(argon2::password_hash::Error::Password) => Ok(false),
-        Err(e) => Err(PasswordError::VerificationFailed(e.to_string())),
-    }
+    PasswordHasher::default().verify(password, hash).map(|verification| verification.matches)
 }
 
 #[cfg(test)]
@@ -132,4 +294,70 @@ mod tests {
         let result = verify_password("password", "invalid-hash");
         assert!(result.is_err());
     }
+
+    fn fast_config() -> PasswordHashConfig {
+        PasswordHashConfig { memory_kib: 1024, iterations: 1, parallelism: 1 }
+    }
+
+    #[test]
+    fn test_hasher_uses_configured_parameters() {
+        let hasher = PasswordHasher::new(fast_config()).unwrap();
+        let hash = hasher.hash("test-password-123").unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+
+        let verification = hasher.verify("test-password-123", &hash).unwrap();
+        assert_eq!(verification, PasswordVerification { matches: true, needs_rehash: false });
+        assert!(!hasher.verify("wrong-password", &hash).unwrap().matches);
+
+        let stronger = PasswordHasher::new(PasswordHashConfig { memory_kib: 2048, ..fast_config() }).unwrap();
+        assert!(stronger.verify("test-password-123", &hash).unwrap().needs_rehash);
+        assert!(PasswordHasher::new(PasswordHashConfig { memory_kib: 1, ..fast_config() }).is_err());
+    }
+
+    #[test]
+    fn test_pepper() {
+        let peppered = PasswordHasher::new(fast_config()).unwrap().with_pepper("server-side-pepper").unwrap();
+        let hash = peppered.hash("test-password-123").unwrap();
+
+        assert!(peppered.verify("test-password-123", &hash).unwrap().matches);
+        let unpeppered = PasswordHasher::new(fast_config()).unwrap();
+        assert!(!unpeppered.verify("test-password-123", &hash).unwrap().matches);
+        assert!(format!("{:?}", peppered).contains("<redacted>"));
+    }
+
+    #[test]
+    fn test_unpeppered_hashes_migrate_to_pepper() {
+        let hash = PasswordHasher::new(fast_config()).unwrap().hash("test-password-123").unwrap();
+        let strict = PasswordHasher::new(fast_config()).unwrap().with_pepper("server-side-pepper").unwrap();
+        assert!(!strict.verify("test-password-123", &hash).unwrap().matches);
+
+        let peppered = strict.allow_unpeppered_legacy(true);
+        let verification = peppered.verify("test-password-123", &hash).unwrap();
+        assert_eq!(verification, PasswordVerification { matches: true, needs_rehash: true });
+        assert!(!peppered.verify("wrong-password", &hash).unwrap().matches);
+
+        // After rehashing the peppered hash no longer needs an upgrade
+        let rehashed = peppered.hash("test-password-123").unwrap();
+        let verification = peppered.verify("test-password-123", &rehashed).unwrap();
+        assert_eq!(verification, PasswordVerification { matches: true, needs_rehash: false });
+    }
+
+    #[test]
+    fn test_legacy_hashes_need_rehash() {
+        let hasher = PasswordHasher::new(fast_config()).unwrap().with_pepper("pepper").unwrap();
+
+        let bcrypt_hash = bcrypt::hash("test-password-123", 4).unwrap();
+        let verification = hasher.verify("test-password-123", &bcrypt_hash).unwrap();
+        assert_eq!(verification, PasswordVerification { matches: true, needs_rehash: true });
+        assert!(!hasher.verify("wrong-password", &bcrypt_hash).unwrap().matches);
+
+        let salt = SaltString::generate(&mut OsRng);
+        let argon2i_hash = Argon2::new_with_secret(b"pepper", Algorithm::Argon2i, Version::V0x13, Params::new(1024, 1, 1, None).unwrap())
+            .unwrap()
+            .hash_password(b"test-password-123", &salt)
+            .unwrap()
+            .to_string();
+        let verification = hasher.verify("test-password-123", &argon2i_hash).unwrap();
+        assert_eq!(verification, PasswordVerification { matches: true, needs_rehash: true });
+    }
 }
@@ -4,7 +4,7 @@
 //!
 //! ## Features
 //!
-//! - Password hashing with Argon2
+//! - Password hashing with Argon2, configurable parameters and bcrypt migration
 //! - AES encryption/decryption
 //! - Secure random generation
 //! - Key derivation
@@ -26,6 +26,6 @@ pub mod encryption;
 pub mod random;
 
 // Re-export main functions
-pub use hashing::{hash_password, verify_password};
+pub use hashing::{hash_password, verify_password, PasswordHasher, PasswordHashConfig, PasswordVerification};
 pub use encryption::{encrypt_data, decrypt_data};
 pub use random::generate_secure_token;
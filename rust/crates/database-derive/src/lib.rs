@@ -0,0 +1,339 @@
+//! # CloudShuttle Database Derive
+//!
+//! `#[derive(Entity)]` maps a struct to a table so that
+//! `cloudshuttle_database::PgRepository` can implement the repository traits
+//! for it. Use it through the `derive` feature of `cloudshuttle-database`.
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! use cloudshuttle_database::{Entity, PgRepository, Repository};
+//!
+//! #[derive(Debug, Clone, sqlx::FromRow, Entity)]
+//! #[entity(table = "projects", tenant, soft_delete, version, audit)]
+//! pub struct Project {
+//!     pub id: Uuid,
+//!     pub tenant_id: Uuid,
+//!     pub name: String,
+//!     pub version: i32,
+//!     pub deleted_at: Option<DateTime<Utc>>,
+//!     pub created_at: DateTime<Utc>,
+//!     pub updated_at: DateTime<Utc>,
+//! }
+//!
+//! let projects = PgRepository::<Project>::new(pool);
+//! let project = projects.find_by_id(id).await?;
+//! ```
+//!
+//! ## Attributes
+//!
+//! | Attribute                  | Default column | Enables                 |
+//! |----------------------------|----------------|-------------------------|
+//! | `table = "..."`            | required       |                         |
+//! | `id = "..."`               | `id`           |                         |
+//! | `tenant` / `tenant = "..."`| `tenant_id`    | `TenantRepository`      |
+//! | `soft_delete` / `= "..."`  | `deleted_at`   | `SoftDeleteRepository`  |
+//! | `version` / `= "..."`      | `version`      | `VersionedRepository`   |
+//! | `audit` / `= "..."`        | `audit_log`    | `AuditableRepository`   |
+//!
+//! `created_at` and `updated_at` columns follow the `BaseEntity` field
+//! conventions and are maintained automatically when present. Column names
+//! honour `#[sqlx(rename = "...")]`, and `#[sqlx(skip)]` fields are not
+//! persisted. `String`, `Uuid`, `DateTime<Utc>`, `NaiveDateTime`,
+//! `NaiveDate` and `NaiveTime` fields (optionally wrapped in `Option`) are
+//! declared in `COLUMN_TYPES`, so string filter values for them bind as the
+//! column type instead of being detected; a `String` field keeps a
+//! UUID-looking value as text. The id field must be a `Uuid`.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, quote_spanned};
+use syn::meta::ParseNestedMeta;
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Fields, GenericArgument, Ident, LitStr, PathArguments, Result, Token,
+    Type,
+};
+
+/// Derive `Entity` and `EntityColumns` for a struct with named fields
+#[proc_macro_derive(Entity, attributes(entity))]
+pub fn derive_entity(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// Options from `#[entity(...)]`
+#[derive(Default)]
+struct EntityOptions {
+    table: Option<LitStr>,
+    id: Option<LitStr>,
+    tenant: Option<LitStr>,
+    soft_delete: Option<LitStr>,
+    version: Option<LitStr>,
+    audit: Option<LitStr>,
+}
+
+impl EntityOptions {
+    fn parse(input: &DeriveInput) -> Result<Self> {
+        let mut options = Self::default();
+        for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("entity")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("table") {
+                    options.table = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("id") {
+                    options.id = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("tenant") {
+                    options.tenant = Some(value_or(&meta, "tenant_id")?);
+                } else if meta.path.is_ident("soft_delete") {
+                    options.soft_delete = Some(value_or(&meta, "deleted_at")?);
+                } else if meta.path.is_ident("version") {
+                    options.version = Some(value_or(&meta, "version")?);
+                } else if meta.path.is_ident("audit") {
+                    options.audit = Some(value_or(&meta, "audit_log")?);
+                } else {
+                    return Err(meta.error("unknown entity attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+/// `key = "value"`, or the conventional default for a bare `key`
+fn value_or(meta: &ParseNestedMeta, default: &str) -> Result<LitStr> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse()
+    } else {
+        Ok(LitStr::new(default, meta.path.get_ident().map_or_else(Span::call_site, Ident::span)))
+    }
+}
+
+/// A persisted field
+struct Column {
+    field: Ident,
+    ty: Type,
+    name: String,
+    json: bool,
+    /// `#[sqlx(json(nullable))]`: `None` is stored as SQL `NULL`
+    nullable: bool,
+    param_type: Option<Ident>,
+}
+
+fn columns(input: &DeriveInput) -> Result<Vec<Column>> {
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("sqlx")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") {
+                return Err(meta.error("`rename_all` is not supported with #[derive(Entity)]; rename fields individually"));
+            }
+            skip_value(&meta)
+        })?;
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new_spanned(input, "#[derive(Entity)] requires named fields")),
+        },
+        _ => return Err(Error::new_spanned(input, "#[derive(Entity)] only supports structs")),
+    };
+
+    let mut columns = Vec::new();
+    for field in fields {
+        let ident = field.ident.clone().expect("named field");
+        let mut name = ident.to_string().trim_start_matches("r#").to_string();
+        let mut skip = false;
+        let mut json = false;
+        let mut nullable = false;
+
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("sqlx")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                } else if meta.path.is_ident("json") {
+                    json = true;
+                    if meta.input.peek(syn::token::Paren) {
+                        meta.parse_nested_meta(|nested| {
+                            if nested.path.is_ident("nullable") {
+                                nullable = true;
+                            }
+                            skip_value(&nested)
+                        })?;
+                    }
+                } else if meta.path.is_ident("flatten") || meta.path.is_ident("try_from") {
+                    return Err(meta.error("not supported with #[derive(Entity)]"));
+                } else {
+                    skip_value(&meta)?;
+                }
+                Ok(())
+            })?;
+        }
+
+        if !skip {
+            let param_type = (!json).then(|| param_type(&field.ty)).flatten();
+            columns.push(Column { field: ident, ty: field.ty.clone(), name, json, nullable, param_type });
+        }
+    }
+    Ok(columns)
+}
+
+/// `ParamType` variant for field types whose string values should not be detected
+fn param_type(ty: &Type) -> Option<Ident> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let variant = match segment.ident.to_string().as_str() {
+        "Option" => {
+            let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+                return None;
+            };
+            return match arguments.args.first()? {
+                GenericArgument::Type(inner) => param_type(inner),
+                _ => None,
+            };
+        }
+        "String" => "Text",
+        "Uuid" => "Uuid",
+        "DateTime" => "Timestamptz",
+        "NaiveDateTime" => "Timestamp",
+        "NaiveDate" => "Date",
+        "NaiveTime" => "Time",
+        _ => return None,
+    };
+    Some(Ident::new(variant, Span::call_site()))
+}
+
+/// Whether a field type is `Uuid`, by its last path segment
+fn is_uuid(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path.qself.is_none() && path.path.segments.last().is_some_and(|segment| {
+            segment.ident == "Uuid" && segment.arguments.is_empty()
+        }),
+        _ => false,
+    }
+}
+
+/// Consume an attribute argument we don't interpret, e.g. `default` or `rename = "..."`
+fn skip_value(meta: &ParseNestedMeta) -> Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|nested| skip_value(&nested))?;
+    }
+    Ok(())
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "#[derive(Entity)] does not support generic structs"));
+    }
+
+    let options = EntityOptions::parse(&input)?;
+    let table = options
+        .table
+        .clone()
+        .ok_or_else(|| Error::new_spanned(&input.ident, "missing #[entity(table = \"...\")]"))?;
+    let columns = columns(&input)?;
+
+    // Every configured column must be one of the struct's fields
+    let find = |name: &LitStr| {
+        columns
+            .iter()
+            .find(|column| column.name == name.value())
+            .ok_or_else(|| Error::new(name.span(), format!("no field maps to column `{}`", name.value())))
+    };
+    let id = options.id.clone().unwrap_or_else(|| LitStr::new("id", Span::call_site()));
+    let id_column = find(&id).map_err(|_| Error::new_spanned(&input.ident, format!("no field maps to id column `{}`", id.value())))?;
+    if !is_uuid(&id_column.ty) {
+        return Err(Error::new_spanned(&id_column.ty, "the id field must be a `Uuid`"));
+    }
+    let id_field = &id_column.field;
+    let id_type = &id_column.ty;
+    for column in [&options.tenant, &options.soft_delete, &options.version].into_iter().flatten() {
+        find(column)?;
+    }
+
+    // Spanned on the field, so a type that only looks like `Uuid` is reported there
+    let id_value = quote_spanned!(id_type.span()=> self.#id_field);
+
+    let name = &input.ident;
+    let column_names = columns.iter().map(|column| &column.name);
+    let column_types = columns.iter().filter_map(|column| {
+        let name = &column.name;
+        column
+            .param_type
+            .as_ref()
+            .map(|variant| quote!((#name, ::cloudshuttle_database::ParamType::#variant)))
+    });
+    let optional = |value: &Option<LitStr>| match value {
+        Some(value) => quote!(::core::option::Option::Some(#value)),
+        None => quote!(::core::option::Option::None),
+    };
+    let conventional = |field: &str| match columns.iter().any(|column| column.name == field) {
+        true => quote!(::core::option::Option::Some(#field)),
+        false => quote!(::core::option::Option::None),
+    };
+    let tenant = optional(&options.tenant);
+    let soft_delete = optional(&options.soft_delete);
+    let version = optional(&options.version);
+    let audit = optional(&options.audit);
+    let created_at = conventional("created_at");
+    let updated_at = conventional("updated_at");
+
+    let binds = columns.iter().map(|column| {
+        let field = &column.field;
+        let name = &column.name;
+        if column.json && column.nullable {
+            quote!(::cloudshuttle_database::__private::bind_column(arguments, #name, self.#field.as_ref().map(::cloudshuttle_database::__private::sqlx::types::Json))?;)
+        } else if column.json {
+            quote!(::cloudshuttle_database::__private::bind_column(arguments, #name, ::cloudshuttle_database::__private::sqlx::types::Json(&self.#field))?;)
+        } else {
+            quote!(::cloudshuttle_database::__private::bind_column(arguments, #name, &self.#field)?;)
+        }
+    });
+
+    let marker = |enabled: bool, path: TokenStream2| {
+        enabled.then(|| quote!(impl #path for #name {}))
+    };
+    let markers = [
+        marker(options.soft_delete.is_some(), quote!(::cloudshuttle_database::SoftDeletable)),
+        marker(options.version.is_some(), quote!(::cloudshuttle_database::Versioned)),
+        marker(options.tenant.is_some(), quote!(::cloudshuttle_database::TenantScoped)),
+        marker(options.audit.is_some(), quote!(::cloudshuttle_database::Audited)),
+    ];
+
+    Ok(quote! {
+        impl ::cloudshuttle_database::EntityColumns for #name {
+            const TABLE: &'static str = #table;
+            const COLUMNS: &'static [&'static str] = &[#(#column_names),*];
+            const COLUMN_TYPES: &'static [(&'static str, ::cloudshuttle_database::ParamType)] = &[#(#column_types),*];
+        }
+
+        impl ::cloudshuttle_database::Entity for #name {
+            const ID_COLUMN: &'static str = #id;
+            const TENANT_COLUMN: ::core::option::Option<&'static str> = #tenant;
+            const SOFT_DELETE_COLUMN: ::core::option::Option<&'static str> = #soft_delete;
+            const VERSION_COLUMN: ::core::option::Option<&'static str> = #version;
+            const CREATED_AT_COLUMN: ::core::option::Option<&'static str> = #created_at;
+            const UPDATED_AT_COLUMN: ::core::option::Option<&'static str> = #updated_at;
+            const AUDIT_TABLE: ::core::option::Option<&'static str> = #audit;
+
+            fn id(&self) -> ::cloudshuttle_database::__private::uuid::Uuid {
+                #id_value
+            }
+
+            fn bind_columns(
+                &self,
+                arguments: &mut ::cloudshuttle_database::__private::sqlx::postgres::PgArguments,
+            ) -> ::cloudshuttle_database::__private::DatabaseResult<()> {
+                #(#binds)*
+                ::core::result::Result::Ok(())
+            }
+        }
+
+        #(#markers)*
+    })
+}
@@ -3,7 +3,8 @@
 use sqlx::{PgPool, postgres::PgPoolOptions};
 use std::time::Duration;
 use cloudshuttle_error_handling::database_error::DatabaseResult;
-use crate::DatabaseTransaction;
+use crate::{BatchExecutor, DatabaseTransaction, QueryExecutor};
+use crate::query::Ident;
 
 /// Database connection manager
 pub struct DatabaseConnection {
@@ -67,6 +68,16 @@ impl DatabaseConnection {
         &self.pool
     }
 
+    /// Create an executor for queries with JSON parameters
+    pub fn executor(&self) -> QueryExecutor {
+        QueryExecutor::new(self.pool.clone())
+    }
+
+    /// Create an executor for batch operations and bulk inserts
+    pub fn batch_executor(&self) -> BatchExecutor {
+        BatchExecutor::new(self.pool.clone())
+    }
+
     /// Execute a transaction with automatic rollback on error
     pub async fn transaction<F, Fut, T>(&self, f: F) -> DatabaseResult<T>
     where
@@ -168,18 +179,20 @@ pub struct PoolMetrics {
 }
 
 /// Query helper trait for common database operations
+///
+/// Table and column names are [`Ident`]s; values are always bound.
 pub trait QueryHelper {
-    async fn find_by_id<T>(&self, table: &str, id: &str) -> DatabaseResult<Option<T>>
+    async fn find_by_id<T>(&self, table: &Ident, id: &str) -> DatabaseResult<Option<T>>
     where
         T: for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> + Send + Unpin;
 
-    async fn exists(&self, table: &str, column: &str, value: &str) -> DatabaseResult<bool>;
+    async fn exists(&self, table: &Ident, column: &Ident, value: &str) -> DatabaseResult<bool>;
 
-    async fn count(&self, table: &str) -> DatabaseResult<i64>;
+    async fn count(&self, table: &Ident) -> DatabaseResult<i64>;
 }
 
 impl QueryHelper for DatabaseConnection {
-    async fn find_by_id<T>(&self, table: &str, id: &str) -> DatabaseResult<Option<T>>
+    async fn find_by_id<T>(&self, table: &Ident, id: &str) -> DatabaseResult<Option<T>>
     where
         T: for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> + Send + Unpin,
     {
@@ -192,7 +205,7 @@ impl QueryHelper for DatabaseConnection {
         Ok(result)
     }
 
-    async fn exists(&self, table: &str, column: &str, value: &str) -> DatabaseResult<bool> {
+    async fn exists(&self, table: &Ident, column: &Ident, value: &str) -> DatabaseResult<bool> {
         let query = format!("SELECT EXISTS(SELECT 1 FROM {} WHERE {} = $1)", table, column);
         let result: (bool,) = sqlx::query_as(&query)
             .bind(value)
@@ -202,7 +215,7 @@ impl QueryHelper for DatabaseConnection {
         Ok(result.0)
     }
 
-    async fn count(&self, table: &str) -> DatabaseResult<i64> {
+    async fn count(&self, table: &Ident) -> DatabaseResult<i64> {
         let query = format!("SELECT COUNT(*) FROM {}", table);
         let result: (i64,) = sqlx::query_as(&query)
             .fetch_one(&self.pool)
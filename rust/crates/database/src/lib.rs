@@ -6,6 +6,9 @@
 //!
 //! - Database connection management and pooling
 //! - Query builder utilities and helpers
+//! - Query execution with JSON parameters
+//! - Keyset (cursor) pagination with signed cursor tokens
+//! - Generic repositories for entities, with `#[derive(Entity)]` behind the `derive` feature
 //! - Transaction management
 //! - Migration support
 //! - Health monitoring
@@ -13,13 +16,14 @@
 //! ## Example
 //!
 //! ```rust
-//! use cloudshuttle_database::{DatabaseConnection, QueryHelper};
+//! use cloudshuttle_database::{DatabaseConnection, Ident, QueryHelper};
 //!
 //! // Create database connection
 //! let db = DatabaseConnection::new("postgresql://...").await?;
 //!
 //! // Use query helpers
-//! let user = db.find_by_id::<User>("users", user_id).await?;
+//! let users = Ident::new("users")?;
+//! let user = db.find_by_id::<User>(&users, user_id).await?;
 //!
 //! // Use transactions
 //! let result = db.transaction(|tx| async move {
@@ -35,6 +39,12 @@ pub mod query;
 pub mod transaction;
 pub mod pool;
 pub mod types;
+pub mod repository;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+
+// Lets `#[derive(Entity)]` output refer to this crate by name in its own tests
+extern crate self as cloudshuttle_database;
 
 // Re-export main types
 pub use connection::{DatabaseConnection, DatabaseConfig};
@@ -42,6 +52,11 @@ pub use migrations::MigrationRunner;
 pub use connection::QueryHelper;
 pub use transaction::{DatabaseTransaction, TransactionResult};
 pub use pool::{ConnectionPool, PoolConfig};
+pub use query::{QueryExecutor, BatchExecutor, JsonRow, ParamType, Ident, ColumnAllowlist, EntityColumns, Expr};
+pub use query::{CursorCodec, CursorPagination, CursorPaginatedResult};
+pub use repository::PgRepository;
+#[cfg(feature = "derive")]
+pub use cloudshuttle_database_derive::Entity;
 
 // Re-export advanced pool types
 pub use pool::advanced::{AdvancedPgPool, AdvancedPoolConfig, PoolMetrics, PoolManager, HealthCheckConfig};
@@ -53,6 +68,16 @@ pub use migrations::advanced::{
 };
 
 // Re-export commonly used types from modular structure
-pub use types::entities::{BaseEntity, SoftDeleteEntity};
+pub use types::entities::{BaseEntity, SoftDeleteEntity, Entity, SoftDeletable, Versioned, TenantScoped, Audited};
 pub use types::models::{DatabaseHealth, HealthStatus, QueryCriteria, Pagination};
 pub use types::traits::{Repository, QueryRepository, TransactionalRepository};
+pub use types::traits::{BatchRepository, SoftDeleteRepository, VersionedRepository, TenantRepository, AuditableRepository};
+
+/// Items used by `#[derive(Entity)]` output; not part of the public API
+#[doc(hidden)]
+pub mod __private {
+    pub use sqlx;
+    pub use uuid;
+    pub use cloudshuttle_error_handling::database_error::DatabaseResult;
+    pub use crate::repository::bind_column;
+}
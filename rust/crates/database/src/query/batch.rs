@@ -1,6 +1,8 @@
 //! Batch query operations and execution
 
 use serde::{Deserialize, Serialize};
+use super::expr::Expr;
+use super::ident::Ident;
 
 /// Batch operation support
 #[derive(Debug, Clone)]
@@ -169,7 +171,7 @@ pub struct BatchOptions {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BatchTransactionMode {
-    /// Each operation in its own transaction
+    /// Each operation under its own savepoint, so failures roll back individually
     PerOperation,
     /// All operations in a single transaction
     AllInOne,
@@ -204,15 +206,15 @@ impl BatchBuilder {
     }
 
     /// Add an INSERT operation
-    pub fn insert<S: Into<String>>(mut self, table: S, values: serde_json::Map<String, serde_json::Value>) -> Self {
-        let columns: Vec<String> = values.keys().cloned().collect();
-        let params: Vec<serde_json::Value> = values.values().cloned().collect();
+    pub fn insert(mut self, table: Ident, values: Vec<(Ident, serde_json::Value)>) -> Self {
+        let columns: Vec<String> = values.iter().map(|(column, _)| column.to_sql()).collect();
+        let params: Vec<serde_json::Value> = values.into_iter().map(|(_, value)| value).collect();
 
         let placeholders: Vec<String> = (1..=params.len()).map(|i| format!("${}", i)).collect();
 
         let query = format!(
             "INSERT INTO {} ({}) VALUES ({})",
-            table.into(),
+            table,
             columns.join(", "),
             placeholders.join(", ")
         );
@@ -222,20 +224,21 @@ impl BatchBuilder {
     }
 
     /// Add an UPDATE operation
-    pub fn update<S: Into<String>>(mut self, table: S, values: serde_json::Map<String, serde_json::Value>, where_clause: S, where_params: Vec<serde_json::Value>) -> Self {
-        let set_clause: Vec<String> = values.keys()
-            .enumerate()
-            .map(|(i, col)| format!("{} = ${}", col, i + 1))
+    pub fn update(mut self, table: Ident, values: Vec<(Ident, serde_json::Value)>, filter: Expr) -> Self {
+        let mut params = Vec::with_capacity(values.len());
+        let set_clause: Vec<String> = values
+            .into_iter()
+            .map(|(column, value)| {
+                params.push(value);
+                format!("{} = ${}", column, params.len())
+            })
             .collect();
 
-        let mut params = values.values().cloned().collect::<Vec<_>>();
-        params.extend(where_params);
-
         let query = format!(
             "UPDATE {} SET {} WHERE {}",
-            table.into(),
+            table,
             set_clause.join(", "),
-            where_clause.into()
+            filter.to_sql(&mut params)
         );
 
         self.operations.push(BatchItem { query, params });
@@ -243,10 +246,11 @@ impl BatchBuilder {
     }
 
     /// Add a DELETE operation
-    pub fn delete<S: Into<String>>(mut self, table: S, where_clause: S, where_params: Vec<serde_json::Value>) -> Self {
-        let query = format!("DELETE FROM {} WHERE {}", table.into(), where_clause.into());
+    pub fn delete(mut self, table: Ident, filter: Expr) -> Self {
+        let mut params = Vec::new();
+        let query = format!("DELETE FROM {} WHERE {}", table, filter.to_sql(&mut params));
 
-        self.operations.push(BatchItem { query, params: where_params });
+        self.operations.push(BatchItem { query, params });
         self
     }
 
@@ -329,15 +333,15 @@ mod tests {
 
     #[test]
     fn test_batch_builder_fluent_api() {
+        let id = |name: &str| Ident::new(name).unwrap();
         let (batch, options) = BatchBuilder::new()
-            .insert("users", serde_json::json!({"name": "Alice", "email": "alice@example.com"}).as_object().unwrap().clone())
+            .insert(id("users"), vec![(id("email"), serde_json::json!("alice@example.com")), (id("name"), serde_json::json!("Alice"))])
             .update(
-                "users",
-                serde_json::json!({"name": "Bob"}).as_object().unwrap().clone(),
-                "id = $2",
-                vec![serde_json::json!(1)]
+                id("users"),
+                vec![(id("name"), serde_json::json!("Bob"))],
+                Expr::eq(id("id"), 1),
             )
-            .delete("users", "id = $1", vec![serde_json::json!(2)])
+            .delete(id("users"), Expr::eq(id("id"), 2))
             .continue_on_error(true)
             .build();
 
@@ -346,17 +350,18 @@ mod tests {
 
         // Check INSERT query
         let insert_op = &batch.operations()[0];
-        assert!(insert_op.query.contains("INSERT INTO users"));
-        assert!(insert_op.query.contains("(email, name)"));
+        assert!(insert_op.query.contains("INSERT INTO \"users\""));
+        assert!(insert_op.query.contains("(\"email\", \"name\")"));
         assert!(insert_op.query.contains("VALUES ($1, $2)"));
 
         // Check UPDATE query
         let update_op = &batch.operations()[1];
-        assert!(update_op.query.contains("UPDATE users SET name = $1 WHERE id = $2"));
+        assert!(update_op.query.contains("UPDATE \"users\" SET \"name\" = $1 WHERE \"id\" = $2"));
+        assert_eq!(update_op.params, vec![serde_json::json!("Bob"), serde_json::json!(1)]);
 
         // Check DELETE query
         let delete_op = &batch.operations()[2];
-        assert!(delete_op.query.contains("DELETE FROM users WHERE id = $1"));
+        assert!(delete_op.query.contains("DELETE FROM \"users\" WHERE \"id\" = $1"));
     }
 
     #[test]
@@ -0,0 +1,640 @@
+//! Batch execution
+//!
+//! Runs a [`BatchOperation`] against a pool according to its [`BatchOptions`]:
+//!
+//! - [`BatchTransactionMode::AllInOne`] runs every operation in one transaction
+//!   and rolls all of them back if any fails. With `continue_on_error` the
+//!   remaining operations still run (each under a savepoint) so that every
+//!   failure is reported before the rollback.
+//! - [`BatchTransactionMode::PerOperation`] runs every operation under its own
+//!   savepoint in one transaction. A failed operation is rolled back on its
+//!   own and the successful ones are committed.
+//! - [`BatchTransactionMode::None`] runs operations in auto-commit mode, up to
+//!   `max_parallel_operations` at a time.
+//!
+//! Large inserts go through [`BatchExecutor::insert_rows`], which chunks rows
+//! into multi-row `INSERT` statements or streams them with `COPY`.
+
+use std::time::{Duration, Instant};
+use cloudshuttle_error_handling::database_error::{DatabaseError, DatabaseResult};
+use serde_json::Value;
+use sqlx::{Executor, PgConnection, PgPool, Postgres};
+
+use super::batch::{BatchItem, BatchOperation, BatchOptions, BatchResult, BatchTransactionMode};
+use super::executor::{self, JsonRow, ParamType};
+use super::ident::Ident;
+
+/// Postgres limit on bind parameters per statement
+const MAX_BIND_PARAMS: usize = u16::MAX as usize;
+
+/// Size of the buffer sent per `COPY` message
+const COPY_BUFFER_SIZE: usize = 1 << 20;
+
+const SAVEPOINT: &str = "batch_item";
+
+/// Executes batch operations against a connection pool
+#[derive(Debug, Clone)]
+pub struct BatchExecutor {
+    pool: PgPool,
+    insert_chunk_size: usize,
+    copy_threshold: usize,
+}
+
+impl BatchExecutor {
+    /// Create a batch executor for a pool
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            insert_chunk_size: 1000,
+            copy_threshold: 10_000,
+        }
+    }
+
+    /// Set the maximum number of rows per multi-row `INSERT`
+    pub fn with_insert_chunk_size(mut self, rows: usize) -> Self {
+        self.insert_chunk_size = rows.max(1);
+        self
+    }
+
+    /// Set the row count from which [`insert_rows`](Self::insert_rows) uses `COPY`
+    pub fn with_copy_threshold(mut self, rows: usize) -> Self {
+        self.copy_threshold = rows;
+        self
+    }
+
+    /// Execute a batch
+    ///
+    /// Failed operations are recorded in the result; an error is returned only
+    /// if the batch itself cannot run (e.g. the transaction cannot be started
+    /// or committed). Operations not run because of an earlier failure are
+    /// recorded as skipped failures.
+    pub async fn execute(&self, batch: &BatchOperation, options: &BatchOptions) -> DatabaseResult<BatchResult> {
+        let start = Instant::now();
+        let mut result = BatchResult::new(batch.len(), Duration::ZERO);
+
+        match options.transaction_mode {
+            BatchTransactionMode::None => self.execute_autocommit(batch, options, &mut result).await,
+            BatchTransactionMode::PerOperation => self.execute_in_transaction(batch, options, false, &mut result).await?,
+            BatchTransactionMode::AllInOne => self.execute_in_transaction(batch, options, true, &mut result).await?,
+        }
+
+        result.results.sort_by_key(|item| item.index);
+        result.execution_time = start.elapsed();
+        tracing::debug!(
+            "Batch finished: {}/{} operations succeeded in {:?}",
+            result.successful_operations,
+            result.total_operations,
+            result.execution_time
+        );
+        Ok(result)
+    }
+
+    async fn execute_autocommit(&self, batch: &BatchOperation, options: &BatchOptions, result: &mut BatchResult) {
+        let parallel = options.max_parallel_operations.unwrap_or(1).max(1);
+        let items: Vec<_> = batch.operations().iter().enumerate().collect();
+
+        for wave in items.chunks(parallel) {
+            let outcomes = futures::future::join_all(
+                wave.iter().map(|(index, item)| async move {
+                    (*index, run_item(&self.pool, item, options.timeout_per_operation).await)
+                }),
+            )
+            .await;
+
+            let mut first_failure = None;
+            for (index, (outcome, elapsed)) in outcomes {
+                match outcome {
+                    Ok(rows) => result.add_success(index, Some(rows), elapsed),
+                    Err(message) => {
+                        result.add_failure(index, message, elapsed);
+                        first_failure.get_or_insert(index);
+                    }
+                }
+            }
+
+            if let Some(failed) = first_failure {
+                if !options.continue_on_error {
+                    let next = wave.last().map_or(0, |(index, _)| index + 1);
+                    skip_remaining(result, next, failed);
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn execute_in_transaction(
+        &self,
+        batch: &BatchOperation,
+        options: &BatchOptions,
+        atomic: bool,
+        result: &mut BatchResult,
+    ) -> DatabaseResult<()> {
+        let mut tx = self.pool.begin().await?;
+        if let Some(timeout) = options.timeout_per_operation {
+            // Enforced by the server so that a timed-out statement can be rolled back
+            let sql = format!("SET LOCAL statement_timeout = {}", timeout.as_millis().max(1));
+            executor::execute(&mut *tx, &sql, &[]).await?;
+        }
+
+        let use_savepoints = !atomic || options.continue_on_error;
+        let mut first_failure = None;
+
+        for (index, item) in batch.operations().iter().enumerate() {
+            if use_savepoints {
+                executor::execute(&mut *tx, &format!("SAVEPOINT {}", SAVEPOINT), &[]).await?;
+            }
+
+            let (outcome, elapsed) = run_item(&mut *tx, item, None).await;
+            match outcome {
+                Ok(rows) => {
+                    if use_savepoints {
+                        executor::execute(&mut *tx, &format!("RELEASE SAVEPOINT {}", SAVEPOINT), &[]).await?;
+                    }
+                    result.add_success(index, Some(rows), elapsed);
+                }
+                Err(message) => {
+                    if use_savepoints {
+                        executor::execute(&mut *tx, &format!("ROLLBACK TO SAVEPOINT {}", SAVEPOINT), &[]).await?;
+                    }
+                    result.add_failure(index, message, elapsed);
+                    first_failure.get_or_insert(index);
+
+                    if !options.continue_on_error {
+                        skip_remaining(result, index + 1, index);
+                        break;
+                    }
+                }
+            }
+        }
+
+        match first_failure {
+            Some(failed) if atomic => {
+                tx.rollback().await?;
+                roll_back_successes(result, failed);
+            }
+            _ => tx.commit().await?,
+        }
+        Ok(())
+    }
+
+    /// Insert rows into a table in one transaction, returning the number of rows inserted
+    ///
+    /// Every row must have the same columns. Batches of at least the copy
+    /// threshold are streamed with `COPY ... FROM STDIN`; smaller ones are
+    /// chunked into multi-row `INSERT` statements whose parameters are cast
+    /// to the declared column types, so plain strings can fill `UUID` or
+    /// timestamp columns. Values for `JSON` and `JSONB` columns are stored as
+    /// JSON whatever their shape, so arrays, numbers and strings go in as-is.
+    pub async fn insert_rows(&self, table: &Ident, rows: &[JsonRow]) -> DatabaseResult<u64> {
+        let Some(first) = rows.first() else {
+            return Ok(0);
+        };
+        let columns: Vec<&str> = first.keys().map(String::as_str).collect();
+        if columns.is_empty() {
+            return Err(DatabaseError::query("Cannot insert rows without columns"));
+        }
+        if let Some(index) = rows
+            .iter()
+            .position(|row| row.len() != columns.len() || !columns.iter().all(|column| row.contains_key(*column)))
+        {
+            return Err(DatabaseError::query(format!(
+                "Row {} does not have the same columns as the first row",
+                index
+            )));
+        }
+        let column_list = columns
+            .iter()
+            .map(|column| Ident::new(column).map(|ident| ident.to_sql()))
+            .collect::<DatabaseResult<Vec<_>>>()?
+            .join(", ");
+
+        let mut tx = self.pool.begin().await?;
+        let types = column_types(&mut tx, table, &columns).await?;
+        let inserted = if rows.len() >= self.copy_threshold {
+            copy_rows(&mut tx, table, &columns, &column_list, &types, rows).await?
+        } else {
+            self.insert_chunks(&mut tx, table, &columns, &column_list, &types, rows).await?
+        };
+        tx.commit().await?;
+
+        tracing::debug!("Inserted {} rows into {}", inserted, table);
+        Ok(inserted)
+    }
+
+    async fn insert_chunks(
+        &self,
+        conn: &mut PgConnection,
+        table: &Ident,
+        columns: &[&str],
+        column_list: &str,
+        types: &[ColumnType],
+        rows: &[JsonRow],
+    ) -> DatabaseResult<u64> {
+        let chunk_size = self.insert_chunk_size.min(MAX_BIND_PARAMS / columns.len()).max(1);
+        let mut inserted = 0;
+
+        for chunk in rows.chunks(chunk_size) {
+            let (sql, params) = multi_row_insert(table, columns, column_list, types, chunk);
+            inserted += executor::execute(&mut *conn, &sql, &params).await?;
+        }
+        Ok(inserted)
+    }
+}
+
+/// Run one batch item, returning its outcome and duration
+async fn run_item<'c, E>(executor: E, item: &BatchItem, timeout: Option<Duration>) -> (Result<u64, String>, Duration)
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let start = Instant::now();
+    let execution = executor::execute(executor, &item.query, &item.params);
+    let outcome = match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, execution).await {
+            Ok(outcome) => outcome.map_err(|e| e.to_string()),
+            Err(_) => Err(format!("Operation timed out after {:?}", timeout)),
+        },
+        None => execution.await.map_err(|e| e.to_string()),
+    };
+    (outcome, start.elapsed())
+}
+
+/// Record operations from `next` onwards as skipped
+fn skip_remaining(result: &mut BatchResult, next: usize, failed: usize) {
+    for index in next..result.total_operations {
+        result.add_failure(index, format!("Skipped after operation {} failed", failed), Duration::ZERO);
+    }
+}
+
+/// Mark successful operations as failed once the transaction is rolled back
+fn roll_back_successes(result: &mut BatchResult, failed: usize) {
+    for item in result.results.iter_mut().filter(|item| item.success) {
+        item.success = false;
+        item.rows_affected = None;
+        item.error_message = Some(format!("Rolled back after operation {} failed", failed));
+    }
+    result.failed_operations += result.successful_operations;
+    result.successful_operations = 0;
+}
+
+fn multi_row_insert(
+    table: &Ident,
+    columns: &[&str],
+    column_list: &str,
+    types: &[ColumnType],
+    rows: &[JsonRow],
+) -> (String, Vec<Value>) {
+    let mut params = Vec::with_capacity(rows.len() * columns.len());
+    let mut values = Vec::with_capacity(rows.len());
+
+    for row in rows {
+        let placeholders: Vec<String> = columns
+            .iter()
+            .zip(types)
+            .map(|(column, column_type)| {
+                let value = &row[*column];
+                if column_type.is_json() {
+                    // A JSONB parameter already has the column's type; NULL stays SQL NULL
+                    params.push(if value.is_null() { Value::Null } else { ParamType::Jsonb.tag(value.clone()) });
+                    return format!("${}", params.len());
+                }
+                params.push(match value {
+                    // Bound as TEXT so the cast parses it, whatever it looks like
+                    Value::String(s) => ParamType::Text.tag(s.as_str()),
+                    other => other.clone(),
+                });
+                format!("${}::{}", params.len(), column_type.name)
+            })
+            .collect();
+        values.push(format!("({})", placeholders.join(", ")));
+    }
+
+    let sql = format!("INSERT INTO {} ({}) VALUES {}", table, column_list, values.join(", "));
+    (sql, params)
+}
+
+async fn copy_rows(
+    conn: &mut PgConnection,
+    table: &Ident,
+    columns: &[&str],
+    column_list: &str,
+    types: &[ColumnType],
+    rows: &[JsonRow],
+) -> DatabaseResult<u64> {
+    let statement = format!("COPY {} ({}) FROM STDIN WITH (FORMAT csv)", table, column_list);
+    let mut copy = conn.copy_in_raw(&statement).await?;
+
+    let mut buffer = Vec::with_capacity(COPY_BUFFER_SIZE);
+    for row in rows {
+        for (position, (column, column_type)) in columns.iter().zip(types).enumerate() {
+            if position > 0 {
+                buffer.push(b',');
+            }
+            write_csv_field(&mut buffer, &row[*column], column_type);
+        }
+        buffer.push(b'\n');
+
+        if buffer.len() >= COPY_BUFFER_SIZE {
+            copy.send(std::mem::take(&mut buffer)).await?;
+        }
+    }
+    if !buffer.is_empty() {
+        copy.send(buffer).await?;
+    }
+
+    Ok(copy.finish().await?)
+}
+
+/// Declared type of a table column
+struct ColumnType {
+    /// Type as rendered by `format_type`, e.g. `uuid` or `character varying(20)`
+    name: String,
+    is_array: bool,
+}
+
+impl ColumnType {
+    fn is_json(&self) -> bool {
+        self.name == "json" || self.name == "jsonb"
+    }
+}
+
+/// Look up the declared types of the given columns, in order
+async fn column_types(conn: &mut PgConnection, table: &Ident, columns: &[&str]) -> DatabaseResult<Vec<ColumnType>> {
+    let rows: Vec<(String, String, bool)> = executor::fetch_all_as(
+        conn,
+        "SELECT a.attname::text, format_type(a.atttypid, a.atttypmod), t.typcategory = 'A' \
+         FROM pg_attribute a JOIN pg_type t ON t.oid = a.atttypid \
+         WHERE a.attrelid = $1::regclass AND a.attnum > 0 AND NOT a.attisdropped",
+        &[Value::String(table.to_sql())],
+    )
+    .await?;
+
+    columns
+        .iter()
+        .map(|column| {
+            rows.iter()
+                .find(|(name, ..)| name == column)
+                .map(|(_, name, is_array)| ColumnType { name: name.clone(), is_array: *is_array })
+                .ok_or_else(|| DatabaseError::query(format!("Column {} does not exist in {}", column, table)))
+        })
+        .collect()
+}
+
+/// Write a JSON value as a CSV field for `COPY`
+///
+/// `null` is written as an unquoted empty field, which `COPY` reads as NULL.
+/// Values for `JSON` and `JSONB` columns are written as JSON text.
+fn write_csv_field(buffer: &mut Vec<u8>, value: &Value, column_type: &ColumnType) {
+    let text = match value {
+        Value::Null => return,
+        _ if column_type.is_json() => value.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) if column_type.is_array => array_literal(items),
+        other => other.to_string(),
+    };
+    buffer.push(b'"');
+    buffer.extend_from_slice(text.replace('"', "\"\"").as_bytes());
+    buffer.push(b'"');
+}
+
+/// Format a JSON array as a Postgres array literal
+fn array_literal(items: &[Value]) -> String {
+    let elements: Vec<String> = items
+        .iter()
+        .map(|item| match item {
+            Value::Null => "NULL".to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::Array(nested) => array_literal(nested),
+            Value::String(s) => quote_array_element(s),
+            Value::Object(_) => quote_array_element(&item.to_string()),
+        })
+        .collect();
+    format!("{{{}}}", elements.join(","))
+}
+
+fn quote_array_element(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::batch::BatchBuilder;
+    use crate::test_util;
+    use serde_json::json;
+
+    async fn create_table(pool: &PgPool) -> String {
+        let table = format!("batch_test_{}", uuid::Uuid::new_v4().simple());
+        let sql = format!("CREATE TABLE {} (id INT4 PRIMARY KEY, name TEXT NOT NULL, tags TEXT[], meta JSONB, owner UUID)", table);
+        executor::execute(pool, &sql, &[]).await.unwrap();
+        table
+    }
+
+    async fn count(pool: &PgPool, table: &str) -> i64 {
+        let (count,): (i64,) = executor::fetch_one_as(pool, &format!("SELECT COUNT(*) FROM {}", table), &[]).await.unwrap();
+        count
+    }
+
+    fn batch(table: &str) -> BatchOperation {
+        BatchBuilder::new()
+            .raw(format!("INSERT INTO {} (id, name) VALUES ($1, $2)", table), vec![json!(1), json!("a")])
+            .raw(format!("INSERT INTO {} (id, name) VALUES ($1, $2)", table), vec![json!(1), json!("duplicate")])
+            .raw(format!("INSERT INTO {} (id, name) VALUES ($1, $2)", table), vec![json!(2), json!("b")])
+            .build()
+            .0
+    }
+
+    fn options(mode: BatchTransactionMode, continue_on_error: bool) -> BatchOptions {
+        BatchOptions {
+            continue_on_error,
+            transaction_mode: mode,
+            ..BatchOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_multi_row_insert() {
+        let rows = vec![
+            json!({"id": 1, "name": "a", "meta": ["x"]}).as_object().unwrap().clone(),
+            json!({"id": 2, "name": "b", "meta": null}).as_object().unwrap().clone(),
+        ];
+        let table = Ident::new("users").unwrap();
+        let types = ["int4", "text", "jsonb"].map(|name| ColumnType { name: name.to_string(), is_array: false });
+        let (sql, params) =
+            multi_row_insert(&table, &["id", "name", "meta"], "\"id\", \"name\", \"meta\"", &types, &rows);
+        assert_eq!(
+            sql,
+            "INSERT INTO \"users\" (\"id\", \"name\", \"meta\") VALUES ($1::int4, $2::text, $3), ($4::int4, $5::text, $6)"
+        );
+        assert_eq!(
+            params,
+            vec![
+                json!(1),
+                ParamType::Text.tag("a"),
+                ParamType::Jsonb.tag(json!(["x"])),
+                json!(2),
+                ParamType::Text.tag("b"),
+                Value::Null,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_csv_fields() {
+        let text = ColumnType { name: "text".to_string(), is_array: false };
+        let jsonb = ColumnType { name: "jsonb".to_string(), is_array: false };
+        let mut buffer = Vec::new();
+        write_csv_field(&mut buffer, &Value::Null, &text);
+        assert!(buffer.is_empty());
+
+        write_csv_field(&mut buffer, &json!("say \"hi\""), &text);
+        assert_eq!(buffer, b"\"say \"\"hi\"\"\"");
+
+        buffer.clear();
+        write_csv_field(&mut buffer, &json!("hi"), &jsonb);
+        assert_eq!(buffer, b"\"\"\"hi\"\"\"");
+
+        assert_eq!(array_literal(&[json!("a\"b"), Value::Null, json!(1)]), r#"{"a\"b",NULL,1}"#);
+    }
+
+    #[test]
+    fn test_result_bookkeeping() {
+        let mut result = BatchResult::new(3, Duration::ZERO);
+        result.add_success(0, Some(1), Duration::ZERO);
+        result.add_failure(1, "boom".to_string(), Duration::ZERO);
+        skip_remaining(&mut result, 2, 1);
+        roll_back_successes(&mut result, 1);
+
+        assert_eq!(result.successful_operations, 0);
+        assert_eq!(result.failed_operations, 3);
+        assert_eq!(result.results[0].error_message.as_deref(), Some("Rolled back after operation 1 failed"));
+        assert_eq!(result.results[2].error_message.as_deref(), Some("Skipped after operation 1 failed"));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_all_in_one_rolls_back() {
+        let pool = test_util::connect_pool().await;
+        let table = create_table(&pool).await;
+        let executor = BatchExecutor::new(pool.clone());
+
+        let result = executor.execute(&batch(&table), &options(BatchTransactionMode::AllInOne, false)).await.unwrap();
+        assert_eq!(result.failed_operations, 3);
+        assert_eq!(result.results.len(), 3);
+        assert_eq!(count(&pool, &table).await, 0);
+
+        // continue_on_error still runs the remaining operations before rolling back
+        let result = executor.execute(&batch(&table), &options(BatchTransactionMode::AllInOne, true)).await.unwrap();
+        assert!(result.results[2].error_message.as_deref().unwrap().starts_with("Rolled back"));
+        assert_eq!(count(&pool, &table).await, 0);
+
+        executor::execute(&pool, &format!("DROP TABLE {}", table), &[]).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_per_operation_savepoints() {
+        let pool = test_util::connect_pool().await;
+        let table = create_table(&pool).await;
+        let executor = BatchExecutor::new(pool.clone());
+
+        let result = executor.execute(&batch(&table), &options(BatchTransactionMode::PerOperation, true)).await.unwrap();
+        assert_eq!(result.successful_operations, 2);
+        assert_eq!(result.failed_operations, 1);
+        assert!(!result.results[1].success);
+        assert_eq!(count(&pool, &table).await, 2);
+
+        executor::execute(&pool, &format!("DROP TABLE {}", table), &[]).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_autocommit_stops_on_error() {
+        let pool = test_util::connect_pool().await;
+        let table = create_table(&pool).await;
+        let executor = BatchExecutor::new(pool.clone());
+
+        let result = executor.execute(&batch(&table), &options(BatchTransactionMode::None, false)).await.unwrap();
+        assert_eq!(result.successful_operations, 1);
+        assert_eq!(result.results[2].error_message.as_deref(), Some("Skipped after operation 1 failed"));
+        assert_eq!(count(&pool, &table).await, 1);
+
+        executor::execute(&pool, &format!("DROP TABLE {}", table), &[]).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_insert_rows_with_insert_and_copy() {
+        let pool = test_util::connect_pool().await;
+        let table = create_table(&pool).await;
+        let rows: Vec<JsonRow> = (0..25)
+            .map(|i| {
+                json!({
+                    "id": i,
+                    "name": format!("user \"{}\"", i),
+                    "tags": ["x", "y,z"],
+                    "meta": {"n": i},
+                    "owner": "550e8400-e29b-41d4-a716-446655440000",
+                })
+                    .as_object()
+                    .unwrap()
+                    .clone()
+            })
+            .collect();
+
+        let ident = Ident::new(&table).unwrap();
+        let inserter = BatchExecutor::new(pool.clone()).with_insert_chunk_size(10);
+        assert_eq!(inserter.insert_rows(&ident, &rows[..15]).await.unwrap(), 15);
+
+        let copier = BatchExecutor::new(pool.clone()).with_copy_threshold(1);
+        assert_eq!(copier.insert_rows(&ident, &rows[15..]).await.unwrap(), 10);
+
+        let row = executor::fetch_one_json(&pool, &format!("SELECT * FROM {} WHERE id = 20", table), &[]).await.unwrap();
+        assert_eq!(row["name"], json!("user \"20\""));
+        assert_eq!(row["tags"], json!(["x", "y,z"]));
+        assert_eq!(row["meta"], json!({"n": 20}));
+        let (owned,): (i64,) = executor::fetch_one_as(
+            &pool,
+            &format!("SELECT COUNT(*) FROM {} WHERE owner = '550e8400-e29b-41d4-a716-446655440000'", table),
+            &[],
+        )
+        .await
+        .unwrap();
+        assert_eq!(owned, 25);
+        assert_eq!(count(&pool, &table).await, 25);
+
+        executor::execute(&pool, &format!("DROP TABLE {}", table), &[]).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_insert_rows_into_jsonb_column() {
+        let pool = test_util::connect_pool().await;
+        let table = create_table(&pool).await;
+        let metas = [json!(["a", "b"]), json!(42), json!("2024-01-15T10:30:00Z"), json!(true)];
+        let rows: Vec<JsonRow> = metas
+            .iter()
+            .chain(&metas)
+            .enumerate()
+            .map(|(i, meta)| json!({"id": i, "name": "n", "meta": meta}).as_object().unwrap().clone())
+            .collect();
+
+        let ident = Ident::new(&table).unwrap();
+        let inserter = BatchExecutor::new(pool.clone());
+        assert_eq!(inserter.insert_rows(&ident, &rows[..4]).await.unwrap(), 4);
+
+        let copier = BatchExecutor::new(pool.clone()).with_copy_threshold(1);
+        assert_eq!(copier.insert_rows(&ident, &rows[4..]).await.unwrap(), 4);
+
+        let stored = executor::fetch_all_json(&pool, &format!("SELECT meta FROM {} ORDER BY id", table), &[])
+            .await
+            .unwrap();
+        let stored: Vec<Value> = stored.into_iter().map(|row| row["meta"].clone()).collect();
+        let expected: Vec<Value> = metas.iter().chain(&metas).cloned().collect();
+        assert_eq!(stored, expected);
+
+        executor::execute(&pool, &format!("DROP TABLE {}", table), &[]).await.unwrap();
+    }
+}
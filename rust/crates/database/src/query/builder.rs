@@ -1,29 +1,64 @@
 //! Query builder for dynamic SQL construction
+//!
+//! Names are [`Ident`]s and conditions are [`Expr`] trees, so the generated
+//! SQL contains only quoted identifiers and `$n` placeholders.
+
+use crate::types::models::{self, QueryCriteria};
+use cloudshuttle_error_handling::database_error::{DatabaseError, DatabaseResult};
+use serde_json::Value;
+use super::cursor::CursorPagination;
+use super::expr::Expr;
+use super::filters::SortDirection;
+use super::ident::{ColumnAllowlist, Ident};
+
+/// JOIN types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinKind {
+    Inner,
+    Left,
+    Right,
+    Full,
+}
+
+impl JoinKind {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Inner => "INNER JOIN",
+            Self::Left => "LEFT JOIN",
+            Self::Right => "RIGHT JOIN",
+            Self::Full => "FULL JOIN",
+        }
+    }
+}
 
-use crate::types::models::QueryCriteria;
-use serde_json;
+/// `<kind> JOIN table ON left = right`
+#[derive(Debug, Clone)]
+struct Join {
+    kind: JoinKind,
+    table: Ident,
+    left: Ident,
+    right: Ident,
+}
 
 /// Query builder for dynamic SQL construction
 #[derive(Debug, Clone)]
 pub struct QueryBuilder {
-    table: String,
-    select_fields: Vec<String>,
-    where_conditions: Vec<String>,
-    params: Vec<serde_json::Value>,
-    order_by: Vec<String>,
+    table: Ident,
+    select_fields: Vec<Ident>,
+    conditions: Vec<Expr>,
+    order_by: Vec<(Ident, SortDirection)>,
     limit: Option<usize>,
     offset: Option<usize>,
-    joins: Vec<String>,
+    joins: Vec<Join>,
 }
 
 impl QueryBuilder {
     /// Create a new query builder for a table
-    pub fn new<S: Into<String>>(table: S) -> Self {
+    pub fn new(table: Ident) -> Self {
         Self {
-            table: table.into(),
-            select_fields: vec!["*".to_string()],
-            where_conditions: Vec::new(),
-            params: Vec::new(),
+            table,
+            select_fields: Vec::new(),
+            conditions: Vec::new(),
             order_by: Vec::new(),
             limit: None,
             offset: None,
@@ -31,33 +66,26 @@ impl QueryBuilder {
         }
     }
 
-    /// Specify which fields to select
-    pub fn select<S: Into<String>>(mut self, fields: Vec<S>) -> Self {
-        self.select_fields = fields.into_iter().map(|s| s.into()).collect();
+    /// Specify which fields to select (all fields by default)
+    pub fn select(mut self, fields: impl IntoIterator<Item = Ident>) -> Self {
+        self.select_fields = fields.into_iter().collect();
         self
     }
 
-    /// Add a WHERE condition
-    pub fn where_eq<S: Into<String>, V: Into<serde_json::Value>>(mut self, field: S, value: V) -> Self {
-        let condition = format!("{} = ${}", field.into(), self.params.len() + 1);
-        self.where_conditions.push(condition);
-        self.params.push(value.into());
-        self
+    /// Add a `field = value` condition
+    pub fn where_eq<V: Into<Value>>(self, field: Ident, value: V) -> Self {
+        self.filter(Expr::eq(field, value))
     }
 
-    /// Add a WHERE condition with custom operator
-    pub fn where_condition<S: Into<String>>(mut self, condition: S) -> Self {
-        self.where_conditions.push(condition.into());
+    /// Add a condition; conditions are combined with AND
+    pub fn filter(mut self, expr: Expr) -> Self {
+        self.conditions.push(expr);
         self
     }
 
     /// Add an ORDER BY clause
-    pub fn order_by<S: Into<String>>(mut self, field: S, direction: Option<&str>) -> Self {
-        let order = match direction {
-            Some("desc") | Some("DESC") => format!("{} DESC", field.into()),
-            _ => field.into(),
-        };
-        self.order_by.push(order);
+    pub fn order_by(mut self, field: Ident, direction: SortDirection) -> Self {
+        self.order_by.push((field, direction));
         self
     }
 
@@ -73,38 +101,53 @@ impl QueryBuilder {
         self
     }
 
-    /// Add a JOIN clause
-    pub fn join<S: Into<String>>(mut self, join: S) -> Self {
-        self.joins.push(join.into());
+    /// Add a JOIN clause on `left = right`
+    pub fn join(mut self, kind: JoinKind, table: Ident, left: Ident, right: Ident) -> Self {
+        self.joins.push(Join { kind, table, left, right });
+        self
+    }
+
+    /// Switch to keyset pagination
+    ///
+    /// Adds `WHERE (a, b) > ($n, $n+1)` for the cursor position (`<` when
+    /// descending or paging backwards), replaces the ORDER BY with the sort
+    /// keys and sets `LIMIT n+1` so the page can tell whether more rows exist.
+    /// Any OFFSET is dropped.
+    pub fn cursor_paginate(mut self, pagination: &CursorPagination) -> Self {
+        if let Some(condition) = pagination.condition() {
+            self.conditions.push(condition);
+        }
+        self.order_by = pagination.order();
+        self.limit = Some(pagination.limit() + 1);
+        self.offset = None;
         self
     }
 
     /// Build the final SQL query and parameters
-    pub fn build(self) -> (String, Vec<serde_json::Value>) {
-        let mut sql = String::new();
+    pub fn build(self) -> (String, Vec<Value>) {
+        let mut params = Vec::new();
+        let mut sql = String::from("SELECT ");
 
         // SELECT clause
-        sql.push_str("SELECT ");
-        sql.push_str(&self.select_fields.join(", "));
-        sql.push_str(" FROM ");
-        sql.push_str(&self.table);
-
-        // JOIN clauses
-        for join in &self.joins {
-            sql.push_str(" ");
-            sql.push_str(join);
-        }
-
-        // WHERE clause
-        if !self.where_conditions.is_empty() {
-            sql.push_str(" WHERE ");
-            sql.push_str(&self.where_conditions.join(" AND "));
+        if self.select_fields.is_empty() {
+            sql.push('*');
+        } else {
+            sql.push_str(&join_idents(&self.select_fields));
         }
+        self.push_from_and_where(&mut sql, &mut params);
 
         // ORDER BY clause
         if !self.order_by.is_empty() {
+            let order: Vec<String> = self
+                .order_by
+                .iter()
+                .map(|(field, direction)| match direction {
+                    SortDirection::Ascending => field.to_sql(),
+                    SortDirection::Descending => format!("{} DESC", field),
+                })
+                .collect();
             sql.push_str(" ORDER BY ");
-            sql.push_str(&self.order_by.join(", "));
+            sql.push_str(&order.join(", "));
         }
 
         // LIMIT clause
@@ -117,122 +160,211 @@ impl QueryBuilder {
             sql.push_str(&format!(" OFFSET {}", offset));
         }
 
-        (sql, self.params)
+        (sql, params)
     }
 
     /// Build a count query
-    pub fn build_count(self) -> (String, Vec<serde_json::Value>) {
-        let mut sql = String::new();
+    pub fn build_count(self) -> (String, Vec<Value>) {
+        let mut params = Vec::new();
+        let mut sql = String::from("SELECT COUNT(*)");
+        self.push_from_and_where(&mut sql, &mut params);
+        (sql, params)
+    }
 
-        sql.push_str("SELECT COUNT(*) FROM ");
-        sql.push_str(&self.table);
+    fn push_from_and_where(&self, sql: &mut String, params: &mut Vec<Value>) {
+        sql.push_str(" FROM ");
+        sql.push_str(&self.table.to_sql());
 
         // JOIN clauses
         for join in &self.joins {
-            sql.push_str(" ");
-            sql.push_str(join);
+            sql.push_str(&format!(" {} {} ON {} = {}", join.kind.as_sql(), join.table, join.left, join.right));
         }
 
         // WHERE clause
-        if !self.where_conditions.is_empty() {
+        if !self.conditions.is_empty() {
             sql.push_str(" WHERE ");
-            sql.push_str(&self.where_conditions.join(" AND "));
+            sql.push_str(&Expr::all(self.conditions.iter().cloned()).to_sql(params));
         }
-
-        (sql, self.params)
     }
 
-    /// Apply QueryCriteria to the builder
-    pub fn apply_criteria(mut self, criteria: QueryCriteria) -> Self {
+    /// Apply QueryCriteria to the builder, resolving field names through an allowlist
+    ///
+    /// Pagination selects one page: `LIMIT page_size OFFSET (page - 1) * page_size`.
+    pub fn apply_criteria(mut self, criteria: QueryCriteria, allowlist: &ColumnAllowlist) -> DatabaseResult<Self> {
         // Apply filters
         for filter in criteria.filters {
-            match filter.operator {
-                crate::types::models::FilterOperator::Equal => {
-                    self = self.where_eq(filter.field, filter.value);
-                }
-                // Add other operators as needed
-                _ => {
-                    // For now, add as custom condition
-                    let condition = format!("{} {} ?", filter.field, filter.operator.to_string());
-                    self = self.where_condition(condition);
-                    self.params.push(filter.value);
-                }
-            }
+            let column = allowlist.resolve(&filter.field)?;
+            let param = |value| allowlist.param(&filter.field, value);
+            let expr = match filter.operator {
+                models::FilterOperator::Equal => Expr::eq(column, param(filter.value)),
+                models::FilterOperator::NotEqual => Expr::ne(column, param(filter.value)),
+                models::FilterOperator::GreaterThan => Expr::gt(column, param(filter.value)),
+                models::FilterOperator::LessThan => Expr::lt(column, param(filter.value)),
+                models::FilterOperator::GreaterThanOrEqual => Expr::gte(column, param(filter.value)),
+                models::FilterOperator::LessThanOrEqual => Expr::lte(column, param(filter.value)),
+                models::FilterOperator::Like => match filter.value {
+                    Value::String(pattern) => Expr::like(column, pattern),
+                    _ => return Err(DatabaseError::query(format!("Filter on {} requires a string pattern", filter.field))),
+                },
+                models::FilterOperator::In => Expr::is_in(column, list_values(filter.value).into_iter().map(param)),
+                models::FilterOperator::NotIn => Expr::not_in(column, list_values(filter.value).into_iter().map(param)),
+                models::FilterOperator::IsNull => Expr::is_null(column),
+                models::FilterOperator::IsNotNull => Expr::is_not_null(column),
+            };
+            self = self.filter(expr);
         }
 
         // Apply sorting
         for sort in criteria.sorting {
             let direction = match sort.direction {
-                crate::types::models::SortDirection::Descending => Some("DESC"),
-                _ => None,
+                models::SortDirection::Ascending => SortDirection::Ascending,
+                models::SortDirection::Descending => SortDirection::Descending,
             };
-            self = self.order_by(sort.field, direction);
+            self = self.order_by(allowlist.resolve(&sort.field)?, direction);
         }
 
         // Apply pagination
         if let Some(pagination) = criteria.pagination {
-            if let Some(limit) = pagination.page_size.checked_mul(pagination.page) {
-                self = self.limit(limit as usize);
-            }
+            self = self.limit(pagination.page_size as usize);
             if pagination.page > 1 {
-                let offset = (pagination.page - 1) * pagination.page_size;
-                self = self.offset(offset as usize);
+                let offset = (pagination.page as usize - 1) * pagination.page_size as usize;
+                self = self.offset(offset);
             }
         }
 
-        self
+        Ok(self)
+    }
+}
+
+fn join_idents(idents: &[Ident]) -> String {
+    idents.iter().map(Ident::to_sql).collect::<Vec<_>>().join(", ")
+}
+
+/// Treat a non-array value as a single-element list
+fn list_values(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(values) => values,
+        value => vec![value],
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::models::{Filter, Pagination, SortOrder};
+
+    fn id(name: &str) -> Ident {
+        Ident::new(name).unwrap()
+    }
 
     #[test]
     fn test_query_builder_basic() {
-        let (sql, params) = QueryBuilder::new("users")
-            .select(vec!["id", "name", "email"])
-            .where_eq("active", true)
-            .order_by("name", None)
+        let (sql, params) = QueryBuilder::new(id("users"))
+            .select([id("id"), id("name"), id("email")])
+            .where_eq(id("active"), true)
+            .order_by(id("name"), SortDirection::Ascending)
             .limit(10)
             .build();
 
-        assert!(sql.contains("SELECT id, name, email FROM users"));
-        assert!(sql.contains("WHERE active = $1"));
-        assert!(sql.contains("ORDER BY name"));
+        assert!(sql.contains("SELECT \"id\", \"name\", \"email\" FROM \"users\""));
+        assert!(sql.contains("WHERE \"active\" = $1"));
+        assert!(sql.contains("ORDER BY \"name\""));
         assert!(sql.contains("LIMIT 10"));
         assert_eq!(params.len(), 1);
     }
 
     #[test]
     fn test_query_builder_count() {
-        let (sql, params) = QueryBuilder::new("users")
-            .where_eq("active", true)
+        let (sql, params) = QueryBuilder::new(id("users"))
+            .where_eq(id("active"), true)
             .build_count();
 
-        assert!(sql.contains("SELECT COUNT(*) FROM users"));
-        assert!(sql.contains("WHERE active = $1"));
+        assert!(sql.contains("SELECT COUNT(*) FROM \"users\""));
+        assert!(sql.contains("WHERE \"active\" = $1"));
         assert_eq!(params.len(), 1);
     }
 
     #[test]
     fn test_query_builder_complex() {
-        let (sql, params) = QueryBuilder::new("users")
-            .select(vec!["id", "name"])
-            .where_eq("active", true)
-            .where_condition("age > 18")
-            .join("LEFT JOIN profiles p ON users.id = p.user_id")
-            .order_by("name", Some("DESC"))
+        let (sql, params) = QueryBuilder::new(id("users"))
+            .select([id("users.id"), id("users.name")])
+            .where_eq(id("active"), true)
+            .filter(Expr::gt(id("age"), 18))
+            .join(JoinKind::Left, id("profiles"), id("users.id"), id("profiles.user_id"))
+            .order_by(id("name"), SortDirection::Descending)
             .limit(50)
             .offset(100)
             .build();
 
-        assert!(sql.contains("SELECT id, name FROM users"));
-        assert!(sql.contains("LEFT JOIN profiles p ON users.id = p.user_id"));
-        assert!(sql.contains("WHERE active = $1 AND age > 18"));
-        assert!(sql.contains("ORDER BY name DESC"));
+        assert!(sql.contains("SELECT \"users\".\"id\", \"users\".\"name\" FROM \"users\""));
+        assert!(sql.contains("LEFT JOIN \"profiles\" ON \"users\".\"id\" = \"profiles\".\"user_id\""));
+        assert!(sql.contains("WHERE (\"active\" = $1) AND (\"age\" > $2)"));
+        assert!(sql.contains("ORDER BY \"name\" DESC"));
         assert!(sql.contains("LIMIT 50"));
         assert!(sql.contains("OFFSET 100"));
+        assert_eq!(params.len(), 2);
+    }
+
+    #[test]
+    fn test_cursor_paginate() {
+        use crate::query::cursor::{Cursor, CursorDirection};
+
+        let keys = [id("created_at"), id("id")];
+        let pagination = CursorPagination::new(keys.clone(), SortDirection::Ascending, 20);
+        let (sql, params) = QueryBuilder::new(id("events"))
+            .where_eq(id("tenant_id"), 1)
+            .offset(40)
+            .cursor_paginate(&pagination)
+            .build();
+        assert_eq!(sql, "SELECT * FROM \"events\" WHERE \"tenant_id\" = $1 ORDER BY \"created_at\", \"id\" LIMIT 21");
         assert_eq!(params.len(), 1);
+
+        let cursor = Cursor::new(
+            vec!["created_at".to_string(), "id".to_string()],
+            SortDirection::Ascending,
+            vec![serde_json::json!("2024-01-01T00:00:00Z"), serde_json::json!(9)],
+            CursorDirection::Prev,
+        );
+        let (sql, params) = QueryBuilder::new(id("events"))
+            .where_eq(id("tenant_id"), 1)
+            .cursor_paginate(&pagination.with_cursor(cursor).unwrap())
+            .build();
+        assert_eq!(
+            sql,
+            "SELECT * FROM \"events\" WHERE (\"tenant_id\" = $1) AND ((\"created_at\", \"id\") < ($2, $3)) \
+             ORDER BY \"created_at\" DESC, \"id\" DESC LIMIT 21"
+        );
+        assert_eq!(params.len(), 3);
+    }
+
+    #[test]
+    fn test_apply_criteria_uses_allowlist() {
+        let allowlist = ColumnAllowlist::new(["name", "status"]).unwrap();
+        let criteria = QueryCriteria {
+            filters: vec![Filter {
+                field: "status".to_string(),
+                operator: models::FilterOperator::In,
+                value: serde_json::json!(["active", "pending"]),
+            }],
+            sorting: vec![SortOrder {
+                field: "name".to_string(),
+                direction: models::SortDirection::Descending,
+            }],
+            pagination: Some(Pagination::new(3, 20)),
+        };
+
+        let (sql, params) = QueryBuilder::new(id("users"))
+            .apply_criteria(criteria.clone(), &allowlist)
+            .unwrap()
+            .build();
+        assert_eq!(
+            sql,
+            "SELECT * FROM \"users\" WHERE \"status\" = ANY($1) ORDER BY \"name\" DESC LIMIT 20 OFFSET 40"
+        );
+        assert_eq!(params, vec![serde_json::json!(["active", "pending"])]);
+
+        let mut injected = criteria;
+        injected.sorting[0].field = "name; DROP TABLE users".to_string();
+        assert!(QueryBuilder::new(id("users")).apply_criteria(injected, &allowlist).is_err());
     }
 }
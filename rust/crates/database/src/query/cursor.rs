@@ -0,0 +1,467 @@
+//! Keyset (cursor) pagination
+//!
+//! Instead of skipping `OFFSET` rows, a page is fetched relative to the sort
+//! key of the last row the client saw:
+//!
+//! ```sql
+//! SELECT * FROM "events" WHERE ("created_at", "id") > ($1, $2)
+//! ORDER BY "created_at", "id" LIMIT 21
+//! ```
+//!
+//! The key tuple travels between requests as an opaque token signed with
+//! HMAC-SHA256, so clients cannot forge positions or swap in a cursor that
+//! was issued for a different sort order. One extra row is fetched to tell
+//! whether another page exists.
+//!
+//! Sort keys must be non-null and the full tuple must be unique (end it with
+//! the primary key), otherwise rows can be skipped or repeated between pages.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use cloudshuttle_error_handling::database_error::{DatabaseError, DatabaseResult};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::postgres::PgRow;
+use super::executor::{column_param, row_to_json, JsonRow};
+use super::expr::{CompareOp, Expr};
+use super::filters::SortDirection;
+use super::ident::Ident;
+use super::results::CursorPaginatedResult;
+
+/// Which way a cursor moves from its position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CursorDirection {
+    /// Rows after the position
+    Next,
+    /// Rows before the position
+    Prev,
+}
+
+/// Decoded cursor: a position in a keyset-ordered result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    /// Sort key column names the position was taken from
+    pub keys: Vec<String>,
+    /// Sort direction of the result the position was taken from
+    pub sort: SortDirection,
+    /// Sort key values of the row at the position
+    pub values: Vec<Value>,
+    /// Direction to page in
+    pub direction: CursorDirection,
+}
+
+impl Cursor {
+    /// Create a cursor
+    pub fn new(keys: Vec<String>, sort: SortDirection, values: Vec<Value>, direction: CursorDirection) -> Self {
+        Self { keys, sort, values, direction }
+    }
+}
+
+/// Signs and verifies cursor tokens
+///
+/// A token is `base64url(json) "." base64url(hmac)`. The secret must be
+/// shared by every instance serving the same API, and rotating it
+/// invalidates outstanding cursors.
+#[derive(Clone)]
+pub struct CursorCodec {
+    key: hmac::Key,
+}
+
+impl CursorCodec {
+    /// Create a codec from a signing secret
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            key: hmac::Key::new(hmac::HMAC_SHA256, secret),
+        }
+    }
+
+    /// Encode and sign a cursor
+    pub fn encode(&self, cursor: &Cursor) -> DatabaseResult<String> {
+        let payload = serde_json::to_vec(cursor)
+            .map_err(|e| DatabaseError::query(format!("Failed to encode cursor: {}", e)))?;
+        let tag = hmac::sign(&self.key, &payload);
+        Ok(format!("{}.{}", URL_SAFE_NO_PAD.encode(&payload), URL_SAFE_NO_PAD.encode(tag.as_ref())))
+    }
+
+    /// Verify and decode a cursor token
+    pub fn decode(&self, token: &str) -> DatabaseResult<Cursor> {
+        let (payload, tag) = token
+            .split_once('.')
+            .ok_or_else(|| DatabaseError::invalid_cursor("malformed token"))?;
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|_| DatabaseError::invalid_cursor("malformed token"))?;
+        let tag = URL_SAFE_NO_PAD
+            .decode(tag)
+            .map_err(|_| DatabaseError::invalid_cursor("malformed token"))?;
+
+        hmac::verify(&self.key, &payload, &tag)
+            .map_err(|_| DatabaseError::invalid_cursor("signature mismatch"))?;
+
+        serde_json::from_slice(&payload).map_err(|e| DatabaseError::invalid_cursor(e.to_string()))
+    }
+}
+
+impl std::fmt::Debug for CursorCodec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CursorCodec").finish_non_exhaustive()
+    }
+}
+
+/// Keyset pagination request: sort keys, page size and an optional position
+///
+/// Apply it with [`QueryBuilder::cursor_paginate`](super::QueryBuilder::cursor_paginate),
+/// run the query, then turn the rows into a page with [`Self::page`],
+/// [`Self::page_json`] or [`Self::page_rows`].
+#[derive(Debug, Clone)]
+pub struct CursorPagination {
+    keys: Vec<Ident>,
+    direction: SortDirection,
+    limit: usize,
+    cursor: Option<Cursor>,
+}
+
+impl CursorPagination {
+    /// Paginate by `keys` in `direction`, `limit` rows per page, starting at the first page
+    pub fn new(keys: impl IntoIterator<Item = Ident>, direction: SortDirection, limit: usize) -> Self {
+        Self {
+            keys: keys.into_iter().collect(),
+            direction,
+            limit: limit.max(1),
+            cursor: None,
+        }
+    }
+
+    /// Continue from a decoded cursor
+    ///
+    /// The cursor must have been issued for the same sort keys and direction.
+    pub fn with_cursor(mut self, cursor: Cursor) -> DatabaseResult<Self> {
+        let matches_order = cursor.sort == self.direction
+            && cursor.keys.len() == self.keys.len()
+            && cursor.keys.iter().zip(&self.keys).all(|(name, key)| name == key.name());
+        if !matches_order {
+            return Err(DatabaseError::invalid_cursor("cursor was issued for a different sort order"));
+        }
+        if cursor.values.len() != self.keys.len() {
+            return Err(DatabaseError::invalid_cursor("cursor has the wrong number of values"));
+        }
+        self.cursor = Some(cursor);
+        Ok(self)
+    }
+
+    /// Continue from a cursor token, if the request carried one
+    pub fn with_token(self, token: Option<&str>, codec: &CursorCodec) -> DatabaseResult<Self> {
+        match token {
+            Some(token) => self.with_cursor(codec.decode(token)?),
+            None => Ok(self),
+        }
+    }
+
+    /// Sort key columns
+    pub fn keys(&self) -> &[Ident] {
+        &self.keys
+    }
+
+    /// Rows per page
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Current position, if any
+    pub fn cursor(&self) -> Option<&Cursor> {
+        self.cursor.as_ref()
+    }
+
+    fn paging_backwards(&self) -> bool {
+        matches!(&self.cursor, Some(cursor) if cursor.direction == CursorDirection::Prev)
+    }
+
+    /// Condition selecting rows past the cursor, if there is one
+    pub(crate) fn condition(&self) -> Option<Expr> {
+        let cursor = self.cursor.as_ref()?;
+        let ascending = self.direction == SortDirection::Ascending;
+        let forwards = cursor.direction == CursorDirection::Next;
+        let op = if ascending == forwards { CompareOp::Gt } else { CompareOp::Lt };
+        Some(Expr::RowCompare {
+            columns: self.keys.clone(),
+            op,
+            values: cursor.values.clone(),
+        })
+    }
+
+    /// ORDER BY the query must use; reversed when paging backwards
+    pub(crate) fn order(&self) -> Vec<(Ident, SortDirection)> {
+        let direction = match (self.direction, self.paging_backwards()) {
+            (SortDirection::Ascending, true) => SortDirection::Descending,
+            (SortDirection::Descending, true) => SortDirection::Ascending,
+            (direction, false) => direction,
+        };
+        self.keys.iter().map(|key| (key.clone(), direction)).collect()
+    }
+
+    /// Turn the fetched rows (up to `limit + 1`) into a page
+    ///
+    /// `key_values` returns a row's sort key values, in key order. UUIDs and
+    /// RFC 3339 timestamps bind back as such; keys on `TIMESTAMP`, `DATE` or
+    /// `TIME` columns must be tagged with their [`ParamType`](super::ParamType)
+    /// so the cursor condition binds them as the column's type.
+    pub fn page<T, F>(&self, mut rows: Vec<T>, codec: &CursorCodec, key_values: F) -> DatabaseResult<CursorPaginatedResult<T>>
+    where
+        F: Fn(&T) -> Vec<Value>,
+    {
+        let has_more = rows.len() > self.limit;
+        rows.truncate(self.limit);
+
+        let backwards = self.paging_backwards();
+        if backwards {
+            rows.reverse();
+        }
+
+        // Going forwards, a previous page exists whenever we started from a cursor;
+        // going backwards, a next page always exists (the one we came from)
+        let (has_next, has_previous) = if backwards {
+            (true, has_more)
+        } else {
+            (has_more, self.cursor.is_some())
+        };
+
+        let token = |row: Option<&T>, direction| {
+            row.map(|row| {
+                let keys = self.keys.iter().map(|key| key.name().to_string()).collect();
+                codec.encode(&Cursor::new(keys, self.direction, key_values(row), direction))
+            })
+            .transpose()
+        };
+        let next_cursor = if has_next { token(rows.last(), CursorDirection::Next)? } else { None };
+        let prev_cursor = if has_previous { token(rows.first(), CursorDirection::Prev)? } else { None };
+
+        Ok(CursorPaginatedResult::new(rows, next_cursor, prev_cursor, self.limit as u32))
+    }
+
+    /// [`Self::page`] for JSON rows, reading each key from the column of the same name
+    ///
+    /// Keys on `TIMESTAMP`, `DATE` or `TIME` columns lose their type in JSON;
+    /// page those with [`Self::page_rows`].
+    pub fn page_json(&self, rows: Vec<JsonRow>, codec: &CursorCodec) -> DatabaseResult<CursorPaginatedResult<JsonRow>> {
+        self.page(rows, codec, |row| {
+            self.keys
+                .iter()
+                .map(|key| row.get(key.name()).cloned().unwrap_or(Value::Null))
+                .collect()
+        })
+    }
+
+    /// [`Self::page`] for raw rows, returning JSON objects
+    ///
+    /// Each key is read from the column of the same name with
+    /// [`column_param`], so it keeps the column's type in the cursor.
+    pub fn page_rows(&self, rows: Vec<PgRow>, codec: &CursorCodec) -> DatabaseResult<CursorPaginatedResult<JsonRow>> {
+        let rows = rows
+            .iter()
+            .map(|row| {
+                let keys = self
+                    .keys
+                    .iter()
+                    .map(|key| column_param(row, key.name()))
+                    .collect::<DatabaseResult<Vec<_>>>()?;
+                Ok((row_to_json(row)?, keys))
+            })
+            .collect::<DatabaseResult<Vec<_>>>()?;
+        let page = self.page(rows, codec, |(_, keys)| keys.clone())?;
+        Ok(CursorPaginatedResult::new(
+            page.data.into_iter().map(|(row, _)| row).collect(),
+            page.next_cursor,
+            page.prev_cursor,
+            page.page_size,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn codec() -> CursorCodec {
+        CursorCodec::new(b"test-secret")
+    }
+
+    fn keys() -> Vec<Ident> {
+        vec![Ident::new("created_at").unwrap(), Ident::new("id").unwrap()]
+    }
+
+    fn rows(ids: std::ops::RangeInclusive<i64>) -> Vec<JsonRow> {
+        ids.map(|id| {
+            let mut row = JsonRow::new();
+            row.insert("created_at".to_string(), json!(format!("2024-01-{:02}T00:00:00Z", id)));
+            row.insert("id".to_string(), json!(id));
+            row
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_codec_round_trip_and_tampering() {
+        let cursor = Cursor::new(vec!["id".to_string()], SortDirection::Ascending, vec![json!(42)], CursorDirection::Next);
+        let token = codec().encode(&cursor).unwrap();
+        assert_eq!(codec().decode(&token).unwrap(), cursor);
+
+        // Re-sign a modified payload with another secret
+        let forged = CursorCodec::new(b"other-secret")
+            .encode(&Cursor::new(vec!["id".to_string()], SortDirection::Ascending, vec![json!(0)], CursorDirection::Next))
+            .unwrap();
+        assert!(matches!(codec().decode(&forged), Err(DatabaseError::InvalidCursor { .. })));
+
+        let (payload, _) = token.split_once('.').unwrap();
+        let (_, forged_tag) = forged.split_once('.').unwrap();
+        assert!(codec().decode(&format!("{}.{}", payload, forged_tag)).is_err());
+        assert!(codec().decode("not-a-cursor").is_err());
+    }
+
+    #[test]
+    fn test_cursor_must_match_sort_keys() {
+        let pagination = CursorPagination::new(keys(), SortDirection::Ascending, 10);
+        let key_names = vec!["created_at".to_string(), "id".to_string()];
+        let cursor = Cursor::new(vec!["name".to_string(), "id".to_string()], SortDirection::Ascending, vec![json!("a"), json!(1)], CursorDirection::Next);
+        assert!(pagination.clone().with_cursor(cursor).is_err());
+
+        let short = Cursor::new(key_names.clone(), SortDirection::Ascending, vec![json!(1)], CursorDirection::Next);
+        assert!(pagination.clone().with_cursor(short).is_err());
+
+        // A cursor from a descending listing cannot continue an ascending one
+        let values = vec![json!("2024-01-01T00:00:00Z"), json!(1)];
+        let descending = Cursor::new(key_names.clone(), SortDirection::Descending, values.clone(), CursorDirection::Next);
+        assert!(pagination.clone().with_cursor(descending).is_err());
+        let ascending = Cursor::new(key_names, SortDirection::Ascending, values, CursorDirection::Next);
+        assert!(pagination.with_cursor(ascending).is_ok());
+
+        // The direction is covered by the signature
+        let token = CursorPagination::new(keys(), SortDirection::Descending, 1)
+            .page_json(rows(1..=2), &codec())
+            .unwrap()
+            .next_cursor
+            .unwrap();
+        let pagination = CursorPagination::new(keys(), SortDirection::Ascending, 1);
+        assert!(matches!(
+            pagination.with_token(Some(&token), &codec()),
+            Err(DatabaseError::InvalidCursor { .. })
+        ));
+    }
+
+    #[test]
+    fn test_paging_forwards_and_back() {
+        let codec = codec();
+        let first = CursorPagination::new(keys(), SortDirection::Ascending, 3);
+
+        // First page: no cursor, an extra row means there is a next page
+        let page = first.page_json(rows(1..=4), &codec).unwrap();
+        assert_eq!(page.data.len(), 3);
+        assert!(page.has_next && !page.has_previous);
+        assert!(page.prev_cursor.is_none());
+
+        // Second page via the next cursor
+        let second = first.clone().with_token(page.next_cursor.as_deref(), &codec).unwrap();
+        assert_eq!(second.cursor().unwrap().values, vec![json!("2024-01-03T00:00:00Z"), json!(3)]);
+        let page = second.page_json(rows(4..=5), &codec).unwrap();
+        assert!(!page.has_next && page.has_previous);
+
+        // Back again: the query returns rows in reverse order, the page restores it
+        let back = first.with_token(page.prev_cursor.as_deref(), &codec).unwrap();
+        assert_eq!(back.cursor().unwrap().direction, CursorDirection::Prev);
+        let mut reversed = rows(1..=3);
+        reversed.reverse();
+        let page = back.page_json(reversed, &codec).unwrap();
+        assert_eq!(page.data[0]["id"], json!(1));
+        assert!(page.has_next && !page.has_previous);
+    }
+
+    #[test]
+    fn test_condition_and_order() {
+        let next = Cursor::new(vec!["created_at".to_string(), "id".to_string()], SortDirection::Ascending, vec![json!("t"), json!(1)], CursorDirection::Next);
+        let prev = Cursor { direction: CursorDirection::Prev, ..next.clone() };
+        let op = |direction, cursor: &Cursor| {
+            let cursor = Cursor { sort: direction, ..cursor.clone() };
+            let pagination = CursorPagination::new(keys(), direction, 5).with_cursor(cursor).unwrap();
+            match pagination.condition() {
+                Some(Expr::RowCompare { op, .. }) => (op, pagination.order()[0].1),
+                other => panic!("unexpected condition {:?}", other),
+            }
+        };
+
+        assert_eq!(op(SortDirection::Ascending, &next), (CompareOp::Gt, SortDirection::Ascending));
+        assert_eq!(op(SortDirection::Ascending, &prev), (CompareOp::Lt, SortDirection::Descending));
+        assert_eq!(op(SortDirection::Descending, &next), (CompareOp::Lt, SortDirection::Descending));
+        assert_eq!(op(SortDirection::Descending, &prev), (CompareOp::Gt, SortDirection::Ascending));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_cursor_pages_against_database() {
+        use crate::query::builder::QueryBuilder;
+        use crate::query::executor::{execute, fetch_all_json, fetch_rows};
+
+        let mut conn = crate::test_util::connect().await;
+
+        execute(&mut conn, "CREATE TEMPORARY TABLE cursor_events (id INT8 PRIMARY KEY, created_at TIMESTAMP NOT NULL)", &[])
+            .await
+            .unwrap();
+        // Pairs of rows share a timestamp, so the id tie-breaker matters
+        execute(
+            &mut conn,
+            "INSERT INTO cursor_events SELECT i, TIMESTAMP '2024-01-01' + (i / 2) * INTERVAL '1 hour' FROM generate_series(1, 7) i",
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let codec = codec();
+        let table = Ident::new("cursor_events").unwrap();
+        let first = CursorPagination::new(keys(), SortDirection::Ascending, 3);
+
+        let mut seen = Vec::new();
+        let mut pagination = first.clone();
+        loop {
+            let (sql, params) = QueryBuilder::new(table.clone()).cursor_paginate(&pagination).build();
+            let page = pagination.page_rows(fetch_rows(&mut conn, &sql, &params).await.unwrap(), &codec).unwrap();
+            seen.extend(page.data.iter().map(|row| row["id"].as_i64().unwrap()));
+            match page.next_cursor {
+                Some(token) => pagination = first.clone().with_token(Some(&token), &codec).unwrap(),
+                None => break,
+            }
+        }
+        assert_eq!(seen, (1..=7).collect::<Vec<_>>());
+
+        // Step back from the last page
+        let (sql, params) = QueryBuilder::new(table.clone()).cursor_paginate(&pagination).build();
+        let last = pagination.page_rows(fetch_rows(&mut conn, &sql, &params).await.unwrap(), &codec).unwrap();
+        let back = first.clone().with_token(last.prev_cursor.as_deref(), &codec).unwrap();
+        let (sql, params) = QueryBuilder::new(table.clone()).cursor_paginate(&back).build();
+        let page = back.page_rows(fetch_rows(&mut conn, &sql, &params).await.unwrap(), &codec).unwrap();
+        let ids: Vec<i64> = page.data.iter().map(|row| row["id"].as_i64().unwrap()).collect();
+        assert_eq!(ids, vec![4, 5, 6]);
+        assert!(page.has_previous && page.has_next);
+
+        // JSON rows carry TIMESTAMPTZ keys as RFC 3339 strings, which bind back as timestamps
+        execute(
+            &mut conn,
+            "CREATE TEMPORARY TABLE cursor_events_tz AS SELECT id, created_at::TIMESTAMPTZ AS created_at FROM cursor_events",
+            &[],
+        )
+        .await
+        .unwrap();
+        let table = Ident::new("cursor_events_tz").unwrap();
+        let mut seen = Vec::new();
+        let mut pagination = first.clone();
+        loop {
+            let (sql, params) = QueryBuilder::new(table.clone()).cursor_paginate(&pagination).build();
+            let page = pagination.page_json(fetch_all_json(&mut conn, &sql, &params).await.unwrap(), &codec).unwrap();
+            seen.extend(page.data.iter().map(|row| row["id"].as_i64().unwrap()));
+            match page.next_cursor {
+                Some(token) => pagination = first.clone().with_token(Some(&token), &codec).unwrap(),
+                None => break,
+            }
+        }
+        assert_eq!(seen, (1..=7).collect::<Vec<_>>());
+    }
+}
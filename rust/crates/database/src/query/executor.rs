@@ -0,0 +1,673 @@
+//! Query execution with JSON parameters
+//!
+//! [`QueryBuilder`](super::QueryBuilder), [`BatchBuilder`](super::BatchBuilder) and
+//! [`FilterOption`](super::FilterOption) produce SQL with `serde_json::Value`
+//! parameters. This module binds those values to Postgres types and maps
+//! result rows back into JSON objects or [`sqlx::FromRow`] types.
+//!
+//! Parameters are bound as follows:
+//!
+//! | JSON value                       | Postgres type                         |
+//! |----------------------------------|---------------------------------------|
+//! | `null`                           | untyped `NULL`                        |
+//! | boolean                          | `BOOL`                                |
+//! | integer / other number           | `INT8` / `FLOAT8`                     |
+//! | string holding a UUID            | `UUID`                                |
+//! | string holding an RFC 3339 time  | `TIMESTAMPTZ`                         |
+//! | any other string                 | `TEXT`                                |
+//! | object                           | `JSONB`                               |
+//! | array of one scalar kind         | `BOOL[]`, `INT8[]`, `FLOAT8[]`, `UUID[]`, `TIMESTAMPTZ[]` or `TEXT[]` |
+//! | array of values tagged as one type | an array of that type               |
+//! | array of objects or arrays       | `JSONB`                               |
+//! | `{"$uuid": ...}` and other tags  | see [`ParamType`]                     |
+//!
+//! Tags override detection: a UUID-looking value compared against a `TEXT`
+//! column is sent as `ParamType::Text.tag(value)`, and dates or times
+//! without an offset as `ParamType::Date.tag(value)` and so on. Arrays
+//! mixing scalar kinds are rejected rather than guessed at.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use cloudshuttle_error_handling::database_error::{DatabaseError, DatabaseResult};
+use serde_json::{Map, Value};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgArguments, PgHasArrayType, PgRow, PgTypeInfo, PgTypeKind};
+use sqlx::postgres::types::Oid;
+use sqlx::{Arguments, Column, Decode, Encode, Executor, PgPool, Postgres, Row, Type, TypeInfo};
+use uuid::Uuid;
+
+/// A JSON object built from a result row, keyed by column name
+pub type JsonRow = Map<String, Value>;
+
+/// Executes SQL with JSON parameters against a connection pool
+///
+/// Use [`DatabaseTransaction`](crate::DatabaseTransaction) for the same
+/// operations inside a transaction.
+#[derive(Debug, Clone)]
+pub struct QueryExecutor {
+    pool: PgPool,
+}
+
+impl QueryExecutor {
+    /// Create an executor for a pool
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Get the underlying pool
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Execute a statement and return the number of affected rows
+    pub async fn execute(&self, sql: &str, params: &[Value]) -> DatabaseResult<u64> {
+        execute(&self.pool, sql, params).await
+    }
+
+    /// Fetch all rows
+    pub async fn fetch_rows(&self, sql: &str, params: &[Value]) -> DatabaseResult<Vec<PgRow>> {
+        fetch_rows(&self.pool, sql, params).await
+    }
+
+    /// Fetch all rows as JSON objects
+    pub async fn fetch_all_json(&self, sql: &str, params: &[Value]) -> DatabaseResult<Vec<JsonRow>> {
+        fetch_all_json(&self.pool, sql, params).await
+    }
+
+    /// Fetch exactly one row as a JSON object
+    pub async fn fetch_one_json(&self, sql: &str, params: &[Value]) -> DatabaseResult<JsonRow> {
+        fetch_one_json(&self.pool, sql, params).await
+    }
+
+    /// Fetch at most one row as a JSON object
+    pub async fn fetch_optional_json(&self, sql: &str, params: &[Value]) -> DatabaseResult<Option<JsonRow>> {
+        fetch_optional_json(&self.pool, sql, params).await
+    }
+
+    /// Fetch all rows as `T`
+    pub async fn fetch_all_as<T>(&self, sql: &str, params: &[Value]) -> DatabaseResult<Vec<T>>
+    where
+        T: for<'r> sqlx::FromRow<'r, PgRow> + Send + Unpin,
+    {
+        fetch_all_as(&self.pool, sql, params).await
+    }
+
+    /// Fetch exactly one row as `T`
+    pub async fn fetch_one_as<T>(&self, sql: &str, params: &[Value]) -> DatabaseResult<T>
+    where
+        T: for<'r> sqlx::FromRow<'r, PgRow> + Send + Unpin,
+    {
+        fetch_one_as(&self.pool, sql, params).await
+    }
+
+    /// Fetch at most one row as `T`
+    pub async fn fetch_optional_as<T>(&self, sql: &str, params: &[Value]) -> DatabaseResult<Option<T>>
+    where
+        T: for<'r> sqlx::FromRow<'r, PgRow> + Send + Unpin,
+    {
+        fetch_optional_as(&self.pool, sql, params).await
+    }
+}
+
+pub(crate) async fn execute<'c, E>(executor: E, sql: &str, params: &[Value]) -> DatabaseResult<u64>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let result = sqlx::query_with(sql, bind_params(params)?).execute(executor).await?;
+    Ok(result.rows_affected())
+}
+
+pub(crate) async fn fetch_rows<'c, E>(executor: E, sql: &str, params: &[Value]) -> DatabaseResult<Vec<PgRow>>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    Ok(sqlx::query_with(sql, bind_params(params)?).fetch_all(executor).await?)
+}
+
+pub(crate) async fn fetch_all_json<'c, E>(executor: E, sql: &str, params: &[Value]) -> DatabaseResult<Vec<JsonRow>>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let rows = sqlx::query_with(sql, bind_params(params)?).fetch_all(executor).await?;
+    rows.iter().map(row_to_json).collect()
+}
+
+pub(crate) async fn fetch_one_json<'c, E>(executor: E, sql: &str, params: &[Value]) -> DatabaseResult<JsonRow>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let row = sqlx::query_with(sql, bind_params(params)?).fetch_one(executor).await?;
+    row_to_json(&row)
+}
+
+pub(crate) async fn fetch_optional_json<'c, E>(executor: E, sql: &str, params: &[Value]) -> DatabaseResult<Option<JsonRow>>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let row = sqlx::query_with(sql, bind_params(params)?).fetch_optional(executor).await?;
+    row.as_ref().map(row_to_json).transpose()
+}
+
+pub(crate) async fn fetch_all_as<'c, E, T>(executor: E, sql: &str, params: &[Value]) -> DatabaseResult<Vec<T>>
+where
+    E: Executor<'c, Database = Postgres>,
+    T: for<'r> sqlx::FromRow<'r, PgRow> + Send + Unpin,
+{
+    Ok(sqlx::query_as_with(sql, bind_params(params)?).fetch_all(executor).await?)
+}
+
+pub(crate) async fn fetch_one_as<'c, E, T>(executor: E, sql: &str, params: &[Value]) -> DatabaseResult<T>
+where
+    E: Executor<'c, Database = Postgres>,
+    T: for<'r> sqlx::FromRow<'r, PgRow> + Send + Unpin,
+{
+    Ok(sqlx::query_as_with(sql, bind_params(params)?).fetch_one(executor).await?)
+}
+
+pub(crate) async fn fetch_optional_as<'c, E, T>(executor: E, sql: &str, params: &[Value]) -> DatabaseResult<Option<T>>
+where
+    E: Executor<'c, Database = Postgres>,
+    T: for<'r> sqlx::FromRow<'r, PgRow> + Send + Unpin,
+{
+    Ok(sqlx::query_as_with(sql, bind_params(params)?).fetch_optional(executor).await?)
+}
+
+/// Bind JSON parameters as `$1..$n` query arguments
+pub fn bind_params(params: &[Value]) -> DatabaseResult<PgArguments> {
+    let mut arguments = PgArguments::default();
+    for (index, value) in params.iter().enumerate() {
+        bind_value(&mut arguments, value)
+            .map_err(|e| DatabaseError::query(format!("Failed to bind parameter ${}: {}", index + 1, e)))?;
+    }
+    Ok(arguments)
+}
+
+/// Postgres type a tagged parameter is bound as
+///
+/// A tagged parameter is a single-key object such as
+/// `{"$uuid": "550e8400-e29b-41d4-a716-446655440000"}`. The tagged value may
+/// be a string, an array of strings (bound as an array of the type) or
+/// `null` (a typed `NULL`); `$jsonb` takes any JSON value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParamType {
+    /// `TEXT`, tagged `$text`, even for strings that look like UUIDs or timestamps
+    Text,
+    /// `UUID`, tagged `$uuid`
+    Uuid,
+    /// `TIMESTAMPTZ` from an RFC 3339 string, tagged `$timestamptz`
+    Timestamptz,
+    /// `TIMESTAMP` from a `2024-01-15T10:30:00` string, tagged `$timestamp`
+    Timestamp,
+    /// `DATE` from a `2024-01-15` string, tagged `$date`
+    Date,
+    /// `TIME` from a `10:30:00` string, tagged `$time`
+    Time,
+    /// `JSONB`, tagged `$jsonb`
+    Jsonb,
+}
+
+impl ParamType {
+    const ALL: [ParamType; 7] = [
+        ParamType::Text,
+        ParamType::Uuid,
+        ParamType::Timestamptz,
+        ParamType::Timestamp,
+        ParamType::Date,
+        ParamType::Time,
+        ParamType::Jsonb,
+    ];
+
+    /// Object key that marks a value as this type
+    pub fn tag_name(self) -> &'static str {
+        match self {
+            ParamType::Text => "$text",
+            ParamType::Uuid => "$uuid",
+            ParamType::Timestamptz => "$timestamptz",
+            ParamType::Timestamp => "$timestamp",
+            ParamType::Date => "$date",
+            ParamType::Time => "$time",
+            ParamType::Jsonb => "$jsonb",
+        }
+    }
+
+    /// Tag a value to be bound as this type
+    pub fn tag(self, value: impl Into<Value>) -> Value {
+        let mut object = Map::with_capacity(1);
+        object.insert(self.tag_name().to_string(), value.into());
+        Value::Object(object)
+    }
+
+    /// Type of a result column, if its values need a tag to be bound back
+    fn of_column(type_info: &PgTypeInfo) -> Option<Self> {
+        match type_info.name() {
+            "UUID" | "UUID[]" => Some(ParamType::Uuid),
+            "TIMESTAMPTZ" | "TIMESTAMPTZ[]" => Some(ParamType::Timestamptz),
+            "TIMESTAMP" => Some(ParamType::Timestamp),
+            "DATE" => Some(ParamType::Date),
+            "TIME" => Some(ParamType::Time),
+            "JSON" | "JSONB" => Some(ParamType::Jsonb),
+            _ => None,
+        }
+    }
+
+    /// Split a tagged parameter into its type and value
+    ///
+    /// Single-key objects whose key starts with `$` but is not a known tag are
+    /// rejected, so a typo does not silently bind as JSONB.
+    fn untag(object: &Map<String, Value>) -> Result<Option<(Self, &Value)>, BoxDynError> {
+        let mut entries = object.iter();
+        let (Some((key, value)), None) = (entries.next(), entries.next()) else {
+            return Ok(None);
+        };
+        if !key.starts_with('$') {
+            return Ok(None);
+        }
+        match Self::ALL.into_iter().find(|ty| ty.tag_name() == key) {
+            Some(ty) => Ok(Some((ty, value))),
+            None => Err(format!("unknown parameter type {}; wrap JSON values as {{\"$jsonb\": ...}}", key).into()),
+        }
+    }
+}
+
+fn bind_value(arguments: &mut PgArguments, value: &Value) -> Result<(), BoxDynError> {
+    match value {
+        Value::Null => arguments.add(UntypedNull),
+        Value::Bool(b) => arguments.add(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => arguments.add(i),
+            None => arguments.add(n.as_f64()),
+        },
+        Value::String(s) => match detect_string(s) {
+            DetectedString::Uuid(uuid) => arguments.add(uuid),
+            DetectedString::Timestamp(timestamp) => arguments.add(timestamp),
+            DetectedString::Text => arguments.add(s.clone()),
+        },
+        Value::Array(items) => bind_array(arguments, items),
+        Value::Object(object) => match ParamType::untag(object)? {
+            Some((ty, value)) => bind_tagged(arguments, ty, value),
+            None => arguments.add(sqlx::types::Json(value.clone())),
+        },
+    }
+}
+
+/// Bind an array of one scalar kind as a Postgres array, an array of objects or arrays as JSONB
+fn bind_array(arguments: &mut PgArguments, items: &[Value]) -> Result<(), BoxDynError> {
+    if let Some(values) = collect(items, Value::as_bool) {
+        return arguments.add(values);
+    }
+    if let Some(values) = collect(items, Value::as_i64) {
+        return arguments.add(values);
+    }
+    if let Some(values) = collect(items, |item| item.is_number().then(|| item.as_f64()).flatten()) {
+        return arguments.add(values);
+    }
+    if let Some(strings) = collect(items, Value::as_str) {
+        if let Some(values) = collect(&strings, |s| Uuid::parse_str(s).ok()) {
+            return arguments.add(values);
+        }
+        if let Some(values) = collect(&strings, |s| parse_timestamp(s)) {
+            return arguments.add(values);
+        }
+        return arguments.add(strings.into_iter().map(str::to_string).collect::<Vec<_>>());
+    }
+    if let Some((ty, values)) = common_tag(items) {
+        return bind_tagged(arguments, ty, &Value::Array(values));
+    }
+    if items.iter().all(|item| item.is_object() || item.is_array()) {
+        return arguments.add(sqlx::types::Json(Value::Array(items.to_vec())));
+    }
+    Err("array elements must all be booleans, numbers or strings; tag JSON arrays as {\"$jsonb\": [...]}".into())
+}
+
+/// Type and values of an array whose elements are all tagged with the same scalar type
+fn common_tag(items: &[Value]) -> Option<(ParamType, Vec<Value>)> {
+    let mut tagged = items.iter().map(|item| match item {
+        Value::Object(object) => ParamType::untag(object).ok().flatten(),
+        _ => None,
+    });
+    let (ty, first) = tagged.next()??;
+    if ty == ParamType::Jsonb {
+        return None;
+    }
+    let mut values = vec![first.clone()];
+    for item in tagged {
+        match item {
+            Some((other, value)) if other == ty => values.push(value.clone()),
+            _ => return None,
+        }
+    }
+    Some((ty, values))
+}
+
+fn collect<'a, I, T>(items: &'a [I], f: impl Fn(&'a I) -> Option<T>) -> Option<Vec<T>> {
+    items.iter().map(f).collect()
+}
+
+enum DetectedString {
+    Uuid(Uuid),
+    Timestamp(DateTime<Utc>),
+    Text,
+}
+
+/// Detect strings holding a UUID or an RFC 3339 timestamp
+fn detect_string(s: &str) -> DetectedString {
+    if let Ok(uuid) = Uuid::parse_str(s) {
+        DetectedString::Uuid(uuid)
+    } else if let Some(timestamp) = parse_timestamp(s) {
+        DetectedString::Timestamp(timestamp)
+    } else {
+        DetectedString::Text
+    }
+}
+
+fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s).ok().map(|t| t.with_timezone(&Utc))
+}
+
+fn bind_tagged(arguments: &mut PgArguments, ty: ParamType, value: &Value) -> Result<(), BoxDynError> {
+    match ty {
+        ParamType::Text => bind_parsed(arguments, ty, value, |s| Ok::<_, BoxDynError>(s.to_string())),
+        ParamType::Uuid => bind_parsed(arguments, ty, value, Uuid::parse_str),
+        ParamType::Timestamptz => bind_parsed(arguments, ty, value, |s| {
+            DateTime::parse_from_rfc3339(s).map(|t| t.with_timezone(&Utc))
+        }),
+        ParamType::Timestamp => bind_parsed(arguments, ty, value, str::parse::<NaiveDateTime>),
+        ParamType::Date => bind_parsed(arguments, ty, value, str::parse::<NaiveDate>),
+        ParamType::Time => bind_parsed(arguments, ty, value, str::parse::<NaiveTime>),
+        ParamType::Jsonb => arguments.add(sqlx::types::Json(value.clone())),
+    }
+}
+
+/// Bind a tagged string, array of strings or `null` as `T`
+fn bind_parsed<T, E>(
+    arguments: &mut PgArguments,
+    ty: ParamType,
+    value: &Value,
+    parse: impl Fn(&str) -> Result<T, E>,
+) -> Result<(), BoxDynError>
+where
+    T: for<'q> Encode<'q, Postgres> + Type<Postgres> + PgHasArrayType + 'static,
+    E: Into<BoxDynError>,
+{
+    let invalid = || format!("{} expects a string, an array of strings or null", ty.tag_name());
+    match value {
+        Value::Null => arguments.add(None::<T>),
+        Value::String(s) => arguments.add(parse(s).map_err(Into::into)?),
+        Value::Array(items) => {
+            let values = items
+                .iter()
+                .map(|item| match item.as_str() {
+                    Some(s) => parse(s).map_err(Into::into),
+                    None => Err(invalid().into()),
+                })
+                .collect::<Result<Vec<T>, BoxDynError>>()?;
+            arguments.add(values)
+        }
+        _ => Err(invalid().into()),
+    }
+}
+
+/// `NULL` sent without a type, so Postgres infers it from the statement
+struct UntypedNull;
+
+impl Type<Postgres> for UntypedNull {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_oid(Oid(0))
+    }
+}
+
+impl Encode<'_, Postgres> for UntypedNull {
+    fn encode_by_ref(&self, _buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        Ok(IsNull::Yes)
+    }
+}
+
+/// Convert a result row into a JSON object keyed by column name
+///
+/// Numeric, network and other types without a JSON mapping are rejected;
+/// cast them in SQL (for example `amount::float8` or `addr::text`).
+pub fn row_to_json(row: &PgRow) -> DatabaseResult<JsonRow> {
+    let mut object = Map::with_capacity(row.len());
+    for column in row.columns() {
+        let value = column_to_json(row, column.ordinal(), column.type_info())
+            .map_err(|e| DatabaseError::query(format!("Failed to decode column {}: {}", column.name(), e)))?;
+        object.insert(column.name().to_string(), value);
+    }
+    Ok(object)
+}
+
+/// Read a column as a parameter that binds back to the column's type
+///
+/// Values JSON has no literal for (UUIDs, timestamps, dates, JSON documents)
+/// are tagged with their [`ParamType`], so they can be sent back in a
+/// comparison such as a keyset cursor condition.
+pub fn column_param(row: &PgRow, column: &str) -> DatabaseResult<Value> {
+    let column = row
+        .try_column(column)
+        .map_err(|e| DatabaseError::query(format!("Failed to read column {}: {}", column, e)))?;
+    let value = column_to_json(row, column.ordinal(), column.type_info())
+        .map_err(|e| DatabaseError::query(format!("Failed to decode column {}: {}", column.name(), e)))?;
+    Ok(match ParamType::of_column(column.type_info()) {
+        Some(ty) if !value.is_null() => ty.tag(value),
+        _ => value,
+    })
+}
+
+fn column_to_json(row: &PgRow, index: usize, type_info: &PgTypeInfo) -> Result<Value, BoxDynError> {
+    match type_info.name() {
+        "BOOL" => decode::<bool>(row, index),
+        "INT2" => decode::<i16>(row, index),
+        "INT4" => decode::<i32>(row, index),
+        "INT8" => decode::<i64>(row, index),
+        "FLOAT4" => decode::<f32>(row, index),
+        "FLOAT8" => decode::<f64>(row, index),
+        "TEXT" | "VARCHAR" | "CHAR" | "NAME" => decode::<String>(row, index),
+        "UUID" => decode::<Uuid>(row, index),
+        "TIMESTAMPTZ" => decode::<DateTime<Utc>>(row, index),
+        "TIMESTAMP" => decode::<chrono::NaiveDateTime>(row, index),
+        "DATE" => decode::<chrono::NaiveDate>(row, index),
+        "TIME" => decode::<chrono::NaiveTime>(row, index),
+        "JSON" | "JSONB" => decode::<Value>(row, index),
+        "BYTEA" => decode::<Vec<u8>>(row, index),
+        "BOOL[]" => decode::<Vec<bool>>(row, index),
+        "INT2[]" => decode::<Vec<i16>>(row, index),
+        "INT4[]" => decode::<Vec<i32>>(row, index),
+        "INT8[]" => decode::<Vec<i64>>(row, index),
+        "FLOAT4[]" => decode::<Vec<f32>>(row, index),
+        "FLOAT8[]" => decode::<Vec<f64>>(row, index),
+        "TEXT[]" | "VARCHAR[]" | "CHAR[]" | "NAME[]" => decode::<Vec<String>>(row, index),
+        "UUID[]" => decode::<Vec<Uuid>>(row, index),
+        "TIMESTAMPTZ[]" => decode::<Vec<DateTime<Utc>>>(row, index),
+        "JSON[]" | "JSONB[]" => decode::<Vec<Value>>(row, index),
+        _ if matches!(type_info.kind(), PgTypeKind::Enum(_)) => {
+            // Enum labels use the text wire format
+            Ok(row.try_get_unchecked::<Option<String>, _>(index)?.map_or(Value::Null, Value::String))
+        }
+        other => Err(format!("unsupported column type {}", other).into()),
+    }
+}
+
+fn decode<'r, T>(row: &'r PgRow, index: usize) -> Result<Value, BoxDynError>
+where
+    T: Decode<'r, Postgres> + Type<Postgres> + serde::Serialize,
+{
+    let value = row.try_get::<Option<T>, _>(index)?;
+    Ok(serde_json::to_value(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use serde_json::json;
+
+    #[test]
+    fn test_string_detection() {
+        assert!(matches!(detect_string("550e8400-e29b-41d4-a716-446655440000"), DetectedString::Uuid(_)));
+        assert!(matches!(detect_string("2024-01-15T10:30:00Z"), DetectedString::Timestamp(_)));
+        assert!(matches!(detect_string("2024-01-15T10:30:00+02:00"), DetectedString::Timestamp(_)));
+        assert!(matches!(detect_string("2024-01-15"), DetectedString::Text));
+        assert!(matches!(detect_string("alice"), DetectedString::Text));
+    }
+
+    #[test]
+    fn test_tagged_params() {
+        let id = "550e8400-e29b-41d4-a716-446655440000";
+        assert_eq!(ParamType::Uuid.tag(id), json!({"$uuid": id}));
+
+        let params = vec![
+            ParamType::Uuid.tag(id),
+            ParamType::Uuid.tag(json!([id, id])),
+            ParamType::Uuid.tag(Value::Null),
+            ParamType::Timestamptz.tag("2024-01-15T10:30:00+02:00"),
+            ParamType::Timestamp.tag("2024-01-15T10:30:00"),
+            ParamType::Date.tag("2024-01-15"),
+            ParamType::Time.tag("10:30:00"),
+            ParamType::Jsonb.tag(json!(["a", 1])),
+            json!([ParamType::Uuid.tag(id), ParamType::Uuid.tag(id)]),
+            json!({"$not_a_tag": 1, "other": 2}),
+        ];
+        assert_eq!(bind_params(&params).unwrap().len(), params.len());
+
+        for invalid in [
+            ParamType::Uuid.tag("alice"),
+            ParamType::Timestamptz.tag("2024-01-15"),
+            ParamType::Uuid.tag(1),
+            json!({"$uid": id}),
+            json!(["a", 1]),
+        ] {
+            assert!(bind_params(&[invalid.clone()]).is_err(), "{} should be rejected", invalid);
+        }
+    }
+
+    #[test]
+    fn test_bind_params() {
+        let params = vec![
+            Value::Null,
+            json!(true),
+            json!(42),
+            json!(1.5),
+            json!("text"),
+            json!({"key": "value"}),
+            json!([1, 2, 3]),
+            json!([{"a": 1}, [2]]),
+            json!([]),
+        ];
+        let arguments = bind_params(&params).unwrap();
+        assert_eq!(arguments.len(), params.len());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_roundtrip_types() {
+        let pool = test_util::connect_pool().await;
+        let executor = QueryExecutor::new(pool);
+
+        let row = executor
+            .fetch_one_json(
+                "SELECT $1::int4 AS int, $2 AS flag, $3 AS ratio, $4 AS id, $5 AS at, $6 AS name, \
+                 $7 AS doc, $8 AS tags, $9::text AS missing, $10 = '550e8400-e29b-41d4-a716-446655440000'::text AS text_id, \
+                 pg_typeof($11)::text AS detected_id, pg_typeof($12)::text AS detected_at",
+                &[
+                    json!(7),
+                    json!(true),
+                    json!(0.25),
+                    ParamType::Uuid.tag("550e8400-e29b-41d4-a716-446655440000"),
+                    ParamType::Timestamptz.tag("2024-01-15T10:30:00Z"),
+                    json!("alice"),
+                    json!({"nested": [1, 2]}),
+                    json!(["a", "b"]),
+                    Value::Null,
+                    ParamType::Text.tag("550e8400-e29b-41d4-a716-446655440000"),
+                    json!("550e8400-e29b-41d4-a716-446655440000"),
+                    json!("2024-01-15T10:30:00Z"),
+                ],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(row["int"], json!(7));
+        assert_eq!(row["flag"], json!(true));
+        assert_eq!(row["ratio"], json!(0.25));
+        assert_eq!(row["id"], json!("550e8400-e29b-41d4-a716-446655440000"));
+        assert_eq!(row["at"], json!("2024-01-15T10:30:00Z"));
+        assert_eq!(row["name"], json!("alice"));
+        assert_eq!(row["doc"], json!({"nested": [1, 2]}));
+        assert_eq!(row["tags"], json!(["a", "b"]));
+        assert_eq!(row["missing"], Value::Null);
+        assert_eq!(row["text_id"], json!(true));
+        assert_eq!(row["detected_id"], json!("uuid"));
+        assert_eq!(row["detected_at"], json!("timestamp with time zone"));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_execute_and_fetch_as() {
+        let pool = test_util::connect_pool().await;
+        let executor = QueryExecutor::new(pool);
+        let table = format!("executor_test_{}", Uuid::new_v4().simple());
+
+        executor
+            .execute(&format!("CREATE TABLE {} (id INT4 PRIMARY KEY, name TEXT, score INT4)", table), &[])
+            .await
+            .unwrap();
+        let inserted = executor
+            .execute(
+                &format!("INSERT INTO {} (id, name, score) VALUES ($1, $2, $3), ($4, $5, $6)", table),
+                &[json!(1), json!("alice"), Value::Null, json!(2), json!("bob"), json!(10)],
+            )
+            .await
+            .unwrap();
+        assert_eq!(inserted, 2);
+
+        let rows: Vec<(i32, String)> = executor
+            .fetch_all_as(&format!("SELECT id, name FROM {} WHERE id = ANY($1) ORDER BY id", table), &[json!([1, 2])])
+            .await
+            .unwrap();
+        assert_eq!(rows, vec![(1, "alice".to_string()), (2, "bob".to_string())]);
+
+        let missing = executor
+            .fetch_optional_json(&format!("SELECT * FROM {} WHERE id = $1", table), &[json!(3)])
+            .await
+            .unwrap();
+        assert!(missing.is_none());
+
+        executor.execute(&format!("DROP TABLE {}", table), &[]).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_transaction_binds_params() {
+        let pool = test_util::connect_pool().await;
+        let mut tx = crate::DatabaseTransaction::new(pool.begin().await.unwrap());
+
+        tx.execute("CREATE TEMPORARY TABLE tx_params (id UUID, created_at TIMESTAMPTZ)", &[]).await.unwrap();
+        let id = Uuid::new_v4();
+        tx.execute(
+            "INSERT INTO tx_params (id, created_at) VALUES ($1, $2)",
+            &[ParamType::Uuid.tag(id.to_string()), ParamType::Timestamptz.tag("2024-01-15T10:30:00Z")],
+        )
+        .await
+        .unwrap();
+
+        let (found,): (Uuid,) = tx
+            .fetch_one_as("SELECT id FROM tx_params WHERE id = $1", &[ParamType::Uuid.tag(id.to_string())])
+            .await
+            .unwrap();
+        assert_eq!(found, id);
+        let rows = tx.fetch_all_json("SELECT created_at FROM tx_params", &[]).await.unwrap();
+        assert_eq!(rows[0]["created_at"], json!("2024-01-15T10:30:00Z"));
+
+        // Values read with column_param bind back as the column's type
+        let row = tx.fetch_rows("SELECT id, created_at FROM tx_params", &[]).await.unwrap().remove(0);
+        let params = [column_param(&row, "id").unwrap(), column_param(&row, "created_at").unwrap()];
+        assert_eq!(params[0], ParamType::Uuid.tag(id.to_string()));
+        let (matched,): (i64,) = tx
+            .fetch_one_as("SELECT count(*) FROM tx_params WHERE (id, created_at) = ($1, $2)", &params)
+            .await
+            .unwrap();
+        assert_eq!(matched, 1);
+
+        tx.rollback().await.unwrap();
+    }
+}
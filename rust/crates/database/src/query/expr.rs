@@ -0,0 +1,295 @@
+//! Typed WHERE expressions
+//!
+//! [`Expr`] replaces hand-written condition strings. Column names are
+//! [`Ident`]s and every value is rendered as a bound parameter, so no part
+//! of an expression is interpolated from caller data.
+
+use serde_json::Value;
+use super::ident::Ident;
+
+/// Comparison operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Like,
+    ILike,
+    /// JSONB or array containment (`@>`)
+    Contains,
+}
+
+impl CompareOp {
+    /// SQL operator
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Eq => "=",
+            Self::NotEq => "<>",
+            Self::Lt => "<",
+            Self::LtEq => "<=",
+            Self::Gt => ">",
+            Self::GtEq => ">=",
+            Self::Like => "LIKE",
+            Self::ILike => "ILIKE",
+            Self::Contains => "@>",
+        }
+    }
+}
+
+/// Boolean expression for WHERE clauses
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// `column <op> $n`
+    Compare { column: Ident, op: CompareOp, value: Value },
+    /// `column = ANY($n)`, or `column <> ALL($n)` when negated
+    In { column: Ident, values: Vec<Value>, negated: bool },
+    /// `column IS NULL`, or `IS NOT NULL` when negated
+    IsNull { column: Ident, negated: bool },
+    /// Row comparison `(a, b) <op> ($n, $n+1)`, used for keyset pagination
+    RowCompare { columns: Vec<Ident>, op: CompareOp, values: Vec<Value> },
+    /// All expressions hold (`TRUE` when empty)
+    And(Vec<Expr>),
+    /// Any expression holds (`FALSE` when empty)
+    Or(Vec<Expr>),
+    /// Negation
+    Not(Box<Expr>),
+}
+
+impl Expr {
+    /// Compare a column with a value
+    pub fn compare(column: Ident, op: CompareOp, value: impl Into<Value>) -> Self {
+        Self::Compare { column, op, value: value.into() }
+    }
+
+    /// `column = value`
+    pub fn eq(column: Ident, value: impl Into<Value>) -> Self {
+        Self::compare(column, CompareOp::Eq, value)
+    }
+
+    /// `column <> value`
+    pub fn ne(column: Ident, value: impl Into<Value>) -> Self {
+        Self::compare(column, CompareOp::NotEq, value)
+    }
+
+    /// `column < value`
+    pub fn lt(column: Ident, value: impl Into<Value>) -> Self {
+        Self::compare(column, CompareOp::Lt, value)
+    }
+
+    /// `column <= value`
+    pub fn lte(column: Ident, value: impl Into<Value>) -> Self {
+        Self::compare(column, CompareOp::LtEq, value)
+    }
+
+    /// `column > value`
+    pub fn gt(column: Ident, value: impl Into<Value>) -> Self {
+        Self::compare(column, CompareOp::Gt, value)
+    }
+
+    /// `column >= value`
+    pub fn gte(column: Ident, value: impl Into<Value>) -> Self {
+        Self::compare(column, CompareOp::GtEq, value)
+    }
+
+    /// `column LIKE pattern`
+    pub fn like(column: Ident, pattern: impl Into<String>) -> Self {
+        Self::compare(column, CompareOp::Like, pattern.into())
+    }
+
+    /// `column ILIKE pattern`
+    pub fn ilike(column: Ident, pattern: impl Into<String>) -> Self {
+        Self::compare(column, CompareOp::ILike, pattern.into())
+    }
+
+    /// `column` is one of `values`
+    pub fn is_in<V: Into<Value>>(column: Ident, values: impl IntoIterator<Item = V>) -> Self {
+        Self::In {
+            column,
+            values: values.into_iter().map(Into::into).collect(),
+            negated: false,
+        }
+    }
+
+    /// `column` is none of `values`
+    pub fn not_in<V: Into<Value>>(column: Ident, values: impl IntoIterator<Item = V>) -> Self {
+        Self::In {
+            column,
+            values: values.into_iter().map(Into::into).collect(),
+            negated: true,
+        }
+    }
+
+    /// `column IS NULL`
+    pub fn is_null(column: Ident) -> Self {
+        Self::IsNull { column, negated: false }
+    }
+
+    /// `column IS NOT NULL`
+    pub fn is_not_null(column: Ident) -> Self {
+        Self::IsNull { column, negated: true }
+    }
+
+    /// All of the expressions
+    pub fn all(exprs: impl IntoIterator<Item = Expr>) -> Self {
+        Self::And(exprs.into_iter().collect())
+    }
+
+    /// Any of the expressions
+    pub fn any(exprs: impl IntoIterator<Item = Expr>) -> Self {
+        Self::Or(exprs.into_iter().collect())
+    }
+
+    /// Combine with another expression using AND
+    pub fn and(self, other: Expr) -> Self {
+        match self {
+            Self::And(mut exprs) => {
+                exprs.push(other);
+                Self::And(exprs)
+            }
+            expr => Self::And(vec![expr, other]),
+        }
+    }
+
+    /// Combine with another expression using OR
+    pub fn or(self, other: Expr) -> Self {
+        match self {
+            Self::Or(mut exprs) => {
+                exprs.push(other);
+                Self::Or(exprs)
+            }
+            expr => Self::Or(vec![expr, other]),
+        }
+    }
+
+    /// Render the expression, appending its values to `params`
+    ///
+    /// Placeholders continue numbering from the current length of `params`.
+    pub fn to_sql(&self, params: &mut Vec<Value>) -> String {
+        match self {
+            Self::Compare { column, op, value } => {
+                params.push(value.clone());
+                format!("{} {} ${}", column, op.as_sql(), params.len())
+            }
+            // An empty array has no element type to infer, so render the constant result
+            Self::In { values, negated, .. } if values.is_empty() => {
+                if *negated { "TRUE" } else { "FALSE" }.to_string()
+            }
+            Self::In { column, values, negated } => {
+                params.push(Value::Array(values.clone()));
+                if *negated {
+                    format!("{} <> ALL(${})", column, params.len())
+                } else {
+                    format!("{} = ANY(${})", column, params.len())
+                }
+            }
+            Self::IsNull { column, negated } => {
+                format!("{} IS {}NULL", column, if *negated { "NOT " } else { "" })
+            }
+            Self::RowCompare { columns, op, values } => {
+                let columns: Vec<String> = columns.iter().map(Ident::to_sql).collect();
+                let placeholders: Vec<String> = values
+                    .iter()
+                    .map(|value| {
+                        params.push(value.clone());
+                        format!("${}", params.len())
+                    })
+                    .collect();
+                format!("({}) {} ({})", columns.join(", "), op.as_sql(), placeholders.join(", "))
+            }
+            Self::And(exprs) => join(exprs, " AND ", "TRUE", params),
+            Self::Or(exprs) => join(exprs, " OR ", "FALSE", params),
+            Self::Not(expr) => format!("NOT ({})", expr.to_sql(params)),
+        }
+    }
+}
+
+impl std::ops::Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Self::Output {
+        Expr::Not(Box::new(self))
+    }
+}
+
+fn join(exprs: &[Expr], separator: &str, empty: &str, params: &mut Vec<Value>) -> String {
+    match exprs {
+        [] => empty.to_string(),
+        [expr] => expr.to_sql(params),
+        exprs => exprs
+            .iter()
+            .map(|expr| format!("({})", expr.to_sql(params)))
+            .collect::<Vec<_>>()
+            .join(separator),
+    }
+}
+
+/// Escape `%`, `_` and `\` so a value matches literally inside a LIKE pattern
+pub fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn col(name: &str) -> Ident {
+        Ident::new(name).unwrap()
+    }
+
+    #[test]
+    fn test_expression_rendering() {
+        let expr = Expr::eq(col("active"), true)
+            .and(Expr::gt(col("age"), 18).or(Expr::is_null(col("age"))))
+            .and(!Expr::is_in(col("role"), ["guest", "banned"]));
+
+        let mut params = Vec::new();
+        let sql = expr.to_sql(&mut params);
+
+        assert_eq!(
+            sql,
+            "(\"active\" = $1) AND ((\"age\" > $2) OR (\"age\" IS NULL)) AND (NOT (\"role\" = ANY($3)))"
+        );
+        assert_eq!(params, vec![json!(true), json!(18), json!(["guest", "banned"])]);
+    }
+
+    #[test]
+    fn test_row_compare() {
+        let expr = Expr::RowCompare {
+            columns: vec![col("created_at"), col("id")],
+            op: CompareOp::Gt,
+            values: vec![json!("2024-01-01T00:00:00Z"), json!(7)],
+        };
+        let mut params = Vec::new();
+        assert_eq!(expr.to_sql(&mut params), "(\"created_at\", \"id\") > ($1, $2)");
+        assert_eq!(params.len(), 2);
+    }
+
+    #[test]
+    fn test_placeholders_continue_numbering() {
+        let mut params = vec![json!("existing")];
+        let sql = Expr::ne(col("name"), "bob").to_sql(&mut params);
+        assert_eq!(sql, "\"name\" <> $2");
+    }
+
+    #[test]
+    fn test_empty_groups_and_lists() {
+        let mut params = Vec::new();
+        assert_eq!(Expr::all([]).to_sql(&mut params), "TRUE");
+        assert_eq!(Expr::any([]).to_sql(&mut params), "FALSE");
+        assert_eq!(Expr::is_in(col("id"), Vec::<i64>::new()).to_sql(&mut params), "FALSE");
+        assert_eq!(Expr::not_in(col("id"), Vec::<i64>::new()).to_sql(&mut params), "TRUE");
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn test_values_are_never_interpolated() {
+        let mut params = Vec::new();
+        let sql = Expr::eq(col("name"), "x' OR '1'='1").to_sql(&mut params);
+        assert_eq!(sql, "\"name\" = $1");
+        assert_eq!(escape_like("50%_off\\"), "50\\%\\_off\\\\");
+    }
+}
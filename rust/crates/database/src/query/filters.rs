@@ -1,6 +1,10 @@
 //! Query filtering and condition building
 
+use cloudshuttle_error_handling::database_error::{DatabaseError, DatabaseResult};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use super::expr::{escape_like, Expr};
+use super::ident::{ColumnAllowlist, Ident};
 
 /// Sorting options
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,7 +13,7 @@ pub struct SortOption {
     pub direction: SortDirection,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SortDirection {
     #[serde(rename = "asc")]
     Ascending,
@@ -32,11 +36,17 @@ impl SortOption {
         }
     }
 
-    pub fn to_sql(&self) -> String {
-        match self.direction {
-            SortDirection::Ascending => self.field.clone(),
-            SortDirection::Descending => format!("{} DESC", self.field),
-        }
+    /// Resolve the sort field through an allowlist
+    pub fn resolve(&self, allowlist: &ColumnAllowlist) -> DatabaseResult<(Ident, SortDirection)> {
+        Ok((allowlist.resolve(&self.field)?, self.direction))
+    }
+
+    pub fn to_sql(&self, allowlist: &ColumnAllowlist) -> DatabaseResult<String> {
+        let column = allowlist.resolve(&self.field)?;
+        Ok(match self.direction {
+            SortDirection::Ascending => column.to_sql(),
+            SortDirection::Descending => format!("{} DESC", column),
+        })
     }
 }
 
@@ -84,7 +94,7 @@ pub enum FilterOperator {
 
 impl FilterOperator {
     /// Convert operator to SQL condition fragment
-    pub fn to_sql_condition(&self, field: &str, param_index: usize) -> String {
+    pub fn to_sql_condition(&self, field: &Ident, param_index: usize) -> String {
         match self {
             FilterOperator::Equal => format!("{} = ${}", field, param_index),
             FilterOperator::NotEqual => format!("{} != ${}", field, param_index),
@@ -157,6 +167,73 @@ pub enum FilterCondition {
     Group(FilterGroup),
 }
 
+impl FilterOption {
+    /// Convert to a typed expression, resolving the field through an allowlist
+    pub fn to_expr(&self, allowlist: &ColumnAllowlist) -> DatabaseResult<Expr> {
+        FilterValidator::validate_filter(self).map_err(DatabaseError::query)?;
+        let column = allowlist.resolve(&self.field)?;
+        let value = self.value.clone();
+        let param = |value| allowlist.param(&self.field, value);
+
+        Ok(match self.operator {
+            FilterOperator::Equal => Expr::eq(column, param(value)),
+            FilterOperator::NotEqual => Expr::ne(column, param(value)),
+            FilterOperator::GreaterThan => Expr::gt(column, param(value)),
+            FilterOperator::LessThan => Expr::lt(column, param(value)),
+            FilterOperator::GreaterThanOrEqual => Expr::gte(column, param(value)),
+            FilterOperator::LessThanOrEqual => Expr::lte(column, param(value)),
+            FilterOperator::Like => Expr::like(column, text(&value)),
+            FilterOperator::In => Expr::is_in(column, array(value).into_iter().map(param)),
+            FilterOperator::NotIn => Expr::not_in(column, array(value).into_iter().map(param)),
+            FilterOperator::IsNull => Expr::is_null(column),
+            FilterOperator::IsNotNull => Expr::is_not_null(column),
+            FilterOperator::Between => {
+                let mut bounds = array(value).into_iter();
+                let (low, high) = (bounds.next().unwrap_or_default(), bounds.next().unwrap_or_default());
+                let (low, high) = (param(low), param(high));
+                Expr::all([Expr::gte(column.clone(), low), Expr::lte(column, high)])
+            }
+            FilterOperator::Contains => Expr::compare(column, super::expr::CompareOp::Contains, value),
+            FilterOperator::StartsWith => Expr::like(column, format!("{}%", escape_like(&text(&value)))),
+            FilterOperator::EndsWith => Expr::like(column, format!("%{}", escape_like(&text(&value)))),
+        })
+    }
+}
+
+impl FilterCondition {
+    /// Convert to a typed expression, resolving fields through an allowlist
+    pub fn to_expr(&self, allowlist: &ColumnAllowlist) -> DatabaseResult<Expr> {
+        match self {
+            FilterCondition::Filter(filter) => filter.to_expr(allowlist),
+            FilterCondition::Group(group) => {
+                let exprs = group
+                    .filters
+                    .iter()
+                    .map(|condition| condition.to_expr(allowlist))
+                    .collect::<DatabaseResult<Vec<_>>>()?;
+                Ok(match group.operator {
+                    FilterGroupOperator::And => Expr::And(exprs),
+                    FilterGroupOperator::Or => Expr::Or(exprs),
+                })
+            }
+        }
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn array(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(values) => values,
+        value => vec![value],
+    }
+}
+
 /// Query filter builder for complex filtering logic
 pub struct FilterBuilder {
     conditions: Vec<FilterCondition>,
@@ -258,23 +335,60 @@ impl FilterValidator {
 mod tests {
     use super::*;
 
+    fn id(name: &str) -> Ident {
+        Ident::new(name).unwrap()
+    }
+
     #[test]
     fn test_sort_option() {
+        let allowlist = ColumnAllowlist::new(["name", "created_at"]).unwrap();
+
         let asc = SortOption::asc("name");
         assert_eq!(asc.field, "name");
-        assert_eq!(asc.to_sql(), "name");
+        assert_eq!(asc.to_sql(&allowlist).unwrap(), "\"name\"");
 
         let desc = SortOption::desc("created_at");
         assert_eq!(desc.field, "created_at");
-        assert_eq!(desc.to_sql(), "created_at DESC");
+        assert_eq!(desc.to_sql(&allowlist).unwrap(), "\"created_at\" DESC");
+
+        assert!(SortOption::asc("name DESC; DROP TABLE users").to_sql(&allowlist).is_err());
     }
 
     #[test]
     fn test_filter_operator_sql() {
-        assert_eq!(FilterOperator::Equal.to_sql_condition("field", 1), "field = $1");
-        assert_eq!(FilterOperator::GreaterThan.to_sql_condition("age", 2), "age > $2");
-        assert_eq!(FilterOperator::Like.to_sql_condition("name", 3), "name LIKE $3");
-        assert_eq!(FilterOperator::IsNull.to_sql_condition("deleted_at", 1), "deleted_at IS NULL");
+        assert_eq!(FilterOperator::Equal.to_sql_condition(&id("field"), 1), "\"field\" = $1");
+        assert_eq!(FilterOperator::GreaterThan.to_sql_condition(&id("age"), 2), "\"age\" > $2");
+        assert_eq!(FilterOperator::Like.to_sql_condition(&id("name"), 3), "\"name\" LIKE $3");
+        assert_eq!(FilterOperator::IsNull.to_sql_condition(&id("deleted_at"), 1), "\"deleted_at\" IS NULL");
+    }
+
+    #[test]
+    fn test_filter_condition_to_expr() {
+        let allowlist = ColumnAllowlist::new(["name", "age"]).unwrap();
+        let condition = FilterCondition::Group(FilterBuilder::or_group(vec![
+            FilterCondition::Filter(FilterOption {
+                field: "name".to_string(),
+                operator: FilterOperator::StartsWith,
+                value: serde_json::json!("50%"),
+            }),
+            FilterCondition::Filter(FilterOption {
+                field: "age".to_string(),
+                operator: FilterOperator::Between,
+                value: serde_json::json!([18, 65]),
+            }),
+        ]));
+
+        let mut params = Vec::new();
+        let sql = condition.to_expr(&allowlist).unwrap().to_sql(&mut params);
+        assert_eq!(sql, "(\"name\" LIKE $1) OR ((\"age\" >= $2) AND (\"age\" <= $3))");
+        assert_eq!(params, vec![serde_json::json!("50\\%%"), serde_json::json!(18), serde_json::json!(65)]);
+
+        let unknown = FilterOption {
+            field: "password".to_string(),
+            operator: FilterOperator::Equal,
+            value: serde_json::json!("x"),
+        };
+        assert!(unknown.to_expr(&allowlist).is_err());
     }
 
     #[test]
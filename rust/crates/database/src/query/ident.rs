@@ -0,0 +1,271 @@
+//! SQL identifiers and column allowlists
+//!
+//! Table and column names cannot be bound as parameters, so every name that
+//! reaches generated SQL goes through [`Ident`], which validates it and
+//! renders it double-quoted. Names coming from requests (sort fields, filter
+//! fields) should be resolved through a [`ColumnAllowlist`] instead of being
+//! turned into identifiers directly.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use cloudshuttle_error_handling::database_error::{DatabaseError, DatabaseResult};
+use serde_json::Value;
+use super::executor::ParamType;
+
+/// Maximum identifier length in Postgres (NAMEDATALEN - 1)
+const MAX_IDENT_LENGTH: usize = 63;
+
+/// A validated, possibly qualified SQL identifier (`column`, `table.column`, `schema.table.column`)
+///
+/// Each part must start with a letter or underscore and contain only ASCII
+/// letters, digits, underscores and `$`. Parts are always quoted when
+/// rendered, so names are case-sensitive: `Ident::new("users")` renders as
+/// `"users"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Ident {
+    parts: Vec<String>,
+}
+
+impl Ident {
+    /// Parse and validate an identifier
+    pub fn new(name: impl AsRef<str>) -> DatabaseResult<Self> {
+        let name = name.as_ref();
+        let parts: Vec<&str> = name.split('.').collect();
+        if parts.len() > 3 {
+            return Err(DatabaseError::invalid_identifier(name, "too many qualifiers"));
+        }
+        for part in &parts {
+            validate_part(name, part)?;
+        }
+
+        Ok(Self {
+            parts: parts.into_iter().map(str::to_string).collect(),
+        })
+    }
+
+    /// Qualify this identifier with a table or schema
+    pub fn qualified_by(&self, qualifier: &Ident) -> DatabaseResult<Self> {
+        let parts: Vec<String> = qualifier.parts.iter().chain(&self.parts).cloned().collect();
+        if parts.len() > 3 {
+            return Err(DatabaseError::invalid_identifier(parts.join("."), "too many qualifiers"));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Unqualified name (the last part)
+    pub fn name(&self) -> &str {
+        self.parts.last().map(String::as_str).unwrap_or_default()
+    }
+
+    /// Render the identifier as quoted SQL
+    pub fn to_sql(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for Ident {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, part) in self.parts.iter().enumerate() {
+            if index > 0 {
+                f.write_str(".")?;
+            }
+            // Validation rules out quotes, but escape anyway so rendering is safe on its own
+            write!(f, "\"{}\"", part.replace('"', "\"\""))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Ident {
+    type Err = DatabaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl TryFrom<&str> for Ident {
+    type Error = DatabaseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<String> for Ident {
+    type Error = DatabaseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+fn validate_part(name: &str, part: &str) -> DatabaseResult<()> {
+    let reason = if part.is_empty() {
+        "empty name"
+    } else if part.len() > MAX_IDENT_LENGTH {
+        "longer than 63 bytes"
+    } else if !part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        "must start with a letter or underscore"
+    } else if !part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$') {
+        "may only contain letters, digits, underscores and $"
+    } else {
+        return Ok(());
+    };
+    Err(DatabaseError::invalid_identifier(name, reason))
+}
+
+/// Columns that callers may refer to by name, e.g. in sort or filter parameters
+///
+/// Each entry maps a public field name to a column, so API field names can
+/// differ from column names (`createdAt` to `created_at`). Fields can also
+/// declare a [`ParamType`], which overrides the executor's detection of
+/// string values: a `TEXT` field keeps UUID-looking values as text, and
+/// `TIMESTAMP` or `DATE` fields get their strings bound as that type.
+#[derive(Debug, Clone, Default)]
+pub struct ColumnAllowlist {
+    columns: BTreeMap<String, Ident>,
+    types: BTreeMap<String, ParamType>,
+}
+
+impl ColumnAllowlist {
+    /// Create an allowlist where each column is exposed under its own name
+    pub fn new<I, S>(columns: I) -> DatabaseResult<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        columns
+            .into_iter()
+            .try_fold(Self::default(), |allowlist, column| {
+                let column = column.as_ref();
+                allowlist.with_alias(column, column)
+            })
+    }
+
+    /// Expose a column under a different field name
+    pub fn with_alias(mut self, field: impl Into<String>, column: impl AsRef<str>) -> DatabaseResult<Self> {
+        self.columns.insert(field.into(), Ident::new(column)?);
+        Ok(self)
+    }
+
+    /// Bind string values for a field as `ty` instead of detecting their type
+    pub fn with_type(mut self, field: impl Into<String>, ty: ParamType) -> Self {
+        self.types.insert(field.into(), ty);
+        self
+    }
+
+    /// Declared parameter type of a field
+    pub fn param_type(&self, field: &str) -> Option<ParamType> {
+        self.types.get(field).copied()
+    }
+
+    /// Tag a string value for a field with the field's declared type
+    pub fn param(&self, field: &str, value: Value) -> Value {
+        match self.param_type(field) {
+            Some(ty) if value.is_string() => ty.tag(value),
+            _ => value,
+        }
+    }
+
+    /// Resolve a field name to its column
+    pub fn resolve(&self, field: &str) -> DatabaseResult<Ident> {
+        self.columns
+            .get(field)
+            .cloned()
+            .ok_or_else(|| DatabaseError::invalid_identifier(field, "not an allowed column"))
+    }
+
+    /// Check if a field name is allowed
+    pub fn contains(&self, field: &str) -> bool {
+        self.columns.contains_key(field)
+    }
+
+    /// Allowed field names
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.columns.keys().map(String::as_str)
+    }
+}
+
+/// Table and column metadata for an entity
+pub trait EntityColumns {
+    /// Table name
+    const TABLE: &'static str;
+
+    /// Columns that may be selected, filtered and sorted on
+    const COLUMNS: &'static [&'static str];
+
+    /// Parameter types of columns whose string values should not be detected
+    const COLUMN_TYPES: &'static [(&'static str, ParamType)] = &[];
+
+    /// Table identifier
+    fn table() -> DatabaseResult<Ident> {
+        Ident::new(Self::TABLE)
+    }
+
+    /// Allowlist of the entity's columns
+    fn allowlist() -> DatabaseResult<ColumnAllowlist> {
+        let allowlist = ColumnAllowlist::new(Self::COLUMNS)?;
+        Ok(Self::COLUMN_TYPES
+            .iter()
+            .fold(allowlist, |allowlist, (column, ty)| allowlist.with_type(*column, *ty)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ident_validation_and_quoting() {
+        assert_eq!(Ident::new("users").unwrap().to_sql(), "\"users\"");
+        assert_eq!(Ident::new("public.users").unwrap().to_sql(), "\"public\".\"users\"");
+        assert_eq!(Ident::new("createdAt").unwrap().to_sql(), "\"createdAt\"");
+        assert_eq!(Ident::new("public.users.id").unwrap().name(), "id");
+
+        for invalid in ["", "1name", "name; DROP TABLE users", "na\"me", "a..b", "a.b.c.d", &"x".repeat(64)] {
+            assert!(Ident::new(invalid).is_err(), "{} should be rejected", invalid);
+        }
+    }
+
+    #[test]
+    fn test_qualified_by() {
+        let column = Ident::new("id").unwrap();
+        let table = Ident::new("users").unwrap();
+        assert_eq!(column.qualified_by(&table).unwrap().to_sql(), "\"users\".\"id\"");
+    }
+
+    #[test]
+    fn test_column_allowlist() {
+        let allowlist = ColumnAllowlist::new(["name", "email"])
+            .unwrap()
+            .with_alias("createdAt", "created_at")
+            .unwrap();
+
+        assert_eq!(allowlist.resolve("createdAt").unwrap().to_sql(), "\"created_at\"");
+        assert!(allowlist.contains("name"));
+
+        let error = allowlist.resolve("password_hash").unwrap_err();
+        assert!(matches!(error, DatabaseError::InvalidIdentifier { .. }));
+        assert!(allowlist.resolve("name; --").is_err());
+    }
+
+    #[test]
+    fn test_entity_columns() {
+        struct User;
+        impl EntityColumns for User {
+            const TABLE: &'static str = "users";
+            const COLUMNS: &'static [&'static str] = &["id", "name"];
+            const COLUMN_TYPES: &'static [(&'static str, ParamType)] = &[("name", ParamType::Text)];
+        }
+
+        assert_eq!(User::table().unwrap().to_sql(), "\"users\"");
+        let allowlist = User::allowlist().unwrap();
+        assert_eq!(allowlist.fields().collect::<Vec<_>>(), vec!["id", "name"]);
+
+        // Declared types override detection; undeclared fields are left to it
+        let uuid = Value::from("550e8400-e29b-41d4-a716-446655440000");
+        assert_eq!(allowlist.param("name", uuid.clone()), ParamType::Text.tag(uuid.clone()));
+        assert_eq!(allowlist.param("id", uuid.clone()), uuid);
+    }
+}
@@ -1,20 +1,30 @@
 //! Query building and execution utilities
 //!
 //! This module provides comprehensive query building, filtering, sorting,
-//! batch operations, and execution options for database operations.
+//! batch operations, execution options, keyset pagination and JSON-parameter execution for database operations.
 
 pub mod builder;
+pub mod ident;
+pub mod expr;
 pub mod results;
 pub mod filters;
 pub mod batch;
 pub mod options;
+pub mod executor;
+pub mod batch_executor;
+pub mod cursor;
 
 // Re-export commonly used types for convenience
-pub use builder::QueryBuilder;
-pub use results::{QueryResult, PaginatedResult, QueryStats, QueryExecutionContext as QueryResultContext};
+pub use builder::{QueryBuilder, JoinKind};
+pub use ident::{Ident, ColumnAllowlist, EntityColumns};
+pub use expr::{Expr, CompareOp};
+pub use results::{QueryResult, PaginatedResult, CursorPaginatedResult, QueryStats, QueryExecutionContext as QueryResultContext};
 pub use filters::{SortOption, SortDirection, FilterOption, FilterOperator, FilterBuilder};
 pub use batch::{BatchOperation, BatchResult, BatchBuilder, BatchOptions};
 pub use options::{QueryOptions, QueryExecutionContext, QueryMetrics, QueryHints};
+pub use executor::{QueryExecutor, JsonRow, ParamType, bind_params, column_param, row_to_json};
+pub use batch_executor::BatchExecutor;
+pub use cursor::{Cursor, CursorCodec, CursorDirection, CursorPagination};
 
 // Re-export all types for backward compatibility
 pub use filters::*;
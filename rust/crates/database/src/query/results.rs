@@ -122,6 +122,46 @@ impl<T> PaginatedResult<T> {
     }
 }
 
+/// Keyset-paginated query result
+///
+/// Cursors are opaque tokens for the rows after the last item and before the
+/// first item; each is `None` when there is no page in that direction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CursorPaginatedResult<T> {
+    pub data: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+    pub page_size: u32,
+    pub has_next: bool,
+    pub has_previous: bool,
+}
+
+impl<T> CursorPaginatedResult<T> {
+    pub fn new(
+        data: Vec<T>,
+        next_cursor: Option<String>,
+        prev_cursor: Option<String>,
+        page_size: u32,
+    ) -> Self {
+        Self {
+            has_next: next_cursor.is_some(),
+            has_previous: prev_cursor.is_some(),
+            data,
+            next_cursor,
+            prev_cursor,
+            page_size,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+}
+
 /// Query execution statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryStats {
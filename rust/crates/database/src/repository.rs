@@ -0,0 +1,702 @@
+//! Repository implementations for [`Entity`] types
+//!
+//! [`PgRepository`] implements the repository traits against a `PgPool` for
+//! any entity with a table mapping, usually from `#[derive(Entity)]`:
+//!
+//! - soft-deleted rows are hidden from the finders and `count`, except `find_deleted`
+//! - `save` upserts on the id column; on update it keeps `created_at` and the
+//!   tenant column, sets `updated_at` to `now()` and increments the version,
+//!   and it refuses to overwrite a row that belongs to another tenant
+//! - `save_versioned` only updates a row that is still at the expected version
+//! - every change to an audited entity is written to its audit table in the
+//!   same transaction
+//!
+//! The audit table needs the [`AuditEntry`] columns:
+//!
+//! ```sql
+//! CREATE TABLE audit_log (
+//!     id UUID PRIMARY KEY,
+//!     entity_id UUID NOT NULL,
+//!     entity_type TEXT NOT NULL,
+//!     action TEXT NOT NULL,
+//!     old_values JSONB,
+//!     new_values JSONB,
+//!     user_id UUID,
+//!     "timestamp" TIMESTAMPTZ NOT NULL,
+//!     ip_address TEXT
+//! );
+//! ```
+
+use std::marker::PhantomData;
+use async_trait::async_trait;
+use cloudshuttle_error_handling::database_error::{DatabaseError, DatabaseResult};
+use serde_json::Value;
+use sqlx::postgres::{PgArguments, PgRow};
+use sqlx::{Arguments, Encode, PgConnection, PgPool, Postgres, Row, Type};
+use uuid::Uuid;
+use crate::query::executor::{bind_params, fetch_all_as, fetch_optional_as};
+use crate::query::{Expr, Ident, ParamType, QueryBuilder, SortDirection};
+use crate::types::entities::{Audited, Entity, SoftDeletable, TenantScoped, Versioned};
+use crate::types::models::QueryCriteria;
+use crate::types::traits::{
+    AuditAction, AuditEntry, AuditableRepository, BatchRepository, QueryRepository, Repository,
+    SoftDeleteRepository, TenantRepository, VersionedRepository,
+};
+
+/// Column the upsert returns to tell an insert from an update
+const INSERTED_COLUMN: &str = "__inserted";
+
+/// Repository for an [`Entity`] backed by a connection pool
+pub struct PgRepository<T> {
+    pool: PgPool,
+    user_id: Option<Uuid>,
+    ip_address: Option<String>,
+    _entity: PhantomData<fn() -> T>,
+}
+
+impl<T: Entity> PgRepository<T> {
+    /// Create a repository
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            user_id: None,
+            ip_address: None,
+            _entity: PhantomData,
+        }
+    }
+
+    /// Attribute audit entries to a user and client address
+    pub fn with_actor(mut self, user_id: Option<Uuid>, ip_address: Option<String>) -> Self {
+        self.user_id = user_id;
+        self.ip_address = ip_address;
+        self
+    }
+
+    /// Get the underlying pool
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Insert or update an entity, optionally requiring the stored version to match
+    async fn upsert(&self, conn: &mut PgConnection, entity: &T, expected_version: Option<i32>) -> DatabaseResult<T> {
+        let id = entity.id();
+        let before = match T::AUDIT_TABLE {
+            Some(_) => snapshot::<T>(conn, id).await?,
+            None => None,
+        };
+
+        let mut arguments = PgArguments::default();
+        entity.bind_columns(&mut arguments)?;
+        if let Some(version) = expected_version {
+            bind_column(&mut arguments, required_name(T::VERSION_COLUMN, "version")?, version)?;
+        }
+
+        let sql = upsert_sql::<T>(expected_version.is_some())?;
+        let row = sqlx::query_with(&sql, arguments).fetch_optional(&mut *conn).await?;
+        let row = match (row, expected_version) {
+            (Some(row), _) => row,
+            (None, Some(expected)) => return Err(DatabaseError::version_conflict(resource::<T>(id), expected)),
+            // The row exists under another tenant
+            (None, None) => return Err(DatabaseError::not_found(resource::<T>(id))),
+        };
+        let saved = T::from_row(&row)?;
+
+        let action = if row.try_get(INSERTED_COLUMN)? { AuditAction::Created } else { AuditAction::Updated };
+        self.record(conn, id, action, before).await?;
+        Ok(saved)
+    }
+
+    /// Set or clear the soft delete timestamp
+    async fn set_deleted(&self, id: Uuid, deleted: bool) -> DatabaseResult<bool> {
+        let column = required(T::SOFT_DELETE_COLUMN, "soft delete")?;
+        let (value, current, action) = if deleted {
+            ("now()", "IS NULL", AuditAction::SoftDeleted)
+        } else {
+            ("NULL", "IS NOT NULL", AuditAction::Restored)
+        };
+        let mut assignments = format!("{} = {}", column, value);
+        if let Some(updated_at) = T::UPDATED_AT_COLUMN {
+            assignments.push_str(&format!(", {} = now()", Ident::new(updated_at)?));
+        }
+        let sql = format!(
+            "UPDATE {} SET {} WHERE {} = $1 AND {} {}",
+            T::table()?,
+            assignments,
+            Ident::new(T::ID_COLUMN)?,
+            column,
+            current
+        );
+
+        let mut tx = self.pool.begin().await?;
+        let before = match T::AUDIT_TABLE {
+            Some(_) => snapshot::<T>(&mut tx, id).await?,
+            None => None,
+        };
+        let changed = sqlx::query(&sql).bind(id).execute(&mut *tx).await?.rows_affected() > 0;
+        if changed {
+            self.record(&mut tx, id, action, before).await?;
+        }
+        tx.commit().await?;
+        Ok(changed)
+    }
+
+    /// Write an audit entry for an audited entity; a no-op otherwise
+    async fn record(&self, conn: &mut PgConnection, id: Uuid, action: AuditAction, before: Option<Value>) -> DatabaseResult<()> {
+        let Some(audit_table) = T::AUDIT_TABLE else {
+            return Ok(());
+        };
+        let after = snapshot::<T>(conn, id).await?;
+        let sql = format!(
+            "INSERT INTO {} (id, entity_id, entity_type, action, old_values, new_values, user_id, \"timestamp\", ip_address) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+            Ident::new(audit_table)?
+        );
+        sqlx::query(&sql)
+            .bind(Uuid::new_v4())
+            .bind(id)
+            .bind(T::TABLE)
+            .bind(action_name(&action))
+            .bind(before)
+            .bind(after)
+            .bind(self.user_id)
+            .bind(chrono::Utc::now())
+            .bind(self.ip_address.as_deref())
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    }
+}
+
+impl<T> Clone for PgRepository<T> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            user_id: self.user_id,
+            ip_address: self.ip_address.clone(),
+            _entity: PhantomData,
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for PgRepository<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PgRepository")
+            .field("entity", &std::any::type_name::<T>())
+            .field("user_id", &self.user_id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<T: Entity> Repository<T> for PgRepository<T> {
+    async fn find_by_id(&self, id: Uuid) -> DatabaseResult<Option<T>> {
+        let (sql, params) = select_live::<T>()?
+            .where_eq(Ident::new(T::ID_COLUMN)?, ParamType::Uuid.tag(id.to_string()))
+            .build();
+        fetch_optional_as(&self.pool, &sql, &params).await
+    }
+
+    async fn save(&self, entity: T) -> DatabaseResult<T> {
+        let mut tx = self.pool.begin().await?;
+        let saved = self.upsert(&mut tx, &entity, None).await?;
+        tx.commit().await?;
+        Ok(saved)
+    }
+
+    async fn delete(&self, id: Uuid) -> DatabaseResult<bool> {
+        let sql = format!("DELETE FROM {} WHERE {} = $1", T::table()?, Ident::new(T::ID_COLUMN)?);
+
+        let mut tx = self.pool.begin().await?;
+        let before = match T::AUDIT_TABLE {
+            Some(_) => snapshot::<T>(&mut tx, id).await?,
+            None => None,
+        };
+        let deleted = sqlx::query(&sql).bind(id).execute(&mut *tx).await?.rows_affected() > 0;
+        if deleted {
+            self.record(&mut tx, id, AuditAction::Deleted, before).await?;
+        }
+        tx.commit().await?;
+        Ok(deleted)
+    }
+}
+
+#[async_trait]
+impl<T: Entity> QueryRepository<T> for PgRepository<T> {
+    async fn find_all(&self) -> DatabaseResult<Vec<T>> {
+        let (sql, params) = select_live::<T>()?.order_by(Ident::new(T::ID_COLUMN)?, SortDirection::Ascending).build();
+        fetch_all_as(&self.pool, &sql, &params).await
+    }
+
+    /// Filter and sort fields must be entity columns
+    async fn find_by_criteria(&self, criteria: QueryCriteria) -> DatabaseResult<Vec<T>> {
+        let (sql, params) = select_live::<T>()?
+            .apply_criteria(criteria, &T::allowlist()?)?
+            .order_by(Ident::new(T::ID_COLUMN)?, SortDirection::Ascending)
+            .build();
+        fetch_all_as(&self.pool, &sql, &params).await
+    }
+
+    async fn count(&self) -> DatabaseResult<i64> {
+        let (sql, params) = select_live::<T>()?.build_count();
+        Ok(sqlx::query_scalar_with(&sql, bind_params(&params)?).fetch_one(&self.pool).await?)
+    }
+}
+
+#[async_trait]
+impl<T: Entity> BatchRepository<T> for PgRepository<T> {
+    /// Save all entities in one transaction
+    async fn save_batch(&self, entities: Vec<T>) -> DatabaseResult<Vec<T>> {
+        let mut tx = self.pool.begin().await?;
+        let mut saved = Vec::with_capacity(entities.len());
+        for entity in &entities {
+            saved.push(self.upsert(&mut tx, entity, None).await?);
+        }
+        tx.commit().await?;
+        Ok(saved)
+    }
+
+    async fn delete_batch(&self, ids: Vec<Uuid>) -> DatabaseResult<usize> {
+        let id_column = Ident::new(T::ID_COLUMN)?;
+        let sql = format!("DELETE FROM {} WHERE {} = ANY($1) RETURNING {}", T::table()?, id_column, id_column);
+
+        let mut tx = self.pool.begin().await?;
+        let mut before = Vec::new();
+        if T::AUDIT_TABLE.is_some() {
+            for id in &ids {
+                before.push((*id, snapshot::<T>(&mut tx, *id).await?));
+            }
+        }
+        let deleted: Vec<Uuid> = sqlx::query_scalar(&sql).bind(&ids).fetch_all(&mut *tx).await?;
+        for (id, snapshot) in before.into_iter().filter(|(id, _)| deleted.contains(id)) {
+            self.record(&mut tx, id, AuditAction::Deleted, snapshot).await?;
+        }
+        tx.commit().await?;
+        Ok(deleted.len())
+    }
+}
+
+#[async_trait]
+impl<T: SoftDeletable> SoftDeleteRepository<T> for PgRepository<T> {
+    async fn soft_delete(&self, id: Uuid) -> DatabaseResult<bool> {
+        self.set_deleted(id, true).await
+    }
+
+    async fn restore(&self, id: Uuid) -> DatabaseResult<bool> {
+        self.set_deleted(id, false).await
+    }
+
+    async fn find_deleted(&self) -> DatabaseResult<Vec<T>> {
+        let (sql, params) = select::<T>()?
+            .filter(Expr::is_not_null(required(T::SOFT_DELETE_COLUMN, "soft delete")?))
+            .order_by(Ident::new(T::ID_COLUMN)?, SortDirection::Ascending)
+            .build();
+        fetch_all_as(&self.pool, &sql, &params).await
+    }
+}
+
+#[async_trait]
+impl<T: Versioned> VersionedRepository<T> for PgRepository<T> {
+    /// Insert the entity, or update it if the stored version is `expected_version`
+    async fn save_versioned(&self, entity: T, expected_version: i32) -> DatabaseResult<T> {
+        let mut tx = self.pool.begin().await?;
+        let saved = self.upsert(&mut tx, &entity, Some(expected_version)).await?;
+        tx.commit().await?;
+        Ok(saved)
+    }
+
+    async fn get_version(&self, id: Uuid) -> DatabaseResult<i32> {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE {} = $1",
+            required(T::VERSION_COLUMN, "version")?,
+            T::table()?,
+            Ident::new(T::ID_COLUMN)?
+        );
+        sqlx::query_scalar(&sql)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| DatabaseError::not_found(resource::<T>(id)))
+    }
+}
+
+#[async_trait]
+impl<T: TenantScoped> TenantRepository<T> for PgRepository<T> {
+    async fn find_by_tenant(&self, tenant_id: Uuid) -> DatabaseResult<Vec<T>> {
+        let (sql, params) = select_live::<T>()?
+            .where_eq(required(T::TENANT_COLUMN, "tenant")?, ParamType::Uuid.tag(tenant_id.to_string()))
+            .order_by(Ident::new(T::ID_COLUMN)?, SortDirection::Ascending)
+            .build();
+        fetch_all_as(&self.pool, &sql, &params).await
+    }
+
+    async fn find_by_tenant_and_id(&self, tenant_id: Uuid, id: Uuid) -> DatabaseResult<Option<T>> {
+        let (sql, params) = select_live::<T>()?
+            .where_eq(required(T::TENANT_COLUMN, "tenant")?, ParamType::Uuid.tag(tenant_id.to_string()))
+            .where_eq(Ident::new(T::ID_COLUMN)?, ParamType::Uuid.tag(id.to_string()))
+            .build();
+        fetch_optional_as(&self.pool, &sql, &params).await
+    }
+}
+
+#[async_trait]
+impl<T: Audited> AuditableRepository<T> for PgRepository<T> {
+    async fn find_with_audit(&self, id: Uuid) -> DatabaseResult<Option<(T, Vec<AuditEntry>)>> {
+        match self.find_by_id(id).await? {
+            Some(entity) => Ok(Some((entity, self.get_audit_trail(id).await?))),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_audit_trail(&self, id: Uuid) -> DatabaseResult<Vec<AuditEntry>> {
+        let sql = format!(
+            "SELECT id, entity_id, entity_type, action, old_values, new_values, user_id, \"timestamp\", ip_address \
+             FROM {} WHERE entity_type = $1 AND entity_id = $2 ORDER BY \"timestamp\", id",
+            Ident::new(required_name(T::AUDIT_TABLE, "audit table")?)?
+        );
+        let rows = sqlx::query(&sql).bind(T::TABLE).bind(id).fetch_all(&self.pool).await?;
+        rows.iter().map(audit_entry).collect()
+    }
+}
+
+/// Bind one column value, naming the column if encoding fails
+#[doc(hidden)]
+pub fn bind_column<'q, V>(arguments: &mut PgArguments, column: &str, value: V) -> DatabaseResult<()>
+where
+    V: Encode<'q, Postgres> + Type<Postgres> + 'q,
+{
+    arguments
+        .add(value)
+        .map_err(|e| DatabaseError::query(format!("Failed to bind column {}: {}", column, e)))
+}
+
+/// `SELECT <columns> FROM <table>`
+fn select<T: Entity>() -> DatabaseResult<QueryBuilder> {
+    let columns = T::COLUMNS.iter().map(Ident::new).collect::<DatabaseResult<Vec<_>>>()?;
+    Ok(QueryBuilder::new(T::table()?).select(columns))
+}
+
+/// [`select`] without soft-deleted rows
+fn select_live<T: Entity>() -> DatabaseResult<QueryBuilder> {
+    let builder = select::<T>()?;
+    Ok(match T::SOFT_DELETE_COLUMN {
+        Some(column) => builder.filter(Expr::is_null(Ident::new(column)?)),
+        None => builder,
+    })
+}
+
+/// Upsert on the id column, returning the stored row
+///
+/// With `check_version`, the expected version is bound after the columns and
+/// the update only applies when it matches. The row also returns
+/// [`INSERTED_COLUMN`], true when the upsert inserted rather than updated.
+fn upsert_sql<T: Entity>(check_version: bool) -> DatabaseResult<String> {
+    let columns = T::COLUMNS.iter().map(Ident::new).collect::<DatabaseResult<Vec<_>>>()?;
+    let id = Ident::new(T::ID_COLUMN)?;
+    let unchanged = [Some(T::ID_COLUMN), T::TENANT_COLUMN, T::CREATED_AT_COLUMN];
+
+    let mut assignments: Vec<String> = T::COLUMNS
+        .iter()
+        .zip(&columns)
+        .filter(|(name, _)| !unchanged.contains(&Some(**name)))
+        .map(|(name, column)| {
+            if Some(*name) == T::UPDATED_AT_COLUMN {
+                format!("{} = now()", column)
+            } else if Some(*name) == T::VERSION_COLUMN {
+                format!("{0} = t.{0} + 1", column)
+            } else {
+                format!("{0} = EXCLUDED.{0}", column)
+            }
+        })
+        .collect();
+    if assignments.is_empty() {
+        assignments.push(format!("{0} = EXCLUDED.{0}", id));
+    }
+
+    let mut conditions = Vec::new();
+    if let Some(tenant) = T::TENANT_COLUMN {
+        conditions.push(format!("t.{0} = EXCLUDED.{0}", Ident::new(tenant)?));
+    }
+    if check_version {
+        let version = required(T::VERSION_COLUMN, "version")?;
+        conditions.push(format!("t.{} = ${}", version, columns.len() + 1));
+    }
+
+    let column_list = columns.iter().map(Ident::to_sql).collect::<Vec<_>>().join(", ");
+    let placeholders = (1..=columns.len()).map(|i| format!("${}", i)).collect::<Vec<_>>().join(", ");
+    let mut sql = format!(
+        "INSERT INTO {} AS t ({}) VALUES ({}) ON CONFLICT ({}) DO UPDATE SET {}",
+        T::table()?,
+        column_list,
+        placeholders,
+        id,
+        assignments.join(", ")
+    );
+    if !conditions.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+    }
+    sql.push_str(&format!(" RETURNING {}, (t.xmax = 0) AS \"{}\"", column_list, INSERTED_COLUMN));
+    Ok(sql)
+}
+
+/// Row as JSON for audit entries, locked for the rest of the transaction
+async fn snapshot<T: Entity>(conn: &mut PgConnection, id: Uuid) -> DatabaseResult<Option<Value>> {
+    let sql = format!("SELECT to_jsonb(t) FROM {} AS t WHERE t.{} = $1 FOR UPDATE", T::table()?, Ident::new(T::ID_COLUMN)?);
+    Ok(sqlx::query_scalar(&sql).bind(id).fetch_optional(&mut *conn).await?)
+}
+
+fn audit_entry(row: &PgRow) -> DatabaseResult<AuditEntry> {
+    let action: String = row.try_get("action")?;
+    Ok(AuditEntry {
+        id: row.try_get("id")?,
+        entity_id: row.try_get("entity_id")?,
+        entity_type: row.try_get("entity_type")?,
+        action: serde_json::from_value(Value::String(action.clone()))
+            .map_err(|_| DatabaseError::query(format!("Unknown audit action {}", action)))?,
+        old_values: row.try_get("old_values")?,
+        new_values: row.try_get("new_values")?,
+        user_id: row.try_get("user_id")?,
+        timestamp: row.try_get("timestamp")?,
+        ip_address: row.try_get("ip_address")?,
+    })
+}
+
+/// Stored name of an audit action, matching its serde representation
+fn action_name(action: &AuditAction) -> &'static str {
+    match action {
+        AuditAction::Created => "Created",
+        AuditAction::Updated => "Updated",
+        AuditAction::Deleted => "Deleted",
+        AuditAction::SoftDeleted => "SoftDeleted",
+        AuditAction::Restored => "Restored",
+    }
+}
+
+fn required_name(column: Option<&'static str>, what: &str) -> DatabaseResult<&'static str> {
+    column.ok_or_else(|| DatabaseError::query(format!("Entity has no {} configured", what)))
+}
+
+fn required(column: Option<&'static str>, what: &str) -> DatabaseResult<Ident> {
+    Ident::new(required_name(column, what)?)
+}
+
+fn resource<T: Entity>(id: Uuid) -> String {
+    format!("{} {}", T::TABLE, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+    use cloudshuttle_database_derive::Entity;
+    use crate::query::EntityColumns;
+    use crate::types::models::{Filter, FilterOperator};
+
+    #[derive(Debug, Clone, sqlx::FromRow, Entity)]
+    #[entity(table = "derive_projects", tenant, soft_delete, version, audit = "derive_audit")]
+    struct Project {
+        id: Uuid,
+        tenant_id: Uuid,
+        #[sqlx(rename = "title")]
+        name: String,
+        #[sqlx(json)]
+        settings: Value,
+        version: i32,
+        deleted_at: Option<DateTime<Utc>>,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+        #[sqlx(skip)]
+        #[allow(dead_code)]
+        cached: Option<String>,
+    }
+
+    #[derive(Debug, sqlx::FromRow, Entity)]
+    #[entity(table = "tags", id = "tag_id")]
+    struct Tag {
+        tag_id: Uuid,
+        label: String,
+    }
+
+    #[derive(Debug, sqlx::FromRow, Entity)]
+    #[entity(table = "derive_drafts")]
+    struct Draft {
+        id: Uuid,
+        #[sqlx(json(nullable))]
+        body: Option<Value>,
+    }
+
+    impl Project {
+        fn new(tenant_id: Uuid, name: &str) -> Self {
+            let now = Utc::now();
+            Self {
+                id: Uuid::new_v4(),
+                tenant_id,
+                name: name.to_string(),
+                settings: serde_json::json!({"public": false}),
+                version: 1,
+                deleted_at: None,
+                created_at: now,
+                updated_at: now,
+                cached: None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_derived_mapping() {
+        assert_eq!(Project::TABLE, "derive_projects");
+        assert_eq!(
+            Project::COLUMNS,
+            &["id", "tenant_id", "title", "settings", "version", "deleted_at", "created_at", "updated_at"]
+        );
+        assert_eq!(Project::TENANT_COLUMN, Some("tenant_id"));
+        assert_eq!(Project::SOFT_DELETE_COLUMN, Some("deleted_at"));
+        assert_eq!(Project::AUDIT_TABLE, Some("derive_audit"));
+        assert_eq!(Project::UPDATED_AT_COLUMN, Some("updated_at"));
+        assert_eq!(Project::allowlist().unwrap().param_type("deleted_at"), Some(ParamType::Timestamptz));
+        assert_eq!(Project::allowlist().unwrap().param_type("settings"), None);
+        // A String column keeps UUID-looking filter values as text
+        assert_eq!(Project::allowlist().unwrap().param_type("title"), Some(ParamType::Text));
+
+        assert_eq!(Tag::ID_COLUMN, "tag_id");
+        assert_eq!(Tag::SOFT_DELETE_COLUMN, None);
+        assert_eq!(Tag::CREATED_AT_COLUMN, None);
+
+        assert_eq!(Draft::COLUMNS, &["id", "body"]);
+        assert_eq!(Draft::allowlist().unwrap().param_type("body"), None);
+    }
+
+    #[test]
+    fn test_upsert_sql() {
+        assert_eq!(
+            upsert_sql::<Tag>(false).unwrap(),
+            "INSERT INTO \"tags\" AS t (\"tag_id\", \"label\") VALUES ($1, $2) \
+             ON CONFLICT (\"tag_id\") DO UPDATE SET \"label\" = EXCLUDED.\"label\" \
+             RETURNING \"tag_id\", \"label\", (t.xmax = 0) AS \"__inserted\""
+        );
+
+        let sql = upsert_sql::<Project>(true).unwrap();
+        assert!(sql.contains("\"version\" = t.\"version\" + 1"));
+        assert!(sql.contains("\"updated_at\" = now()"));
+        assert!(!sql.contains("\"created_at\" = EXCLUDED"));
+        assert!(!sql.contains("\"tenant_id\" = EXCLUDED.\"tenant_id\","));
+        assert!(sql.contains("WHERE t.\"tenant_id\" = EXCLUDED.\"tenant_id\" AND t.\"version\" = $9"));
+        assert!(upsert_sql::<Tag>(true).is_err());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a PostgreSQL database
+    async fn test_repository_against_database() {
+        // Temporary tables only exist on the connection that created them
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&crate::test_util::database_url())
+            .await
+            .expect("Failed to connect to test database");
+        sqlx::query(
+            "CREATE TEMPORARY TABLE derive_projects (id UUID PRIMARY KEY, tenant_id UUID NOT NULL, title TEXT NOT NULL, \
+             settings JSONB NOT NULL, version INT4 NOT NULL, deleted_at TIMESTAMPTZ, created_at TIMESTAMPTZ NOT NULL, \
+             updated_at TIMESTAMPTZ NOT NULL)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "CREATE TEMPORARY TABLE derive_audit (id UUID PRIMARY KEY, entity_id UUID NOT NULL, entity_type TEXT NOT NULL, \
+             action TEXT NOT NULL, old_values JSONB, new_values JSONB, user_id UUID, \"timestamp\" TIMESTAMPTZ NOT NULL, \
+             ip_address TEXT)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query("CREATE TEMPORARY TABLE derive_drafts (id UUID PRIMARY KEY, body JSONB)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // Nullable JSON columns store `None` as SQL NULL
+        let drafts = PgRepository::<Draft>::new(pool.clone());
+        let draft = drafts.save(Draft { id: Uuid::new_v4(), body: None }).await.unwrap();
+        assert!(draft.body.is_none());
+        let is_null: bool = sqlx::query_scalar("SELECT body IS NULL FROM derive_drafts WHERE id = $1")
+            .bind(draft.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert!(is_null);
+
+        let user = Uuid::new_v4();
+        let repo = PgRepository::<Project>::new(pool).with_actor(Some(user), Some("10.0.0.1".to_string()));
+        let tenant = Uuid::new_v4();
+
+        // Create, then update: the version is bumped and created_at kept
+        let project = repo.save(Project::new(tenant, "alpha")).await.unwrap();
+        let mut renamed = project.clone();
+        renamed.name = "beta".to_string();
+        renamed.created_at = Utc::now() + chrono::Duration::days(1);
+        let renamed = repo.save(renamed).await.unwrap();
+        assert_eq!(renamed.version, 2);
+        assert_eq!(renamed.created_at, project.created_at);
+        assert_eq!(repo.get_version(project.id).await.unwrap(), 2);
+
+        // Optimistic locking
+        let stale = repo.save_versioned(renamed.clone(), 1).await.unwrap_err();
+        assert!(matches!(stale, DatabaseError::VersionConflict { expected: 1, .. }));
+        assert_eq!(repo.save_versioned(renamed.clone(), 2).await.unwrap().version, 3);
+
+        // Another tenant cannot take over the row
+        let mut hijacked = renamed.clone();
+        hijacked.tenant_id = Uuid::new_v4();
+        assert!(matches!(repo.save(hijacked).await, Err(DatabaseError::NotFound { .. })));
+
+        // Queries
+        let other = repo.save_batch(vec![Project::new(tenant, "gamma"), Project::new(Uuid::new_v4(), "delta")]).await.unwrap();
+        assert_eq!(repo.count().await.unwrap(), 3);
+        assert_eq!(repo.find_by_tenant(tenant).await.unwrap().len(), 2);
+        assert!(repo.find_by_tenant_and_id(tenant, other[1].id).await.unwrap().is_none());
+        let criteria = QueryCriteria {
+            filters: vec![Filter {
+                field: "title".to_string(),
+                operator: FilterOperator::Equal,
+                value: serde_json::json!("gamma"),
+            }],
+            ..QueryCriteria::default()
+        };
+        let found = repo.find_by_criteria(criteria).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].settings, serde_json::json!({"public": false}));
+        // UUID columns take plain string filter values
+        let criteria = QueryCriteria {
+            filters: vec![Filter {
+                field: "tenant_id".to_string(),
+                operator: FilterOperator::In,
+                value: serde_json::json!([tenant.to_string()]),
+            }],
+            ..QueryCriteria::default()
+        };
+        assert_eq!(repo.find_by_criteria(criteria).await.unwrap().len(), 2);
+
+        // Soft delete hides the row until it is restored
+        assert!(repo.soft_delete(project.id).await.unwrap());
+        assert!(!repo.soft_delete(project.id).await.unwrap());
+        assert!(repo.find_by_id(project.id).await.unwrap().is_none());
+        assert_eq!(repo.find_deleted().await.unwrap().len(), 1);
+        assert!(repo.restore(project.id).await.unwrap());
+
+        // Audit trail
+        let (_, trail) = repo.find_with_audit(project.id).await.unwrap().unwrap();
+        let actions: Vec<&str> = trail.iter().map(|entry| action_name(&entry.action)).collect();
+        assert_eq!(actions, vec!["Created", "Updated", "Updated", "SoftDeleted", "Restored"]);
+        assert!(trail[0].old_values.is_none());
+        assert_eq!(trail[1].new_values.as_ref().unwrap()["title"], "beta");
+        assert_eq!(trail[0].user_id, Some(user));
+
+        // Hard deletes
+        assert_eq!(repo.delete_batch(vec![other[0].id, other[1].id, Uuid::new_v4()]).await.unwrap(), 2);
+        assert!(repo.delete(project.id).await.unwrap());
+        assert_eq!(repo.count().await.unwrap(), 0);
+        let trail = repo.get_audit_trail(other[0].id).await.unwrap();
+        assert!(matches!(trail.last().unwrap().action, AuditAction::Deleted));
+    }
+}
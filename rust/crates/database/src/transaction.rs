@@ -2,6 +2,7 @@
 
 use sqlx::{Transaction, postgres::PgRow};
 use cloudshuttle_error_handling::database_error::DatabaseResult;
+use crate::query::executor::{self, JsonRow};
 
 /// Database transaction wrapper with automatic rollback on drop
 pub struct DatabaseTransaction<'a> {
@@ -18,16 +19,9 @@ impl<'a> DatabaseTransaction<'a> {
         }
     }
 
-    /// Execute a query within the transaction
-    pub async fn execute(&mut self, query: &str, _params: &[&(dyn sqlx::Encode<'_, sqlx::Postgres> + Sync)]) -> DatabaseResult<u64> {
-        let tx = self.tx.as_mut().ok_or_else(|| {
-            sqlx::Error::Configuration("Transaction already committed or rolled back".into())
-        })?;
-
-        // Execute the query within the transaction
-        // Note: For now, ignoring params and using simple query execution
-        let result = sqlx::query(query).execute(&mut **tx).await?;
-        Ok(result.rows_affected())
+    /// Execute a query within the transaction, binding JSON parameters as `$1..$n`
+    pub async fn execute(&mut self, query: &str, params: &[serde_json::Value]) -> DatabaseResult<u64> {
+        executor::execute(self.connection()?, query, params).await
     }
 
     /// Execute a SELECT query and return the first row
@@ -35,13 +29,7 @@ impl<'a> DatabaseTransaction<'a> {
     where
         T: for<'r> sqlx::FromRow<'r, PgRow> + Send + Unpin,
     {
-        let tx = self.tx.as_mut().ok_or_else(|| {
-            sqlx::Error::Configuration("Transaction already committed or rolled back".into())
-        })?;
-
-        // Execute the query within the transaction
-        let result = sqlx::query_as::<_, T>(query).fetch_one(&mut **tx).await?;
-        Ok(result)
+        self.fetch_one_as(query, &[]).await
     }
 
     /// Execute a SELECT query and return optional first row
@@ -49,13 +37,7 @@ impl<'a> DatabaseTransaction<'a> {
     where
         T: for<'r> sqlx::FromRow<'r, PgRow> + Send + Unpin,
     {
-        let tx = self.tx.as_mut().ok_or_else(|| {
-            sqlx::Error::Configuration("Transaction already committed or rolled back".into())
-        })?;
-
-        // Execute the query within the transaction
-        let result = sqlx::query_as::<_, T>(query).fetch_optional(&mut **tx).await?;
-        Ok(result)
+        self.fetch_optional_as(query, &[]).await
     }
 
     /// Execute a SELECT query and return all rows
@@ -63,13 +45,58 @@ impl<'a> DatabaseTransaction<'a> {
     where
         T: for<'r> sqlx::FromRow<'r, PgRow> + Send + Unpin,
     {
+        self.fetch_all_as(query, &[]).await
+    }
+
+    /// Fetch all rows, binding JSON parameters
+    pub async fn fetch_rows(&mut self, query: &str, params: &[serde_json::Value]) -> DatabaseResult<Vec<PgRow>> {
+        executor::fetch_rows(self.connection()?, query, params).await
+    }
+
+    /// Fetch all rows as JSON objects
+    pub async fn fetch_all_json(&mut self, query: &str, params: &[serde_json::Value]) -> DatabaseResult<Vec<JsonRow>> {
+        executor::fetch_all_json(self.connection()?, query, params).await
+    }
+
+    /// Fetch exactly one row as a JSON object
+    pub async fn fetch_one_json(&mut self, query: &str, params: &[serde_json::Value]) -> DatabaseResult<JsonRow> {
+        executor::fetch_one_json(self.connection()?, query, params).await
+    }
+
+    /// Fetch at most one row as a JSON object
+    pub async fn fetch_optional_json(&mut self, query: &str, params: &[serde_json::Value]) -> DatabaseResult<Option<JsonRow>> {
+        executor::fetch_optional_json(self.connection()?, query, params).await
+    }
+
+    /// Fetch all rows as `T`, binding JSON parameters
+    pub async fn fetch_all_as<T>(&mut self, query: &str, params: &[serde_json::Value]) -> DatabaseResult<Vec<T>>
+    where
+        T: for<'r> sqlx::FromRow<'r, PgRow> + Send + Unpin,
+    {
+        executor::fetch_all_as(self.connection()?, query, params).await
+    }
+
+    /// Fetch exactly one row as `T`, binding JSON parameters
+    pub async fn fetch_one_as<T>(&mut self, query: &str, params: &[serde_json::Value]) -> DatabaseResult<T>
+    where
+        T: for<'r> sqlx::FromRow<'r, PgRow> + Send + Unpin,
+    {
+        executor::fetch_one_as(self.connection()?, query, params).await
+    }
+
+    /// Fetch at most one row as `T`, binding JSON parameters
+    pub async fn fetch_optional_as<T>(&mut self, query: &str, params: &[serde_json::Value]) -> DatabaseResult<Option<T>>
+    where
+        T: for<'r> sqlx::FromRow<'r, PgRow> + Send + Unpin,
+    {
+        executor::fetch_optional_as(self.connection()?, query, params).await
+    }
+
+    fn connection(&mut self) -> DatabaseResult<&mut sqlx::PgConnection> {
         let tx = self.tx.as_mut().ok_or_else(|| {
             sqlx::Error::Configuration("Transaction already committed or rolled back".into())
         })?;
-
-        // Execute the query within the transaction
-        let result = sqlx::query_as::<_, T>(query).fetch_all(&mut **tx).await?;
-        Ok(result)
+        Ok(&mut **tx)
     }
 
     /// Commit the transaction
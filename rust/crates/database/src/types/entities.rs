@@ -1,6 +1,9 @@
 //! Base entity types and common functionality
 
+use crate::query::EntityColumns;
+use cloudshuttle_error_handling::database_error::DatabaseResult;
 use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgArguments, PgRow};
 use uuid::Uuid;
 
 /// Common database entity fields
@@ -65,6 +68,48 @@ impl Default for SoftDeleteEntity {
     }
 }
 
+/// Table mapping used by [`PgRepository`](crate::PgRepository)
+///
+/// Usually generated with `#[derive(Entity)]` (the `derive` feature).
+/// `COLUMNS` lists every persisted column in field order, and
+/// [`bind_columns`](Self::bind_columns) binds the field values in that same
+/// order. Optional columns follow the [`BaseEntity`] and [`SoftDeleteEntity`]
+/// field names by convention.
+pub trait Entity: EntityColumns + for<'r> sqlx::FromRow<'r, PgRow> + Send + Sync + Unpin + 'static {
+    /// Primary key column (a UUID)
+    const ID_COLUMN: &'static str;
+    /// Tenant column, usually `tenant_id`
+    const TENANT_COLUMN: Option<&'static str> = None;
+    /// Soft delete timestamp column, usually `deleted_at`
+    const SOFT_DELETE_COLUMN: Option<&'static str> = None;
+    /// Optimistic locking version column (INT4), usually `version`
+    const VERSION_COLUMN: Option<&'static str> = None;
+    /// Creation timestamp column, kept unchanged on update
+    const CREATED_AT_COLUMN: Option<&'static str> = None;
+    /// Modification timestamp column, set to `now()` on update
+    const UPDATED_AT_COLUMN: Option<&'static str> = None;
+    /// Table that receives an [`AuditEntry`](super::traits::AuditEntry) for every change
+    const AUDIT_TABLE: Option<&'static str> = None;
+
+    /// Primary key value
+    fn id(&self) -> Uuid;
+
+    /// Bind every column value, in `COLUMNS` order
+    fn bind_columns(&self, arguments: &mut PgArguments) -> DatabaseResult<()>;
+}
+
+/// Entity with a soft delete column
+pub trait SoftDeletable: Entity {}
+
+/// Entity with an optimistic locking version column
+pub trait Versioned: Entity {}
+
+/// Entity with a tenant column
+pub trait TenantScoped: Entity {}
+
+/// Entity whose changes are written to an audit table
+pub trait Audited: Entity {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -32,6 +32,15 @@ pub enum DatabaseError {
 
     #[error("Pool exhausted: {message}")]
     PoolExhausted { message: String },
+
+    #[error("Invalid identifier: {identifier} - {reason}")]
+    InvalidIdentifier { identifier: String, reason: String },
+
+    #[error("Invalid cursor: {reason}")]
+    InvalidCursor { reason: String },
+
+    #[error("Version conflict: {resource} is not at version {expected}")]
+    VersionConflict { resource: String, expected: i32 },
 }
 
 impl DatabaseError {
@@ -74,6 +83,24 @@ impl DatabaseError {
         Self::PoolExhausted { message: message.into() }
     }
 
+    pub fn invalid_identifier<I: Into<String>, R: Into<String>>(identifier: I, reason: R) -> Self {
+        Self::InvalidIdentifier {
+            identifier: identifier.into(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn invalid_cursor<S: Into<String>>(reason: S) -> Self {
+        Self::InvalidCursor { reason: reason.into() }
+    }
+
+    pub fn version_conflict<S: Into<String>>(resource: S, expected: i32) -> Self {
+        Self::VersionConflict {
+            resource: resource.into(),
+            expected,
+        }
+    }
+
     pub fn http_status(&self) -> http::StatusCode {
         match self {
             Self::Connection { .. } => http::StatusCode::SERVICE_UNAVAILABLE,
@@ -85,6 +112,9 @@ impl DatabaseError {
             Self::DuplicateKey { .. } => http::StatusCode::CONFLICT,
             Self::Timeout { .. } => http::StatusCode::GATEWAY_TIMEOUT,
             Self::PoolExhausted { .. } => http::StatusCode::SERVICE_UNAVAILABLE,
+            Self::InvalidIdentifier { .. } => http::StatusCode::BAD_REQUEST,
+            Self::InvalidCursor { .. } => http::StatusCode::BAD_REQUEST,
+            Self::VersionConflict { .. } => http::StatusCode::CONFLICT,
         }
     }
 
@@ -99,6 +129,9 @@ impl DatabaseError {
             Self::DuplicateKey { .. } => "DATABASE_DUPLICATE_KEY",
             Self::Timeout { .. } => "DATABASE_TIMEOUT",
             Self::PoolExhausted { .. } => "DATABASE_POOL_EXHAUSTED",
+            Self::InvalidIdentifier { .. } => "DATABASE_INVALID_IDENTIFIER",
+            Self::InvalidCursor { .. } => "DATABASE_INVALID_CURSOR",
+            Self::VersionConflict { .. } => "DATABASE_VERSION_CONFLICT",
         }
     }
 
@@ -113,6 +146,9 @@ impl DatabaseError {
             Self::DuplicateKey { key } => format!("{} already exists", key),
             Self::Timeout { operation } => format!("Database {} timed out", operation),
             Self::PoolExhausted { .. } => "Database connection pool exhausted".to_string(),
+            Self::InvalidIdentifier { identifier, .. } => format!("Invalid field: {}", identifier),
+            Self::InvalidCursor { .. } => "Invalid pagination cursor".to_string(),
+            Self::VersionConflict { resource, .. } => format!("{} was modified by another request", resource),
         }
     }
 }